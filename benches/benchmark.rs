@@ -1,57 +1,1039 @@
-#[macro_use]
-extern crate bencher;
+use crappylinkedlists::allocs::{measure_allocs, CountingAlloc};
+#[cfg(feature = "allocator_api")]
+use crappylinkedlists::arena::Bump;
+use crappylinkedlists::hybrid::HybridList;
+use crappylinkedlists::indexed::IndexedList;
+use crappylinkedlists::linked4;
 use crappylinkedlists::linked5::List;
-use std::mem::size_of;
-use bencher::Bencher;
+use crappylinkedlists::linked6;
+use crappylinkedlists::linked7;
+use crappylinkedlists::linked8;
+use crappylinkedlists::lockfree::mpsc;
+use crappylinkedlists::ops::{DoubleEndedOps, LinkedListOps, PositionalOps};
+use crappylinkedlists::selforg::{SelfOrgList, Strategy};
+use crappylinkedlists::unrolled::UnrolledList;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use std::collections::{LinkedList, VecDeque};
 
-fn create_new(bench: &mut Bencher) {
-    bench.iter(|| {
-        List::new()
-    })
+/* So that measure_allocs() works here too. It costs a couple of thread local
+adds per allocation, the same for every list. */
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+/* The same six operations on every list, at three sizes, so that the
+numbers can be compared: one group per list, one benchmark per operation and
+size. `cargo bench -- linked6/` runs one list, `cargo bench -- sort/100000`
+one operation on all of them. VecDeque and std's LinkedList are there as the
+baselines, the first one is what you should be using anyway.
+
+The operations that are O(n) per call on some lists (pop_back on a singly
+linked list, inserting in the middle) do OPS calls on a list of the given
+size instead of emptying it, otherwise 100k would take all day. */
+const SIZES: [usize; 3] = [10, 1_000, 100_000];
+const OPS: usize = 100;
+
+trait Subject: Sized {
+    const NAME: &'static str;
+
+    fn construct(values: &[i64]) -> Self;
+    fn sum(&self) -> i64;
+    fn push_front(&mut self, value: i64);
+    fn pop_back(&mut self) -> Option<i64>;
+    fn insert(&mut self, at: usize, value: i64);
+    fn sort(&mut self);
+}
+
+/* Most of them through LinkedListOps, with insert() and sort() by hand. */
+macro_rules! through_ops {
+    () => {
+        fn construct(values: &[i64]) -> Self {
+            LinkedListOps::from_slice(values)
+        }
+        fn sum(&self) -> i64 {
+            self.iter().sum()
+        }
+        fn push_front(&mut self, value: i64) {
+            LinkedListOps::push_front(self, value)
+        }
+        fn pop_back(&mut self) -> Option<i64> {
+            DoubleEndedOps::pop_back(self)
+        }
+    };
+}
+
+impl Subject for linked4::List<i64> {
+    const NAME: &'static str = "linked4";
+    through_ops!();
+
+    fn insert(&mut self, at: usize, value: i64) {
+        PositionalOps::insert(self, at, value)
+    }
+    fn sort(&mut self) {
+        self.sort()
+    }
+}
+
+/* linked5 can't insert in the middle, but it can split and concat. */
+impl Subject for List {
+    const NAME: &'static str = "linked5";
+    through_ops!();
+
+    fn insert(&mut self, at: usize, value: i64) {
+        let (mut left, right) = std::mem::take(self).split_at(at);
+        left.append(value);
+        left.concat(right);
+        *self = left;
+    }
+    fn sort(&mut self) {
+        self.sort()
+    }
+}
+
+/* These two have no sort, they go through a Vec. */
+impl Subject for linked6::List<i64> {
+    const NAME: &'static str = "linked6";
+    through_ops!();
+
+    fn insert(&mut self, at: usize, value: i64) {
+        PositionalOps::insert(self, at, value)
+    }
+    fn sort(&mut self) {
+        let mut values = self.to_vec();
+        values.sort();
+        *self = values.into_iter().collect();
+    }
+}
+
+impl Subject for UnrolledList<i64> {
+    const NAME: &'static str = "unrolled";
+    through_ops!();
+
+    fn insert(&mut self, at: usize, value: i64) {
+        PositionalOps::insert(self, at, value)
+    }
+    fn sort(&mut self) {
+        let mut values = self.to_vec();
+        values.sort();
+        *self = values.into_iter().collect();
+    }
+}
+
+impl Subject for VecDeque<i64> {
+    const NAME: &'static str = "VecDeque";
+
+    fn construct(values: &[i64]) -> Self {
+        values.iter().copied().collect()
+    }
+    fn sum(&self) -> i64 {
+        self.iter().sum()
+    }
+    fn push_front(&mut self, value: i64) {
+        self.push_front(value)
+    }
+    fn pop_back(&mut self) -> Option<i64> {
+        self.pop_back()
+    }
+    fn insert(&mut self, at: usize, value: i64) {
+        self.insert(at, value)
+    }
+    fn sort(&mut self) {
+        self.make_contiguous().sort()
+    }
 }
 
-fn create_from_vec_10(bench: &mut Bencher) {
-    let d: Vec<i64> = vec![1,2,3,4,5,6,7,8,9,10];
-    bench.iter(|| {
-        List::from_vec(&d)
+/* std's LinkedList has no insert() either, and no sort(). */
+impl Subject for LinkedList<i64> {
+    const NAME: &'static str = "std::LinkedList";
+
+    fn construct(values: &[i64]) -> Self {
+        values.iter().copied().collect()
+    }
+    fn sum(&self) -> i64 {
+        self.iter().sum()
+    }
+    fn push_front(&mut self, value: i64) {
+        self.push_front(value)
+    }
+    fn pop_back(&mut self) -> Option<i64> {
+        self.pop_back()
+    }
+    fn insert(&mut self, at: usize, value: i64) {
+        let mut right = self.split_off(at);
+        self.push_back(value);
+        self.append(&mut right);
+    }
+    fn sort(&mut self) {
+        let mut values: Vec<i64> = self.iter().copied().collect();
+        values.sort();
+        *self = values.into_iter().collect();
+    }
+}
+
+/* A fixed pseudo-random sequence, so every list gets the same numbers. */
+fn lcg(seed: &mut u64) -> u64 {
+    *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+    *seed >> 33
+}
+
+fn shuffled(len: usize) -> Vec<i64> {
+    let mut seed = len as u64;
+    (0..len)
+        .map(|_| (lcg(&mut seed) % 1_000_000) as i64)
+        .collect()
+}
+
+fn bench_subject<L: Subject>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(L::NAME);
+    for &size in SIZES.iter() {
+        let values = shuffled(size);
+        let ops = OPS.min(size);
+        /* Positions for insert, each one valid for the list at that point. */
+        let mut seed = 1;
+        let positions: Vec<usize> = (0..OPS)
+            .map(|i| lcg(&mut seed) as usize % (size + i + 1))
+            .collect();
+
+        group.throughput(Throughput::Elements(size as u64));
+        let (_, allocs) = measure_allocs(|| L::construct(&values));
+        println!(
+            "{}/construct/{}: {} allocations, {} bytes at the peak",
+            L::NAME,
+            size,
+            allocs.allocs + allocs.reallocs,
+            allocs.peak
+        );
+        group.bench_with_input(BenchmarkId::new("construct", size), &values, |b, values| {
+            b.iter(|| L::construct(values))
+        });
+        let list = L::construct(&values);
+        group.bench_with_input(BenchmarkId::new("iterate", size), &list, |b, list| {
+            b.iter(|| list.sum())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("push_front", size),
+            &values,
+            |b, values| {
+                b.iter(|| {
+                    let mut list = L::construct(&[]);
+                    for value in values {
+                        list.push_front(*value);
+                    }
+                    list
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("sort", size), &values, |b, values| {
+            b.iter_batched(
+                || L::construct(values),
+                |mut list| {
+                    list.sort();
+                    list
+                },
+                BatchSize::LargeInput,
+            )
+        });
+
+        group.throughput(Throughput::Elements(ops as u64));
+        group.bench_with_input(BenchmarkId::new("pop_back", size), &values, |b, values| {
+            b.iter_batched(
+                || L::construct(values),
+                |mut list| {
+                    for _ in 0..ops {
+                        list.pop_back();
+                    }
+                    list
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.throughput(Throughput::Elements(OPS as u64));
+        group.bench_with_input(
+            BenchmarkId::new("random_insert", size),
+            &values,
+            |b, values| {
+                b.iter_batched(
+                    || L::construct(values),
+                    |mut list| {
+                        for (value, at) in positions.iter().enumerate() {
+                            list.insert(*at, value as i64);
+                        }
+                        list
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+fn by_list(c: &mut Criterion) {
+    bench_subject::<linked4::List<i64>>(c);
+    bench_subject::<List>(c);
+    bench_subject::<linked6::List<i64>>(c);
+    bench_subject::<UnrolledList<i64>>(c);
+    bench_subject::<VecDeque<i64>>(c);
+    bench_subject::<LinkedList<i64>>(c);
+}
+
+/* Everything below is what was here before, one benchmark per question. */
+
+fn construction(c: &mut Criterion) {
+    let d: Vec<i64> = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+    c.bench_function("create_new", |b| b.iter(List::new));
+    c.bench_function("create_from_vec_10", |b| b.iter(|| List::from_vec(&d)));
+    let d_1k: Vec<i64> = d.iter().copied().cycle().take(1000).collect();
+    c.bench_function("create_from_vec_1k", |b| b.iter(|| List::from_vec(&d_1k)));
+    // The Vec and the second pass that from_vec() doesn't need anymore.
+    c.bench_function("create_from_vec_two_passes_1k", |b| {
+        b.iter(|| List::from_vec_two_passes(&d_1k))
+    });
+    c.bench_function("create_from_concat_10x100", |b| {
+        b.iter(|| {
+            let mut l = List::from_vec(&d);
+            for _ in 0..100 {
+                l.concat(List::from_vec(&d))
+            }
+            l
+        })
+    });
+
+    // new_slow walks the whole list for every item it appends.
+    let d_1k: Vec<i64> = (0..1000).collect();
+    c.bench_function("linked4_new_slow_1k", |b| {
+        b.iter(|| linked4::List::new_slow(&d_1k))
+    });
+    c.bench_function("linked4_new_1k", |b| b.iter(|| linked4::List::new(&d_1k)));
+    c.bench_function("linked4_new_fast_1k", |b| {
+        b.iter(|| linked4::List::new_fast(&d_1k))
+    });
+    // add_item appends using the cached tail, so this should be much faster.
+    c.bench_function("linked4_add_item_1k", |b| {
+        b.iter(|| {
+            let mut l = linked4::List::new(&[]);
+            for v in d_1k.iter() {
+                l.add_item(*v);
+            }
+            l
+        })
+    });
+    // linked7 grows its Vec a few times on the way, or once with reserve().
+    c.bench_function("linked7_push_back_1k", |b| {
+        b.iter(|| {
+            let mut l = linked7::List::new();
+            for v in d_1k.iter() {
+                l.push_back(*v);
+            }
+            l
+        })
+    });
+    c.bench_function("linked7_reserve_push_back_1k", |b| {
+        b.iter(|| {
+            let mut l = linked7::List::new();
+            l.reserve(d_1k.len());
+            for v in d_1k.iter() {
+                l.push_back(*v);
+            }
+            l
+        })
+    });
+    c.bench_function("linked4_concat_copy_10x100", |b| {
+        b.iter(|| {
+            let mut l = linked4::List::new(&d);
+            let other = linked4::List::new(&d);
+            for _ in 0..100 {
+                l.concat_copy(&other)
+            }
+            l
+        })
     });
-    bench.bytes = (d.len() * size_of::<i64>()) as u64;
-    
 }
 
-fn create_from_vec_1k(bench: &mut Bencher) {
-    let data_prev: Vec<i64> = vec![1,2,3,4,5,6,7,8,9,10];
-    let mut d = data_prev.clone();
-    for _ in 1..100 {
-        d.extend(&data_prev);
+/* linked6: the Option-based List against the SentinelList, doing the same
+thing. The difference is the branches for the empty/head/tail cases. */
+fn push_pop_mix<L, PushB, PushF, PopF, PopB>(
+    list: &mut L,
+    push_back: PushB,
+    push_front: PushF,
+    pop_front: PopF,
+    pop_back: PopB,
+) -> i64
+where
+    PushB: Fn(&mut L, i64),
+    PushF: Fn(&mut L, i64),
+    PopF: Fn(&mut L) -> Option<i64>,
+    PopB: Fn(&mut L) -> Option<i64>,
+{
+    let mut sum = 0;
+    // Grows and shrinks back to empty often, which is where the branches are.
+    for i in 0..1000 {
+        push_back(list, i);
+        push_front(list, i);
+        if i % 3 == 0 {
+            sum += pop_front(list).unwrap_or(0);
+            sum += pop_back(list).unwrap_or(0);
+            sum += pop_front(list).unwrap_or(0);
+            sum += pop_back(list).unwrap_or(0);
+        }
     }
+    sum
+}
 
-    bench.iter(|| {
-        List::from_vec(&d)
+fn sentinels(c: &mut Criterion) {
+    c.bench_function("linked6_option_push_pop_1k", |b| {
+        b.iter(|| {
+            let mut l = linked6::List::new();
+            push_pop_mix(
+                &mut l,
+                |l, v| l.push_back(v),
+                |l, v| l.push_front(v),
+                |l| l.pop_front(),
+                |l| l.pop_back(),
+            )
+        })
+    });
+    c.bench_function("linked6_sentinel_push_pop_1k", |b| {
+        b.iter(|| {
+            let mut l = linked6::SentinelList::new();
+            push_pop_mix(
+                &mut l,
+                |l, v| l.push_back(v),
+                |l, v| l.push_front(v),
+                |l| l.pop_front(),
+                |l| l.pop_back(),
+            )
+        })
+    });
+    c.bench_function("linked6_option_queue_1k", |b| {
+        b.iter(|| {
+            let mut l = linked6::List::new();
+            let mut sum = 0;
+            for i in 0..1000 {
+                l.push_back(i);
+                sum += l.pop_front().unwrap();
+            }
+            sum
+        })
+    });
+    c.bench_function("linked6_sentinel_queue_1k", |b| {
+        b.iter(|| {
+            let mut l = linked6::SentinelList::new();
+            let mut sum = 0;
+            for i in 0..1000 {
+                l.push_back(i);
+                sum += l.pop_front().unwrap();
+            }
+            sum
+        })
     });
-    bench.bytes = (d.len() * size_of::<i64>()) as u64;
 }
 
+/* Sequential scans: linked5 jumps to a new allocation (and a RefCell borrow)
+for every value, the unrolled list only once every 32 values. */
+fn scans(c: &mut Criterion) {
+    let d: Vec<i64> = (0..10_000).collect();
+    let l = List::from_vec(&d);
+    c.bench_function("linked5_scan_10k", |b| b.iter(|| l.iter().sum::<i64>()));
+    let l: UnrolledList<i64> = d.iter().copied().collect();
+    c.bench_function("unrolled_scan_10k", |b| b.iter(|| l.iter().sum::<i64>()));
+    c.bench_function("linked5_from_vec_10k", |b| b.iter(|| List::from_vec(&d)));
+    c.bench_function("unrolled_from_iter_10k", |b| {
+        b.iter(|| d.iter().copied().collect::<UnrolledList<i64>>())
+    });
+}
+
+/* Cache behaviour: the same sum over 100k values, which is 2.4MB of linked6
+nodes, more than most L2 caches hold. A Vec is read front to back, one
+cache line per eight values, and the prefetcher sees the pattern and loads
+the next lines before they're asked for. A list can only be as good as
+where its nodes are:
+- contiguous: built with nothing else going on, the allocator hands out
+  the nodes one after another, and the walk is almost a Vec scan with
+  bigger elements.
+- fragmented: something else allocated between every two nodes, so they
+  are spread over several times the memory, and every step loads a line
+  that's mostly somebody else's.
+- compacted: the fragmented one after compact().
+linked7 is the same thing inside a Vec: inserting after random handles
+leaves the list order and the slot order unrelated, and compact() puts
+them back in line. */
+const CACHE_LEN: usize = 100_000;
 
-fn create_from_concat_10x100(bench: &mut Bencher) {
-    let d: Vec<i64> = vec![1,2,3,4,5,6,7,8,9,10];
-    let n = 100;
-    bench.iter(|| {
-        let mut l = List::from_vec(&d);
-        for _ in 0..n {
-            l.concat(List::from_vec(&d))
+/* The noise is returned too: it's whatever else the program keeps, and if
+it were freed, its holes would be where compact() got its new nodes from. */
+fn fragmented_linked6(len: usize) -> (linked6::List<i64>, Vec<Vec<u8>>) {
+    let mut seed = 3;
+    let mut noise = Vec::with_capacity(len);
+    let mut list = linked6::List::new();
+    for value in 0..len as i64 {
+        list.push_back(value);
+        noise.push(vec![0u8; 16 + (lcg(&mut seed) % 240) as usize]);
+    }
+    (list, noise)
+}
+
+fn scattered_linked7(len: usize) -> linked7::List<i64> {
+    let mut seed = 3;
+    let mut list = linked7::List::with_capacity(len);
+    let mut handles = vec![list.push_back(0)];
+    for value in 1..len as i64 {
+        let at = handles[lcg(&mut seed) as usize % handles.len()];
+        handles.push(list.insert_after(at, value).unwrap());
+    }
+    list
+}
+
+fn cache(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cache");
+    group.throughput(Throughput::Elements(CACHE_LEN as u64));
+    let values: Vec<i64> = (0..CACHE_LEN as i64).collect();
+    group.bench_function("vec", |b| b.iter(|| values.iter().sum::<i64>()));
+
+    let contiguous: linked6::List<i64> = values.iter().copied().collect();
+    group.bench_function("linked6_contiguous", |b| {
+        b.iter(|| contiguous.iter().sum::<i64>())
+    });
+    let (mut fragmented, _noise) = fragmented_linked6(CACHE_LEN);
+    group.bench_function("linked6_fragmented", |b| {
+        b.iter(|| fragmented.iter().sum::<i64>())
+    });
+    fragmented.compact();
+    group.bench_function("linked6_compacted", |b| {
+        b.iter(|| fragmented.iter().sum::<i64>())
+    });
+
+    let mut scattered = scattered_linked7(CACHE_LEN);
+    group.bench_function("linked7_scattered", |b| {
+        b.iter(|| scattered.iter().sum::<i64>())
+    });
+    scattered.compact();
+    group.bench_function("linked7_compacted", |b| {
+        b.iter(|| scattered.iter().sum::<i64>())
+    });
+    group.finish();
+}
+
+/* What linked5's runtime checks cost, against linked6 doing the same with
+raw pointers. Per step of a walk, linked5 borrows the node's RefCell (check
+the flag, set it, clear it), clones the Rc to the next node (a count up)
+and drops the one to the last (a count down, and a check for zero). linked6
+loads a pointer. Popping is the same again, with a borrow_mut() and Weak
+links to clear. The values and the order are the same for both, and so is
+the number of allocations: one node per value, which is most of what a pop
+costs either way, so the gap shows best in the walks. */
+const CHECKS_SIZES: [usize; 2] = [1_000, 100_000];
+
+fn runtime_checks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("runtime_checks");
+    for &size in CHECKS_SIZES.iter() {
+        let values = shuffled(size);
+        let mut rc = List::from_vec(&values);
+        let mut raw: linked6::List<i64> = values.iter().copied().collect();
+        group.throughput(Throughput::Elements(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("linked5_iter", size), &rc, |b, list| {
+            b.iter(|| list.iter().sum::<i64>())
+        });
+        group.bench_with_input(BenchmarkId::new("linked6_iter", size), &raw, |b, list| {
+            b.iter(|| list.iter().sum::<i64>())
+        });
+        /* iter_mut() hands out the Rc of every node, and the caller borrows
+        it: a clone and a borrow per value, with nothing else around them. */
+        group.bench_function(BenchmarkId::new("linked5_iter_mut", size), |b| {
+            b.iter(|| rc.iter_mut().map(|node| node.borrow().value).sum::<i64>())
+        });
+        group.bench_function(BenchmarkId::new("linked6_iter_mut", size), |b| {
+            b.iter(|| raw.iter_mut().map(|value| *value).sum::<i64>())
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("linked5_pop_first", size),
+            &values,
+            |b, values| {
+                b.iter_batched(
+                    || List::from_vec(values),
+                    |mut list| while list.pop_first().is_some() {},
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("linked6_pop_front", size),
+            &values,
+            |b, values| {
+                b.iter_batched(
+                    || values.iter().copied().collect::<linked6::List<i64>>(),
+                    |mut list| while list.pop_front().is_some() {},
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("linked5_pop_tail", size),
+            &values,
+            |b, values| {
+                b.iter_batched(
+                    || List::from_vec(values),
+                    |mut list| while list.pop_tail().is_some() {},
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("linked6_pop_back", size),
+            &values,
+            |b, values| {
+                b.iter_batched(
+                    || values.iter().copied().collect::<linked6::List<i64>>(),
+                    |mut list| while list.pop_back().is_some() {},
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+/* Joining two lists of the same size, in bytes of values joined per second.
+The copies are O(n): concat_copy() clones every value of the other list into
+new nodes, and so does linked5's concat_ref(). The moves are O(1): linked4's
+concat() and linked5's concat() hang the other chain from the cached tail,
+linked6's append() and splice_after() relink two pointers each way. At 1M
+elements that's the difference between milliseconds and nanoseconds.
+linked5 can also splice in the middle, with split_at() and two concats, but
+finding the middle is a walk. Building and dropping the lists isn't timed. */
+const CONCAT_SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+fn concat(c: &mut Criterion) {
+    let mut group = c.benchmark_group("concat");
+    /* Every sample builds two lists of up to 1M nodes. */
+    group.sample_size(10);
+    for &size in CONCAT_SIZES.iter() {
+        let values = shuffled(size);
+        group.throughput(Throughput::Bytes((size * std::mem::size_of::<i64>()) as u64));
+        let linked4_pair = || (linked4::List::new(&values), linked4::List::new(&values));
+        let linked5_pair = || (List::from_vec(&values), List::from_vec(&values));
+        let linked6_pair = || {
+            (
+                values.iter().copied().collect::<linked6::List<i64>>(),
+                values.iter().copied().collect::<linked6::List<i64>>(),
+            )
+        };
+
+        group.bench_function(BenchmarkId::new("linked4_concat_copy", size), |b| {
+            b.iter_batched(
+                linked4_pair,
+                |(mut list, other)| {
+                    list.concat_copy(&other);
+                    (list, other)
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("linked4_concat", size), |b| {
+            b.iter_batched(
+                linked4_pair,
+                |(mut list, other)| {
+                    list.concat(other);
+                    list
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("linked5_concat_ref", size), |b| {
+            b.iter_batched(
+                linked5_pair,
+                |(mut list, other)| {
+                    list.concat_ref(&other);
+                    (list, other)
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("linked5_concat", size), |b| {
+            b.iter_batched(
+                linked5_pair,
+                |(mut list, other)| {
+                    list.concat(other);
+                    list
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("linked5_splice_middle", size), |b| {
+            b.iter_batched(
+                linked5_pair,
+                |(list, other)| {
+                    let (mut front, back) = list.split_at(size / 2);
+                    front.concat(other);
+                    front.concat(back);
+                    front
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("linked6_append", size), |b| {
+            b.iter_batched(
+                linked6_pair,
+                |(mut list, mut other)| {
+                    list.append(&mut other);
+                    list
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("linked6_splice_after", size), |b| {
+            b.iter_batched(
+                linked6_pair,
+                |(mut list, other)| {
+                    list.cursor_front_mut().splice_after(other);
+                    list
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/* Allocation churn, and what a NodePool saves of it. Both workloads start
+from a list of POOL_LEN values and do POOL_OPS pushes and as many pops. The
+queue pushes at the back and pops at the front, one of each, so it stays at
+POOL_LEN; the bursts push POOL_LEN and pop them all again, like a stack that
+fills and empties. Without a pool every push is a malloc and every pop a
+free. With one, once the list has been as long as it gets, neither is: the
+counts printed before each run are over a whole run of the workload. */
+const POOL_LEN: usize = 1_000;
+const POOL_OPS: usize = 10_000;
+
+type Churn = fn(&mut linked6::List<i64>) -> i64;
+
+fn churn_queue(list: &mut linked6::List<i64>) -> i64 {
+    let mut sum = 0;
+    for value in 0..POOL_OPS as i64 {
+        list.push_back(value);
+        sum += list.pop_front().unwrap_or(0);
+    }
+    sum
+}
+
+fn churn_bursts(list: &mut linked6::List<i64>) -> i64 {
+    let mut sum = 0;
+    for _ in 0..POOL_OPS / POOL_LEN {
+        for value in 0..POOL_LEN as i64 {
+            list.push_back(value);
+        }
+        for _ in 0..POOL_LEN {
+            sum += list.pop_back().unwrap_or(0);
         }
-        l
+    }
+    sum
+}
+
+fn pool(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pool");
+    group.throughput(Throughput::Elements(POOL_OPS as u64));
+    let workloads: [(&str, Churn); 2] = [("queue", churn_queue), ("bursts", churn_bursts)];
+    for &(name, workload) in workloads.iter() {
+        let mut plain: linked6::List<i64> = (0..POOL_LEN as i64).collect();
+        let mut pooled = linked6::List::with_pool(linked6::NodePool::new());
+        pooled.extend(0..POOL_LEN as i64);
+        /* The bursts need POOL_LEN more nodes than the list starts with. One
+        run to fill the pool, so that what's measured is the steady state. */
+        workload(&mut pooled);
+        for (list_name, list) in [("linked6", &mut plain), ("linked6_pooled", &mut pooled)] {
+            let (_, allocs) = measure_allocs(|| workload(list));
+            println!(
+                "pool/{}/{}: {} allocations, {} frees",
+                list_name, name, allocs.allocs, allocs.frees
+            );
+        }
+        group.bench_function(BenchmarkId::new("linked6", name), |b| {
+            b.iter(|| workload(&mut plain))
+        });
+        group.bench_function(BenchmarkId::new("linked6_pooled", name), |b| {
+            b.iter(|| workload(&mut pooled))
+        });
+    }
+    group.finish();
+}
+
+/* A list's whole life, built with push_back() and then dropped, on the
+global allocator and, with the allocator_api feature, in a Bump. In the
+Bump, pushing is an offset bump instead of a malloc, and dropping still
+walks the list but frees nothing. Forgetting the list instead skips the walk
+as well: i64 has nothing to drop, and reset() takes all the nodes back at
+once. */
+const ARENA_LEN: usize = 100_000;
+
+fn arena(c: &mut Criterion) {
+    let mut group = c.benchmark_group("arena");
+    group.throughput(Throughput::Elements(ARENA_LEN as u64));
+    group.bench_function("linked6", |b| {
+        b.iter(|| {
+            let mut list = linked6::List::new();
+            list.extend(0..ARENA_LEN as i64);
+            list.len()
+        })
     });
-    bench.bytes = (n * d.len() * size_of::<i64>()) as u64;
-    
+    /* 24 bytes a node, with room to spare. Nightly only, see arena. */
+    #[cfg(feature = "allocator_api")]
+    {
+        let mut bump = Bump::with_capacity(ARENA_LEN * 32);
+        group.bench_function("linked6_bump", |b| {
+            b.iter(|| {
+                let len = {
+                    let mut list = linked6::List::new_in(&bump);
+                    list.extend(0..ARENA_LEN as i64);
+                    list.len()
+                };
+                bump.reset();
+                len
+            })
+        });
+        group.bench_function("linked6_bump_forget", |b| {
+            b.iter(|| {
+                let mut list = linked6::List::new_in(&bump);
+                list.extend(0..ARENA_LEN as i64);
+                let len = list.len();
+                std::mem::forget(list);
+                bump.reset();
+                len
+            })
+        });
+    }
+    group.finish();
 }
 
+/* Where HybridList stops paying off. Each iteration builds a list of `size`
+values with push_back(), sums it and drops it: the life of a short list. Up
+to N that's no allocation at all, and it should beat linked6 by the cost of
+the mallocs and frees. Past N it spills, and it's linked6 plus the N values
+it had to move, and a bigger struct to move around. */
+const HYBRID_SIZES: [usize; 7] = [2, 4, 8, 12, 16, 32, 64];
 
-benchmark_group!(benches, 
-    create_new, 
-    create_from_vec_10, 
-    create_from_vec_1k,
-    create_from_concat_10x100,
+fn hybrid_build_sum<const N: usize>(size: usize) -> i64 {
+    let mut list: HybridList<i64, N> = HybridList::new();
+    for value in 0..size as i64 {
+        list.push_back(value);
+    }
+    list.iter().sum()
+}
+
+fn hybrid(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hybrid");
+    for &size in HYBRID_SIZES.iter() {
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::new("vec", size), &size, |b, &size| {
+            b.iter(|| (0..size as i64).collect::<Vec<_>>().iter().sum::<i64>())
+        });
+        group.bench_with_input(BenchmarkId::new("linked6", size), &size, |b, &size| {
+            b.iter(|| {
+                let mut list = linked6::List::new();
+                for value in 0..size as i64 {
+                    list.push_back(value);
+                }
+                list.iter().sum::<i64>()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("hybrid4", size), &size, |b, &size| {
+            b.iter(|| hybrid_build_sum::<4>(size))
+        });
+        group.bench_with_input(BenchmarkId::new("hybrid8", size), &size, |b, &size| {
+            b.iter(|| hybrid_build_sum::<8>(size))
+        });
+        group.bench_with_input(BenchmarkId::new("hybrid16", size), &size, |b, &size| {
+            b.iter(|| hybrid_build_sum::<16>(size))
+        });
+    }
+    group.finish();
+}
+
+/* Self-organizing lists: 1000 lookups among 256 values. Skewed keys ask for
+value k about twice as often as k + 1; uniform keys ask for all of them
+equally. The popular values start at the back, and the list is kept between
+iterations, so this measures the list once it has settled. */
+fn lookup_keys(skewed: bool) -> Vec<i64> {
+    let mut seed: u64 = 1;
+    (0..1000)
+        .map(|_| {
+            let r = lcg(&mut seed);
+            if skewed {
+                r.trailing_ones() as i64
+            } else {
+                (r % 256) as i64
+            }
+        })
+        .collect()
+}
+
+fn selforg(c: &mut Criterion) {
+    let strategies = [
+        ("static", Strategy::Static),
+        ("transpose", Strategy::Transpose),
+        ("mtf", Strategy::MoveToFront),
+    ];
+    for (keys_name, skewed) in [("skewed", true), ("uniform", false)] {
+        let keys = lookup_keys(skewed);
+        for (name, strategy) in strategies {
+            let mut l = SelfOrgList::new(strategy);
+            l.extend((0..256).rev());
+            let id = format!("selforg_{}_{}_1k", name, keys_name);
+            c.bench_function(&id, |b| {
+                b.iter(|| keys.iter().filter_map(|k| l.find(k)).sum::<usize>())
+            });
+        }
+    }
+}
+
+/* Random access: 100 gets at scattered positions of a 10k list. The unrolled
+list still walks to the chunk, one chunk at a time; the indexable skip list
+jumps there in O(log n). */
+fn scattered_indices(len: usize) -> Vec<usize> {
+    let mut seed: u64 = 1;
+    (0..100).map(|_| lcg(&mut seed) as usize % len).collect()
+}
+
+fn random_access(c: &mut Criterion) {
+    let l: UnrolledList<i64> = (0..10_000).collect();
+    let indices = scattered_indices(l.len());
+    c.bench_function("unrolled_get_10k", |b| {
+        b.iter(|| indices.iter().filter_map(|i| l.get(*i)).sum::<i64>())
+    });
+    let l: IndexedList<i64> = (0..10_000).collect();
+    c.bench_function("indexed_get_10k", |b| {
+        b.iter(|| indices.iter().filter_map(|i| l.get(*i)).sum::<i64>())
+    });
+}
+
+/* Channel throughput: 4 producers send 10k values each to one receiver. */
+const PRODUCERS: usize = 4;
+const PER_PRODUCER: usize = 10_000;
+
+fn channels(c: &mut Criterion) {
+    c.bench_function("lockfree_mpsc_4x10k", |b| {
+        b.iter(|| {
+            let (tx, rx) = mpsc::channel();
+            let producers: Vec<_> = (0..PRODUCERS)
+                .map(|_| {
+                    let tx = tx.clone();
+                    std::thread::spawn(move || {
+                        for i in 0..PER_PRODUCER {
+                            tx.send(i);
+                        }
+                    })
+                })
+                .collect();
+            drop(tx);
+            let total: usize = rx.iter().sum();
+            for p in producers {
+                p.join().unwrap();
+            }
+            total
+        })
+    });
+    c.bench_function("std_mpsc_4x10k", |b| {
+        b.iter(|| {
+            let (tx, rx) = std::sync::mpsc::channel();
+            let producers: Vec<_> = (0..PRODUCERS)
+                .map(|_| {
+                    let tx = tx.clone();
+                    std::thread::spawn(move || {
+                        for i in 0..PER_PRODUCER {
+                            tx.send(i).unwrap();
+                        }
+                    })
+                })
+                .collect();
+            drop(tx);
+            let total: usize = rx.iter().sum();
+            for p in producers {
+                p.join().unwrap();
+            }
+            total
+        })
+    });
+}
+
+/* Every list through the same generic code, with LinkedListOps. A queue
+that keeps about half its values, so the pops aren't always on a fresh list. */
+fn ops_queue_1k<L: LinkedListOps<Item = i64>>(c: &mut Criterion, name: &str) {
+    c.bench_function(&format!("ops_{}_queue_1k", name), |b| {
+        b.iter(|| {
+            let mut l = L::default();
+            let mut sum = 0;
+            for i in 0..1000 {
+                l.push_back(i);
+                l.push_front(i);
+                sum += l.pop_front().unwrap();
+            }
+            sum + l.len() as i64
+        })
+    });
+}
+
+fn ops_queues(c: &mut Criterion) {
+    ops_queue_1k::<linked4::List<i64>>(c, "linked4");
+    ops_queue_1k::<List>(c, "linked5");
+    ops_queue_1k::<linked6::List<i64>>(c, "linked6");
+    ops_queue_1k::<linked7::List<i64>>(c, "linked7");
+    ops_queue_1k::<linked8::List<i64>>(c, "linked8");
+    ops_queue_1k::<UnrolledList<i64>>(c, "unrolled");
+    ops_queue_1k::<IndexedList<i64>>(c, "indexed");
+}
+
+/* rayon against a plain loop, on the same linked6 list: once with almost no
+work per value, where splitting a list is all overhead, and once with some. */
+#[cfg(feature = "rayon")]
+fn busy_work(v: u64) -> u64 {
+    let mut seed = v;
+    let mut x = 0;
+    for _ in 0..200 {
+        x = lcg(&mut seed);
+    }
+    x >> 27
+}
+
+#[cfg(feature = "rayon")]
+fn parallel(c: &mut Criterion) {
+    use rayon::prelude::*;
+    let list: linked6::List<u64> = (0..100_000).collect();
+    c.bench_function("linked6_iter_sum_100k", |b| {
+        b.iter(|| list.iter().sum::<u64>())
+    });
+    c.bench_function("linked6_par_iter_sum_100k", |b| {
+        b.iter(|| list.par_iter().sum::<u64>())
+    });
+    let list: linked6::List<u64> = (0..10_000).collect();
+    c.bench_function("linked6_iter_busy_10k", |b| {
+        b.iter(|| list.iter().map(|&v| busy_work(v)).sum::<u64>())
+    });
+    c.bench_function("linked6_par_iter_busy_10k", |b| {
+        b.iter(|| list.par_iter().map(|&v| busy_work(v)).sum::<u64>())
+    });
+}
+
+criterion_group!(
+    benches,
+    by_list,
+    construction,
+    sentinels,
+    scans,
+    cache,
+    runtime_checks,
+    concat,
+    pool,
+    arena,
+    hybrid,
+    selforg,
+    random_access,
+    channels,
+    ops_queues,
 );
-benchmark_main!(benches);
\ No newline at end of file
+#[cfg(feature = "rayon")]
+criterion_group!(par_benches, parallel);
+
+#[cfg(feature = "rayon")]
+criterion_main!(benches, par_benches);
+#[cfg(not(feature = "rayon"))]
+criterion_main!(benches);
@@ -0,0 +1,13 @@
+#![no_main]
+/* Both of linked6's lists: the Option<NonNull> one and the sentinel one.
+They can do everything the harness knows, inserts and removes in the
+middle included, which is where most of the pointer rewiring happens. */
+use crappylinkedlists::differential::{run_positional, Op};
+use crappylinkedlists::linked6;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let ops = Op::from_bytes(data);
+    run_positional::<linked6::List<i64>>(&ops);
+    run_positional::<linked6::SentinelList<i64>>(&ops);
+});
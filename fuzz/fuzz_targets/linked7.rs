@@ -0,0 +1,11 @@
+#![no_main]
+/* linked7 keeps its nodes in a Vec with a free list, so there's no unsafe,
+but plenty of indexes to get wrong. No positional inserts there, only what a
+deque does: the ops it can't do are skipped. */
+use crappylinkedlists::differential::{run_deque, Op};
+use crappylinkedlists::linked7;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    run_deque::<linked7::List<i64>>(&Op::from_bytes(data));
+});
@@ -0,0 +1,200 @@
+#![allow(dead_code)]
+/*
+Counting allocations
+===========================================================================
+
+Half of what these lists promise is about the allocator. concat() on linked5
+moves the other list's nodes over, so it shouldn't allocate anything.
+from_vec() should make one Rc per value. split_off() and append() on linked6
+should only move pointers around. None of that shows in the values, and a
+benchmark only says it got slower, not why.
+
+CountingAlloc wraps the system allocator and counts, per thread, every
+alloc, free and realloc, the bytes asked for, and how many bytes were live at
+the worst point. measure_allocs() runs a closure and returns what it did:
+
+    let (list, allocs) = measure_allocs(|| linked6::List::from_slice(&[1, 2, 3]));
+    assert_eq!(allocs.allocs, 3);
+
+Only allocations made on the calling thread count. The tests run in parallel,
+and anything global would pick up the other tests' allocations. A list that
+hands its work to other threads won't show it here.
+
+It only counts when it's the program's #[global_allocator]. The library's
+own tests install it (see the bottom of this file), and so does
+benches/benchmark.rs. A program that uses the lists doesn't get it unless it
+asks for it, and measure_allocs() then returns all zeros: installed() says
+which one it is.
+
+The lists check themselves after every change in debug builds (see
+invariants), and those checks allocate. They run inside uncounted(), so the
+numbers are the same in debug and release builds.
+*/
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/* What happened on this thread, from when it started. Also what
+measure_allocs() returns, where everything is for the closure only. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Allocs {
+    pub allocs: usize,
+    pub frees: usize,
+    pub reallocs: usize,
+    /* Asked for, in allocs and reallocs that grew. Frees don't take it down. */
+    pub bytes: usize,
+    /* Given back, in frees and reallocs that shrank. */
+    pub freed: usize,
+    /* The most bytes that were live at once, above what was live before. */
+    pub peak: usize,
+}
+
+impl Allocs {
+    /* Allocated and not freed yet. Negative if more was freed than taken,
+    like in a closure that drops a list built before it. */
+    pub fn net(&self) -> isize {
+        self.allocs as isize - self.frees as isize
+    }
+
+    /* The same, in bytes: what the closure left allocated. */
+    pub fn net_bytes(&self) -> isize {
+        self.bytes as isize - self.freed as isize
+    }
+}
+
+/* Cells in a const thread_local don't allocate on first use, which matters:
+the first use is inside the allocator. */
+thread_local! {
+    static COUNTS: Cell<Allocs> = const {
+        Cell::new(Allocs {
+            allocs: 0,
+            frees: 0,
+            reallocs: 0,
+            bytes: 0,
+            freed: 0,
+            peak: 0,
+        })
+    };
+    static LIVE: Cell<usize> = const { Cell::new(0) };
+}
+
+/* try_with, because a thread that's being torn down still frees things
+after its thread locals are gone. Those just don't count. */
+fn count(update: impl FnOnce(&mut Allocs, &mut usize)) {
+    let _ = COUNTS.try_with(|counts| {
+        let _ = LIVE.try_with(|live| {
+            let mut c = counts.get();
+            let mut l = live.get();
+            update(&mut c, &mut l);
+            c.peak = c.peak.max(l);
+            counts.set(c);
+            live.set(l);
+        });
+    });
+}
+
+pub struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            count(|counts, live| {
+                counts.allocs += 1;
+                counts.bytes += layout.size();
+                *live += layout.size();
+            });
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            count(|counts, live| {
+                counts.allocs += 1;
+                counts.bytes += layout.size();
+                *live += layout.size();
+            });
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        count(|counts, live| {
+            counts.frees += 1;
+            counts.freed += layout.size();
+            /* Saturating: it may have come from another thread. */
+            *live = live.saturating_sub(layout.size());
+        });
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            count(|counts, live| {
+                counts.reallocs += 1;
+                counts.bytes += new_size.saturating_sub(layout.size());
+                counts.freed += layout.size().saturating_sub(new_size);
+                *live = live.saturating_sub(layout.size()) + new_size;
+            });
+        }
+        new
+    }
+}
+
+/* Runs `f` and returns what it allocated, on this thread. Calls can be
+nested: the outer one counts everything the inner one did too. */
+pub fn measure_allocs<R>(f: impl FnOnce() -> R) -> (R, Allocs) {
+    let before = COUNTS.with(Cell::get);
+    let live = LIVE.with(Cell::get);
+    /* The peak starts again from here, and the old one is put back after. */
+    COUNTS.with(|counts| {
+        counts.set(Allocs {
+            peak: live,
+            ..before
+        })
+    });
+    let result = f();
+    let after = COUNTS.with(Cell::get);
+    COUNTS.with(|counts| {
+        counts.set(Allocs {
+            peak: before.peak.max(after.peak),
+            ..after
+        })
+    });
+    let allocs = Allocs {
+        allocs: after.allocs - before.allocs,
+        frees: after.frees - before.frees,
+        reallocs: after.reallocs - before.reallocs,
+        bytes: after.bytes - before.bytes,
+        freed: after.freed - before.freed,
+        peak: after.peak - live,
+    };
+    (result, allocs)
+}
+
+/* Runs `f` as if it never happened, for the counts. What it allocates has to
+be freed before it returns. */
+pub fn uncounted<R>(f: impl FnOnce() -> R) -> R {
+    let counts = COUNTS.with(Cell::get);
+    let live = LIVE.with(Cell::get);
+    let result = f();
+    COUNTS.with(|c| c.set(counts));
+    LIVE.with(|l| l.set(live));
+    result
+}
+
+/* Whether CountingAlloc is the #[global_allocator], so that the numbers
+from measure_allocs() mean anything. */
+pub fn installed() -> bool {
+    let (_, allocs) = measure_allocs(|| drop(std::hint::black_box(Box::new(0u8))));
+    allocs.allocs == 1
+}
+
+#[cfg(test)]
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+#[cfg(test)]
+mod test;
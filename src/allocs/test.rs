@@ -0,0 +1,163 @@
+use super::*;
+use crate::ops::LinkedListOps;
+use crate::{linked4, linked5, linked6, linked7, unrolled};
+
+const LEN: usize = 100;
+
+fn values() -> Vec<i64> {
+    (0..LEN as i64).collect()
+}
+
+#[test]
+fn test_installed() {
+    assert!(installed());
+    let (_, allocs) = measure_allocs(|| ());
+    assert_eq!(allocs, Allocs::default());
+}
+
+#[test]
+fn test_counts() {
+    let (boxed, allocs) = measure_allocs(|| Box::new([0u64; 4]));
+    assert_eq!(allocs.allocs, 1);
+    assert_eq!(allocs.frees, 0);
+    assert_eq!(allocs.bytes, 32);
+    assert_eq!(allocs.peak, 32);
+    let (_, allocs) = measure_allocs(|| drop(boxed));
+    assert_eq!(allocs.frees, 1);
+    assert_eq!(allocs.net(), -1);
+    assert_eq!(allocs.net_bytes(), -32);
+    assert_eq!(allocs.peak, 0);
+
+    /* Grows to 64 bytes, and the Vec is gone by the end: peak isn't. */
+    let (_, allocs) = measure_allocs(|| {
+        let mut v: Vec<u8> = Vec::with_capacity(16);
+        v.extend(std::iter::repeat_n(1, 64));
+    });
+    assert_eq!((allocs.allocs, allocs.reallocs, allocs.frees), (1, 1, 1));
+    assert_eq!(allocs.bytes, 64);
+    assert_eq!(allocs.net_bytes(), 0);
+    assert_eq!(allocs.peak, 64);
+}
+
+#[test]
+fn test_nested() {
+    let (inner, outer) = measure_allocs(|| {
+        let first = Box::new(1u64);
+        let (second, inner) = measure_allocs(|| Box::new(2u64));
+        drop((first, second));
+        inner
+    });
+    assert_eq!(inner.allocs, 1);
+    assert_eq!(inner.peak, 8);
+    assert_eq!((outer.allocs, outer.frees), (2, 2));
+    assert_eq!(outer.peak, 16);
+}
+
+/* Other threads' allocations aren't ours. */
+#[test]
+fn test_other_threads() {
+    let (_, allocs) = measure_allocs(|| {
+        let handle = std::thread::spawn(|| linked6::List::from_slice(&values()).len());
+        handle.join().unwrap()
+    });
+    assert!(allocs.allocs < LEN);
+}
+
+/* concat() moves the nodes of the other list, it doesn't copy them. */
+#[test]
+fn test_linked5_concat() {
+    let mut list = linked5::List::from_vec(&values());
+    let other = linked5::List::from_vec(&values());
+    let (_, allocs) = measure_allocs(|| list.concat(other));
+    assert_eq!(allocs, Allocs::default());
+    assert_eq!(list.len(), 2 * LEN);
+
+    /* concat_ref() copies, one node per value plus the Vec in between. */
+    let other = linked5::List::from_vec(&values());
+    let (_, allocs) = measure_allocs(|| list.concat_ref(&other));
+    assert!(allocs.allocs > LEN);
+}
+
+/* One Rc per value, and nothing else. from_vec_two_passes() also has the
+Vec of nodes it keeps while it links them. */
+#[test]
+fn test_linked5_from_vec() {
+    let values = values();
+    let (list, allocs) = measure_allocs(|| linked5::List::from_vec(&values));
+    assert_eq!(allocs.allocs, LEN);
+    assert_eq!(allocs.frees, 0);
+    let (_, allocs) = measure_allocs(|| linked5::List::from_vec_two_passes(&values));
+    assert_eq!(allocs.allocs, LEN + 1);
+    assert_eq!(allocs.frees, 1);
+    let (_, allocs) = measure_allocs(|| drop(list));
+    assert_eq!(allocs.frees, LEN);
+    let (_, allocs) = measure_allocs(|| linked5::List::from_vec(&[]));
+    assert_eq!(allocs.allocs, 0);
+}
+
+#[test]
+fn test_one_per_node() {
+    let values = values();
+    let (_, allocs) = measure_allocs(|| linked4::List::new(&values));
+    assert_eq!(allocs.allocs, LEN);
+    let (_, allocs) = measure_allocs(|| linked6::List::from_slice(&values));
+    assert_eq!(allocs.allocs, LEN);
+    let (_, allocs) = measure_allocs(|| linked6::SentinelList::from_slice(&values));
+    assert_eq!(allocs.allocs, LEN + 1);
+    /* Chunks of 32, not one per value. */
+    let (_, allocs) = measure_allocs(|| unrolled::UnrolledList::<i64>::from_slice(&values));
+    assert!(allocs.allocs <= 2 * (LEN / 32 + 1), "{:?}", allocs);
+}
+
+/* The operations that only relink. */
+#[test]
+fn test_no_allocations() {
+    let mut list = linked4::List::new(&values());
+    let (_, allocs) = measure_allocs(|| {
+        list.reverse();
+        list.sort();
+        list.rotate_left(7);
+        list.concat(linked4::List::default());
+    });
+    assert_eq!(allocs, Allocs::default());
+    let other = linked4::List::new(&values());
+    let (_, allocs) = measure_allocs(|| list.concat(other));
+    assert_eq!(allocs, Allocs::default());
+    assert_eq!(list.len(), 2 * LEN);
+
+    let mut list = linked6::List::from_slice(&values());
+    let (_, allocs) = measure_allocs(|| {
+        let mut back = list.split_off(LEN / 3);
+        list.reverse();
+        list.append(&mut back);
+    });
+    assert_eq!(allocs, Allocs::default());
+
+    let mut list = linked5::List::from_vec(&values());
+    let (_, allocs) = measure_allocs(|| list.sort());
+    assert_eq!(allocs, Allocs::default());
+}
+
+/* linked7 keeps the slot a pop left free, so pushing again doesn't ask for
+memory. */
+#[test]
+fn test_linked7_reuses_slots() {
+    let mut list = linked7::List::from_slice(&values());
+    let (_, allocs) = measure_allocs(|| {
+        for value in 0..LEN as i64 {
+            list.pop_front();
+            list.push_back(value);
+        }
+    });
+    assert_eq!(allocs.allocs + allocs.reallocs, 0);
+
+    /* And reserve() makes room for many at once. */
+    let mut list = linked7::List::new();
+    let (_, allocs) = measure_allocs(|| {
+        list.reserve(LEN);
+        for value in 0..LEN as i64 {
+            list.push_back(value);
+        }
+    });
+    assert_eq!(allocs.allocs + allocs.reallocs, 1);
+}
@@ -0,0 +1,188 @@
+#![allow(dead_code)]
+/*
+Lists in an arena
+===========================================================================
+
+Every linked6 node is its own allocation, and every one of them is freed on
+its own when the list drops: a million nodes, a million calls to free. An
+arena turns that around. It hands out memory from one big chunk by bumping
+an offset, frees nothing one by one, and gives the whole chunk back at once.
+A parser that builds a thousand small lists for one file, and throws them
+all away when the file is done, doesn't need to free them one at a time.
+
+std has a trait for this, Allocator, and its collections take one as a
+parameter: Vec<T, A>, Box<T, A>, LinkedList<T, A>. It's still unstable, so
+all of this is behind a feature, `--features allocator_api`, which needs
+nightly. With it, linked6's List takes one too:
+
+    List<T, A: Allocator = Global>
+
+and any std allocator works, including this module's Bump, which can then
+go into Vec::new_in() as well. Without it, List<T> is all there is: the
+same list with Global inside, where Global is a small copy of std's that
+calls std::alloc and nothing else can implement. Global is zero sized, so
+either way a list that doesn't ask for an allocator pays nothing for it.
+
+    let bump = Bump::with_capacity(1 << 20);
+    let mut list = linked6::List::new_in(&bump);
+    list.push_back(1);
+
+Nothing says two allocators of the same type can free each other's memory,
+and two lists on two different Bumps are both List<T, &Bump>. So the methods
+that move nodes from one list into another, append() and splice_after(),
+are only there for Global, which can free anything. split_off() is fine: the
+new list gets a clone of the allocator, and clones of an allocator must be
+able to free what the others allocated.
+
+Dropping a list in a Bump still walks it, to drop the values, and the frees
+are no-ops. For values that don't need dropping, mem::forget(list) skips the
+walk too, and reset() or dropping the Bump frees it all in O(1). Forgetting
+a list of Strings that way leaks their own heap memory, of course.
+
+On my machine, building and dropping a list of 100k values (the "arena"
+benchmarks) takes a quarter of the time in a Bump, and a tenth with forget().
+
+linked4 takes one too, List<T, A> and DoublyList<T, A>, and so does its
+node type: LinkedList1<T, A>, which is LinkedList1In on the inside, like
+linked6's ListIn. On stable Box has no A, so the node carries a PhantomData
+for it there, and can only be built with new() and new_box(): its public
+fields no longer make a `LinkedList1 { value, next }`. The Boxes are std's,
+and a Box keeps its allocator next to the pointer: with a &Bump every `next`
+is two words instead of one. The upside is that every node knows where it
+goes back to, so concat() can take a list from another arena.
+*/
+#[cfg(feature = "allocator_api")]
+use std::alloc::handle_alloc_error;
+use std::alloc::{alloc, dealloc, Layout};
+#[cfg(feature = "allocator_api")]
+use std::cell::Cell;
+use std::ptr::NonNull;
+
+#[cfg(feature = "allocator_api")]
+pub use std::alloc::{AllocError, Allocator, Global};
+
+#[cfg(not(feature = "allocator_api"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllocError;
+
+/* std's Allocator, minus the methods with default implementations.
+Safety is the same deal too: allocate() must return memory that fits the
+layout and stays valid until it's deallocated, and the caller of
+deallocate() must pass memory that this allocator, or a clone of it,
+allocated with that same layout. Sealed: outside this crate it can be
+named, but not implemented, so Global is the only one there is. */
+#[cfg(not(feature = "allocator_api"))]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe trait Allocator: sealed::Sealed {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError>;
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+}
+
+#[cfg(not(feature = "allocator_api"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Global;
+
+/* What Box uses. Zero sized allocations are a dangling pointer, as in std:
+the allocator can't be asked for them. */
+#[cfg(not(feature = "allocator_api"))]
+unsafe impl Allocator for Global {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let ptr = if layout.size() == 0 {
+            NonNull::new(layout.align() as *mut u8)
+        } else {
+            NonNull::new(unsafe { alloc(layout) })
+        };
+        let ptr = ptr.ok_or(AllocError)?;
+        Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() != 0 {
+            dealloc(ptr.as_ptr(), layout);
+        }
+    }
+}
+
+#[cfg(not(feature = "allocator_api"))]
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::Global {}
+}
+
+/* The chunk's own alignment. Anything that needs more gets padded. */
+#[cfg(feature = "allocator_api")]
+const CHUNK_ALIGN: usize = 16;
+
+/* The simplest arena there is: one chunk, allocated up front, never grown.
+Running out is an AllocError, which a list turns into handle_alloc_error(),
+so size it for the worst case. The Cell makes it !Sync, so the lists that
+borrow it stay on one thread. */
+#[cfg(feature = "allocator_api")]
+pub struct Bump {
+    chunk: NonNull<u8>,
+    layout: Layout,
+    used: Cell<usize>,
+}
+
+/* It owns the chunk, so it can take it along to another thread. */
+#[cfg(feature = "allocator_api")]
+unsafe impl Send for Bump {}
+
+#[cfg(feature = "allocator_api")]
+impl Bump {
+    pub fn with_capacity(capacity: usize) -> Self {
+        let layout = Layout::from_size_align(capacity.max(1), CHUNK_ALIGN).unwrap();
+        let chunk =
+            NonNull::new(unsafe { alloc(layout) }).unwrap_or_else(|| handle_alloc_error(layout));
+        Bump {
+            chunk,
+            layout,
+            used: Cell::new(0),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.layout.size()
+    }
+
+    /* Bytes handed out so far, padding included. Frees don't lower it. */
+    pub fn used(&self) -> usize {
+        self.used.get()
+    }
+
+    /* Frees everything at once. It takes &mut, so no list can still be
+    borrowing the arena: they've all been dropped, or forgotten. */
+    pub fn reset(&mut self) {
+        self.used.set(0);
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+unsafe impl Allocator for Bump {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let used = self.used.get();
+        let next = self.chunk.as_ptr() as usize + used;
+        let padding = next.wrapping_neg() & (layout.align() - 1);
+        let start = used + padding;
+        let end = start.checked_add(layout.size()).ok_or(AllocError)?;
+        if end > self.capacity() {
+            return Err(AllocError);
+        }
+        self.used.set(end);
+        let ptr = unsafe { NonNull::new_unchecked(self.chunk.as_ptr().add(start)) };
+        Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+    }
+
+    /* Nothing: it all goes at once, in reset() or drop. */
+    unsafe fn deallocate(&self, _ptr: NonNull<u8>, _layout: Layout) {}
+}
+
+#[cfg(feature = "allocator_api")]
+impl Drop for Bump {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.chunk.as_ptr(), self.layout) };
+    }
+}
+
+#[cfg(all(test, feature = "allocator_api"))]
+mod test;
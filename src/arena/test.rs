@@ -0,0 +1,137 @@
+use super::*;
+use crate::allocs::measure_allocs;
+use crate::footprint::HeapSize;
+use crate::{linked4, linked6};
+use std::mem;
+use std::rc::Rc;
+
+#[test]
+fn test_bump() {
+    let bump = Bump::with_capacity(64);
+    let a = bump.allocate(Layout::new::<u8>()).unwrap();
+    let b = bump.allocate(Layout::new::<u64>()).unwrap();
+    /* Padded up to the next multiple of 8. */
+    assert_eq!(b.cast::<u8>().as_ptr() as usize % 8, 0);
+    assert_eq!(bump.used(), 16);
+    assert!(a.cast::<u8>().as_ptr() < b.cast::<u8>().as_ptr());
+    unsafe { bump.deallocate(b.cast(), Layout::new::<u64>()) };
+    assert_eq!(bump.used(), 16);
+    assert_eq!(bump.allocate(Layout::new::<[u8; 64]>()), Err(AllocError));
+    assert!(bump.allocate(Layout::new::<[u8; 48]>()).is_ok());
+    assert_eq!(bump.used(), bump.capacity());
+}
+
+/* One allocation for the chunk, none for the nodes, one free for all of
+them. */
+#[test]
+fn test_list_in_bump() {
+    let (_, allocs) = measure_allocs(|| {
+        let bump = Bump::with_capacity(1 << 16);
+        let mut list = linked6::List::new_in(&bump);
+        list.extend(0..100i64);
+        assert_eq!(bump.used(), list.heap_size());
+        for _ in 0..50 {
+            list.pop_front();
+        }
+        list.push_front(-1);
+        assert_eq!(bump.used(), 101 * list.heap_size() / 51);
+        let mut rest = list.split_off(1);
+        rest.reverse();
+        rest.compact();
+        assert_eq!(rest.len(), 50);
+        assert_eq!(rest.front(), Some(&99));
+        assert_eq!((list.len(), list.front()), (1, Some(&-1)));
+    });
+    assert_eq!((allocs.allocs, allocs.frees), (1, 1));
+}
+
+#[test]
+fn test_values_still_dropped() {
+    let counter = Rc::new(());
+    let bump = Bump::with_capacity(1 << 12);
+    let mut list = linked6::List::new_in(&bump);
+    for _ in 0..10 {
+        list.push_back(Rc::clone(&counter));
+    }
+    let mut cursor = list.cursor_front_mut();
+    cursor.move_next();
+    cursor.remove_current();
+    cursor.insert_after(Rc::clone(&counter));
+    let copy = list.clone();
+    assert_eq!(Rc::strong_count(&counter), 21);
+    drop((list, copy));
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
+
+/* linked4's Boxes each keep their own &Bump, two words per `next`. That's
+also why concat() takes a list from another arena: each node goes back to
+its own. */
+#[test]
+fn test_linked4_in_bump() {
+    let (_, allocs) = measure_allocs(|| {
+        let bump = Bump::with_capacity(1 << 12);
+        let other_bump = Bump::with_capacity(1 << 12);
+        let mut list = linked4::List::new_in(&bump);
+        list.append_slice(&[3, 1, 2]);
+        list.push_front(0i64);
+        assert_eq!(bump.used(), list.heap_size());
+        assert_eq!(list.heap_size(), 4 * 3 * mem::size_of::<usize>());
+        let mut other = linked4::List::new_in(&other_bump);
+        other.add_item(9);
+        list.concat(other);
+        list.sort();
+        let rest = list.split_off(2);
+        assert!(list.iter().copied().eq([0, 1]));
+        assert!(rest.iter().copied().eq([2, 3, 9]));
+        let mut doubly = linked4::DoublyList::new_in(&bump);
+        doubly.push_back(1);
+        doubly.push_front(0);
+        assert!(doubly.iter().rev().copied().eq([1, 0]));
+    });
+    /* The two chunks, and the Vec append_slice() clones the slice into. */
+    assert_eq!((allocs.allocs, allocs.frees), (3, 3));
+}
+
+#[test]
+fn test_linked4_values_still_dropped() {
+    let counter = Rc::new(());
+    let bump = Bump::with_capacity(1 << 12);
+    let mut list = linked4::List::new_in(&bump);
+    for _ in 0..10 {
+        list.add_item(Rc::clone(&counter));
+    }
+    let mut cursor = list.cursor_front_mut();
+    cursor.move_next();
+    cursor.remove_current();
+    cursor.insert_after(Rc::clone(&counter));
+    let copy = list.clone();
+    assert_eq!(Rc::strong_count(&counter), 21);
+    drop((list, copy));
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
+
+/* The O(1) way out, for values that don't need dropping. */
+#[test]
+fn test_forget_and_reset() {
+    let mut bump = Bump::with_capacity(1 << 16);
+    let mut list = linked6::List::new_in(&bump);
+    list.extend(0..1000i32);
+    let (_, allocs) = measure_allocs(|| mem::forget(list));
+    assert_eq!(allocs.frees, 0);
+    bump.reset();
+    assert_eq!(bump.used(), 0);
+    let mut list = linked6::List::new_in(&bump);
+    list.push_back(1);
+    assert_eq!(list.to_vec(), vec![1]);
+}
+
+/* With the real trait, std's collections can use the same arena. */
+#[test]
+fn test_std_collections_in_bump() {
+    let bump = Bump::with_capacity(1 << 12);
+    let mut values = Vec::with_capacity_in(4, &bump);
+    values.extend([1, 2, 3]);
+    let boxed = Box::new_in(7, &bump);
+    assert_eq!(values.iter().sum::<i32>() + *boxed, 13);
+    assert!(bump.used() >= 16);
+}
@@ -2,16 +2,12 @@
 
 fn linked1_probes() {
     use crappylinkedlists::linked1::*;
-    size_l8();
-    size_l8i();
-    size_l2i();
-    size_stupidthing();
-    size_l64();
-    // size_l65();
-    size_a8();
-    size_op8();
-    size_ob8();
-    size_oi64();
+    print!("{}", SizeTable(&size_report()));
+}
+
+fn footprint_probes() {
+    use crappylinkedlists::footprint::*;
+    print!("{}", FootprintTable(&footprint_report(1000)));
 }
 
 fn linked3_probes() {
@@ -20,41 +16,73 @@ fn linked3_probes() {
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("profile") {
+        return profile(args.get(1).map(String::as_str), args.get(2));
+    }
     linked1_probes();
+    footprint_probes();
     linked3_probes();
-    crappylinkedlists::linked4::List::new(&[3,6,8,9]);
-    // profile_linked4_concat_huge();
+    crappylinkedlists::linked4::I64List::new(&[3,6,8,9]);
+}
+
+/* `profile <name> [scale]` runs one of the workloads in profiling, under
+cpuprofiler if we were built with --features profiling. Without a name, or
+with one that doesn't exist, it lists them. */
+fn profile(name: Option<&str>, scale: Option<&String>) {
+    use crappylinkedlists::profiling::*;
+    let profile = match name.and_then(find) {
+        Some(profile) => profile,
+        None => {
+            if let Some(name) = name {
+                eprintln!("There's no profile called {}.", name);
+            }
+            eprintln!("Usage: profile <name> [scale], where name is one of:");
+            for profile in PROFILES {
+                eprintln!("    {:<18} {}", profile.name, profile.about);
+            }
+            std::process::exit(if name.is_some() { 1 } else { 0 });
+        }
+    };
+    let scale = match scale.map(|scale| scale.parse()) {
+        None => DEFAULT_SCALE,
+        Some(Ok(scale)) => scale,
+        Some(Err(e)) => {
+            eprintln!("The scale has to be a number: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let start = std::time::Instant::now();
+    let checksum = run_profiled(profile, scale);
+    println!(
+        "{} at scale {}: checksum {}, {:?}",
+        profile.name,
+        scale,
+        checksum,
+        start.elapsed()
+    );
 }
 
-/*
-fn profile_linked4_concat_huge() {
+#[cfg(feature = "profiling")]
+fn run_profiled(profile: &crappylinkedlists::profiling::Profile, scale: usize) -> i64 {
     /*
     This requires:
     sudo apt install google-perftools libgoogle-perftools4 libgoogle-perftools-dev
     */
     use cpuprofiler::PROFILER;
 
+    let path = format!("{}.profile", profile.name);
     // Unlock the mutex and start the profiler
-    PROFILER.lock().unwrap().start("./my-prof.profile").expect("Couldn't start");
-
-    use crappylinkedlists::linked4::List;
-    // Testing performance here...
-    let data_prev = vec![3,8,1,2,9,5,12,6,3,1,0,7,6,5,4,3,1,6,8,9,5,3,2,1,5,7,8,4,6];
-    let mut data = data_prev.clone();
-    for _ in 1..100 {
-        data.extend(&data_prev);
-    }
-    let mut test = data.clone();
-    let mut l = List::new(&data);
-    for _ in 1..=100 {
-        // Every time this is called, it has to find the tail. This is the most expensive operation.
-        l.concat_copy(&List::new(&data));
-        test.extend(&data);
-    }
-    let lvec = l.to_vec();
-    assert_eq!(test, lvec);
-
+    PROFILER.lock().unwrap().start(path.clone()).expect("Couldn't start");
+    let checksum = (profile.run)(scale);
     // Unwrap the mutex and stop the profiler
     PROFILER.lock().unwrap().stop().expect("Couldn't stop");
+    println!("Profile written to {}", path);
+    checksum
+}
 
-}*/
+#[cfg(not(feature = "profiling"))]
+fn run_profiled(profile: &crappylinkedlists::profiling::Profile, scale: usize) -> i64 {
+    eprintln!("Built without --features profiling, so this only times it.");
+    (profile.run)(scale)
+}
@@ -0,0 +1,311 @@
+#![allow(dead_code)]
+/*
+Branded lifetimes: linked5 without the runtime checks
+===========================================================================
+
+linked5 settled for Rc<RefCell<Node>>, because Rc lets a node have two
+owners (the previous node and the next one) and RefCell lets us mutate it
+anyway. Every borrow() and borrow_mut() counts borrows at runtime, and
+panics if we get it wrong. linked3 tried Cell, which is checked at compile
+time, but only works with Copy values.
+
+Is there a way to get RefCell's "mutate through a shared pointer" with the
+check done at compile time? There is, and it's a neat trick from the
+GhostCell paper (Yanovski, Dang, Jung and Dreyer, 2021).
+
+The idea is to split the permission from the data. Each node is a
+GhostCell, which holds a value but can't be read on its own. To read it, we
+have to show a &GhostToken; to write it, a &mut GhostToken. There's only one
+token, so the borrow checker does the work RefCell did at runtime: while
+someone holds &mut token, nobody else can read or write any cell.
+
+The catch is making sure that a token only opens its own cells, and not the
+cells of another list with another token. That's what the 'id lifetime is
+for. GhostToken::new() hands out a token whose 'id is brand new, unlike any
+other, and only cells with the same 'id can be opened with it. The lifetime
+is invariant (see InvariantLifetime below), so the compiler can't shrink or
+stretch it to make two different brands match: mixing them is a compile
+error, not a panic.
+
+The price is ergonomics. Everything happens inside the closure given to
+GhostToken::new(), and every method takes the token as an extra argument.
+And mutating one node locks all of them, because there's only one token:
+we can't hold two &mut to two different nodes at once, which RefCell would
+allow. Ownership is still done with Rc and Weak, exactly like linked5, so
+the reference counts are still there. What's gone is the borrow flag and
+every check on it.
+*/
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::rc::{Rc, Weak};
+
+/* A lifetime that can't be turned into any other lifetime: fn(&'id) makes it
+contravariant, -> &'id makes it covariant, and both together, invariant. */
+type InvariantLifetime<'id> = PhantomData<fn(&'id ()) -> &'id ()>;
+
+pub struct GhostToken<'id> {
+    _brand: InvariantLifetime<'id>,
+}
+
+impl<'id> GhostToken<'id> {
+    /* The `for<'new>` is what makes the brand unique: the closure has to
+    work for any lifetime at all, so it can't assume it's the same as any
+    other one. The name is the one from the paper, even if clippy expects
+    new() to return the token itself. */
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<R>(f: impl for<'new> FnOnce(GhostToken<'new>) -> R) -> R {
+        f(GhostToken {
+            _brand: PhantomData,
+        })
+    }
+}
+
+pub struct GhostCell<'id, T> {
+    _brand: InvariantLifetime<'id>,
+    value: UnsafeCell<T>,
+}
+
+impl<'id, T> GhostCell<'id, T> {
+    pub fn new(value: T) -> Self {
+        GhostCell {
+            _brand: PhantomData,
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    /* Having the cell and the token shared means nobody can have the token
+    mutably, so nobody is writing to any cell of this brand. */
+    pub fn borrow<'a>(&'a self, _token: &'a GhostToken<'id>) -> &'a T {
+        unsafe { &*self.value.get() }
+    }
+
+    /* And holding the token mutably means nobody else can use it, to read
+    or to write, for as long as the returned borrow lives. */
+    pub fn borrow_mut<'a>(&'a self, _token: &'a mut GhostToken<'id>) -> &'a mut T {
+        unsafe { &mut *self.value.get() }
+    }
+
+    /* With the cell itself borrowed mutably, no token is needed at all. */
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+type NodeRef<'id, T> = Rc<GhostCell<'id, Node<'id, T>>>;
+
+struct Node<'id, T> {
+    value: T,
+    prev: Option<Weak<GhostCell<'id, Node<'id, T>>>>,
+    next: Option<NodeRef<'id, T>>,
+}
+
+/* Same shape as linked5: next pointers own, prev pointers and the tail are
+weak. Every node has exactly one Rc pointing to it, from the node before or
+from head, which is what lets the pops unwrap it. */
+pub struct List<'id, T> {
+    head: Option<NodeRef<'id, T>>,
+    tail: Option<Weak<GhostCell<'id, Node<'id, T>>>>,
+    len: usize,
+}
+
+impl<'id, T> Default for List<'id, T> {
+    fn default() -> Self {
+        List {
+            head: None,
+            tail: None,
+            len: 0,
+        }
+    }
+}
+
+/* Only the list ever holds an Rc to a node, and pops unlink it first. */
+fn unwrap_node<'id, T>(node: NodeRef<'id, T>) -> Node<'id, T> {
+    match Rc::try_unwrap(node) {
+        Ok(cell) => cell.into_inner(),
+        Err(_) => unreachable!("a node with more than one owner"),
+    }
+}
+
+impl<'id, T> List<'id, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_back(&mut self, value: T, token: &mut GhostToken<'id>) {
+        let node = Rc::new(GhostCell::new(Node {
+            value,
+            prev: self.tail.clone(),
+            next: None,
+        }));
+        let weak = Rc::downgrade(&node);
+        match self.tail.as_ref().and_then(Weak::upgrade) {
+            Some(tail) => tail.borrow_mut(token).next = Some(node),
+            None => self.head = Some(node),
+        }
+        self.tail = Some(weak);
+        self.len += 1;
+    }
+
+    pub fn push_front(&mut self, value: T, token: &mut GhostToken<'id>) {
+        let next = self.head.take();
+        let node = Rc::new(GhostCell::new(Node {
+            value,
+            prev: None,
+            next: None,
+        }));
+        /* The obvious way, matching on node.borrow(token).next and writing to
+        it with next.borrow_mut(token), doesn't compile: that's reading one
+        cell while writing another with the same token. So the old head is
+        fixed up first, and only then moved into the new node. */
+        match &next {
+            Some(next) => next.borrow_mut(token).prev = Some(Rc::downgrade(&node)),
+            None => self.tail = Some(Rc::downgrade(&node)),
+        }
+        node.borrow_mut(token).next = next;
+        self.head = Some(node);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self, token: &mut GhostToken<'id>) -> Option<T> {
+        let node = unwrap_node(self.head.take()?);
+        match node.next {
+            Some(next) => {
+                next.borrow_mut(token).prev = None;
+                self.head = Some(next);
+            }
+            None => self.tail = None,
+        }
+        self.len -= 1;
+        Some(node.value)
+    }
+
+    pub fn pop_back(&mut self, token: &mut GhostToken<'id>) -> Option<T> {
+        let tail = self.tail.take()?.upgrade()?;
+        let prev = tail.borrow(token).prev.as_ref().and_then(Weak::upgrade);
+        drop(tail);
+        /* Take the one strong reference to the tail from whoever has it. */
+        let node = match prev {
+            Some(prev) => {
+                self.tail = Some(Rc::downgrade(&prev));
+                prev.borrow_mut(token).next.take()
+            }
+            None => self.head.take(),
+        };
+        self.len -= 1;
+        node.map(|node| unwrap_node(node).value)
+    }
+
+    pub fn front<'a>(&'a self, token: &'a GhostToken<'id>) -> Option<&'a T> {
+        self.head.as_ref().map(|node| &node.borrow(token).value)
+    }
+
+    /* The tail is weak, so there's no Rc here to borrow from. But the node
+    is alive for as long as the list is borrowed, because the node before it
+    owns it, and removing it takes &mut self. So the pointer inside the Weak
+    is good for 'a. */
+    pub fn back<'a>(&'a self, token: &'a GhostToken<'id>) -> Option<&'a T> {
+        let tail = self.tail.as_ref()?;
+        let tail: &'a GhostCell<'id, Node<'id, T>> = unsafe { &*tail.as_ptr() };
+        Some(&tail.borrow(token).value)
+    }
+
+    pub fn iter<'a>(&'a self, token: &'a GhostToken<'id>) -> Iter<'a, 'id, T> {
+        Iter {
+            next: self.head.as_ref(),
+            token,
+            remaining: self.len,
+        }
+    }
+
+    /* There's no iter_mut(): it would hand out a &mut for every node, and
+    each one needs the only &mut token. Calling back for one node at a time
+    is as far as it goes. */
+    pub fn for_each_mut<F: FnMut(&mut T)>(&self, token: &mut GhostToken<'id>, mut f: F) {
+        let mut cur = self.head.clone();
+        while let Some(node) = cur {
+            let node = node.borrow_mut(token);
+            f(&mut node.value);
+            cur = node.next.clone();
+        }
+    }
+
+    pub fn to_vec(&self, token: &GhostToken<'id>) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter(token).cloned().collect()
+    }
+}
+
+/* Dropping doesn't need the token: nodes are unwrapped one at a time, and an
+owned GhostCell can be opened without one. Doing it in a loop keeps long
+lists from recursing through every node's drop. */
+impl<'id, T> Drop for List<'id, T> {
+    fn drop(&mut self) {
+        let mut cur = self.head.take();
+        while let Some(node) = cur {
+            cur = unwrap_node(node).next;
+        }
+    }
+}
+
+pub struct Iter<'a, 'id, T> {
+    next: Option<&'a NodeRef<'id, T>>,
+    token: &'a GhostToken<'id>,
+    remaining: usize,
+}
+
+impl<'a, 'id, T> Iterator for Iter<'a, 'id, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next?.borrow(self.token);
+        self.next = node.next.as_ref();
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, 'id, T> ExactSizeIterator for Iter<'a, 'id, T> {}
+
+/*
+What the compiler now refuses, that linked5 would have caught at runtime (or
+not at all):
+
+    GhostToken::new(|mut token| {
+        let mut list = List::new();
+        list.push_back(1, &mut token);
+        let first = list.front(&token).unwrap();
+        list.push_back(2, &mut token); // error: token (and list) borrowed by `first`
+        println!("{}", first);
+    });
+
+And mixing brands:
+
+    GhostToken::new(|mut a| {
+        GhostToken::new(|mut b| {
+            let mut list = List::new();
+            list.push_back(1, &mut a);
+            list.push_back(2, &mut b); // error: the brands of a and b differ
+        })
+    });
+*/
+
+#[cfg(test)]
+mod test;
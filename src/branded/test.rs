@@ -0,0 +1,80 @@
+use super::*;
+
+#[test]
+fn test_push_pop_iter() {
+    GhostToken::new(|mut token| {
+        let mut list = List::new();
+        assert!(list.is_empty());
+        assert_eq!(list.pop_front(&mut token), None);
+        assert_eq!(list.pop_back(&mut token), None);
+        list.push_back(2, &mut token);
+        list.push_back(3, &mut token);
+        list.push_front(1, &mut token);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.to_vec(&token), vec![1, 2, 3]);
+        assert_eq!(list.front(&token), Some(&1));
+        assert_eq!(list.back(&token), Some(&3));
+        assert_eq!(list.iter(&token).len(), 3);
+
+        assert_eq!(list.pop_back(&mut token), Some(3));
+        assert_eq!(list.pop_front(&mut token), Some(1));
+        assert_eq!(list.pop_back(&mut token), Some(2));
+        assert!(list.is_empty());
+        assert_eq!(list.front(&token), None);
+        list.push_front(4, &mut token);
+        assert_eq!(list.back(&token), Some(&4));
+    });
+}
+
+#[test]
+fn test_for_each_mut() {
+    let total = GhostToken::new(|mut token| {
+        let mut list = List::new();
+        for i in 1..=4 {
+            list.push_back(i, &mut token);
+        }
+        list.for_each_mut(&mut token, |v| *v *= 10);
+        assert_eq!(list.to_vec(&token), vec![10, 20, 30, 40]);
+        list.iter(&token).sum::<i64>()
+    });
+    assert_eq!(total, 100);
+}
+
+#[test]
+fn test_two_lists_one_token() {
+    GhostToken::new(|mut token| {
+        let mut a = List::new();
+        let mut b = List::new();
+        a.push_back("a", &mut token);
+        b.push_back("b", &mut token);
+        // Reading both at once only needs the token shared.
+        let both: Vec<_> = a.iter(&token).chain(b.iter(&token)).collect();
+        assert_eq!(both, vec![&"a", &"b"]);
+    });
+}
+
+#[test]
+fn test_values_are_dropped() {
+    let counter = std::rc::Rc::new(());
+    GhostToken::new(|mut token| {
+        let mut list = List::new();
+        for _ in 0..6 {
+            list.push_back(std::rc::Rc::clone(&counter), &mut token);
+        }
+        list.pop_back(&mut token);
+        list.pop_front(&mut token);
+        assert_eq!(std::rc::Rc::strong_count(&counter), 5);
+    });
+    assert_eq!(std::rc::Rc::strong_count(&counter), 1);
+}
+
+#[test]
+fn test_drop_long_list() {
+    GhostToken::new(|mut token| {
+        let mut list = List::new();
+        for i in 0..200_000 {
+            list.push_back(i, &mut token);
+        }
+        assert_eq!(list.len(), 200_000);
+    });
+}
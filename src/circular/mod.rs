@@ -0,0 +1,302 @@
+#![allow(dead_code)]
+/*
+Round and round: a circular list with a sentinel
+===========================================================================
+
+Every list so far has two ends, and every method that touches a link has to
+ask "is there a node here, or None?" first. linked6 is full of those matches:
+push_front is different when the list is empty, pop_back is different when
+it's the last node, and so on.
+
+A circular list joins the ends: the last node's next is the first node, and
+the first node's prev is the last one. Add one extra node that holds no
+value, the sentinel, sitting between the last and the first, and there's no
+None left anywhere. An empty list is just the sentinel pointing to itself.
+Inserting is always "between these two nodes", removing is always "join the
+two neighbours", and there are no special cases.
+
+The ring has no natural start, so instead the list keeps a current position,
+which is handy for things like round-robin scheduling: take the current one,
+move on, and come back to the first one after the last. rotate() moves it,
+push() and pop() insert and remove right there, iter() goes around once
+starting from it, and iter_cycle() never stops going around.
+
+The sentinel is always skipped: the current position is only on the
+sentinel when the list is empty.
+
+Unsafe-wise, this is linked6 again. The ring itself, with its sentinel, is
+in ring.rs, along with its rules, and linked6's SentinelList is built on it
+too. The list only adds one rule of its own: current points to the sentinel
+exactly when len is 0.
+*/
+use crate::footprint::HeapSize;
+use crate::topology::{NodeLinks, Topology};
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::mem::size_of;
+use std::ptr::NonNull;
+
+pub(crate) mod ring;
+use ring::{Node, Ring};
+
+pub struct CircularList<T> {
+    ring: Ring<T>,
+    current: NonNull<Node<T>>,
+}
+
+/* current only points into the ring, which is as thread safe as T is. */
+unsafe impl<T: Send> Send for CircularList<T> {}
+unsafe impl<T: Sync> Sync for CircularList<T> {}
+
+impl<T> Default for CircularList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> CircularList<T> {
+    pub fn new() -> Self {
+        let ring = Ring::new();
+        CircularList {
+            current: ring.sentinel(),
+            ring,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /* The neighbours of a node, jumping over the sentinel. Only meaningful
+    when the list isn't empty. */
+    fn next_value_node(&self, node: NonNull<Node<T>>) -> NonNull<Node<T>> {
+        let next = self.ring.next_of(node);
+        if next == self.ring.sentinel() {
+            self.ring.next_of(next)
+        } else {
+            next
+        }
+    }
+
+    fn prev_value_node(&self, node: NonNull<Node<T>>) -> NonNull<Node<T>> {
+        let prev = self.ring.prev_of(node);
+        if prev == self.ring.sentinel() {
+            self.ring.prev_of(prev)
+        } else {
+            prev
+        }
+    }
+
+    pub fn current(&self) -> Option<&T> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(unsafe { (*self.current.as_ptr()).value.assume_init_ref() })
+    }
+
+    pub fn current_mut(&mut self) -> Option<&mut T> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(unsafe { (*self.current.as_ptr()).value.assume_init_mut() })
+    }
+
+    /* Moves the current position forward by `steps`, or backwards if it's
+    negative. Going around the whole ring is pointless, so we only walk the
+    remainder, and in the shorter direction. */
+    pub fn rotate(&mut self, steps: isize) {
+        if self.is_empty() {
+            return;
+        }
+        let len = self.len() as isize;
+        let forward = steps.rem_euclid(len);
+        if forward <= len / 2 {
+            for _ in 0..forward {
+                self.current = self.next_value_node(self.current);
+            }
+        } else {
+            for _ in forward..len {
+                self.current = self.prev_value_node(self.current);
+            }
+        }
+    }
+
+    /* Inserts at the current position: the new value becomes the current
+    one, and the old current comes right after it. */
+    pub fn push(&mut self, value: T) {
+        self.current = self.ring.insert_before(self.current, value);
+    }
+
+    /* Removes the current value, and the next one becomes current. */
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let node = self.current;
+        self.current = if self.len() == 1 {
+            self.ring.sentinel()
+        } else {
+            self.next_value_node(node)
+        };
+        Some(unsafe { self.ring.unlink(node) })
+    }
+
+    /* The sentinel still marks where the ring "starts", so values can be
+    added at either side of it without moving the current position (unless
+    there was none). */
+    pub fn push_back(&mut self, value: T) {
+        let node = self.ring.insert_before(self.ring.sentinel(), value);
+        if self.len() == 1 {
+            self.current = node;
+        }
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        let first = self.ring.next_of(self.ring.sentinel());
+        let node = self.ring.insert_before(first, value);
+        if self.len() == 1 {
+            self.current = node;
+        }
+    }
+
+    /* One full revolution, starting at the current position. */
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            list: self,
+            node: self.current,
+            remaining: self.len(),
+        }
+    }
+
+    /* Goes around forever, skipping the sentinel. Empty lists give nothing. */
+    pub fn iter_cycle(&self) -> IterCycle<'_, T> {
+        IterCycle {
+            list: self,
+            node: self.current,
+        }
+    }
+
+    /* Starts at the current position, like iter(). */
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+/* The ring's, so it starts after the sentinel, like the links go, and not
+at current. */
+impl<T> Topology for CircularList<T> {
+    fn topology(&self) -> Vec<NodeLinks> {
+        self.ring.topology()
+    }
+}
+
+impl<T> HeapSize for CircularList<T> {
+    fn heap_size(&self) -> usize {
+        (self.len() + 1) * size_of::<Node<T>>()
+    }
+}
+
+pub struct Iter<'a, T> {
+    list: &'a CircularList<T>,
+    node: NonNull<Node<T>>,
+    remaining: usize,
+}
+
+/* node only points into the list we borrow, like the reference next to it. */
+unsafe impl<'a, T: Sync> Send for Iter<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for Iter<'a, T> {}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.node;
+        self.node = self.list.next_value_node(node);
+        self.remaining -= 1;
+        Some(unsafe { (*node.as_ptr()).value.assume_init_ref() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+pub struct IterCycle<'a, T> {
+    list: &'a CircularList<T>,
+    node: NonNull<Node<T>>,
+}
+
+unsafe impl<'a, T: Sync> Send for IterCycle<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for IterCycle<'a, T> {}
+
+impl<'a, T> Iterator for IterCycle<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.list.is_empty() {
+            return None;
+        }
+        let node = self.node;
+        self.node = self.list.next_value_node(node);
+        Some(unsafe { (*node.as_ptr()).value.assume_init_ref() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.list.is_empty() {
+            (0, Some(0))
+        } else {
+            (usize::MAX, None)
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a CircularList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> FromIterator<T> for CircularList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = CircularList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for CircularList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CircularList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,138 @@
+/*
+The ring underneath
+===========================================================================
+
+CircularList and linked6's SentinelList are the same thing underneath: a
+ring of nodes, closed by a sentinel that holds no value. This is that ring,
+written once. It allocates the sentinel, links nodes in before another one,
+unlinks them, and frees whatever is left when it drops. What the lists add
+on top is where they start from: CircularList from its current position,
+SentinelList from the sentinel, like any list with two ends.
+
+The rules, kept by every method here before it returns:
+- Every node, the sentinel included, has valid prev and next pointers, and
+  following next from the sentinel comes back to it after len + 1 steps.
+- The sentinel's value is never initialized, and every other value is.
+- Every node was created with Box::new and is freed exactly once, here.
+*/
+use crate::topology::{id_of, NodeLinks, Topology};
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr::NonNull;
+
+pub(crate) struct Node<T> {
+    pub(crate) value: MaybeUninit<T>,
+    pub(crate) prev: NonNull<Node<T>>,
+    pub(crate) next: NonNull<Node<T>>,
+}
+
+pub(crate) struct Ring<T> {
+    sentinel: NonNull<Node<T>>,
+    len: usize,
+    _boxes: PhantomData<Box<Node<T>>>,
+}
+
+/* Raw pointers aren't Send or Sync, but these only point to nodes the ring
+owns, so it's as thread safe as T is. */
+unsafe impl<T: Send> Send for Ring<T> {}
+unsafe impl<T: Sync> Sync for Ring<T> {}
+
+impl<T> Ring<T> {
+    pub(crate) fn new() -> Self {
+        /* The sentinel has to point to itself, and it doesn't have an address
+        until it's allocated, so it starts with dangling links for a moment. */
+        let sentinel = NonNull::from(Box::leak(Box::new(Node {
+            value: MaybeUninit::uninit(),
+            prev: NonNull::dangling(),
+            next: NonNull::dangling(),
+        })));
+        unsafe {
+            (*sentinel.as_ptr()).prev = sentinel;
+            (*sentinel.as_ptr()).next = sentinel;
+        }
+        Ring {
+            sentinel,
+            len: 0,
+            _boxes: PhantomData,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn sentinel(&self) -> NonNull<Node<T>> {
+        self.sentinel
+    }
+
+    pub(crate) fn next_of(&self, node: NonNull<Node<T>>) -> NonNull<Node<T>> {
+        unsafe { (*node.as_ptr()).next }
+    }
+
+    pub(crate) fn prev_of(&self, node: NonNull<Node<T>>) -> NonNull<Node<T>> {
+        unsafe { (*node.as_ptr()).prev }
+    }
+
+    /* The one place where nodes are created. No ifs: both neighbours always
+    exist, even if they're both the sentinel. */
+    pub(crate) fn insert_before(&mut self, next: NonNull<Node<T>>, value: T) -> NonNull<Node<T>> {
+        let prev = self.prev_of(next);
+        let node = NonNull::from(Box::leak(Box::new(Node {
+            value: MaybeUninit::new(value),
+            prev,
+            next,
+        })));
+        unsafe {
+            (*prev.as_ptr()).next = node;
+            (*next.as_ptr()).prev = node;
+        }
+        self.len += 1;
+        node
+    }
+
+    /* And the one place where they're freed. `node` must be in the ring, and
+    must not be the sentinel. */
+    pub(crate) unsafe fn unlink(&mut self, node: NonNull<Node<T>>) -> T {
+        debug_assert!(node != self.sentinel);
+        let node = Box::from_raw(node.as_ptr());
+        (*node.prev.as_ptr()).next = node.next;
+        (*node.next.as_ptr()).prev = node.prev;
+        self.len -= 1;
+        node.value.assume_init()
+    }
+}
+
+/* Both lists describe themselves as this. The sentinel isn't a node here: a
+next or prev pointing to it is None. It starts after the sentinel, like the
+links go. */
+impl<T> Topology for Ring<T> {
+    fn topology(&self) -> Vec<NodeLinks> {
+        let id = |node: NonNull<Node<T>>| {
+            Some(node)
+                .filter(|&node| node != self.sentinel)
+                .map(|node| id_of(node.as_ptr()))
+        };
+        let node = |node: NonNull<Node<T>>| unsafe { &*node.as_ptr() };
+        std::iter::successors(Some(node(self.sentinel).next), |&cur| Some(node(cur).next))
+            .take(self.len)
+            .map(|cur| NodeLinks {
+                id: id_of(cur.as_ptr()),
+                next: id(node(cur).next),
+                prev: id(node(cur).prev),
+                counts: None,
+            })
+            .collect()
+    }
+}
+
+impl<T> Drop for Ring<T> {
+    fn drop(&mut self) {
+        while self.len > 0 {
+            let first = self.next_of(self.sentinel);
+            unsafe { self.unlink(first) };
+        }
+        /* The sentinel's value was never initialized, and MaybeUninit doesn't
+        drop anything, so this only frees the memory. */
+        unsafe { drop(Box::from_raw(self.sentinel.as_ptr())) };
+    }
+}
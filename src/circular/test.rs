@@ -0,0 +1,114 @@
+use super::*;
+use std::rc::Rc;
+
+#[test]
+fn test_empty() {
+    let mut list: CircularList<i64> = CircularList::new();
+    assert!(list.is_empty());
+    assert_eq!(list.current(), None);
+    assert_eq!(list.pop(), None);
+    list.rotate(3);
+    assert_eq!(list.iter().next(), None);
+    assert_eq!(list.iter_cycle().next(), None);
+}
+
+#[test]
+fn test_rotate() {
+    let mut list: CircularList<i64> = (0..5).collect();
+    assert_eq!(list.current(), Some(&0));
+    list.rotate(1);
+    assert_eq!(list.current(), Some(&1));
+    list.rotate(-2);
+    assert_eq!(list.current(), Some(&4));
+    list.rotate(3);
+    assert_eq!(list.current(), Some(&2));
+    list.rotate(5);
+    assert_eq!(list.current(), Some(&2));
+    list.rotate(-12);
+    assert_eq!(list.current(), Some(&0));
+    list.rotate(isize::MIN);
+    assert_eq!(list.current(), Some(&2));
+    assert_eq!(list.to_vec(), vec![2, 3, 4, 0, 1]);
+}
+
+#[test]
+fn test_push_pop_at_current() {
+    let mut list = CircularList::new();
+    list.push(3);
+    list.push(1);
+    list.rotate(1);
+    list.push(2);
+    // Current is 2, then 3 and around to 1.
+    assert_eq!(list.to_vec(), vec![2, 3, 1]);
+    assert_eq!(list.pop(), Some(2));
+    assert_eq!(list.current(), Some(&3));
+    // Popping the last one before the sentinel wraps around.
+    assert_eq!(list.pop(), Some(3));
+    assert_eq!(list.current(), Some(&1));
+    *list.current_mut().unwrap() = 10;
+    assert_eq!(list.pop(), Some(10));
+    assert!(list.is_empty());
+    assert_eq!(list.current(), None);
+    list.push(4);
+    assert_eq!(list.current(), Some(&4));
+}
+
+#[test]
+fn test_push_front_back() {
+    let mut list = CircularList::new();
+    list.push_back(2);
+    assert_eq!(list.current(), Some(&2));
+    list.push_back(3);
+    list.push_front(1);
+    assert_eq!(list.current(), Some(&2));
+    assert_eq!(list.to_vec(), vec![2, 3, 1]);
+    list.rotate(1);
+    assert_eq!(list.to_vec(), vec![3, 1, 2]);
+    assert_eq!(format!("{:?}", list), "[3, 1, 2]");
+}
+
+#[test]
+fn test_one_revolution() {
+    let mut list: CircularList<i64> = (1..=4).collect();
+    list.rotate(2);
+    let mut iter = list.iter();
+    assert_eq!(iter.len(), 4);
+    assert_eq!(iter.by_ref().copied().collect::<Vec<_>>(), vec![3, 4, 1, 2]);
+    assert_eq!(iter.next(), None);
+    assert_eq!((&list).into_iter().sum::<i64>(), 10);
+}
+
+#[test]
+fn test_round_robin() {
+    let mut tasks: CircularList<(&str, u32)> =
+        vec![("a", 2), ("b", 1), ("c", 3)].into_iter().collect();
+    let mut order = vec![];
+    while let Some((name, left)) = tasks.current_mut() {
+        order.push(*name);
+        *left -= 1;
+        if *left == 0 {
+            tasks.pop();
+        } else {
+            tasks.rotate(1);
+        }
+    }
+    assert_eq!(order, vec!["a", "b", "c", "a", "c", "c"]);
+
+    let list: CircularList<i64> = (0..3).collect();
+    let cycled: Vec<i64> = list.iter_cycle().take(8).copied().collect();
+    assert_eq!(cycled, vec![0, 1, 2, 0, 1, 2, 0, 1]);
+}
+
+#[test]
+fn test_values_are_dropped() {
+    let counter = Rc::new(());
+    let mut list = CircularList::new();
+    for _ in 0..6 {
+        list.push(Rc::clone(&counter));
+        list.rotate(1);
+    }
+    list.pop();
+    assert_eq!(Rc::strong_count(&counter), 6);
+    drop(list);
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
@@ -0,0 +1,304 @@
+#![allow(dead_code)]
+/*
+Differential testing: every list against a VecDeque
+===========================================================================
+
+Most of the lists here juggle raw pointers by hand, and the bugs that come
+with that are the quiet kind: a tail pointer left behind by one pop in a
+particular order, a len that's off by one after an insert at the very end.
+Unit tests only find the orders somebody thought of.
+
+So instead we take a long random sequence of operations and run it on a list
+and on a VecDeque side by side. VecDeque is the oracle: it's in std, it's
+been tested to death, and it can do everything our lists can. After every
+single step the two must agree on what the step returned, on len(), and on
+every value, in order. The first step where they don't is the bug, and the
+panic message says which one it was and what came before it.
+
+Not every list can do every operation, see the traits in `ops`. The harness
+has one run_*() per level, and each one skips the operations the list can't
+do. A skipped one isn't applied to the oracle either, so they never drift.
+
+Positions in Insert, Remove and Nth are taken modulo len + 1 when the step
+runs. That way any sequence at all is valid, whatever it was generated from,
+and the position can still land one past the end: inserting there is a
+push_back, removing there must give None.
+
+This lives in the library, not under #[cfg(test)], so that anything else
+that generates operations (proptest, a fuzzer) can feed the same harness.
+Whatever it finds can be written down as text and kept as a test, see
+oputil.
+*/
+use crate::ops::{DoubleEndedOps, LinkedListOps, PositionalOps};
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+
+/* Knuth's 64-bit LCG, behind random_sequence() and every randomized test in
+the crate. It's not very random, and that's the point: a seed is the same
+sequence on every machine, so whatever it finds can be replayed. The low
+bits repeat quickly (the lowest one just alternates), so callers take what
+they need from the top. */
+#[derive(Debug, Clone)]
+pub(crate) struct Lcg(u64);
+
+impl Lcg {
+    pub(crate) fn new(seed: u64) -> Self {
+        Lcg(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1);
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    PushFront(i64),
+    PushBack(i64),
+    PopFront,
+    PopBack,
+    Insert(usize, i64),
+    Remove(usize),
+    /* Reads one value through iter(), without touching the list. */
+    Nth(usize),
+}
+
+impl Op {
+    /* Pushes are twice as likely as anything else, so the lists actually
+    grow instead of hovering around empty. */
+    pub fn random_sequence(seed: u64, count: usize) -> Vec<Op> {
+        let mut rng = Lcg::new(seed);
+        let mut ops = Vec::with_capacity(count);
+        for _ in 0..count {
+            let seed = rng.next_u64();
+            let value = ((seed >> 40) % 1000) as i64;
+            let at = (seed >> 20) as usize % 1024;
+            let op = match (seed >> 33) % 9 {
+                0 | 1 => Op::PushFront(value),
+                2 | 3 => Op::PushBack(value),
+                4 => Op::PopFront,
+                5 => Op::PopBack,
+                6 => Op::Insert(at, value),
+                7 => Op::Remove(at),
+                _ => Op::Nth(at),
+            };
+            ops.push(op);
+        }
+        ops
+    }
+
+    /* For the fuzzer (see fuzz/), which only gives us bytes. One byte picks
+    the operation, and the ones that need a value or a position take the
+    next byte for each. Every input decodes to something, down to the last
+    byte: an argument that's cut off is 0. That matters, a decoder that
+    rejects inputs makes the fuzzer waste its time on them. */
+    pub fn from_bytes(bytes: &[u8]) -> Vec<Op> {
+        let mut bytes = bytes.iter().copied();
+        let mut ops = Vec::new();
+        while let Some(tag) = bytes.next() {
+            let mut arg = || bytes.next().unwrap_or(0);
+            let op = match tag % 7 {
+                0 => Op::PushFront(arg() as i8 as i64),
+                1 => Op::PushBack(arg() as i8 as i64),
+                2 => Op::PopFront,
+                3 => Op::PopBack,
+                4 => {
+                    let at = arg() as usize;
+                    Op::Insert(at, arg() as i8 as i64)
+                }
+                5 => Op::Remove(arg() as usize),
+                _ => Op::Nth(arg() as usize),
+            };
+            ops.push(op);
+        }
+        ops
+    }
+}
+
+pub struct Harness<L> {
+    list: L,
+    oracle: VecDeque<i64>,
+    /* Every step that ran, for the panic message. */
+    done: Vec<Op>,
+}
+
+impl<L: LinkedListOps<Item = i64>> Default for Harness<L> {
+    fn default() -> Self {
+        Harness {
+            list: L::default(),
+            oracle: VecDeque::new(),
+            done: Vec::new(),
+        }
+    }
+}
+
+impl<L: LinkedListOps<Item = i64>> Harness<L> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn list(&self) -> &L {
+        &self.list
+    }
+
+    /* The last few steps are usually enough to see what happened. */
+    fn context(&self) -> String {
+        let from = self.done.len().saturating_sub(16);
+        format!(
+            "at step {} (0-based), after ...{:?}",
+            self.done.len() - 1,
+            &self.done[from..]
+        )
+    }
+
+    fn check_result(&self, got: Option<i64>, want: Option<i64>) {
+        assert_eq!(got, want, "wrong value returned {}", self.context());
+    }
+
+    fn check(&self) {
+        let len = self.oracle.len();
+        assert_eq!(self.list.len(), len, "wrong len() {}", self.context());
+        assert_eq!(
+            self.list.is_empty(),
+            len == 0,
+            "wrong is_empty() {}",
+            self.context()
+        );
+        let values: Vec<i64> = self.list.iter().map(|v| *v.borrow()).collect();
+        assert!(
+            values.iter().eq(self.oracle.iter()),
+            "the lists differ {}:\n  list:   {:?}\n  oracle: {:?}",
+            self.context(),
+            values,
+            self.oracle
+        );
+    }
+
+    /* Returns false, and does nothing, for what a queue can't do. */
+    fn step_queue(&mut self, op: Op) -> bool {
+        match op {
+            Op::PushFront(value) => {
+                self.done.push(op);
+                self.list.push_front(value);
+                self.oracle.push_front(value);
+            }
+            Op::PushBack(value) => {
+                self.done.push(op);
+                self.list.push_back(value);
+                self.oracle.push_back(value);
+            }
+            Op::PopFront => {
+                self.done.push(op);
+                let got = self.list.pop_front();
+                let want = self.oracle.pop_front();
+                self.check_result(got, want);
+            }
+            Op::Nth(at) => {
+                self.done.push(op);
+                let at = at % (self.oracle.len() + 1);
+                let got = self.list.iter().nth(at).map(|v| *v.borrow());
+                self.check_result(got, self.oracle.get(at).copied());
+            }
+            Op::PopBack | Op::Insert(..) | Op::Remove(..) => return false,
+        }
+        true
+    }
+}
+
+impl<L: DoubleEndedOps<Item = i64>> Harness<L> {
+    fn step_deque(&mut self, op: Op) -> bool {
+        match op {
+            Op::PopBack => {
+                self.done.push(op);
+                let got = self.list.pop_back();
+                let want = self.oracle.pop_back();
+                self.check_result(got, want);
+                true
+            }
+            _ => self.step_queue(op),
+        }
+    }
+}
+
+impl<L: DoubleEndedOps<Item = i64> + PositionalOps> Harness<L> {
+    fn step_positional(&mut self, op: Op) -> bool {
+        match op {
+            Op::Insert(at, value) => {
+                self.done.push(op);
+                let at = at % (self.oracle.len() + 1);
+                self.list.insert(at, value);
+                self.oracle.insert(at, value);
+                true
+            }
+            Op::Remove(at) => {
+                self.done.push(op);
+                let at = at % (self.oracle.len() + 1);
+                let got = self.list.remove(at);
+                let want = self.oracle.remove(at);
+                self.check_result(got, want);
+                true
+            }
+            _ => self.step_deque(op),
+        }
+    }
+}
+
+/* One step at a time, checked like the run_*() below do, for tests that
+want to look at the list in between: its chunks, its levels, its tail. */
+impl<L: LinkedListOps<Item = i64>> Harness<L> {
+    pub fn queue_op(&mut self, op: Op) {
+        if self.step_queue(op) {
+            self.check();
+        }
+    }
+}
+
+impl<L: DoubleEndedOps<Item = i64>> Harness<L> {
+    pub fn deque_op(&mut self, op: Op) {
+        if self.step_deque(op) {
+            self.check();
+        }
+    }
+}
+
+impl<L: DoubleEndedOps<Item = i64> + PositionalOps> Harness<L> {
+    pub fn positional_op(&mut self, op: Op) {
+        if self.step_positional(op) {
+            self.check();
+        }
+    }
+}
+
+/* Runs the ops a queue can do: pushes, pop_front() and reads. */
+pub fn run_queue<L: LinkedListOps<Item = i64>>(ops: &[Op]) -> Harness<L> {
+    let mut harness = Harness::new();
+    for &op in ops {
+        harness.queue_op(op);
+    }
+    harness
+}
+
+/* Adds pop_back(). */
+pub fn run_deque<L: DoubleEndedOps<Item = i64>>(ops: &[Op]) -> Harness<L> {
+    let mut harness = Harness::new();
+    for &op in ops {
+        harness.deque_op(op);
+    }
+    harness
+}
+
+/* Everything, inserts and removes in the middle included. */
+pub fn run_positional<L>(ops: &[Op]) -> Harness<L>
+where
+    L: DoubleEndedOps<Item = i64> + PositionalOps,
+{
+    let mut harness = Harness::new();
+    for &op in ops {
+        harness.positional_op(op);
+    }
+    harness
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,143 @@
+use super::*;
+use crate::ops::ReadOps;
+use crate::{hybrid, indexed, linked4, linked5, linked6, linked7, linked8, unrolled};
+
+const SEEDS: u64 = 20;
+const STEPS: usize = 400;
+
+#[test]
+fn test_positional_lists() {
+    for seed in 0..SEEDS {
+        let ops = Op::random_sequence(seed, STEPS);
+        run_positional::<linked4::List<i64>>(&ops);
+        run_positional::<linked6::List<i64>>(&ops);
+        run_positional::<linked6::SentinelList<i64>>(&ops);
+        /* Small chunks, so they split and merge all the time. */
+        run_positional::<unrolled::UnrolledList<i64, 4>>(&ops);
+        run_positional::<indexed::IndexedList<i64>>(&ops);
+        run_positional::<hybrid::HybridList<i64, 4>>(&ops);
+    }
+}
+
+#[test]
+fn test_deques() {
+    for seed in 0..SEEDS {
+        let ops = Op::random_sequence(seed, STEPS);
+        run_deque::<linked4::DoublyList<i64>>(&ops);
+        run_deque::<linked5::List>(&ops);
+        run_deque::<linked7::List<i64>>(&ops);
+    }
+}
+
+#[test]
+fn test_queues() {
+    for seed in 0..SEEDS {
+        let ops = Op::random_sequence(seed, STEPS);
+        run_queue::<linked8::List<i64>>(&ops);
+    }
+}
+
+#[test]
+fn test_sequences_use_every_op() {
+    let ops = Op::random_sequence(1, STEPS);
+    let harness = run_positional::<linked6::List<i64>>(&ops);
+    assert_eq!(harness.done.len(), STEPS);
+    for kind in 0..7 {
+        assert!(ops.iter().any(|op| match op {
+            Op::PushFront(_) => kind == 0,
+            Op::PushBack(_) => kind == 1,
+            Op::PopFront => kind == 2,
+            Op::PopBack => kind == 3,
+            Op::Insert(..) => kind == 4,
+            Op::Remove(_) => kind == 5,
+            Op::Nth(_) => kind == 6,
+        }));
+    }
+    /* A queue skips what it can't do, and so does the oracle. */
+    let harness = run_queue::<linked8::List<i64>>(&ops);
+    assert!(harness.done.len() < STEPS);
+    assert_eq!(harness.list().len(), harness.oracle.len());
+}
+
+#[test]
+fn test_ops_from_bytes() {
+    assert_eq!(Op::from_bytes(&[]), vec![]);
+    assert_eq!(
+        Op::from_bytes(&[0, 5, 1, 255, 2, 3, 4, 1, 9, 5, 7, 6, 8]),
+        vec![
+            Op::PushFront(5),
+            Op::PushBack(-1),
+            Op::PopFront,
+            Op::PopBack,
+            Op::Insert(1, 9),
+            Op::Remove(7),
+            Op::Nth(8),
+        ]
+    );
+    /* Cut short: the missing arguments are 0. */
+    assert_eq!(Op::from_bytes(&[11, 3]), vec![Op::Insert(3, 0)]);
+    assert_eq!(Op::from_bytes(&[7]), vec![Op::PushFront(0)]);
+    for seed in 0..SEEDS {
+        let bytes: Vec<u8> = Op::random_sequence(seed, 64)
+            .iter()
+            .map(|op| match op {
+                Op::PushFront(v) | Op::PushBack(v) => *v as u8,
+                Op::Insert(at, _) | Op::Remove(at) | Op::Nth(at) => *at as u8,
+                Op::PopFront | Op::PopBack => 2,
+            })
+            .collect();
+        run_positional::<linked6::List<i64>>(&Op::from_bytes(&bytes));
+    }
+}
+
+/* A list with a bug the harness has to find: it counts its values on the
+side, and forgets to when pop_back() empties it. */
+#[derive(Default)]
+struct Miscounted {
+    list: linked6::List<i64>,
+    len: usize,
+}
+
+impl ReadOps for Miscounted {
+    type Item = i64;
+    type Iter<'a> = linked6::Iter<'a, i64>;
+
+    fn len(&self) -> usize {
+        self.len
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+        self.list.iter()
+    }
+}
+
+impl LinkedListOps for Miscounted {
+    fn push_front(&mut self, value: i64) {
+        self.len += 1;
+        self.list.push_front(value);
+    }
+    fn push_back(&mut self, value: i64) {
+        self.len += 1;
+        self.list.push_back(value);
+    }
+    fn pop_front(&mut self) -> Option<i64> {
+        self.len = self.len.saturating_sub(1);
+        self.list.pop_front()
+    }
+}
+
+impl DoubleEndedOps for Miscounted {
+    fn pop_back(&mut self) -> Option<i64> {
+        if self.len > 1 {
+            self.len -= 1;
+        }
+        self.list.pop_back()
+    }
+}
+
+#[test]
+#[should_panic(expected = "wrong len()")]
+fn test_harness_finds_a_bug() {
+    for seed in 0..SEEDS {
+        run_deque::<Miscounted>(&Op::random_sequence(seed, STEPS));
+    }
+}
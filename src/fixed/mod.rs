@@ -0,0 +1,333 @@
+#![allow(dead_code)]
+/*
+A linked list that never allocates
+===========================================================================
+
+linked7 showed that links can be indices into a Vec. But the Vec is still on
+the heap, and it grows (and reallocates) as the list does. If we know the
+most values we'll ever hold, the slots can be a plain array instead, right
+inside the list: [Slot<T>; N]. Then there's no allocation at all, ever. The
+whole list can live on the stack, or inside some other struct, and pushing
+never has to ask anybody for memory.
+
+That's exactly what you need where there's no allocator: kernels, firmware,
+interrupt handlers. So this module only uses core, not std or alloc, to keep
+it ready for the no_std story.
+
+It's linked7 without the generations and with the Vec swapped for an array:
+free slots are chained through the array into a free list, and every slot
+starts out in it. There's no "grow" step, so when the free list runs out the
+push fails with Full. linked1's InlineList had a Full too, but it only held
+i64s and could just drop the value. Here the value could be anything, so Full
+hands it back, and the caller decides what to do with it.
+
+Every slot is always initialized (as either a node or a free slot), so this
+is also all safe code, no MaybeUninit required. The price is that a free
+slot still takes as much room as a node does.
+*/
+use crate::footprint::HeapSize;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::mem;
+
+/* Returned by the pushes and inserts when all N slots are taken. It gives
+back the value that didn't fit. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Full<T>(pub T);
+
+struct Node<T> {
+    value: T,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+enum Slot<T> {
+    Occupied(Node<T>),
+    Free { next_free: Option<usize> },
+}
+
+pub struct FixedList<T, const N: usize> {
+    slots: [Slot<T>; N],
+    head: Option<usize>,
+    tail: Option<usize>,
+    free: Option<usize>,
+    len: usize,
+}
+
+impl<T, const N: usize> Default for FixedList<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/* That's the whole point. */
+impl<T, const N: usize> HeapSize for FixedList<T, N> {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl<T, const N: usize> FixedList<T, N> {
+    pub fn new() -> Self {
+        FixedList {
+            slots: core::array::from_fn(|i| Slot::Free {
+                next_free: if i + 1 < N { Some(i + 1) } else { None },
+            }),
+            head: None,
+            tail: None,
+            free: if N > 0 { Some(0) } else { None },
+            len: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    /* The links only ever point to occupied slots. */
+    fn node(&self, index: usize) -> &Node<T> {
+        match &self.slots[index] {
+            Slot::Occupied(node) => node,
+            Slot::Free { .. } => unreachable!("a link to a free slot"),
+        }
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node<T> {
+        match &mut self.slots[index] {
+            Slot::Occupied(node) => node,
+            Slot::Free { .. } => unreachable!("a link to a free slot"),
+        }
+    }
+
+    fn alloc(&mut self, value: T) -> Result<usize, Full<T>> {
+        let index = match self.free {
+            Some(index) => index,
+            None => return Err(Full(value)),
+        };
+        self.free = match self.slots[index] {
+            Slot::Free { next_free } => next_free,
+            Slot::Occupied(_) => unreachable!("an occupied slot in the free list"),
+        };
+        self.slots[index] = Slot::Occupied(Node {
+            value,
+            prev: None,
+            next: None,
+        });
+        Ok(index)
+    }
+
+    fn release(&mut self, index: usize) -> T {
+        let free = Slot::Free {
+            next_free: self.free,
+        };
+        self.free = Some(index);
+        match mem::replace(&mut self.slots[index], free) {
+            Slot::Occupied(node) => node.value,
+            Slot::Free { .. } => unreachable!("releasing a free slot"),
+        }
+    }
+
+    fn link(&mut self, index: usize, prev: Option<usize>, next: Option<usize>) {
+        let node = self.node_mut(index);
+        node.prev = prev;
+        node.next = next;
+        match prev {
+            Some(prev) => self.node_mut(prev).next = Some(index),
+            None => self.head = Some(index),
+        }
+        match next {
+            Some(next) => self.node_mut(next).prev = Some(index),
+            None => self.tail = Some(index),
+        }
+        self.len += 1;
+    }
+
+    fn unlink(&mut self, index: usize) -> T {
+        let node = self.node(index);
+        let (prev, next) = (node.prev, node.next);
+        match prev {
+            Some(prev) => self.node_mut(prev).next = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => self.node_mut(next).prev = prev,
+            None => self.tail = prev,
+        }
+        self.len -= 1;
+        self.release(index)
+    }
+
+    /* The slot of the node at position `at`, walking from whichever end is
+    closer. */
+    fn slot_at(&self, at: usize) -> Option<usize> {
+        if at >= self.len {
+            return None;
+        }
+        if at < self.len / 2 {
+            let mut index = self.head?;
+            for _ in 0..at {
+                index = self.node(index).next?;
+            }
+            Some(index)
+        } else {
+            let mut index = self.tail?;
+            for _ in at + 1..self.len {
+                index = self.node(index).prev?;
+            }
+            Some(index)
+        }
+    }
+
+    pub fn push_front(&mut self, value: T) -> Result<(), Full<T>> {
+        let index = self.alloc(value)?;
+        self.link(index, None, self.head);
+        Ok(())
+    }
+
+    pub fn push_back(&mut self, value: T) -> Result<(), Full<T>> {
+        let index = self.alloc(value)?;
+        self.link(index, self.tail, None);
+        Ok(())
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let head = self.head?;
+        Some(self.unlink(head))
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail = self.tail?;
+        Some(self.unlink(tail))
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.map(|index| &self.node(index).value)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.tail.map(|index| &self.node(index).value)
+    }
+
+    pub fn get(&self, at: usize) -> Option<&T> {
+        let index = self.slot_at(at)?;
+        Some(&self.node(index).value)
+    }
+
+    pub fn get_mut(&mut self, at: usize) -> Option<&mut T> {
+        let index = self.slot_at(at)?;
+        Some(&mut self.node_mut(index).value)
+    }
+
+    /* Inserts the value so that it ends up at position `at`. Panics if `at`
+    is past the end, like Vec::insert. */
+    pub fn insert(&mut self, at: usize, value: T) -> Result<(), Full<T>> {
+        assert!(
+            at <= self.len,
+            "insert: the len is {} but the index is {}",
+            self.len,
+            at
+        );
+        let next = self.slot_at(at);
+        let prev = match next {
+            Some(next) => self.node(next).prev,
+            None => self.tail,
+        };
+        let index = self.alloc(value)?;
+        self.link(index, prev, next);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, at: usize) -> Option<T> {
+        let index = self.slot_at(at)?;
+        Some(self.unlink(index))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, N> {
+        Iter {
+            list: self,
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+        }
+    }
+}
+
+pub struct Iter<'a, T, const N: usize> {
+    list: &'a FixedList<T, N>,
+    front: Option<usize>,
+    back: Option<usize>,
+    remaining: usize,
+}
+
+impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.list.node(self.front?);
+        self.front = node.next;
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T, const N: usize> DoubleEndedIterator for Iter<'a, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.list.node(self.back?);
+        self.back = node.prev;
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+}
+
+impl<'a, T, const N: usize> ExactSizeIterator for Iter<'a, T, N> {}
+impl<'a, T, const N: usize> FusedIterator for Iter<'a, T, N> {}
+
+impl<'a, T, const N: usize> IntoIterator for &'a FixedList<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for FixedList<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, const N: usize, const M: usize> PartialEq<FixedList<T, M>> for FixedList<T, N> {
+    fn eq(&self, other: &FixedList<T, M>) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+#[cfg(test)]
+mod test;
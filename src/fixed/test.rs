@@ -0,0 +1,129 @@
+use super::*;
+use crate::differential::Lcg;
+use std::collections::VecDeque;
+
+#[test]
+fn test_push_pop() {
+    let mut list: FixedList<i64, 3> = FixedList::new();
+    assert!(list.is_empty());
+    assert_eq!(list.capacity(), 3);
+    assert_eq!(list.pop_front(), None);
+    assert_eq!(list.push_back(2), Ok(()));
+    assert_eq!(list.push_front(1), Ok(()));
+    assert_eq!(list.push_back(3), Ok(()));
+    assert!(list.is_full());
+    assert_eq!(list.push_back(4), Err(Full(4)));
+    assert_eq!(list.push_front(0), Err(Full(0)));
+    assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+    assert_eq!(list.front(), Some(&1));
+    assert_eq!(list.back(), Some(&3));
+    assert_eq!(
+        list.iter().rev().copied().collect::<Vec<_>>(),
+        vec![3, 2, 1]
+    );
+
+    assert_eq!(list.pop_back(), Some(3));
+    assert_eq!(list.push_front(0), Ok(()));
+    assert_eq!(list.pop_front(), Some(0));
+    assert_eq!(list.pop_front(), Some(1));
+    assert_eq!(list.pop_back(), Some(2));
+    assert_eq!(list.pop_back(), None);
+}
+
+#[test]
+fn test_full_gives_the_value_back() {
+    let mut list: FixedList<String, 1> = FixedList::new();
+    list.push_back("kept".to_string()).unwrap();
+    let Full(value) = list.insert(0, "bounced".to_string()).unwrap_err();
+    assert_eq!(value, "bounced");
+    assert_eq!(list.front().map(String::as_str), Some("kept"));
+}
+
+#[test]
+fn test_zero_capacity() {
+    let mut list: FixedList<i64, 0> = FixedList::new();
+    assert!(list.is_full());
+    assert_eq!(list.push_back(1), Err(Full(1)));
+    assert_eq!(list.iter().next(), None);
+}
+
+#[test]
+fn test_insert_remove() {
+    let mut list: FixedList<char, 8> = FixedList::new();
+    list.insert(0, 'b').unwrap();
+    list.insert(0, 'a').unwrap();
+    list.insert(2, 'd').unwrap();
+    list.insert(2, 'c').unwrap();
+    assert_eq!(list.iter().collect::<String>(), "abcd");
+    assert_eq!(list.get(2), Some(&'c'));
+    assert_eq!(list.get(4), None);
+    *list.get_mut(3).unwrap() = 'e';
+    assert_eq!(list.remove(1), Some('b'));
+    assert_eq!(list.remove(3), None);
+    assert_eq!(list.iter().collect::<String>(), "ace");
+}
+
+#[test]
+#[should_panic(expected = "insert: the len is 0 but the index is 1")]
+fn test_insert_out_of_range() {
+    let mut list: FixedList<i64, 4> = FixedList::new();
+    let _ = list.insert(1, 1);
+}
+
+#[test]
+fn test_against_vecdeque() {
+    let mut list: FixedList<u64, 16> = FixedList::new();
+    let mut model = VecDeque::new();
+    let mut rng = Lcg::new(7);
+    for step in 0..5000 {
+        let r = rng.next_u64();
+        let at = (r >> 40) as usize % (model.len() + 1);
+        match (r >> 33) % 6 {
+            0 | 1 => {
+                let pushed = list.insert(at, step);
+                if model.len() < 16 {
+                    assert_eq!(pushed, Ok(()));
+                    model.insert(at, step);
+                } else {
+                    assert_eq!(pushed, Err(Full(step)));
+                }
+            }
+            2 => assert_eq!(list.remove(at), model.remove(at)),
+            3 => assert_eq!(list.pop_front(), model.pop_front()),
+            4 => {
+                if list.push_back(step).is_ok() {
+                    model.push_back(step);
+                }
+            }
+            _ => assert_eq!(list.get(at), model.get(at)),
+        }
+        assert_eq!(list.len(), model.len());
+    }
+    assert!(list.iter().eq(model.iter()));
+}
+
+#[test]
+fn test_values_are_dropped() {
+    let counter = std::rc::Rc::new(());
+    {
+        let mut list: FixedList<_, 5> = FixedList::new();
+        for _ in 0..5 {
+            list.push_back(std::rc::Rc::clone(&counter)).unwrap();
+        }
+        assert!(list.push_back(std::rc::Rc::clone(&counter)).is_err());
+        list.remove(2);
+        assert_eq!(std::rc::Rc::strong_count(&counter), 5);
+    }
+    assert_eq!(std::rc::Rc::strong_count(&counter), 1);
+}
+
+#[test]
+fn test_equality() {
+    let mut a: FixedList<i64, 4> = FixedList::new();
+    let mut b: FixedList<i64, 8> = FixedList::new();
+    a.push_back(1).unwrap();
+    b.push_front(1).unwrap();
+    assert_eq!(a, b);
+    b.push_back(2).unwrap();
+    assert_ne!(a, b);
+}
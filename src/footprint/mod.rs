@@ -0,0 +1,171 @@
+#![allow(dead_code)]
+/*
+How much memory a list really takes
+===========================================================================
+
+linked1 measured its lists with size_of, which works because everything
+there is on the stack. From linked4 on, the list itself is a couple of
+pointers and a len, and the values are out on the heap, one node at a time,
+each with its own links. size_of::<linked5::List>() is 24 bytes whether it
+holds nothing or a million values.
+
+heap_size() is the other half: the bytes the list asked the allocator for.
+It's worked out from the types, not measured, so it's cheap enough to call
+anywhere:
+
+    node count * node size            linked4, linked6, linked8, selforg...
+    + the sentinel node               linked6::SentinelList, circular
+    + the Rc counts in front of it    linked5, two words per node
+    Vec capacity * slot size          linked7, used or not
+    chunks * chunk size               unrolled, full or not
+    + each node's Vec of links        skiplist, indexed
+
+The values are counted where the list keeps them, inside the nodes. If they
+own heap memory themselves, like a String, that's not counted: it would be
+the same in any list. Neither is what the allocator keeps for itself around
+each block, which depends on the allocator. So the real cost of a node is a
+bit more than this, and small nodes are the ones that lose the most.
+
+footprint_report() builds every list with the same values and puts it all
+in a table, bytes per element included. The tests check heap_size() against
+what the lists actually allocated, with allocs::measure_allocs().
+*/
+use crate::ops::LinkedListOps;
+use crate::{circular, fixed, hybrid, indexed, linked4, linked5, linked6, linked7, linked8};
+use crate::{selforg, skiplist, unrolled};
+use std::alloc::Layout;
+use std::fmt;
+use std::iter::FromIterator;
+use std::mem::size_of;
+
+pub trait HeapSize {
+    fn heap_size(&self) -> usize;
+}
+
+/* What Rc::new(value) allocates: the strong and weak counts, then the value,
+padded like a struct with those three fields would be. */
+pub(crate) fn rc_size<T>() -> usize {
+    let counts = Layout::new::<[usize; 2]>();
+    let (layout, _) = counts.extend(Layout::new::<T>()).unwrap();
+    layout.pad_to_align().size()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Footprint {
+    pub name: &'static str,
+    pub len: usize,
+    /* size_of the list itself, wherever it lives. */
+    pub inline: usize,
+    pub heap: usize,
+}
+
+impl Footprint {
+    pub fn of<L: HeapSize>(name: &'static str, list: &L, len: usize) -> Self {
+        Footprint {
+            name,
+            len,
+            inline: size_of::<L>(),
+            heap: list.heap_size(),
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.inline + self.heap
+    }
+
+    pub fn bytes_per_element(&self) -> f64 {
+        self.total() as f64 / self.len as f64
+    }
+}
+
+/* A Vec with exactly len values, as what everything else is compared to. */
+impl<T> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>()
+    }
+}
+
+/* Every list, holding the values 0..len as i64. fixed can't hold more than
+its 1024 slots, and it's the same size with any number of values. */
+pub fn footprint_report(len: usize) -> Vec<Footprint> {
+    let values: Vec<i64> = (0..len as i64).collect();
+    let mut fixed: fixed::FixedList<i64, 1024> = fixed::FixedList::new();
+    for value in values.iter().take(1024) {
+        let _ = fixed.push_back(*value);
+    }
+    let mut selforg = selforg::SelfOrgList::new(selforg::Strategy::Static);
+    selforg.extend(values.iter().copied());
+    vec![
+        Footprint::of("Vec", &values, len),
+        Footprint::of("linked4", &linked4::List::from_slice(&values), len),
+        Footprint::of(
+            "linked4::doubly",
+            &linked4::DoublyList::from_slice(&values),
+            len,
+        ),
+        Footprint::of("linked5", &linked5::List::from_vec(&values), len),
+        Footprint::of("linked6", &linked6::List::from_slice(&values), len),
+        Footprint::of(
+            "linked6::sentinel",
+            &linked6::SentinelList::from_slice(&values),
+            len,
+        ),
+        Footprint::of("linked7", &linked7::List::from_slice(&values), len),
+        Footprint::of("linked8", &linked8::List::from_slice(&values), len),
+        Footprint::of(
+            "circular",
+            &circular::CircularList::from_iter(values.clone()),
+            len,
+        ),
+        Footprint::of(
+            "unrolled",
+            &unrolled::UnrolledList::<_>::from_slice(&values),
+            len,
+        ),
+        Footprint::of(
+            "skiplist",
+            &skiplist::SkipList::from_iter(values.clone()),
+            len,
+        ),
+        Footprint::of("indexed", &indexed::IndexedList::from_slice(&values), len),
+        Footprint::of("selforg", &selforg, len),
+        Footprint::of("fixed<1024>", &fixed, len.min(1024)),
+        Footprint::of(
+            "hybrid<8>",
+            &hybrid::HybridList::<_, 8>::from_iter(values.clone()),
+            len,
+        ),
+    ]
+}
+
+/* Like linked1's SizeTable:
+
+    name                 len  inline     heap  per element
+    Vec                 1000      24     8000         8.02
+*/
+pub struct FootprintTable<'a>(pub &'a [Footprint]);
+
+impl<'a> fmt::Display for FootprintTable<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<18} {:>5}  {:>6}  {:>7}  {:>11}",
+            "name", "len", "inline", "heap", "per element"
+        )?;
+        for entry in self.0 {
+            writeln!(
+                f,
+                "{:<18} {:>5}  {:>6}  {:>7}  {:>11.2}",
+                entry.name,
+                entry.len,
+                entry.inline,
+                entry.heap,
+                entry.bytes_per_element()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,116 @@
+use super::*;
+use crate::allocs::measure_allocs;
+use crate::ops::PositionalOps;
+
+const LEN: usize = 100;
+
+/* heap_size() has to be exactly what building the list left allocated. */
+fn check<L: HeapSize>(build: impl FnOnce() -> L) -> L {
+    let (list, allocs) = measure_allocs(build);
+    assert_eq!(list.heap_size() as isize, allocs.net_bytes());
+    list
+}
+
+fn values() -> Vec<i64> {
+    (0..LEN as i64).collect()
+}
+
+#[test]
+fn test_matches_the_allocator() {
+    let values = values();
+    check(|| values.clone());
+    check(|| linked4::List::from_slice(&values));
+    check(|| linked4::DoublyList::from_slice(&values));
+    check(|| linked5::List::from_vec(&values));
+    check(|| linked6::List::from_slice(&values));
+    check(|| linked6::SentinelList::from_slice(&values));
+    check(|| linked7::List::from_slice(&values));
+    check(|| linked8::List::from_slice(&values));
+    check(|| circular::CircularList::from_iter(values.clone()));
+    check(|| unrolled::UnrolledList::<i64>::from_slice(&values));
+    check(|| unrolled::UnrolledList::<i64, 3>::from_slice(&values));
+    check(|| skiplist::SkipList::from_iter(values.clone()));
+    check(|| indexed::IndexedList::from_slice(&values));
+    check(|| {
+        let mut list = selforg::SelfOrgList::new(selforg::Strategy::Static);
+        list.extend(values.iter().copied());
+        list
+    });
+    check(|| {
+        let mut list: fixed::FixedList<i64, 16> = fixed::FixedList::new();
+        list.push_back(1).unwrap();
+        list
+    });
+}
+
+/* And it keeps up as the list changes. Empty, only the sentinels are left. */
+#[test]
+fn test_after_changes() {
+    let values = values();
+    let mut list = check(|| linked6::List::from_slice(&values));
+    let (_, allocs) = measure_allocs(|| {
+        for at in (0..LEN).step_by(3) {
+            list.remove(at / 2);
+        }
+    });
+    assert_eq!(list.len(), LEN - 34);
+    assert_eq!(allocs.net_bytes(), -34 * size_of::<i64>() as isize * 3);
+    assert_eq!(list.heap_size(), (LEN - 34) * 3 * size_of::<i64>());
+
+    /* linked7 keeps its slots. */
+    let mut list = linked7::List::from_slice(&values);
+    let before = list.heap_size();
+    while list.pop_front().is_some() {}
+    assert_eq!(list.heap_size(), before);
+    let mut list = unrolled::UnrolledList::<i64>::from_slice(&values);
+    let before = list.heap_size() as isize;
+    let (_, allocs) = measure_allocs(|| {
+        for at in 0..LEN / 2 {
+            list.remove(at);
+        }
+    });
+    assert_eq!(list.heap_size() as isize, before + allocs.net_bytes());
+
+    assert_eq!(linked6::List::<i64>::new().heap_size(), 0);
+    assert_eq!(
+        linked6::SentinelList::<i64>::new().heap_size(),
+        3 * size_of::<i64>()
+    );
+    assert_eq!(
+        circular::CircularList::<i64>::new().heap_size(),
+        3 * size_of::<i64>()
+    );
+}
+
+#[test]
+fn test_rc_size() {
+    assert_eq!(rc_size::<u8>(), 24);
+    assert_eq!(rc_size::<i64>(), 24);
+    assert_eq!(rc_size::<[u64; 3]>(), 40);
+    let (_, allocs) = measure_allocs(|| std::rc::Rc::new(0u8));
+    assert_eq!(allocs.bytes, rc_size::<u8>());
+}
+
+#[test]
+fn test_report() {
+    let report = footprint_report(1000);
+    let by_name = |name: &str| report.iter().find(|entry| entry.name == name).unwrap();
+    assert_eq!(by_name("Vec").heap, 8000);
+    /* A value and a next: 16 bytes. Add a prev: 24. linked5 also has the
+    RefCell's flag and the Rc's two counts: 48. */
+    assert_eq!(by_name("linked4").heap, 16_000);
+    assert_eq!(by_name("linked8").heap, 16_000);
+    assert_eq!(by_name("linked6").heap, 24_000);
+    assert_eq!(by_name("linked5").heap, 48_000);
+    assert_eq!(by_name("fixed<1024>").heap, 0);
+    /* Spilled: linked6's nodes, and the empty FixedList still inline. */
+    assert_eq!(by_name("hybrid<8>").heap, 24_000);
+    assert!(by_name("hybrid<8>").inline > by_name("linked6").inline);
+    assert!(report
+        .iter()
+        .all(|entry| entry.name == "Vec" || entry.total() > by_name("Vec").total()));
+
+    let table = FootprintTable(&report).to_string();
+    assert_eq!(table.lines().count(), report.len() + 1);
+    assert!(table.contains("linked5"));
+}
@@ -0,0 +1,285 @@
+#![allow(dead_code)]
+/*
+Small lists inline, big ones linked
+===========================================================================
+
+Most lists in a real program are short. A list of the children of a node, of
+the arguments of a call, of the items in a cart: a handful of values, and
+only now and then a few hundred. linked6 pays an allocation per value from
+the very first one, and fixed never allocates but can't hold more than N.
+
+SmallVec's trick works for lists too. HybridList is an enum: up to N values
+it's a FixedList, right inside the HybridList, with no allocation at all.
+The push that doesn't fit moves everything into a linked6::List, the list
+"spills", and from then on it's a linked list like any other:
+
+    Inline(FixedList<T, N>)   0 allocations, up to N values
+    Spilled(linked6::List<T>) 1 allocation per value, as many as you like
+
+Spilling costs N allocations at once, and it happens once. The list doesn't
+go back inline by itself when it shrinks again: a list that grew past N is
+likely to do it again, and one that sits right at the edge would move every
+value back and forth on every push and pop. shrink_to_inline() does it when
+asked.
+
+The catch is in the size. An enum is as big as its biggest variant, and
+that's the FixedList: N slots, each as big as a node. A spilled HybridList
+still carries all of them around, empty. Too big an N and every list pays for
+room it doesn't use; too small and most lists spill anyway. The benchmarks
+(the "hybrid" group) show where the crossover is against plain linked6.
+*/
+use crate::fixed::{self, FixedList, Full};
+use crate::footprint::HeapSize;
+use crate::linked6;
+use crate::ops::PositionalOps;
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+
+pub enum HybridList<T, const N: usize = 8> {
+    Inline(FixedList<T, N>),
+    Spilled(linked6::List<T>),
+}
+
+impl<T, const N: usize> Default for HybridList<T, N> {
+    fn default() -> Self {
+        HybridList::Inline(FixedList::new())
+    }
+}
+
+impl<T, const N: usize> HybridList<T, N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            HybridList::Inline(list) => list.len(),
+            HybridList::Spilled(list) => list.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_inline(&self) -> bool {
+        matches!(self, HybridList::Inline(_))
+    }
+
+    /* Back to an empty inline list, freeing every node if it had spilled. */
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /* Moves the values into a linked6 list, in order, and returns it. If
+    we've spilled already, that's just the list we have. */
+    fn spill(&mut self) -> &mut linked6::List<T> {
+        if let HybridList::Inline(inline) = self {
+            let mut spilled = linked6::List::new();
+            while let Some(value) = inline.pop_front() {
+                spilled.push_back(value);
+            }
+            *self = HybridList::Spilled(spilled);
+        }
+        match self {
+            HybridList::Spilled(list) => list,
+            HybridList::Inline(_) => unreachable!("just spilled"),
+        }
+    }
+
+    /* The other way, if everything fits again. Returns whether the list is
+    inline now. */
+    pub fn shrink_to_inline(&mut self) -> bool {
+        if let HybridList::Spilled(spilled) = self {
+            if spilled.len() > N {
+                return false;
+            }
+            let mut inline = FixedList::new();
+            while let Some(value) = spilled.pop_front() {
+                /* len() <= N, so there's always room. */
+                let _ = inline.push_back(value);
+            }
+            *self = HybridList::Inline(inline);
+        }
+        true
+    }
+
+    /* Each push tries the FixedList first. When it's full we get the value
+    back in the Full, spill, and push it there. */
+    pub fn push_front(&mut self, value: T) {
+        let value = match self {
+            HybridList::Inline(inline) => match inline.push_front(value) {
+                Ok(()) => return,
+                Err(Full(value)) => value,
+            },
+            HybridList::Spilled(_) => value,
+        };
+        self.spill().push_front(value);
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        let value = match self {
+            HybridList::Inline(inline) => match inline.push_back(value) {
+                Ok(()) => return,
+                Err(Full(value)) => value,
+            },
+            HybridList::Spilled(_) => value,
+        };
+        self.spill().push_back(value);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        match self {
+            HybridList::Inline(list) => list.pop_front(),
+            HybridList::Spilled(list) => list.pop_front(),
+        }
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        match self {
+            HybridList::Inline(list) => list.pop_back(),
+            HybridList::Spilled(list) => list.pop_back(),
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        match self {
+            HybridList::Inline(list) => list.front(),
+            HybridList::Spilled(list) => list.front(),
+        }
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        match self {
+            HybridList::Inline(list) => list.back(),
+            HybridList::Spilled(list) => list.back(),
+        }
+    }
+
+    pub fn get(&self, at: usize) -> Option<&T> {
+        match self {
+            HybridList::Inline(list) => list.get(at),
+            HybridList::Spilled(list) => list.iter().nth(at),
+        }
+    }
+
+    /* Panics if `at` is past the end, like Vec::insert. */
+    pub fn insert(&mut self, at: usize, value: T) {
+        let value = match self {
+            HybridList::Inline(inline) => match inline.insert(at, value) {
+                Ok(()) => return,
+                Err(Full(value)) => value,
+            },
+            HybridList::Spilled(_) => value,
+        };
+        PositionalOps::insert(self.spill(), at, value);
+    }
+
+    pub fn remove(&mut self, at: usize) -> Option<T> {
+        match self {
+            HybridList::Inline(list) => list.remove(at),
+            HybridList::Spilled(list) => PositionalOps::remove(list, at),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, N> {
+        match self {
+            HybridList::Inline(list) => Iter::Inline(list.iter()),
+            HybridList::Spilled(list) => Iter::Spilled(list.iter()),
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+/* Nothing while inline, that's the whole point. */
+impl<T, const N: usize> HeapSize for HybridList<T, N> {
+    fn heap_size(&self) -> usize {
+        match self {
+            HybridList::Inline(list) => list.heap_size(),
+            HybridList::Spilled(list) => list.heap_size(),
+        }
+    }
+}
+
+/* The same enum again, one level down. */
+pub enum Iter<'a, T, const N: usize> {
+    Inline(fixed::Iter<'a, T, N>),
+    Spilled(linked6::Iter<'a, T>),
+}
+
+impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Iter::Inline(iter) => iter.next(),
+            Iter::Spilled(iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Iter::Inline(iter) => iter.size_hint(),
+            Iter::Spilled(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<'a, T, const N: usize> DoubleEndedIterator for Iter<'a, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self {
+            Iter::Inline(iter) => iter.next_back(),
+            Iter::Spilled(iter) => iter.next_back(),
+        }
+    }
+}
+
+impl<'a, T, const N: usize> ExactSizeIterator for Iter<'a, T, N> {}
+impl<'a, T, const N: usize> FusedIterator for Iter<'a, T, N> {}
+
+impl<'a, T, const N: usize> IntoIterator for &'a HybridList<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, const N: usize> Extend<T> for HybridList<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for HybridList<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for HybridList<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/* Inline or not doesn't matter, only the values do. */
+impl<T: PartialEq, const N: usize> PartialEq for HybridList<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+#[cfg(test)]
+mod test;
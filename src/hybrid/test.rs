@@ -0,0 +1,85 @@
+use super::*;
+use crate::allocs::measure_allocs;
+
+#[test]
+fn test_spills_past_n() {
+    let mut list: HybridList<i64, 4> = HybridList::new();
+    for value in 0..4 {
+        list.push_back(value);
+    }
+    assert!(list.is_inline());
+    list.push_front(-1);
+    assert!(!list.is_inline());
+    assert_eq!(list.to_vec(), vec![-1, 0, 1, 2, 3]);
+    assert_eq!(list.front(), Some(&-1));
+    assert_eq!(list.back(), Some(&3));
+    assert_eq!(list.get(2), Some(&1));
+    assert_eq!(list.get(5), None);
+
+    /* It stays spilled until asked. */
+    assert_eq!(list.pop_front(), Some(-1));
+    assert_eq!(list.pop_back(), Some(3));
+    assert!(!list.is_inline());
+    assert!(list.shrink_to_inline());
+    assert!(list.is_inline());
+    assert_eq!(list.to_vec(), vec![0, 1, 2]);
+    list.extend(3..10);
+    assert!(!list.shrink_to_inline());
+    list.clear();
+    assert!(list.is_inline() && list.is_empty());
+}
+
+#[test]
+fn test_insert_remove() {
+    let mut list: HybridList<i64, 3> = vec![1, 3].into_iter().collect();
+    list.insert(1, 2);
+    assert!(list.is_inline());
+    /* Full: this one goes in the middle of the spilled list. */
+    list.insert(1, 10);
+    assert!(!list.is_inline());
+    assert_eq!(list.to_vec(), vec![1, 10, 2, 3]);
+    assert_eq!(list.remove(1), Some(10));
+    assert_eq!(list.remove(3), None);
+    list.insert(3, 4);
+    assert_eq!(list.to_vec(), vec![1, 2, 3, 4]);
+}
+
+#[test]
+#[should_panic(expected = "insert: the len is 2 but the index is 3")]
+fn test_insert_out_of_range() {
+    let mut list: HybridList<i64, 3> = vec![1, 2].into_iter().collect();
+    list.insert(3, 0);
+}
+
+/* The whole point: nothing allocated up to N, and one node per value after
+the spill, the first N included. */
+#[test]
+fn test_allocations() {
+    let (list, allocs) = measure_allocs(|| (0..8).collect::<HybridList<i64, 8>>());
+    assert_eq!(allocs.allocs, 0);
+    assert_eq!(list.heap_size(), 0);
+    let (list, allocs) = measure_allocs(|| (0..9).collect::<HybridList<i64, 8>>());
+    assert_eq!(allocs.allocs, 9);
+    assert_eq!(list.heap_size() as isize, allocs.net_bytes());
+}
+
+#[test]
+fn test_traits() {
+    let inline: HybridList<i64, 8> = vec![3, 8, 1].into_iter().collect();
+    let mut spilled: HybridList<i64, 8> = (0..20).collect();
+    while spilled.len() > 3 {
+        spilled.pop_front();
+    }
+    assert_ne!(inline, spilled);
+    spilled.clear();
+    spilled.extend(vec![3, 8, 1]);
+    assert_eq!(inline, spilled);
+    assert_eq!(format!("{:?}", inline), "[3, 8, 1]");
+    assert_eq!(
+        (&inline).into_iter().rev().copied().collect::<Vec<_>>(),
+        vec![1, 8, 3]
+    );
+    let spilled: HybridList<i64, 2> = (0..5).collect();
+    assert_eq!(spilled.iter().len(), 5);
+    assert_eq!(spilled.iter().next_back(), Some(&4));
+}
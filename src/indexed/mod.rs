@@ -0,0 +1,415 @@
+#![allow(dead_code)]
+/*
+Indexable skip lists: random access in O(log n)
+===========================================================================
+
+"Linked lists have terrible random access" is the first thing anybody says
+about them, and it's true: get(i) walks i nodes. The skip list fixed search
+by adding express lanes on top of the list. The same lanes can fix indexing
+too, if every link also remembers how far it jumps.
+
+So every link here has a width: how many nodes it skips over on level 0.
+Walking to position i is then like searching in a skip list, except that
+instead of comparing values we add up widths: on every level, run along
+while the next jump doesn't go past i, then go down. O(log n) on average,
+the same as the search.
+
+Inserting or removing a node changes the widths of the links that jump over
+it, but those are exactly the predecessors the search already found, one per
+level: a node in the middle gets its links split in two, and every link
+above it just gets one wider (or narrower). Still O(log n).
+
+Unlike SkipList, this isn't sorted by itself. It's a sequence, like a Vec or
+a VecDeque, where values go wherever insert_at() puts them. But if they're
+kept sorted (with insert_sorted(), for example), rank() tells how many values
+are smaller than a given one, also in O(log n), by adding up widths during a
+normal search. With get() that makes an order statistics list: the median is
+just get(len / 2).
+
+Like in the skip list, the levels come from coin flips, the nodes are linked
+with NonNull like linked6, and every node holds its tower of links in a Vec.
+
+To keep the arithmetic free of special cases, the head counts as position 0,
+the nodes as 1 to len, and a link that points to nothing jumps to an
+imaginary node at len + 1.
+
+In benches/benchmark.rs, scattered gets on 10k values are about 3 times
+faster than with the unrolled list, which has to hop from chunk to chunk.
+That gap grows with the length: the unrolled list is still O(n / 32).
+*/
+use crate::footprint::HeapSize;
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ptr::NonNull;
+
+const MAX_LEVEL: usize = 32;
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+struct Span<T> {
+    next: Link<T>,
+    width: usize,
+}
+
+impl<T> Clone for Span<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Span<T> {}
+
+struct Node<T> {
+    value: T,
+    spans: Vec<Span<T>>,
+}
+
+pub struct IndexedList<T> {
+    head: [Span<T>; MAX_LEVEL],
+    levels: usize,
+    len: usize,
+    rng: u64,
+    _boxes: PhantomData<Box<Node<T>>>,
+}
+
+unsafe impl<T: Send> Send for IndexedList<T> {}
+unsafe impl<T: Sync> Sync for IndexedList<T> {}
+
+impl<T> Default for IndexedList<T> {
+    fn default() -> Self {
+        Self::with_seed(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+impl<T> IndexedList<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        IndexedList {
+            head: [Span {
+                next: None,
+                width: 1,
+            }; MAX_LEVEL],
+            levels: 0,
+            len: 0,
+            rng: seed.max(1),
+            _boxes: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn levels(&self) -> usize {
+        self.levels
+    }
+
+    pub fn clear(&mut self) {
+        let mut cur = self.head[0].next;
+        while let Some(node) = cur {
+            let node = unsafe { Box::from_raw(node.as_ptr()) };
+            cur = node.spans[0].next;
+        }
+        self.head[0] = Span {
+            next: None,
+            width: 1,
+        };
+        self.levels = 0;
+        self.len = 0;
+    }
+
+    fn random_level(&mut self) -> usize {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (1 + self.rng.trailing_ones() as usize).min(MAX_LEVEL)
+    }
+
+    /* The link at `level` of a node, where None is the head. */
+    fn span(&self, node: Link<T>, level: usize) -> Span<T> {
+        match node {
+            None => self.head[level],
+            Some(node) => unsafe { (&(*node.as_ptr()).spans)[level] },
+        }
+    }
+
+    fn span_mut(&mut self, node: Link<T>, level: usize) -> &mut Span<T> {
+        match node {
+            None => &mut self.head[level],
+            Some(node) => unsafe { &mut (&mut (*node.as_ptr()).spans)[level] },
+        }
+    }
+
+    fn value_of(&self, node: NonNull<Node<T>>) -> &T {
+        unsafe { &(*node.as_ptr()).value }
+    }
+
+    /* For every level, the last node at a position below `position`, and
+    that node's position. */
+    fn predecessors(&self, position: usize) -> [(Link<T>, usize); MAX_LEVEL] {
+        let mut update = [(None, 0); MAX_LEVEL];
+        let (mut cur, mut pos) = (None, 0);
+        for level in (0..self.levels).rev() {
+            loop {
+                let span = self.span(cur, level);
+                match span.next {
+                    Some(next) if pos + span.width < position => {
+                        cur = Some(next);
+                        pos += span.width;
+                    }
+                    _ => break,
+                }
+            }
+            update[level] = (cur, pos);
+        }
+        update
+    }
+
+    fn node_at(&self, index: usize) -> Link<T> {
+        if index >= self.len {
+            return None;
+        }
+        let update = self.predecessors(index + 1);
+        self.span(update[0].0, 0).next
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.node_at(index).map(|node| self.value_of(node))
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.node_at(index)
+            .map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    /* Inserts the value so that it ends up at `index`. Panics if `index` is
+    past the end, like Vec::insert. */
+    pub fn insert_at(&mut self, index: usize, value: T) {
+        assert!(
+            index <= self.len,
+            "insert_at: the len is {} but the index is {}",
+            self.len,
+            index
+        );
+        let position = index + 1;
+        let mut update = self.predecessors(position);
+        let height = self.random_level();
+        /* New levels start with the head jumping straight to the end. */
+        for (level, slot) in update.iter_mut().enumerate().take(height).skip(self.levels) {
+            *slot = (None, 0);
+            self.head[level] = Span {
+                next: None,
+                width: self.len + 1,
+            };
+        }
+        self.levels = self.levels.max(height);
+        let node = NonNull::from(Box::leak(Box::new(Node {
+            value,
+            spans: Vec::with_capacity(height),
+        })));
+        for (level, &(prev, pos)) in update.iter().enumerate().take(self.levels) {
+            let span = self.span(prev, level);
+            if level < height {
+                /* The old jump from prev is split in two around the new
+                node. Where it landed is one further now. */
+                unsafe {
+                    (*node.as_ptr()).spans.push(Span {
+                        next: span.next,
+                        width: pos + span.width + 1 - position,
+                    })
+                };
+                *self.span_mut(prev, level) = Span {
+                    next: Some(node),
+                    width: position - pos,
+                };
+            } else {
+                self.span_mut(prev, level).width += 1;
+            }
+        }
+        self.len += 1;
+    }
+
+    pub fn remove_at(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        let update = self.predecessors(index + 1);
+        let target = self.span(update[0].0, 0).next?;
+        let node = unsafe { Box::from_raw(target.as_ptr()) };
+        for (level, &(prev, _)) in update.iter().enumerate().take(self.levels) {
+            let prev_span = self.span_mut(prev, level);
+            match node.spans.get(level) {
+                /* Two jumps become one, minus the node that's gone. */
+                Some(span) => {
+                    prev_span.next = span.next;
+                    prev_span.width += span.width - 1;
+                }
+                None => prev_span.width -= 1,
+            }
+        }
+        while self.levels > 0 && self.head[self.levels - 1].next.is_none() {
+            self.levels -= 1;
+        }
+        self.len -= 1;
+        Some(node.value)
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        self.insert_at(0, value);
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        self.insert_at(self.len, value);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.remove_at(0)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.remove_at(self.len.checked_sub(1)?)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head[0].next,
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+/* These only make sense if the list is sorted. If it isn't, the answers are
+meaningless (but still safe), the same deal as slice::binary_search. */
+impl<T: Ord> IndexedList<T> {
+    /* How many values are smaller than `value`, which is also the index
+    where it is, or where it would go. */
+    pub fn rank(&self, value: &T) -> usize {
+        let (mut cur, mut pos) = (None, 0);
+        for level in (0..self.levels).rev() {
+            loop {
+                let span = self.span(cur, level);
+                match span.next {
+                    Some(next) if self.value_of(next) < value => {
+                        cur = Some(next);
+                        pos += span.width;
+                    }
+                    _ => break,
+                }
+            }
+        }
+        pos
+    }
+
+    /* Inserts before any equal values, and returns where it went. */
+    pub fn insert_sorted(&mut self, value: T) -> usize {
+        let index = self.rank(&value);
+        self.insert_at(index, value);
+        index
+    }
+}
+
+impl<T> Drop for IndexedList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/* Like the skip list's, with a width next to every link. */
+impl<T> HeapSize for IndexedList<T> {
+    fn heap_size(&self) -> usize {
+        let mut bytes = 0;
+        let mut cur = self.head[0].next;
+        while let Some(node) = cur {
+            let node = unsafe { &*node.as_ptr() };
+            bytes += size_of::<Node<T>>() + node.spans.capacity() * size_of::<Span<T>>();
+            cur = node.spans[0].next;
+        }
+        bytes
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Link<T>,
+    remaining: usize,
+    _list: PhantomData<&'a Node<T>>,
+}
+
+/* Only reads nodes of a list it borrows, so it's a &IndexedList<T> as far as
+threads are concerned. */
+unsafe impl<'a, T: Sync> Send for Iter<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for Iter<'a, T> {}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = unsafe { &*self.next?.as_ptr() };
+        self.next = node.spans[0].next;
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+impl<'a, T> IntoIterator for &'a IndexedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> Extend<T> for IndexedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for IndexedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for IndexedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for IndexedList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+#[cfg(test)]
+mod test;
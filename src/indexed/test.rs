@@ -0,0 +1,115 @@
+use super::*;
+use crate::differential::{Harness, Lcg, Op};
+
+/* Walks every level and checks that each width is the real distance, in
+level 0 positions, to where the link lands. */
+fn check_widths<T>(list: &IndexedList<T>) {
+    let mut positions = std::collections::HashMap::new();
+    let mut cur = list.head[0].next;
+    let mut pos = 1;
+    while let Some(node) = cur {
+        positions.insert(node, pos);
+        cur = list.span(cur, 0).next;
+        pos += 1;
+    }
+    assert_eq!(positions.len(), list.len());
+    for level in 0..list.levels() {
+        let (mut cur, mut pos) = (None, 0);
+        loop {
+            let span = list.span(cur, level);
+            let landing = match span.next {
+                Some(next) => positions[&next],
+                None => list.len() + 1,
+            };
+            assert_eq!(span.width, landing - pos, "level {}", level);
+            match span.next {
+                Some(next) => {
+                    cur = Some(next);
+                    pos = landing;
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[test]
+fn test_push_pop() {
+    let mut list = IndexedList::new();
+    assert_eq!(list.pop_back(), None);
+    list.push_back(2);
+    list.push_back(3);
+    list.push_front(1);
+    check_widths(&list);
+    assert_eq!(list.to_vec(), vec![1, 2, 3]);
+    assert_eq!(list.get(1), Some(&2));
+    assert_eq!(list.get(3), None);
+    *list.get_mut(2).unwrap() = 30;
+    assert_eq!(format!("{:?}", list), "[1, 2, 30]");
+    assert_eq!(list.pop_back(), Some(30));
+    assert_eq!(list.pop_front(), Some(1));
+    assert_eq!(list.pop_front(), Some(2));
+    assert!(list.is_empty());
+    assert_eq!(list.levels(), 0);
+    list.push_back(4);
+    assert_eq!(list.iter().len(), 1);
+}
+
+#[test]
+fn test_against_vec() {
+    let mut harness: Harness<IndexedList<i64>> = Harness::new();
+    for (step, op) in Op::random_sequence(11, 4000).into_iter().enumerate() {
+        harness.positional_op(op);
+        if step % 100 == 1 {
+            check_widths(harness.list());
+        }
+    }
+    check_widths(harness.list());
+}
+
+#[test]
+fn test_order_statistics() {
+    let mut list = IndexedList::new();
+    let mut model: Vec<u64> = Vec::new();
+    let mut rng = Lcg::new(5);
+    for _ in 0..1000 {
+        let value = (rng.next_u64() >> 33) % 500;
+        let index = list.insert_sorted(value);
+        assert_eq!(index, model.partition_point(|v| *v < value));
+        model.insert(index, value);
+    }
+    check_widths(&list);
+    assert_eq!(list.to_vec(), model);
+    for value in 0..510 {
+        assert_eq!(list.rank(&value), model.partition_point(|v| *v < value));
+    }
+    assert_eq!(list.get(list.len() / 2), Some(&model[500]));
+}
+
+#[test]
+#[should_panic(expected = "insert_at: the len is 1 but the index is 2")]
+fn test_insert_out_of_range() {
+    let mut list = IndexedList::new();
+    list.push_back(1);
+    list.insert_at(2, 2);
+}
+
+#[test]
+fn test_levels_are_logarithmic() {
+    let list: IndexedList<u32> = (0..1 << 14).collect();
+    assert!(list.levels() >= 10 && list.levels() <= 24);
+    assert_eq!(list.get(12345), Some(&12345));
+}
+
+#[test]
+fn test_values_are_dropped() {
+    let counter = std::rc::Rc::new(());
+    {
+        let mut list: IndexedList<_> = (0..50).map(|_| counter.clone()).collect();
+        list.remove_at(10);
+        list.clear();
+        list.push_back(counter.clone());
+        assert_eq!(std::rc::Rc::strong_count(&counter), 2);
+    }
+    assert_eq!(std::rc::Rc::strong_count(&counter), 1);
+}
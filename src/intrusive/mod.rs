@@ -0,0 +1,292 @@
+#![allow(dead_code)]
+/*
+Intrusive lists: the nodes are yours
+===========================================================================
+
+Every list so far allocates its own nodes and copies (or moves) our values
+into them. An intrusive list turns that around: our own type carries the
+links, as a field, and the list just threads through values that already
+exist, wherever they are. Nothing is allocated, ever. Pushing and removing
+only rewrites a few pointers.
+
+This is how the Linux kernel keeps most of its lists, and how async runtimes
+keep the tasks waiting on a mutex or a channel: each waiting future has a
+link field, lives on some stack or inside some other future, and joins the
+queue of waiters without allocating. Removing one from the middle (because
+it got cancelled) is O(1), because it knows where its own links are.
+
+There are two problems to solve.
+
+The first one is getting from a value to its links and back. A type opts in
+by holding a Link<Self> and implementing Linked, which just says which field
+it is. The trait is unsafe because the list trusts it blindly: it has to
+return the same Link every time.
+
+The second one is that the list keeps pointers to values it doesn't own, so
+those values can't move or go away while they're in it. Going away is what
+the 'a lifetime takes care of: the list borrows every node for 'a, so the
+borrow checker won't let them be dropped or mutated before the list is gone.
+Moving is what Pin is for. Link contains PhantomPinned, so any type holding
+one is !Unpin, and once it's been pinned it will never be moved again, until
+it's dropped. Strictly, with the borrow we'd be fine without Pin. But the
+runtimes go one step further and let a node unlink itself when it's dropped,
+without any borrow keeping the list alive, and that's only sound if it can't
+have moved since it was linked. Pin is the promise that makes that possible,
+so it's part of the API from the start.
+
+Links are Cells, because the nodes are only ever borrowed shared: many
+nodes, one list, and the user may still be reading them.
+*/
+use std::cell::Cell;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::marker::{PhantomData, PhantomPinned};
+use std::pin::Pin;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub struct Link<T> {
+    prev: Cell<Option<NonNull<T>>>,
+    next: Cell<Option<NonNull<T>>>,
+    /* The id of the list the node is in, or 0. Lists can move around, so
+    their address is no good to tell them apart. */
+    owner: Cell<usize>,
+    _pinned: PhantomPinned,
+}
+
+impl<T> Link<T> {
+    pub const fn new() -> Self {
+        Link {
+            prev: Cell::new(None),
+            next: Cell::new(None),
+            owner: Cell::new(0),
+            _pinned: PhantomPinned,
+        }
+    }
+
+    pub fn is_linked(&self) -> bool {
+        self.owner.get() != 0
+    }
+
+    fn reset(&self) {
+        self.prev.set(None);
+        self.next.set(None);
+        self.owner.set(0);
+    }
+}
+
+impl<T> Default for Link<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Link<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Link")
+            .field("linked", &self.is_linked())
+            .finish()
+    }
+}
+
+/* Safety: link() must always return the same field of self, or the list
+will end up following pointers into whatever it returned instead. */
+#[allow(clippy::missing_safety_doc)]
+pub unsafe trait Linked: Sized {
+    fn link(&self) -> &Link<Self>;
+}
+
+static NEXT_LIST_ID: AtomicUsize = AtomicUsize::new(1);
+
+pub struct List<'a, T: Linked> {
+    id: usize,
+    head: Option<NonNull<T>>,
+    tail: Option<NonNull<T>>,
+    len: usize,
+    _nodes: PhantomData<Pin<&'a T>>,
+}
+
+impl<'a, T: Linked> Default for List<'a, T> {
+    fn default() -> Self {
+        List {
+            id: NEXT_LIST_ID.fetch_add(1, Ordering::Relaxed),
+            head: None,
+            tail: None,
+            len: 0,
+            _nodes: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Linked> List<'a, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /* Every pointer in the list came from a &'a T, so it's valid for 'a. */
+    fn node(&self, node: NonNull<T>) -> &'a T {
+        unsafe { &*node.as_ptr() }
+    }
+
+    /* And every node came in pinned, so it can go out pinned. */
+    fn pinned(&self, node: NonNull<T>) -> Pin<&'a T> {
+        unsafe { Pin::new_unchecked(self.node(node)) }
+    }
+
+    fn claim(&self, node: &T) {
+        assert!(
+            !node.link().is_linked(),
+            "intrusive: the node is already in a list"
+        );
+        node.link().owner.set(self.id);
+    }
+
+    pub fn push_back(&mut self, node: Pin<&'a T>) {
+        let node = node.get_ref();
+        self.claim(node);
+        let link = node.link();
+        link.prev.set(self.tail);
+        let ptr = NonNull::from(node);
+        match self.tail {
+            Some(tail) => self.node(tail).link().next.set(Some(ptr)),
+            None => self.head = Some(ptr),
+        }
+        self.tail = Some(ptr);
+        self.len += 1;
+    }
+
+    pub fn push_front(&mut self, node: Pin<&'a T>) {
+        let node = node.get_ref();
+        self.claim(node);
+        let link = node.link();
+        link.next.set(self.head);
+        let ptr = NonNull::from(node);
+        match self.head {
+            Some(head) => self.node(head).link().prev.set(Some(ptr)),
+            None => self.tail = Some(ptr),
+        }
+        self.head = Some(ptr);
+        self.len += 1;
+    }
+
+    /* The node must be in this list. */
+    fn unlink(&mut self, ptr: NonNull<T>) {
+        let link = self.node(ptr).link();
+        let (prev, next) = (link.prev.get(), link.next.get());
+        match prev {
+            Some(prev) => self.node(prev).link().next.set(next),
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => self.node(next).link().prev.set(prev),
+            None => self.tail = prev,
+        }
+        link.reset();
+        self.len -= 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<Pin<&'a T>> {
+        let head = self.head?;
+        self.unlink(head);
+        Some(self.pinned(head))
+    }
+
+    pub fn pop_back(&mut self) -> Option<Pin<&'a T>> {
+        let tail = self.tail?;
+        self.unlink(tail);
+        Some(self.pinned(tail))
+    }
+
+    pub fn front(&self) -> Option<Pin<&'a T>> {
+        self.head.map(|node| self.pinned(node))
+    }
+
+    pub fn back(&self) -> Option<Pin<&'a T>> {
+        self.tail.map(|node| self.pinned(node))
+    }
+
+    /* O(1): the node knows which list it's in. */
+    pub fn contains(&self, node: &T) -> bool {
+        node.link().owner.get() == self.id
+    }
+
+    /* Also O(1), the node knows its neighbours. Returns false if the node
+    isn't in this list. */
+    pub fn remove(&mut self, node: &T) -> bool {
+        if !self.contains(node) {
+            return false;
+        }
+        self.unlink(NonNull::from(node));
+        true
+    }
+
+    pub fn iter(&self) -> Iter<'_, 'a, T> {
+        Iter {
+            next: self.head,
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+}
+
+/* The nodes outlive the list, and may go into another one after this. So
+they're all unlinked on the way out. */
+impl<'a, T: Linked> Drop for List<'a, T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+/* Borrows the list, so it can't change under the iterator, but hands out
+the nodes for all of 'a. */
+pub struct Iter<'l, 'a, T: Linked> {
+    next: Option<NonNull<T>>,
+    remaining: usize,
+    _list: PhantomData<&'l List<'a, T>>,
+}
+
+impl<'l, 'a, T: Linked> Iterator for Iter<'l, 'a, T> {
+    type Item = Pin<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node: &'a T = unsafe { &*self.next?.as_ptr() };
+        self.next = node.link().next.get();
+        self.remaining -= 1;
+        Some(unsafe { Pin::new_unchecked(node) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'l, 'a, T: Linked> ExactSizeIterator for Iter<'l, 'a, T> {}
+impl<'l, 'a, T: Linked> FusedIterator for Iter<'l, 'a, T> {}
+
+impl<'l, 'a, T: Linked> IntoIterator for &'l List<'a, T> {
+    type Item = Pin<&'a T>;
+    type IntoIter = Iter<'l, 'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T: Linked + fmt::Debug> fmt::Debug for List<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.iter().map(Pin::get_ref))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test;
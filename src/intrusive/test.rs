@@ -0,0 +1,121 @@
+use super::*;
+use std::pin::pin;
+
+/* A stand-in for a future waiting on a lock or a channel: it lives wherever
+the task put it, and joins the queue of waiters through its own link. */
+#[derive(Debug)]
+struct Waiter {
+    id: u32,
+    woken: Cell<bool>,
+    link: Link<Waiter>,
+}
+
+impl Waiter {
+    fn new(id: u32) -> Self {
+        Waiter {
+            id,
+            woken: Cell::new(false),
+            link: Link::new(),
+        }
+    }
+}
+
+unsafe impl Linked for Waiter {
+    fn link(&self) -> &Link<Self> {
+        &self.link
+    }
+}
+
+fn wake_one(queue: &mut List<'_, Waiter>) -> Option<u32> {
+    let waiter = queue.pop_front()?;
+    waiter.woken.set(true);
+    Some(waiter.id)
+}
+
+fn ids(queue: &List<'_, Waiter>) -> Vec<u32> {
+    queue.iter().map(|w| w.id).collect()
+}
+
+#[test]
+fn test_wait_queue() {
+    let a = pin!(Waiter::new(1));
+    let b = pin!(Waiter::new(2));
+    let c = pin!(Waiter::new(3));
+    let mut queue = List::new();
+    assert!(queue.is_empty());
+    queue.push_back(a.as_ref());
+    queue.push_back(b.as_ref());
+    queue.push_back(c.as_ref());
+    assert_eq!(queue.len(), 3);
+    assert_eq!(ids(&queue), vec![1, 2, 3]);
+    assert!(a.link.is_linked());
+
+    assert_eq!(wake_one(&mut queue), Some(1));
+    assert!(a.woken.get());
+    assert!(!a.link.is_linked());
+    assert!(!b.woken.get());
+    assert_eq!(queue.front().map(|w| w.id), Some(2));
+    assert_eq!(queue.back().map(|w| w.id), Some(3));
+}
+
+#[test]
+fn test_cancel_from_the_middle() {
+    let waiters = [
+        pin!(Waiter::new(1)),
+        pin!(Waiter::new(2)),
+        pin!(Waiter::new(3)),
+        pin!(Waiter::new(4)),
+    ];
+    let mut queue = List::new();
+    for w in waiters.iter() {
+        queue.push_back(w.as_ref());
+    }
+    // Waiter 2's task was cancelled: it leaves the queue without being woken.
+    assert!(queue.remove(&waiters[1]));
+    assert!(!queue.remove(&waiters[1]));
+    assert!(queue.remove(&waiters[3]));
+    assert_eq!(ids(&queue), vec![1, 3]);
+    assert_eq!(queue.back().map(|w| w.id), Some(3));
+    // And it can wait again, at the front this time.
+    queue.push_front(waiters[1].as_ref());
+    assert_eq!(ids(&queue), vec![2, 1, 3]);
+
+    let mut woken = vec![];
+    while let Some(id) = wake_one(&mut queue) {
+        woken.push(id);
+    }
+    assert_eq!(woken, vec![2, 1, 3]);
+    assert!(!waiters[3].woken.get());
+    assert!(queue.pop_back().is_none());
+}
+
+#[test]
+#[should_panic(expected = "intrusive: the node is already in a list")]
+fn test_node_in_two_lists_panics() {
+    let a = pin!(Waiter::new(1));
+    let mut first = List::new();
+    let mut second = List::new();
+    first.push_back(a.as_ref());
+    second.push_back(a.as_ref());
+}
+
+#[test]
+fn test_lists_tell_nodes_apart() {
+    let a = pin!(Waiter::new(1));
+    let b = pin!(Waiter::new(2));
+    let mut first = List::new();
+    first.push_back(a.as_ref());
+    {
+        let mut second = List::new();
+        second.push_back(b.as_ref());
+        assert!(!first.contains(&b));
+        assert!(!first.remove(&b));
+        assert!(second.contains(&b));
+        assert_eq!(second.iter().len(), 1);
+    }
+    // Dropping the second list unlinked b, so it can join the first one.
+    assert!(!b.link.is_linked());
+    first.push_back(b.as_ref());
+    assert_eq!(ids(&first), vec![1, 2]);
+    assert!(format!("{:?}", first).starts_with("[Waiter { id: 1"));
+}
@@ -0,0 +1,92 @@
+#![allow(dead_code)]
+/*
+Checking the links, after every change
+===========================================================================
+
+Every list that keeps more than a bare chain makes promises to itself: len
+is the number of nodes, tail is the last one, every prev points back at the
+node whose next points to us, every hole in linked7's Vec is on the free
+list. The unsafe ones depend on them: a tail pointer to a freed node is a
+use after free on the next push_back().
+
+linked5 had assert_invariants() for a while. This makes it a trait, for all
+the lists with promises like that, and returns what's wrong instead of
+panicking, so a test can check that a broken list is caught as broken.
+
+The lists also check themselves, with debug_check(), at the end of every
+method that changes them. Only in debug builds, and only while they're short:
+check() walks the whole list, and doing that after every push turns building
+a million node list into n^2 steps. Bugs in the links don't need long lists
+to show up, the tests and the differential harness find them on short ones.
+*/
+use std::error::Error;
+use std::fmt;
+
+/* Up to here the lists check themselves after every change. */
+pub const DEBUG_CHECK_MAX_LEN: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvariantError {
+    /* len says one thing, walking the list another. */
+    WrongLen { len: usize, counted: usize },
+    /* A list with no first node still has a last one, or the other way. */
+    HeadWithoutTail,
+    TailWithoutHead,
+    /* The first node has a prev, or the last one a next. */
+    PrevBeforeHead,
+    NextAfterTail,
+    /* Following next from `index` and then prev doesn't come back. */
+    BrokenPrev { index: usize },
+    /* The cached tail isn't the last node, or can't be reached at all. */
+    WrongTail,
+    /* linked7: a slot is both in the list and a hole, or in neither. */
+    BrokenFreeList { slot: usize },
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantError::WrongLen { len, counted } => {
+                write!(f, "len is {} but there are {} nodes", len, counted)
+            }
+            InvariantError::HeadWithoutTail => write!(f, "list has a head but no tail"),
+            InvariantError::TailWithoutHead => write!(f, "empty list with a tail"),
+            InvariantError::PrevBeforeHead => write!(f, "first node has a prev"),
+            InvariantError::NextAfterTail => write!(f, "last node has a next"),
+            InvariantError::BrokenPrev { index } => {
+                write!(f, "node {} is not the prev of its next", index)
+            }
+            InvariantError::WrongTail => write!(f, "tail is not the last node"),
+            InvariantError::BrokenFreeList { slot } => {
+                write!(f, "slot {} is wrong on the free list", slot)
+            }
+        }
+    }
+}
+
+impl Error for InvariantError {}
+
+pub trait CheckInvariants {
+    fn check(&self) -> Result<(), InvariantError>;
+
+    /* Panics with what's wrong. For tests. */
+    fn assert_check(&self) {
+        if let Err(err) = self.check() {
+            panic!("broken list: {}", err);
+        }
+    }
+}
+
+/* The hook the lists call after changing. `len` is what the list believes
+its length is, which is enough to decide whether it's too long to walk.
+Whatever the check allocates isn't the list's, so measure_allocs() doesn't
+see it. */
+#[inline]
+pub(crate) fn debug_check<L: CheckInvariants + ?Sized>(list: &L, len: usize) {
+    if cfg!(debug_assertions) && len <= DEBUG_CHECK_MAX_LEN {
+        crate::allocs::uncounted(|| list.assert_check());
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,71 @@
+use super::*;
+use crate::differential::{run_deque, run_positional, run_queue, Op};
+use crate::{linked4, linked5, linked6, linked7, linked8};
+
+/* The lists check themselves after every step, so just running them through
+the differential harness already exercises every check() on healthy lists.
+Here we also check them at the end, from the outside. */
+#[test]
+fn test_healthy_lists_pass() {
+    for seed in 0..10 {
+        let ops = Op::random_sequence(seed, 300);
+        run_positional::<linked4::List<i64>>(&ops)
+            .list()
+            .assert_check();
+        run_positional::<linked6::List<i64>>(&ops)
+            .list()
+            .assert_check();
+        run_deque::<linked5::List>(&ops).list().assert_check();
+        run_deque::<linked7::List<i64>>(&ops).list().assert_check();
+        run_queue::<linked8::List<i64>>(&ops).list().assert_check();
+    }
+}
+
+#[test]
+fn test_empty_lists_pass() {
+    assert_eq!(linked4::List::<i64>::default().check(), Ok(()));
+    assert_eq!(linked5::List::new().check(), Ok(()));
+    assert_eq!(linked6::List::<i64>::new().check(), Ok(()));
+    assert_eq!(linked7::List::<i64>::new().check(), Ok(()));
+    assert_eq!(linked8::List::<i64>::new().check(), Ok(()));
+}
+
+#[test]
+fn test_messages() {
+    assert_eq!(
+        InvariantError::WrongLen { len: 3, counted: 2 }.to_string(),
+        "len is 3 but there are 2 nodes"
+    );
+    assert_eq!(
+        InvariantError::BrokenPrev { index: 4 }.to_string(),
+        "node 4 is not the prev of its next"
+    );
+    assert_eq!(
+        InvariantError::BrokenFreeList { slot: 1 }.to_string(),
+        "slot 1 is wrong on the free list"
+    );
+}
+
+/* Always broken, and says how long it is. */
+struct Broken(usize);
+
+impl CheckInvariants for Broken {
+    fn check(&self) -> Result<(), InvariantError> {
+        Err(InvariantError::WrongTail)
+    }
+}
+
+#[test]
+#[should_panic(expected = "broken list: tail is not the last node")]
+fn test_assert_check() {
+    Broken(0).assert_check();
+}
+
+#[test]
+fn test_debug_check() {
+    let short = std::panic::catch_unwind(|| debug_check(&Broken(3), 3));
+    assert_eq!(short.is_err(), cfg!(debug_assertions));
+    /* Too long to walk after every change, so it isn't. */
+    let len = DEBUG_CHECK_MAX_LEN + 1;
+    debug_check(&Broken(len), len);
+}
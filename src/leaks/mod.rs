@@ -0,0 +1,170 @@
+#![allow(dead_code)]
+/*
+Counting drops
+===========================================================================
+
+A list that forgets to free a node doesn't crash, it doesn't even look wrong:
+every test that reads the values back still passes. linked5 is the easy one to
+get wrong. Its prev links are Weak on purpose, and one strong prev would be
+enough for two nodes to keep each other alive forever, quietly.
+
+So the values themselves keep count. A Tracker hands out DropTrackers, each
+one a number plus a shared counter. Creating or cloning one counts up, dropping
+one counts down and writes down which value went. After the list is gone, the
+counter has to be back at zero: nothing leaked. And since every value that
+was dropped is written down, a value dropped twice shows up too.
+
+The counts are atomics, so the lists that move values between threads can
+carry them as well. They're leaked on purpose, a few bytes per Tracker: if a
+DropTracker held an Arc, dropping one twice would also free the Arc twice,
+and the check meant to catch that would be a bug of its own.
+
+linked5 only holds i64, so it can't carry a DropTracker. For that one the
+test keeps a Weak to each node (see linked5::List::weak_nodes()) and checks
+that none of them can be upgraded once the list is dropped.
+*/
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+#[derive(Default)]
+struct Counts {
+    created: AtomicUsize,
+    dropped: AtomicUsize,
+    order: Mutex<Vec<i64>>,
+}
+
+#[derive(Clone, Copy)]
+pub struct Tracker {
+    counts: &'static Counts,
+}
+
+impl Default for Tracker {
+    fn default() -> Self {
+        Tracker {
+            counts: Box::leak(Box::default()),
+        }
+    }
+}
+
+impl Tracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn track(&self, value: i64) -> DropTracker {
+        self.counts.created.fetch_add(1, Ordering::SeqCst);
+        DropTracker {
+            value,
+            counts: self.counts,
+        }
+    }
+
+    /* Values 0..len, like the other test helpers. */
+    pub fn values(&self, len: usize) -> Vec<DropTracker> {
+        (0..len as i64).map(|value| self.track(value)).collect()
+    }
+
+    pub fn created(&self) -> usize {
+        self.counts.created.load(Ordering::SeqCst)
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.counts.dropped.load(Ordering::SeqCst)
+    }
+
+    pub fn alive(&self) -> usize {
+        self.created() - self.dropped()
+    }
+
+    /* The values, in the order they were dropped. */
+    pub fn drop_order(&self) -> Vec<i64> {
+        self.counts.order.lock().unwrap().clone()
+    }
+
+    pub fn assert_no_leaks(&self) {
+        assert_eq!(
+            self.alive(),
+            0,
+            "{} of {} values were never dropped",
+            self.alive(),
+            self.created()
+        );
+    }
+}
+
+pub struct DropTracker {
+    value: i64,
+    counts: &'static Counts,
+}
+
+impl DropTracker {
+    pub fn value(&self) -> i64 {
+        self.value
+    }
+}
+
+/* A clone is one more value that has to be dropped. */
+impl Clone for DropTracker {
+    fn clone(&self) -> Self {
+        self.counts.created.fetch_add(1, Ordering::SeqCst);
+        DropTracker {
+            value: self.value,
+            counts: self.counts,
+        }
+    }
+}
+
+/* Dropping the same value twice is usually a use after free too, and the
+count going over what was created is the first place it shows. */
+impl Drop for DropTracker {
+    fn drop(&mut self) {
+        let dropped = self.counts.dropped.fetch_add(1, Ordering::SeqCst) + 1;
+        assert!(
+            dropped <= self.counts.created.load(Ordering::SeqCst),
+            "value {} dropped twice",
+            self.value
+        );
+        /* Don't panic again on a poisoned lock, we may be unwinding already. */
+        if let Ok(mut order) = self.counts.order.lock() {
+            order.push(self.value);
+        }
+    }
+}
+
+/* Compared by value, so the sorted lists can hold them. */
+impl PartialEq for DropTracker {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Eq for DropTracker {}
+
+impl PartialOrd for DropTracker {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DropTracker {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl Hash for DropTracker {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+impl fmt::Debug for DropTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+#[cfg(test)]
+mod test;
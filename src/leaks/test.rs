@@ -0,0 +1,568 @@
+use super::*;
+use crate::ops::{DoubleEndedOps, LinkedListOps};
+use crate::{branded, circular, fixed, hybrid, indexed, intrusive, linked3, linked4, linked5};
+use crate::{linked6, linked7, linked8, lockfree, selforg, skiplist, sync, unrolled};
+use std::borrow::Borrow;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::rc::Rc;
+
+const LEN: usize = 100;
+
+fn values(list: impl IntoIterator<Item = DropTracker>) -> Vec<i64> {
+    list.into_iter().map(|value| value.value()).collect()
+}
+
+/* Panics at the third value, like a closure that panics in the middle of
+a for loop over the list. */
+fn panic_halfway<I: Borrow<DropTracker>>(iter: impl Iterator<Item = I>) {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        for (i, value) in iter.enumerate() {
+            assert!(value.borrow().value() > -2);
+            if i == 2 {
+                panic!("halfway");
+            }
+        }
+    }));
+    assert!(result.is_err());
+}
+
+/* What every queue can do: push at both ends, pop at the front, iterate and
+be dropped with values still inside. */
+fn check_queue<L: LinkedListOps<Item = DropTracker>>() {
+    let tracker = Tracker::new();
+    let mut list = L::from_slice(&tracker.values(LEN));
+    list.push_front(tracker.track(-1));
+    list.push_back(tracker.track(LEN as i64));
+    assert_eq!(list.pop_front().map(|value| value.value()), Some(-1));
+    assert_eq!(tracker.alive(), LEN + 1);
+    /* A panic while iterating leaves the list alone. */
+    panic_halfway(list.iter());
+    assert_eq!(list.len(), LEN + 1);
+    drop(list);
+    tracker.assert_no_leaks();
+}
+
+fn check_deque<L: DoubleEndedOps<Item = DropTracker>>() {
+    check_queue::<L>();
+    let tracker = Tracker::new();
+    let mut list = L::from_slice(&tracker.values(LEN));
+    assert_eq!(
+        list.pop_back().map(|value| value.value()),
+        Some(LEN as i64 - 1)
+    );
+    while list.len() > LEN / 2 {
+        list.pop_back();
+        list.pop_front();
+    }
+    assert_eq!(tracker.alive(), list.len());
+    drop(list);
+    tracker.assert_no_leaks();
+}
+
+/* The tracker itself: a forgotten list is reported, and so is a value
+dropped twice. */
+#[test]
+#[should_panic(expected = "100 of 100 values were never dropped")]
+fn test_tracker_reports_leaks() {
+    let tracker = Tracker::new();
+    let list: linked6::List<DropTracker> = tracker.values(LEN).into_iter().collect();
+    std::mem::forget(list);
+    tracker.assert_no_leaks();
+}
+
+#[test]
+#[should_panic(expected = "value 7 dropped twice")]
+fn test_tracker_reports_double_drops() {
+    let tracker = Tracker::new();
+    let value = tracker.track(7);
+    let copy = unsafe { std::ptr::read(&value) };
+    drop(value);
+    drop(copy);
+}
+
+#[test]
+fn test_tracker_counts() {
+    let tracker = Tracker::new();
+    let values = tracker.values(3);
+    let clone = values[1].clone();
+    assert_eq!((tracker.created(), tracker.alive()), (4, 4));
+    drop(values);
+    assert_eq!(tracker.drop_order(), vec![0, 1, 2]);
+    assert_eq!(clone.value(), 1);
+    drop(clone);
+    tracker.assert_no_leaks();
+}
+
+/* The Box chains drop front to back, one node at a time, and so do the
+lists that clear() by popping. */
+#[test]
+fn test_drop_order() {
+    let front_to_back: Vec<i64> = (0..LEN as i64).collect();
+    let tracker = Tracker::new();
+    drop(linked4::List::from_slice(&tracker.values(LEN)));
+    assert_eq!(&tracker.drop_order()[LEN..], &front_to_back[..]);
+
+    let tracker = Tracker::new();
+    let list: linked6::List<DropTracker> = tracker.values(LEN).into_iter().collect();
+    drop(list);
+    assert_eq!(tracker.drop_order(), front_to_back);
+
+    let tracker = Tracker::new();
+    let list: linked8::List<DropTracker> = tracker.values(LEN).into_iter().collect();
+    drop(list);
+    assert_eq!(tracker.drop_order(), front_to_back);
+}
+
+#[test]
+fn test_linked4_list() {
+    check_deque::<linked4::List<DropTracker>>();
+
+    let tracker = Tracker::new();
+    let mut list = linked4::List::from_slice(&tracker.values(LEN));
+    let rest = list.split_off(LEN / 2);
+    assert_eq!(
+        values(rest),
+        (LEN as i64 / 2..LEN as i64).collect::<Vec<_>>()
+    );
+    list.clear();
+    assert_eq!(tracker.alive(), 0);
+
+    /* A half eaten IntoIter drops the rest, even while unwinding. */
+    let list = linked4::List::from_slice(&tracker.values(LEN));
+    panic_halfway(list.into_iter());
+    tracker.assert_no_leaks();
+
+    /* sort_by() and retain() run our closures with the chain taken out of
+    the list. If one panics, the nodes it held are dropped on the way out. */
+    let mut list = linked4::List::from_slice(&tracker.values(LEN));
+    let sorting = panic::catch_unwind(AssertUnwindSafe(|| {
+        list.sort_by(|a, b| {
+            assert_ne!(a.value(), LEN as i64 / 2, "halfway");
+            a.cmp(b)
+        })
+    }));
+    assert!(sorting.is_err());
+    assert_eq!(tracker.alive(), list.len());
+    list.append_slice(&tracker.values(LEN));
+    let retaining = panic::catch_unwind(AssertUnwindSafe(|| {
+        list.retain(|value| {
+            assert_ne!(value.value(), LEN as i64 / 2, "halfway");
+            value.value() % 2 == 0
+        })
+    }));
+    assert!(retaining.is_err());
+    assert_eq!(tracker.alive(), list.len());
+
+    /* Draining only halfway still empties the list. */
+    list.append_slice(&tracker.values(LEN));
+    list.drain().take(LEN / 2).for_each(drop);
+    assert!(list.is_empty());
+    list.append_slice(&tracker.values(LEN));
+    let odd = list
+        .drain_filter(|value| value.value() % 2 == 1)
+        .take(3)
+        .count();
+    assert_eq!((odd, tracker.alive()), (3, LEN - 3));
+
+    let mut cursor = list.cursor_front_mut();
+    cursor.move_next();
+    cursor.remove_current();
+    let rest = cursor.split_after();
+    drop(rest);
+    drop(list);
+    tracker.assert_no_leaks();
+}
+
+#[test]
+fn test_linked4_doubly() {
+    check_deque::<linked4::DoublyList<DropTracker>>();
+}
+
+/* linked5 only holds i64, so it's the nodes we follow, through Weak. */
+#[test]
+fn test_linked5() {
+    fn assert_freed(nodes: &[std::rc::Weak<std::cell::RefCell<linked5::Node>>]) {
+        let leaked = nodes.iter().filter(|node| node.upgrade().is_some()).count();
+        assert_eq!(leaked, 0, "{} of {} nodes leaked", leaked, nodes.len());
+    }
+    let values: Vec<i64> = (0..LEN as i64).collect();
+    let mut list = linked5::List::from_vec(&values);
+    let mut nodes = list.weak_nodes();
+    list.insert_first(-1);
+    list.append(LEN as i64);
+    nodes.extend(list.weak_nodes());
+    list.pop_first();
+    list.pop_tail();
+    list.truncate(LEN - 10);
+    let (front, mut back) = list.split_at(LEN / 2);
+    back.concat(linked5::List::from_vec(&values));
+    nodes.extend(back.weak_nodes());
+    /* A handle from iter_mut() keeps its node alive, and through `next`
+    every node after it, until the handle goes too. */
+    let handles: Vec<_> = back.iter_mut().take(3).collect();
+    drop(back);
+    assert!(nodes
+        .iter()
+        .any(|node| node.ptr_eq(&Rc::downgrade(&handles[0]))));
+    assert_eq!(Rc::strong_count(&handles[0]), 1);
+    drop(handles);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        for value in front.iter() {
+            assert_ne!(value, LEN as i64 / 4, "halfway");
+        }
+    }));
+    assert!(result.is_err());
+    drop(front);
+    assert_freed(&nodes);
+}
+
+#[test]
+#[should_panic(expected = "100 of 100 nodes leaked")]
+fn test_linked5_reports_leaks() {
+    let list = linked5::List::from_vec(&(0..LEN as i64).collect::<Vec<_>>());
+    let nodes = list.weak_nodes();
+    std::mem::forget(list);
+    let leaked = nodes.iter().filter(|node| node.upgrade().is_some()).count();
+    assert_eq!(leaked, 0, "{} of {} nodes leaked", leaked, nodes.len());
+}
+
+#[test]
+fn test_linked6_list() {
+    check_deque::<linked6::List<DropTracker>>();
+
+    let tracker = Tracker::new();
+    let mut list: linked6::List<DropTracker> = tracker.values(LEN).into_iter().collect();
+    let mut rest = list.split_off(LEN / 2);
+    list.append(&mut rest);
+    assert!(rest.is_empty());
+    let rest = list.cursor_front_mut().split_after();
+    assert_eq!(rest.len(), LEN - 1);
+    drop(rest);
+    list.clear();
+    tracker.assert_no_leaks();
+
+    let list: linked6::List<DropTracker> = tracker.values(LEN).into_iter().collect();
+    panic_halfway(list.into_iter());
+    tracker.assert_no_leaks();
+}
+
+/* A pooled node has had its value moved out: the pool must never drop it
+again, and recycling it must not leave the value behind. */
+#[test]
+fn test_linked6_pool() {
+    let tracker = Tracker::new();
+    let mut list = linked6::List::with_pool(linked6::NodePool::with_limit(LEN / 2));
+    for value in tracker.values(LEN) {
+        list.push_back(value);
+        if list.len() > 5 {
+            list.pop_front();
+        }
+    }
+    list.clear();
+    tracker.assert_no_leaks();
+    list.extend(tracker.values(LEN));
+    list.cursor_front_mut().remove_current();
+    let pool = list.take_pool();
+    panic_halfway(list.into_iter());
+    drop(pool);
+    tracker.assert_no_leaks();
+}
+
+#[test]
+fn test_linked6_sentinel() {
+    check_deque::<linked6::SentinelList<DropTracker>>();
+    let tracker = Tracker::new();
+    let mut list: linked6::SentinelList<DropTracker> = tracker.values(LEN).into_iter().collect();
+    list.remove(LEN / 2);
+    list.clear();
+    tracker.assert_no_leaks();
+}
+
+#[test]
+fn test_linked7() {
+    check_deque::<linked7::List<DropTracker>>();
+    let tracker = Tracker::new();
+    let mut list = linked7::List::new();
+    let handles: Vec<_> = tracker
+        .values(LEN)
+        .into_iter()
+        .map(|value| list.push_back(value))
+        .collect();
+    for handle in handles.iter().step_by(3) {
+        list.remove(*handle);
+    }
+    /* The freed slots get reused. */
+    list.insert_after(handles[1], tracker.track(-1));
+    list.clear();
+    tracker.assert_no_leaks();
+}
+
+#[test]
+fn test_linked8() {
+    check_queue::<linked8::List<DropTracker>>();
+    let tracker = Tracker::new();
+    let mut list: linked8::List<DropTracker> = tracker.values(LEN).into_iter().collect();
+    list.clear();
+    tracker.assert_no_leaks();
+}
+
+#[test]
+fn test_unrolled() {
+    check_deque::<unrolled::UnrolledList<DropTracker, 4>>();
+    let tracker = Tracker::new();
+    let mut list: unrolled::UnrolledList<DropTracker, 4> = unrolled::UnrolledList::new();
+    for (i, value) in tracker.values(LEN).into_iter().enumerate() {
+        list.insert(i / 2, value);
+    }
+    while list.len() > LEN / 2 {
+        list.remove(list.len() / 3);
+    }
+    list.clear();
+    tracker.assert_no_leaks();
+}
+
+#[test]
+fn test_indexed() {
+    check_deque::<indexed::IndexedList<DropTracker>>();
+    let tracker = Tracker::new();
+    let mut list = indexed::IndexedList::with_seed(5);
+    for value in tracker.values(LEN).into_iter().rev() {
+        list.insert_sorted(value);
+    }
+    list.remove_at(LEN / 2);
+    list.clear();
+    tracker.assert_no_leaks();
+}
+
+/* A full list hands the value back instead of keeping it. */
+#[test]
+fn test_fixed() {
+    let tracker = Tracker::new();
+    let mut list: fixed::FixedList<DropTracker, 8> = fixed::FixedList::new();
+    for value in tracker.values(LEN) {
+        if let Err(fixed::Full(value)) = list.push_back(value) {
+            assert!(list.is_full());
+            drop(value);
+        }
+    }
+    list.pop_front();
+    list.remove(3);
+    list.insert(2, tracker.track(-1)).unwrap();
+    panic_halfway(list.iter());
+    drop(list);
+    tracker.assert_no_leaks();
+
+    let mut list: fixed::FixedList<DropTracker, 8> = fixed::FixedList::new();
+    list.push_front(tracker.track(0)).unwrap();
+    list.clear();
+    tracker.assert_no_leaks();
+}
+
+/* Spilling moves every value out of the FixedList, and shrinking moves them
+back: none may be dropped on the way, or twice. */
+#[test]
+fn test_hybrid() {
+    check_deque::<hybrid::HybridList<DropTracker, 4>>();
+    let tracker = Tracker::new();
+    let mut list: hybrid::HybridList<DropTracker, 4> = tracker.values(6).into_iter().collect();
+    list.pop_back();
+    list.pop_back();
+    list.remove(1);
+    assert!(list.shrink_to_inline());
+    list.insert(1, tracker.track(-1));
+    list.insert(1, tracker.track(-2));
+    panic_halfway(list.iter());
+    drop(list);
+    tracker.assert_no_leaks();
+}
+
+#[test]
+fn test_circular() {
+    let tracker = Tracker::new();
+    let mut ring: circular::CircularList<DropTracker> = tracker.values(LEN).into_iter().collect();
+    ring.rotate(7);
+    ring.pop();
+    ring.push_front(tracker.track(-1));
+    panic_halfway(ring.iter_cycle());
+    drop(ring);
+    tracker.assert_no_leaks();
+
+    let mut ring: circular::CircularList<DropTracker> = tracker.values(LEN).into_iter().collect();
+    ring.clear();
+    tracker.assert_no_leaks();
+}
+
+/* A value that's already there is dropped instead of inserted. */
+#[test]
+fn test_skiplist() {
+    let tracker = Tracker::new();
+    let mut list = skiplist::SkipList::with_seed(3);
+    for value in tracker.values(LEN) {
+        list.insert(value);
+    }
+    assert!(!list.insert(tracker.track(0)));
+    assert_eq!(tracker.alive(), LEN);
+    list.remove(&tracker.track(LEN as i64 / 2));
+    panic_halfway(list.iter());
+    drop(list);
+    tracker.assert_no_leaks();
+
+    let mut list = skiplist::SkipList::new();
+    list.insert(tracker.track(1));
+    list.clear();
+    tracker.assert_no_leaks();
+}
+
+#[test]
+fn test_selforg() {
+    let tracker = Tracker::new();
+    let mut list = selforg::SelfOrgList::new(selforg::Strategy::MoveToFront);
+    for value in tracker.values(LEN) {
+        list.push_back(value);
+    }
+    list.find(&tracker.track(LEN as i64 - 1));
+    list.remove(&tracker.track(3));
+    panic_halfway(list.iter());
+    drop(list);
+    tracker.assert_no_leaks();
+
+    let mut list = selforg::SelfOrgList::new(selforg::Strategy::Transpose);
+    list.push_front(tracker.track(1));
+    list.clear();
+    tracker.assert_no_leaks();
+}
+
+/* The arena owns every value it handed out, until it goes itself. */
+#[test]
+fn test_linked3_arena() {
+    let tracker = Tracker::new();
+    let arena = linked3::Arena::with_capacity(4);
+    let refs: Vec<&DropTracker> = tracker
+        .values(LEN)
+        .into_iter()
+        .map(|value| arena.alloc(value))
+        .collect();
+    assert_eq!(refs[LEN - 1].value(), LEN as i64 - 1);
+    assert_eq!(tracker.alive(), LEN);
+    drop(arena);
+    tracker.assert_no_leaks();
+}
+
+struct Node {
+    value: DropTracker,
+    link: intrusive::Link<Node>,
+}
+
+unsafe impl intrusive::Linked for Node {
+    fn link(&self) -> &intrusive::Link<Self> {
+        &self.link
+    }
+}
+
+/* The intrusive list owns nothing: the nodes are ours, and are dropped by
+us, linked or not. */
+#[test]
+fn test_intrusive() {
+    let tracker = Tracker::new();
+    let nodes: Vec<Pin<Box<Node>>> = tracker
+        .values(LEN)
+        .into_iter()
+        .map(|value| {
+            Box::pin(Node {
+                value,
+                link: intrusive::Link::new(),
+            })
+        })
+        .collect();
+    let mut list = intrusive::List::new();
+    for node in &nodes {
+        list.push_back(node.as_ref());
+    }
+    list.pop_front();
+    assert_eq!(list.front().map(|node| node.value.value()), Some(1));
+    drop(list);
+    drop(nodes);
+    tracker.assert_no_leaks();
+}
+
+#[test]
+fn test_branded() {
+    let tracker = Tracker::new();
+    branded::GhostToken::new(|mut token| {
+        let mut list = branded::List::new();
+        for value in tracker.values(LEN) {
+            list.push_back(value, &mut token);
+        }
+        list.pop_front(&mut token);
+        list.pop_back(&mut token);
+        panic_halfway(list.iter(&token));
+    });
+    tracker.assert_no_leaks();
+}
+
+#[test]
+fn test_sync() {
+    let tracker = Tracker::new();
+    let list = sync::SyncList::new();
+    for value in tracker.values(LEN) {
+        list.push_back(value);
+    }
+    list.push_front(tracker.track(-1));
+    list.pop_front();
+    list.pop_back();
+    drop(list);
+    tracker.assert_no_leaks();
+}
+
+/* Whatever is still in them when they're dropped is dropped with them. Not
+under loom: its atomics only work inside loom::model(). */
+#[cfg(not(loom))]
+#[test]
+fn test_lockfree() {
+    let tracker = Tracker::new();
+    let stack = lockfree::Stack::new();
+    let queue = lockfree::Queue::new();
+    std::thread::scope(|s| {
+        for t in 0..3 {
+            let (stack, queue, tracker) = (&stack, &queue, &tracker);
+            s.spawn(move || {
+                for i in 0..LEN as i64 {
+                    stack.push(tracker.track(t * 1000 + i));
+                    queue.push(tracker.track(t * 1000 + i));
+                    if i % 2 == 0 {
+                        stack.pop();
+                        queue.pop();
+                    }
+                }
+            });
+        }
+    });
+    drop(stack);
+    drop(queue);
+    tracker.assert_no_leaks();
+
+    let (sender, receiver) = lockfree::mpsc::channel();
+    for value in tracker.values(LEN) {
+        sender.send(value);
+    }
+    receiver.try_recv();
+    drop(sender);
+    drop(receiver);
+    tracker.assert_no_leaks();
+}
+
+#[cfg(feature = "crossbeam")]
+#[test]
+fn test_lockfree_epoch() {
+    let tracker = Tracker::new();
+    let stack = lockfree::EpochStack::new();
+    for value in tracker.values(LEN) {
+        stack.push(value);
+    }
+    stack.pop();
+    drop(stack);
+    /* Popped nodes are freed once the epoch moves on, which may be after
+    we look. The values themselves are dropped right away though. */
+    tracker.assert_no_leaks();
+}
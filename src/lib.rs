@@ -1,5 +1,41 @@
+/* linked1 nests types hundreds of levels deep, see nested_list! there. */
+#![recursion_limit = "512"]
+/* std's Allocator trait, instead of arena's copy of it. Nightly only. */
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 pub mod linked1;
 pub mod linked2;
 pub mod linked3;
 pub mod linked4;
-pub mod linked5;
\ No newline at end of file
+pub mod linked5;
+pub mod linked6;
+pub mod linked7;
+pub mod linked8;
+pub mod circular;
+pub mod unrolled;
+pub mod skiplist;
+pub mod intrusive;
+pub mod branded;
+pub mod selforg;
+pub mod fixed;
+pub mod hybrid;
+pub mod arena;
+pub mod indexed;
+pub mod lockfree;
+pub mod sync;
+pub mod ops;
+pub mod differential;
+pub mod oputil;
+pub mod invariants;
+pub mod topology;
+pub mod allocs;
+pub mod footprint;
+pub mod profiling;
+#[cfg(test)]
+mod properties;
+#[cfg(test)]
+mod soak;
+#[cfg(test)]
+mod leaks;
+#[cfg(test)]
+mod panics;
+pub mod threadsafety;
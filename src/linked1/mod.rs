@@ -1,5 +1,7 @@
 #![allow(dead_code)]
-use std::mem::size_of;
+use std::fmt;
+use std::mem::{align_of, size_of};
+use std::num::NonZeroI64;
 /*
 Value-Only Linked Lists
 ===========================================================================
@@ -32,7 +34,7 @@ It fails because it has infinite size. Is it possible to declare something
 that inlines the result without going infinite? i.e. It has finite size.
 */
 
-struct LinkedList2<T> {
+pub struct LinkedList2<T> {
     value: i64,
     next: Option<T>,
 }
@@ -123,6 +125,80 @@ pub fn size_l65() {
 }
 */
 
+/*
+It turns out the aliases weren't the problem. The error says "queries overflow
+the depth limit" while computing the layout of the type: every level of
+nesting is a couple of nested layout queries (the struct and its Option), and
+the default limit is 128. So L64 fits and L65 doesn't, no matter how we spell
+it. The fix is raising the limit for the crate, which lib.rs now does with
+#![recursion_limit = "512"].
+
+Spelling it is still annoying though. To get L<L<L<...<()>...>>> with N levels
+we'd need a new alias for every size. So here's a macro: nested_list!(65) is
+that type, 65 levels deep.
+
+macro_rules! can't count down from a number, so the counting is done once,
+with a trait: Depth<N> knows its type is L<the type of Depth<N - 1>>. The
+depths! macro writes that impl for every pair of consecutive numbers we give
+it, up to 256, which is the deepest that the recursion limit allows.
+*/
+pub struct Depth<const N: usize>;
+
+pub trait Nested {
+    type List;
+}
+
+impl Nested for Depth<0> {
+    type List = ();
+}
+
+macro_rules! depths {
+    ($prev:literal $next:literal $($rest:literal)*) => {
+        impl Nested for Depth<$next> {
+            type List = L<<Depth<$prev> as Nested>::List>;
+        }
+        depths!($next $($rest)*);
+    };
+    ($last:literal) => {};
+}
+
+depths!(
+    0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28
+    29 30 31 32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53
+    54 55 56 57 58 59 60 61 62 63 64 65 66 67 68 69 70 71 72 73 74 75 76 77 78
+    79 80 81 82 83 84 85 86 87 88 89 90 91 92 93 94 95 96 97 98 99 100 101 102
+    103 104 105 106 107 108 109 110 111 112 113 114 115 116 117 118 119 120 121
+    122 123 124 125 126 127 128 129 130 131 132 133 134 135 136 137 138 139 140
+    141 142 143 144 145 146 147 148 149 150 151 152 153 154 155 156 157 158 159
+    160 161 162 163 164 165 166 167 168 169 170 171 172 173 174 175 176 177 178
+    179 180 181 182 183 184 185 186 187 188 189 190 191 192 193 194 195 196 197
+    198 199 200 201 202 203 204 205 206 207 208 209 210 211 212 213 214 215 216
+    217 218 219 220 221 222 223 224 225 226 227 228 229 230 231 232 233 234 235
+    236 237 238 239 240 241 242 243 244 245 246 247 248 249 250 251 252 253 254
+    255 256
+);
+
+#[macro_export]
+macro_rules! nested_list {
+    ($n:literal) => {
+        <$crate::linked1::Depth<$n> as $crate::linked1::Nested>::List
+    };
+}
+
+type L65 = nested_list!(65);
+pub fn size_l65() {
+    println!("Size of L65: {}", size_of::<L65>()) // 528 bytes
+}
+
+/*
+528 bytes: 65*8 for the values, and still only 8 bytes for the Options. So it
+was never one bit per Option. All of them share a single byte: the innermost
+Option uses 0 and 1 in it for None and Some, the next one out uses 2 for its
+own None, and so on. That byte is really counting how many values there are.
+A byte runs out after 256 or so levels, which is where a second word appears:
+nested_list!(256) takes 2064 bytes, not 2056.
+*/
+
 /*
 You might say, this is stupid, it's simply an array [Option<i64>; 8] or
 whatever. And you will be right. Or is it? Let's check!
@@ -138,6 +214,256 @@ It's 128 bytes, not 72 bytes! Rust cannot pack the options together for an
 array, so it's using two words per item.
 */
 
+/*
+But we can do the packing ourselves. That's what the nested L<L<...>> types
+were doing behind our backs: one word for all the "is there a value" flags,
+and then the values, one after the other. So let's write exactly that, with
+an array for the values and a u64 for the flags, one bit per slot.
+
+Const generics let us choose the capacity without nesting types, and unlike
+L8 or L64, we can actually use it: push, pop and iterate. A u64 has 64 bits,
+so that's also the maximum capacity, same as L64.
+*/
+
+/* Returned by push() when there's no room left. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Full;
+
+pub struct InlineList<const N: usize> {
+    values: [i64; N],
+    present: u64,
+}
+
+impl<const N: usize> Default for InlineList<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> InlineList<N> {
+    /* Checked when the type is used, so InlineList<65> fails to compile
+    instead of losing values at runtime. */
+    const FITS_IN_BITMAP: () = assert!(N <= 64, "InlineList holds at most 64 values");
+
+    pub fn new() -> Self {
+        let () = Self::FITS_IN_BITMAP;
+        InlineList {
+            values: [0; N],
+            present: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /* Values are only pushed and popped at the end, so the bits set are
+    always the first ones, and counting them is the length. */
+    pub fn len(&self) -> usize {
+        self.present.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.present == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+
+    pub fn push(&mut self, value: i64) -> Result<(), Full> {
+        let index = self.len();
+        if index == N {
+            return Err(Full);
+        }
+        self.values[index] = value;
+        self.present |= 1 << index;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<i64> {
+        let index = self.len().checked_sub(1)?;
+        self.present &= !(1 << index);
+        Some(self.values[index])
+    }
+
+    pub fn get(&self, index: usize) -> Option<i64> {
+        if index < N && self.present & (1 << index) != 0 {
+            Some(self.values[index])
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> InlineIter<'_, N> {
+        InlineIter {
+            list: self,
+            index: 0,
+        }
+    }
+
+    /* The whole list lives inline, so this is all the memory it uses. */
+    pub fn size(&self) -> usize {
+        size_of::<Self>()
+    }
+}
+
+pub struct InlineIter<'a, const N: usize> {
+    list: &'a InlineList<N>,
+    index: usize,
+}
+
+impl<'a, const N: usize> Iterator for InlineIter<'a, N> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.list.get(self.index)?;
+        self.index += 1;
+        Some(value)
+    }
+}
+
+pub fn size_inline_list() {
+    println!("Size of InlineList<8>: {}", size_of::<InlineList<8>>()) // 72 bytes
+}
+
+/*
+72 bytes for 8 values, the same as L8, and 520 for InlineList<64>, the same as
+L64. The nested types pack even tighter than this, though: see L65 above.
+*/
+
+/*
+Still, L8 and friends deserve an API of their own. They're types we can
+build and measure but not use, and it turns out they can be used: through a
+trait, implemented once for each kind of piece they're made of, each one
+calling into the next.
+
+- () is the end, it can't hold anything.
+- LinkedList2<T> holds its value, and passes the rest to its `next`.
+- Option<T> is either nothing, or a T.
+
+LinkedList2 always has its value, so it can't be empty, and its first value
+can't be popped. For a list that can be empty, wrap it: Option<L8>.
+
+The iterator can't keep a cursor into the chain, because every level is a
+different type. So it asks by index, and get() walks down from the top every
+time. That's O(n) per value, but n is 256 at most anyway.
+*/
+pub trait InlineChain: Sized {
+    const CAPACITY: usize;
+
+    /* A chain holding just this value, or Full if it can't hold any. */
+    fn from_value(value: i64) -> Result<Self, Full>;
+    fn len(&self) -> usize;
+    fn get(&self, index: usize) -> Option<i64>;
+    fn push(&mut self, value: i64) -> Result<(), Full>;
+    fn pop(&mut self) -> Option<i64>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /* Not called iter(), as Option already has one of its own. */
+    fn values(&self) -> InlineChainIter<'_, Self> {
+        InlineChainIter {
+            chain: self,
+            index: 0,
+        }
+    }
+}
+
+impl InlineChain for () {
+    const CAPACITY: usize = 0;
+
+    fn from_value(_value: i64) -> Result<Self, Full> {
+        Err(Full)
+    }
+    fn len(&self) -> usize {
+        0
+    }
+    fn get(&self, _index: usize) -> Option<i64> {
+        None
+    }
+    fn push(&mut self, _value: i64) -> Result<(), Full> {
+        Err(Full)
+    }
+    fn pop(&mut self) -> Option<i64> {
+        None
+    }
+}
+
+impl<T: InlineChain> InlineChain for LinkedList2<T> {
+    const CAPACITY: usize = 1 + T::CAPACITY;
+
+    fn from_value(value: i64) -> Result<Self, Full> {
+        Ok(LinkedList2 { value, next: None })
+    }
+    fn len(&self) -> usize {
+        1 + self.next.len()
+    }
+    fn get(&self, index: usize) -> Option<i64> {
+        match index {
+            0 => Some(self.value),
+            _ => self.next.get(index - 1),
+        }
+    }
+    fn push(&mut self, value: i64) -> Result<(), Full> {
+        self.next.push(value)
+    }
+    /* Only pops from `next`: our own value stays. */
+    fn pop(&mut self) -> Option<i64> {
+        self.next.pop()
+    }
+}
+
+impl<T: InlineChain> InlineChain for Option<T> {
+    const CAPACITY: usize = T::CAPACITY;
+
+    fn from_value(value: i64) -> Result<Self, Full> {
+        T::from_value(value).map(Some)
+    }
+    fn len(&self) -> usize {
+        self.as_ref().map_or(0, T::len)
+    }
+    fn get(&self, index: usize) -> Option<i64> {
+        self.as_ref()?.get(index)
+    }
+    fn push(&mut self, value: i64) -> Result<(), Full> {
+        match self {
+            Some(inner) => inner.push(value),
+            None => {
+                *self = Some(T::from_value(value)?);
+                Ok(())
+            }
+        }
+    }
+    /* If the inner chain is down to the one value it can't pop, the Option
+    takes it by becoming None. */
+    fn pop(&mut self) -> Option<i64> {
+        let inner = self.as_mut()?;
+        if inner.len() > 1 {
+            inner.pop()
+        } else {
+            self.take()?.get(0)
+        }
+    }
+}
+
+pub struct InlineChainIter<'a, C> {
+    chain: &'a C,
+    index: usize,
+}
+
+impl<'a, C: InlineChain> Iterator for InlineChainIter<'a, C> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.chain.get(self.index)?;
+        self.index += 1;
+        Some(value)
+    }
+}
+
 /*
 And yes, the examples on this file do not follow the definition of what
 a Linked List is: The items are not linked together, because we're not storing
@@ -171,6 +497,202 @@ to be effectively of zero size, because it uses the NULL (0x00) value to store
 None. For regular values, the Option takes 1 word (8 bytes) plus the type size.
 */
 
+/*
+That trick has a name: a niche. It's any bit pattern a type can never hold,
+which Option can borrow to mean None. References and Box can't be null, so
+null is a niche. NonZeroI64 can't be 0. An enum only uses the discriminants
+it declares, so every other value is a niche. A plain i64 uses all its 64
+bits, so there's no niche and Option needs a tag of its own.
+
+Let's turn that into something we can check. LayoutInfo records a type's size
+and alignment, and the size of an Option of it: if they're the same, the
+Option found a niche.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutInfo {
+    pub name: &'static str,
+    pub size: usize,
+    pub align: usize,
+    pub option_size: usize,
+}
+
+impl LayoutInfo {
+    pub fn of<T>(name: &'static str) -> Self {
+        LayoutInfo {
+            name,
+            size: size_of::<T>(),
+            align: align_of::<T>(),
+            option_size: size_of::<Option<T>>(),
+        }
+    }
+
+    pub fn has_niche(&self) -> bool {
+        self.option_size == self.size
+    }
+}
+
+/* Explicit discriminants leave holes: 0, 3, 5..=255 are all niches. */
+enum Explicit {
+    A = 1,
+    B = 2,
+    C = 4,
+}
+
+pub fn niche_report() -> Vec<LayoutInfo> {
+    vec![
+        LayoutInfo::of::<i64>("i64"),
+        LayoutInfo::of::<NonZeroI64>("NonZeroI64"),
+        LayoutInfo::of::<Box<i64>>("Box<i64>"),
+        LayoutInfo::of::<&i64>("&i64"),
+        LayoutInfo::of::<Explicit>("Explicit"),
+        LayoutInfo::of::<Option<i64>>("Option<i64>"),
+        LayoutInfo::of::<L2i>("L2i"),
+    ]
+}
+
+/*
+Rust is also free to reorder struct fields to waste less padding, and to put
+enums in the smallest integer that fits. #[repr(C)] takes both freedoms away:
+fields stay in the order we wrote them, and enums are as big as a C int. So
+each of these is declared twice, once each way, to compare.
+*/
+struct Padded {
+    a: u8,
+    b: i64,
+    c: u8,
+}
+
+#[repr(C)]
+struct PaddedC {
+    a: u8,
+    b: i64,
+    c: u8,
+}
+
+#[repr(C)]
+struct StupidThingC {
+    v: i64,
+    n: i64,
+}
+
+#[repr(C)]
+struct LinkedList2C<T> {
+    value: i64,
+    next: Option<T>,
+}
+
+#[repr(C)]
+enum ExplicitC {
+    A = 1,
+    B = 2,
+    C = 4,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReprComparison {
+    pub rust: LayoutInfo,
+    pub repr_c: LayoutInfo,
+}
+
+impl ReprComparison {
+    fn of<R, C>(rust: &'static str, repr_c: &'static str) -> Self {
+        ReprComparison {
+            rust: LayoutInfo::of::<R>(rust),
+            repr_c: LayoutInfo::of::<C>(repr_c),
+        }
+    }
+
+    /* How many bytes Rust's own layout saves over the C one. */
+    pub fn saved(&self) -> usize {
+        self.repr_c.size - self.rust.size
+    }
+}
+
+pub fn repr_comparisons() -> Vec<ReprComparison> {
+    vec![
+        ReprComparison::of::<Padded, PaddedC>("Padded", "PaddedC"),
+        ReprComparison::of::<StupidThing, StupidThingC>("StupidThing", "StupidThingC"),
+        ReprComparison::of::<L<L<L<()>>>, LinkedList2C<LinkedList2C<LinkedList2C<()>>>>(
+            "L<L<L<()>>>",
+            "LinkedList2C x3",
+        ),
+        ReprComparison::of::<Explicit, ExplicitC>("Explicit", "ExplicitC"),
+    ]
+}
+
+/*
+The results (checked in the tests): Padded goes from 24 bytes in C to 16, as
+Rust puts both u8 together after the i64. The enum goes from 4 bytes to 1.
+But the lists and StupidThing are the same: they have nothing to reorder. And
+repr(C) doesn't take niches away, Option<ExplicitC> is still 4 bytes, so our
+nested lists pack the same either way.
+*/
+
+/*
+All those println!s are fine to look at, but not to check. So here's the same
+data as a list that tests can assert on, and a table to print it.
+
+bytes_per_element is how much each i64 we store really costs. For the Option
+of a pointer there's one element, the pointer itself.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizeEntry {
+    pub name: &'static str,
+    pub bytes: usize,
+    pub bytes_per_element: f64,
+}
+
+impl SizeEntry {
+    fn of<T>(name: &'static str, elements: usize) -> Self {
+        let bytes = size_of::<T>();
+        SizeEntry {
+            name,
+            bytes,
+            bytes_per_element: bytes as f64 / elements as f64,
+        }
+    }
+}
+
+pub fn size_report() -> Vec<SizeEntry> {
+    vec![
+        SizeEntry::of::<L8>("L8", 8),
+        SizeEntry::of::<L8i>("L8i", 8),
+        SizeEntry::of::<L2i>("L2i", 2),
+        SizeEntry::of::<StupidThing>("StupidThing", 2),
+        SizeEntry::of::<L64>("L64", 64),
+        SizeEntry::of::<L65>("L65", 65),
+        SizeEntry::of::<A8>("A8", 8),
+        SizeEntry::of::<InlineList<8>>("InlineList<8>", 8),
+        SizeEntry::of::<OP8>("OP8", 1),
+        SizeEntry::of::<OB8>("OB8", 1),
+        SizeEntry::of::<OI64>("OI64", 1),
+    ]
+}
+
+/* Renders the entries as a table:
+
+    name            bytes  per element
+    L8                 72         9.00
+*/
+pub struct SizeTable<'a>(pub &'a [SizeEntry]);
+
+impl<'a> fmt::Display for SizeTable<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<15} {:>5}  {:>11}", "name", "bytes", "per element")?;
+        for entry in self.0 {
+            writeln!(
+                f,
+                "{:<15} {:>5}  {:>11.2}",
+                entry.name, entry.bytes, entry.bytes_per_element
+            )?;
+        }
+        Ok(())
+    }
+}
+
 /*
 That's all for stack-only values! Let's experiment with stack pointers!
 */
+
+#[cfg(test)]
+mod test;
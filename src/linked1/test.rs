@@ -0,0 +1,176 @@
+use super::*;
+
+#[test]
+fn test_inline_list() {
+    let mut list = InlineList::<3>::new();
+    assert!(list.is_empty());
+    assert_eq!(list.capacity(), 3);
+    assert_eq!(list.pop(), None);
+    list.push(3).unwrap();
+    list.push(8).unwrap();
+    list.push(1).unwrap();
+    assert!(list.is_full());
+    assert_eq!(list.push(9), Err(Full));
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec![3, 8, 1]);
+    assert_eq!(list.get(1), Some(8));
+    assert_eq!(list.get(3), None);
+
+    assert_eq!(list.pop(), Some(1));
+    assert_eq!(list.get(2), None);
+    list.push(5).unwrap();
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec![3, 8, 5]);
+    while list.pop().is_some() {}
+    assert!(list.is_empty());
+    assert_eq!(list.iter().count(), 0);
+}
+
+#[test]
+fn test_inline_list_full_capacity() {
+    let mut list = InlineList::<64>::new();
+    for i in 0..64 {
+        list.push(i).unwrap();
+    }
+    assert_eq!(list.push(64), Err(Full));
+    assert_eq!(list.iter().sum::<i64>(), (0..64).sum());
+    assert_eq!(list.pop(), Some(63));
+    assert_eq!(list.len(), 63);
+}
+
+#[test]
+fn test_inline_list_size() {
+    // One word of flags plus the values, same as the nested types.
+    assert_eq!(InlineList::<8>::new().size(), size_of::<L8>());
+    assert_eq!(InlineList::<64>::new().size(), size_of::<L64>());
+    assert_eq!(InlineList::<0>::new().size(), 8);
+}
+
+#[test]
+fn test_nested_list_sizes() {
+    assert_eq!(size_of::<nested_list!(0)>(), 0);
+    assert_eq!(size_of::<nested_list!(8)>(), size_of::<L8>());
+    assert_eq!(size_of::<nested_list!(64)>(), size_of::<L64>());
+    // One tag byte (padded to a word) is shared by all the Options...
+    assert_eq!(size_of::<nested_list!(65)>(), 65 * 8 + 8);
+    assert_eq!(size_of::<nested_list!(128)>(), 128 * 8 + 8);
+    // ...until it runs out of values and a second one is needed.
+    assert_eq!(size_of::<nested_list!(256)>(), 256 * 8 + 16);
+}
+
+#[test]
+fn test_size_report() {
+    let report = size_report();
+    let bytes = |name: &str| report.iter().find(|e| e.name == name).unwrap().bytes;
+    assert_eq!(bytes("L8"), 72);
+    assert_eq!(bytes("L8"), bytes("L8i"));
+    assert_eq!(bytes("L64"), 520);
+    assert_eq!(bytes("A8"), 128);
+    assert_eq!(bytes("InlineList<8>"), bytes("L8"));
+    // Pointers use the null niche: the Option is free.
+    assert_eq!(bytes("OP8"), size_of::<usize>());
+    assert_eq!(bytes("OB8"), size_of::<usize>());
+    assert_eq!(bytes("OI64"), 16);
+
+    let l64 = report.iter().find(|e| e.name == "L64").unwrap();
+    assert_eq!(l64.bytes_per_element, 520.0 / 64.0);
+    // Nested Options pack better than an array of them.
+    let a8 = report.iter().find(|e| e.name == "A8").unwrap();
+    assert!(l64.bytes_per_element < a8.bytes_per_element);
+}
+
+#[test]
+fn test_size_table() {
+    let entries = [SizeEntry {
+        name: "L8",
+        bytes: 72,
+        bytes_per_element: 9.0,
+    }];
+    let table = SizeTable(&entries).to_string();
+    let lines: Vec<_> = table.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "name            bytes  per element");
+    assert_eq!(lines[1], "L8                 72         9.00");
+    assert_eq!(SizeTable(&size_report()).to_string().lines().count(), 12);
+}
+
+#[test]
+fn test_inline_chain() {
+    assert_eq!(L8::CAPACITY, 8);
+    assert_eq!(L64::CAPACITY, 64);
+
+    let mut list = L8::from_value(3).unwrap();
+    list.push(8).unwrap();
+    list.push(1).unwrap();
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.values().collect::<Vec<_>>(), vec![3, 8, 1]);
+    for i in 0..5 {
+        list.push(i).unwrap();
+    }
+    assert_eq!(list.len(), 8);
+    assert_eq!(list.push(99), Err(Full));
+    assert_eq!(list.get(7), Some(4));
+
+    for _ in 0..7 {
+        assert!(list.pop().is_some());
+    }
+    // The first value stays: a LinkedList2 is never empty.
+    assert_eq!(list.pop(), None);
+    assert_eq!(list.values().collect::<Vec<_>>(), vec![3]);
+}
+
+#[test]
+fn test_inline_chain_option() {
+    let mut list: Option<L64> = None;
+    assert!(list.is_empty());
+    assert_eq!(list.pop(), None);
+    for i in 0..64 {
+        list.push(i).unwrap();
+    }
+    assert_eq!(list.push(64), Err(Full));
+    assert_eq!(list.values().sum::<i64>(), (0..64).sum());
+    for i in (0..64).rev() {
+        assert_eq!(list.pop(), Some(i));
+    }
+    assert!(list.is_none());
+
+    let mut nothing = ();
+    assert_eq!(nothing.push(1), Err(Full));
+    assert_eq!(Option::<()>::from_value(1), Err(Full));
+}
+
+#[test]
+fn test_niche_report() {
+    let report = niche_report();
+    let find = |name: &str| *report.iter().find(|e| e.name == name).unwrap();
+    assert!(!find("i64").has_niche());
+    assert_eq!(find("i64").option_size, 16);
+    assert!(find("NonZeroI64").has_niche());
+    assert!(find("Box<i64>").has_niche());
+    assert!(find("&i64").has_niche());
+    let explicit = find("Explicit");
+    assert_eq!((explicit.size, explicit.option_size), (1, 1));
+    // The Option's own tag has room for one more None.
+    assert!(find("Option<i64>").has_niche());
+    // And so does the Option inside the list.
+    assert!(find("L2i").has_niche());
+    assert_eq!(find("NonZeroI64").align, 8);
+}
+
+#[test]
+fn test_repr_comparisons() {
+    let report = repr_comparisons();
+    let find = |name: &str| *report.iter().find(|c| c.rust.name == name).unwrap();
+
+    let padded = find("Padded");
+    assert_eq!((padded.rust.size, padded.repr_c.size), (16, 24));
+    assert_eq!(padded.saved(), 8);
+
+    assert_eq!(find("StupidThing").saved(), 0);
+    let lists = find("L<L<L<()>>>");
+    assert_eq!(lists.saved(), 0);
+    assert!(lists.repr_c.has_niche());
+
+    let explicit = find("Explicit");
+    assert_eq!((explicit.rust.size, explicit.repr_c.size), (1, 4));
+    assert!(explicit.repr_c.has_niche());
+}
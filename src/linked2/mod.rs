@@ -1,4 +1,5 @@
 #![allow(dead_code)]
+use std::fmt;
 /*
 Reference Linked Lists
 ===========================================================================
@@ -79,9 +80,9 @@ values, sometimes, this does make sense. But for LinkedLists this is absurd.
 So we have to go with the generics approach to define a lifetime:
 */
 
-pub struct LinkedList2<'a> {
-    value: i64,
-    next: Option<&'a LinkedList2<'a>>,
+pub struct LinkedList2<'a, T> {
+    value: T,
+    next: Option<&'a LinkedList2<'a, T>>,
 }
 
 /*
@@ -95,28 +96,31 @@ more precise, the children lifetime must outlive the parent.
 This makes sense. The parent cannot be freed if it has children, so the parent
 must outlive them.
 
-The size of this struct is going to be two words (16bytes). The first word is
-the value, and the next word is the pointer to memory.
+The size of this struct is going to be two words (16bytes) for an i64. The
+first word is the value, and the next word is the pointer to memory.
+
+The value can be anything, T. Nothing here needs to copy it: the list only
+hands out references to the values, which live as long as the nodes do.
 
 Let's begin with an implementation for this:
 */
-impl<'a> LinkedList2<'a> {
+impl<'a, T> LinkedList2<'a, T> {
     /* The constructor is quite simple: */
-    pub fn new(value: i64, next: Option<&'a LinkedList2<'a>>) -> Self {
+    pub fn new(value: T, next: Option<&'a LinkedList2<'a, T>>) -> Self {
         LinkedList2 { value, next }
     }
 
     /* Some getters and setters for public access: */
-    pub fn value(&self) -> i64 {
-        self.value
+    pub fn value(&self) -> &T {
+        &self.value
     }
-    pub fn set_value(&mut self, value: i64) {
+    pub fn set_value(&mut self, value: T) {
         self.value = value;
     }
     pub fn next(&self) -> Option<&Self> {
         self.next
     }
-    pub fn set_next(&mut self, next: Option<&'a LinkedList2<'a>>) {
+    pub fn set_next(&mut self, next: Option<&'a LinkedList2<'a, T>>) {
         self.next = next;
     }
 }
@@ -127,27 +131,28 @@ We will need a struct that can hold the current position or we'll consume
 our own items while iterating. Because of this, we will need a function
 that returns one of those iterable structs:
 */
-pub struct IterLinkedList2<'a> {
-    cursor: Option<&'a LinkedList2<'a>>,
+pub struct IterLinkedList2<'a, T> {
+    cursor: Option<&'a LinkedList2<'a, T>>,
 }
 
 /* And now we implement a iter() function that returns this struct: */
-impl<'a> LinkedList2<'a> {
-    pub fn iter(&self) -> IterLinkedList2 {
+impl<'a, T> LinkedList2<'a, T> {
+    pub fn iter(&self) -> IterLinkedList2<T> {
         IterLinkedList2 {
             cursor: Some(&self),
         }
     }
 }
 
-/* And the iterator. We need to implement the trait */
-impl<'a> Iterator for IterLinkedList2<'a> {
-    type Item = i64;
+/* And the iterator. We need to implement the trait. It yields references to
+the values, which are valid as long as the nodes are: 'a. */
+impl<'a, T> Iterator for IterLinkedList2<'a, T> {
+    type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
         /* We get the return value. Using map() we can translate from
-        Option<LinkedList> to Option<c.value(i64)> */
-        let ret = self.cursor.map(|c| c.value);
+        Option<LinkedList> to Option<&c.value> */
+        let ret = self.cursor.map(|c| &c.value);
         /* Now we have to advance the cursor to the next item. Flatten is used
         to remove the Option<Option<T>> and leave a single one. */
         self.cursor = self.cursor.map(|c| c.next).flatten();
@@ -156,9 +161,9 @@ impl<'a> Iterator for IterLinkedList2<'a> {
 }
 
 /* So far so good. Let's add functionality to add, remove, etc... */
-impl<'a> LinkedList2<'a> {
+impl<'a, T> LinkedList2<'a, T> {
     /* We will need first a method that finds the tail */
-    fn tail(&self) -> &Self {
+    pub fn tail(&self) -> &Self {
         let mut cur = self;
         while cur.next.is_some() {
             cur = cur.next.unwrap()
@@ -176,7 +181,7 @@ impl<'a> LinkedList2<'a> {
     // }
 
     /* Insert is more complicated. We want to insert after this item */
-    fn insert(&mut self, item: &'a mut LinkedList2) -> Option<&Self> {
+    fn insert(&mut self, item: &'a mut LinkedList2<T>) -> Option<&Self> {
         /* first switch our next with that item */
         let oldnext = self.next.replace(item);
         /* now we need to add the remaining part of the list at the end */
@@ -191,6 +196,55 @@ impl<'a> LinkedList2<'a> {
     }
 }
 
+/* Writing is a mess, but reading works just fine, as shared references can be
+copied around all we want. So at least the read-only side can be complete: */
+impl<'a, T> LinkedList2<'a, T> {
+    /* There's no list struct to hold a counter, so we count the nodes. */
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /* A chain always has at least the node we're calling it on. */
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /* Node number n, counting self as 0. */
+    pub fn nth(&self, n: usize) -> Option<&Self> {
+        let mut cur = self;
+        for _ in 0..n {
+            cur = cur.next?;
+        }
+        Some(cur)
+    }
+
+    /* The first node holding that value. */
+    pub fn find(&self, value: &T) -> Option<&Self>
+    where
+        T: PartialEq,
+    {
+        let mut cur = self;
+        loop {
+            if cur.value == *value {
+                return Some(cur);
+            }
+            cur = cur.next?;
+        }
+    }
+
+    pub fn contains(&self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.find(value).is_some()
+    }
+
+    /* The value in the tail. Never None, for the same reason as is_empty(). */
+    pub fn last(&self) -> &T {
+        &self.tail().value
+    }
+}
+
 /*
 I will stop here because I believe this is already a complete disaster. It's
 very inconvenient for the caller to do the other half of the operations.
@@ -238,42 +292,35 @@ impl<'a> LinkedList3<'a> {
     // }
 
     /*
-    Dang! this is quite unfixable. Because it's inside the struct, reading
-    the pointer will imply that there will be two pointers now, both mutable.
-
-    The only way around is a nasty one, removing the pointer as we iterate. In
-    this way there will be one and only one pointer to the same place at once.
-
-    In order to return a mutable reference, we will need to remove the reference
-    from the previous leaf and leave None. The caller would need to put it back.
-
-    But, to put back the reference to the now last tail, the caller would need
-    to call this function again, cleaning a new item. So in short, this means
-    the caller MUST recreate the entire chain in order to change a single item.
-
-    Not cool.
+    Dang! This looked unfixable to me at first: I thought that reading the
+    pointer meant there would be two pointers to the same node, both mutable.
+
+    So my first version took the pointers out as it walked, swapping nodes
+    around with mem::swap. I wasn't sure it worked, and it didn't: it moved
+    the values from node to node and cut the chain, so self ended up as a
+    single node holding the tail's value.
+
+    The real problem is much smaller. `cur.next.unwrap()` tries to *move* the
+    &mut out of the node, and we can't move out of something we only borrow.
+    What we want is a reborrow: as_deref_mut() gives a &mut to the next node
+    that borrows from `cur`, and the chain stays as it is. Each step hands the
+    borrow down to the next node, so there's never two of them at once.
     */
     fn tail_mut(&mut self) -> &mut Self {
-        let mut prev: Option<&mut Self> = None;
-        let cur = self;
-        while let Some(next) = cur.next.take() {
-            /* We have to pacify the borrow checker doing both operations at once:
-            first, put the next value onto cur, so we advance, but also store the
-            old value somewhere (next) so we can access it later. */
-            std::mem::swap(&mut cur.next, &mut prev);
-            std::mem::swap(cur, next);
-            prev.replace(next);
+        let mut cur = self;
+        while cur.next.is_some() {
+            cur = cur.next.as_deref_mut().unwrap();
         }
         cur
     }
 }
 /*
-Does the above code work? I'm not sure, and not even going to bother proving it.
-Even if it works as intended (Rust seems happy about it) it's going to be a
-really stupid idea.
-
-If you have to re-do the whole list, just redo it from scratch.
-It's going to be simpler.
+So tail_mut() works after all. But that's as far as &mut links take us. Each
+node is mutably borrowed by the one before it, so the only way to reach any of
+them is walking from the first, and they still have to be declared backwards,
+each one outliving the ones that point to it. Inserting or removing means
+taking a node out of the chain and putting it back with the right lifetime,
+and that's the same mess we had with insert() above.
 
 So, if the "next" is read-only there's a big problem. If it's mutable we have
 even a bigger problem. So what now?
@@ -297,14 +344,15 @@ Why? Because we will need to use an array or vector to hold the actual data.
 Is this a linked list anymore?
 */
 
-type Node4<'a> = LinkedList2<'a>;
+type Node4<'a> = LinkedList2<'a, i64>;
 
-struct LinkedList4<'a> {
-    pub first: Option<&'a Node4<'a>>,
-    pub data: Vec<Node4<'a>>,
-}
+/* This was my first try: the manager owns the nodes in a Vec.
+
+    struct LinkedList4<'a> {
+        pub first: Option<&'a Node4<'a>>,
+        pub data: Vec<Node4<'a>>,
+    }
 
-/*
 I've used a vector and not an array which is a dynamic structure. It would be
 "better" to use static arrays to show that we're actually managing our data
 and not simply letting others to do it for us. And also it would be amazing to
@@ -340,44 +388,44 @@ a wrapper around the nodes, but that's a different story.
 
 We'll see a RefCell version later, for now let's get crazy once again:
 */
-impl<'a> LinkedList4<'a> {
-    fn new() -> Self {
-        LinkedList4 {
-            first: None,
-            data: vec![],
-        }
-    }
-    fn tail(&self) -> Option<&Node4> {
-        self.first.map(|f| f.tail())
-    }
-    fn tail_idx(&mut self) -> Option<usize> {
-        match self.tail() {
-            None => None,
-            Some(tail) => {
-                for (i, n) in self.data.iter().enumerate() {
-                    if n as *const Node4 == tail as *const Node4 {
-                        return Some(i);
-                    }
-                }
-                None
-            }
-        }
-    }
-
-    fn append(&'a mut self, value: i64) {
-        self.data.push(Node4 { value, next: None });
-        if let Some(_i) = self.tail_idx() {
-            let _last = self.data.last();
-            //         ----------------
-            //         |
-            //         immutable borrow occurs here
-            //         argument requires that `self.data` is borrowed for `'a`
-            // if let Some(mut tail) = self.data.get_mut(i) {
-            //     tail.next = last;
-            // }
-        }
-    }
-}
+// impl<'a> LinkedList4<'a> {
+//     fn new() -> Self {
+//         LinkedList4 {
+//             first: None,
+//             data: vec![],
+//         }
+//     }
+//     fn tail(&self) -> Option<&Node4> {
+//         self.first.map(|f| f.tail())
+//     }
+//     fn tail_idx(&mut self) -> Option<usize> {
+//         match self.tail() {
+//             None => None,
+//             Some(tail) => {
+//                 for (i, n) in self.data.iter().enumerate() {
+//                     if n as *const Node4 == tail as *const Node4 {
+//                         return Some(i);
+//                     }
+//                 }
+//                 None
+//             }
+//         }
+//     }
+//
+//     fn append(&'a mut self, value: i64) {
+//         self.data.push(Node4 { value, next: None });
+//         if let Some(_i) = self.tail_idx() {
+//             let _last = self.data.last();
+//             //         ----------------
+//             //         |
+//             //         immutable borrow occurs here
+//             //         argument requires that `self.data` is borrowed for `'a`
+//             // if let Some(mut tail) = self.data.get_mut(i) {
+//             //     tail.next = last;
+//             // }
+//         }
+//     }
+// }
 /*
 At least to me, this looks impossible to fix. The borrow checker is not going
 to prove that data.last() and data.get_mut() do not retrieve the same address.
@@ -389,3 +437,209 @@ to create a second Vec<&Node4> and put all the references there, the original
 Vec<Node4> would be locked for read-only the whole time. (I don't think this is
 even possible to do)
 */
+
+/*
+Coming back to this one: the Vec was the real problem, not the idea. A Vec
+moves all its contents when it grows, so the borrow checker is right not to
+let a &Node4 into it live across a push(). What we need is storage that never
+moves a node once it's in, and hands out references that live as long as the
+storage itself. That's an arena: it keeps nodes in chunks and starts a new,
+bigger chunk instead of growing a full one. linked3 has one (Arena), and we
+borrow it here.
+
+The safety argument lives in Arena::alloc(), which is the only unsafe code
+involved: a chunk is never pushed past its capacity, so it never reallocates;
+and chunks are only freed when the arena is dropped. So every &Node4 it gives
+out stays valid for as long as the arena is borrowed, which is 'a. The
+manager can't own the arena (the nodes would point into their own owner), so
+it borrows it instead, and the arena has to be created first.
+
+The second problem is that nodes can't change once something points to
+them, as `next` is a shared reference. prepend() is fine: the new node points
+to the old first, nobody changes. But append() has to rewrite the tail's
+`next`, and that's exactly what we can't do. Without that, appending means
+copying the whole chain into new nodes that end in the new one, n of them
+for every value, and the old ones left in the arena as garbage.
+
+Rewriting a `next` through a shared reference is what Cell is for, and
+that's the next chapter. linked3 has a node with a Cell for `next`, and a
+manager over an arena, ManagedList, that appends in O(1). So LinkedList4
+borrows those too, and only adds a length: the nodes are linked3's, and
+each value goes into the arena once, whichever end it's added to.
+*/
+use crate::linked3::{self, Arena, IterLinkedList1, ManagedList};
+
+type ArenaNode<'a> = linked3::LinkedList1<'a, i64>;
+
+pub struct LinkedList4<'a> {
+    nodes: ManagedList<'a, i64>,
+    len: usize,
+}
+
+impl<'a> LinkedList4<'a> {
+    pub fn new(data: &'a Arena<ArenaNode<'a>>) -> Self {
+        LinkedList4 {
+            nodes: ManagedList::new(data),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn first(&self) -> Option<&'a ArenaNode<'a>> {
+        self.nodes.first()
+    }
+
+    pub fn tail(&self) -> Option<&'a ArenaNode<'a>> {
+        self.first().map(|f| f.tail())
+    }
+
+    /* O(1): the new node points to the old chain, nothing else changes. */
+    pub fn prepend(&mut self, value: i64) {
+        self.nodes.push_front(value);
+        self.len += 1;
+    }
+
+    /* O(1) too: the old tail's `next` is a Cell now, so it can point to the
+    new node. */
+    pub fn append(&mut self, value: i64) {
+        self.nodes.append(value);
+        self.len += 1;
+    }
+
+    pub fn iter(&self) -> IterLinkedList1<'a, i64> {
+        self.nodes.iter()
+    }
+
+    pub fn to_vec(&self) -> Vec<i64> {
+        self.nodes.to_vec()
+    }
+}
+
+/*
+A few traits so these lists can be compared and printed like the others.
+They're read-only, so there's nothing stopping us. Both walk the chain in a
+loop: a derived Debug would recurse once per node through `next`.
+*/
+impl<'a, T> LinkedList2<'a, T> {
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+/* LinkedList2 is covariant in 'a (a longer-lived chain can pass for a
+shorter-lived one), so Rust finds a common lifetime and one impl is enough. */
+impl<'a, T: PartialEq> PartialEq for LinkedList2<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for LinkedList2<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/* LinkedList3 had no iterator, so here's one. The borrow of the chain ('s)
+is kept apart from the lifetime of the links ('a), see below why. */
+struct Values3<'s, 'a> {
+    cursor: Option<&'s LinkedList3<'a>>,
+}
+
+impl<'s, 'a> Iterator for Values3<'s, 'a> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.cursor?;
+        self.cursor = node.next.as_deref();
+        Some(node.value)
+    }
+}
+
+impl<'a> LinkedList3<'a> {
+    fn values(&self) -> Values3<'_, 'a> {
+        Values3 { cursor: Some(self) }
+    }
+
+    pub fn to_vec(&self) -> Vec<i64> {
+        self.values().collect()
+    }
+}
+
+/* But &'a mut makes LinkedList3 invariant in 'a: two chains from different
+places have different types for good, so we compare across lifetimes. */
+impl<'a, 'b> PartialEq<LinkedList3<'b>> for LinkedList3<'a> {
+    fn eq(&self, other: &LinkedList3<'b>) -> bool {
+        self.values().eq(other.values())
+    }
+}
+
+impl<'a> fmt::Debug for LinkedList3<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.values()).finish()
+    }
+}
+
+/*
+Before leaving, one small thing that makes LinkedList2 bearable: building it.
+
+Every node has to be a local that outlives the ones pointing to it, so the
+last node must be declared first. Writing that by hand is tedious and easy to
+get wrong, so here's a macro that does it:
+
+    chain!(3, 8, 1 => |head| head.iter().copied().collect::<Vec<_>>())
+
+It reverses the values, declares one node per value on the stack, each one
+pointing to the one declared before it, and then calls the closure with the
+head. The nodes die when the macro's block ends, so whatever the closure
+returns can't borrow them.
+
+Reversing a list in macro_rules! is done by moving the values one by one into
+an accumulator (@rev). Then @build nests one block per node. Each `node` is
+declared in a different expansion, so hygiene keeps them apart, and the
+reference to the previous one is passed down as a token.
+*/
+#[macro_export]
+macro_rules! chain {
+    ($($value:expr),+ => $f:expr) => {
+        $crate::chain!(@rev []; $($value),+ => $f)
+    };
+    (@rev [$($rev:expr),*]; $head:expr $(, $tail:expr)* => $f:expr) => {
+        $crate::chain!(@rev [$head $(, $rev)*]; $($tail),* => $f)
+    };
+    (@rev [$last:expr $(, $rest:expr)*]; => $f:expr) => {{
+        let node = $crate::linked2::LinkedList2::new($last, None);
+        $crate::chain!(@build &node; $($rest),* => $f)
+    }};
+    (@build $next:expr; $value:expr $(, $rest:expr)* => $f:expr) => {{
+        let node = $crate::linked2::LinkedList2::new($value, Some($next));
+        $crate::chain!(@build &node; $($rest),* => $f)
+    }};
+    (@build $head:expr; => $f:expr) => {
+        $crate::linked2::with_head($head, $f)
+    };
+}
+
+/* Calling the closure right there, as ($f)($head), makes Rust check the
+closure before it looks at the argument, so it can't guess the type of head.
+Going through a function tells it. */
+#[doc(hidden)]
+pub fn with_head<'a, T, R>(
+    head: &'a LinkedList2<'a, T>,
+    f: impl FnOnce(&'a LinkedList2<'a, T>) -> R,
+) -> R {
+    f(head)
+}
+
+#[cfg(test)]
+mod test;
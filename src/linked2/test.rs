@@ -0,0 +1,176 @@
+use super::*;
+
+#[test]
+fn test_chain_macro() {
+    let values = chain!(3, 8, 1 => |head| head.iter().copied().collect::<Vec<_>>());
+    assert_eq!(values, vec![3, 8, 1]);
+
+    chain!(3, 8, 1 => |head| {
+        assert_eq!(*head.value(), 3);
+        assert_eq!(*head.next().unwrap().value(), 8);
+        assert_eq!(*head.tail().value(), 1);
+        assert!(head.tail().next().is_none());
+    });
+
+    let single = chain!(5 => |head| (*head.value(), *head.tail().value()));
+    assert_eq!(single, (5, 5));
+}
+
+#[test]
+fn test_chain_macro_matches_hand_built() {
+    // What chain!(3, 8, 1 => ...) saves us from writing:
+    let n1 = LinkedList2::new(1, None);
+    let n8 = LinkedList2::new(8, Some(&n1));
+    let n3 = LinkedList2::new(3, Some(&n8));
+    let by_hand: Vec<_> = n3.iter().copied().collect();
+    assert_eq!(chain!(3, 8, 1 => |head| head.iter().copied().collect::<Vec<_>>()), by_hand);
+}
+
+#[test]
+fn test_non_copy_values() {
+    let world = LinkedList2::new(String::from("world"), None);
+    let hello = LinkedList2::new(String::from("hello"), Some(&world));
+    let words: Vec<&String> = hello.iter().collect();
+    assert_eq!(words, ["hello", "world"]);
+    assert_eq!(hello.tail().value(), "world");
+
+    let lengths = chain!(vec![1, 2], vec![3] => |head| head.iter().map(Vec::len).collect::<Vec<_>>());
+    assert_eq!(lengths, vec![2, 1]);
+}
+
+#[test]
+fn test_read_only_queries() {
+    chain!(3, 8, 1, 8 => |head| {
+        assert_eq!(head.len(), 4);
+        assert!(!head.is_empty());
+        assert_eq!(*head.nth(0).unwrap().value(), 3);
+        assert_eq!(*head.nth(3).unwrap().value(), 8);
+        assert!(head.nth(4).is_none());
+        assert!(std::ptr::eq(head.nth(3).unwrap(), head.tail()));
+
+        // find() gives the first match, and the rest of the chain from there.
+        let found = head.find(&8).unwrap();
+        assert!(std::ptr::eq(found, head.nth(1).unwrap()));
+        assert_eq!(found.len(), 3);
+        assert!(head.find(&7).is_none());
+        assert!(head.contains(&1));
+        assert!(!head.contains(&2));
+        assert_eq!(*head.last(), 8);
+    });
+
+    let single = LinkedList2::new("only", None);
+    assert_eq!(single.len(), 1);
+    assert_eq!(*single.last(), "only");
+    assert!(single.nth(1).is_none());
+}
+
+#[test]
+fn test_linked_list4_arena() {
+    // A tiny first chunk, so the nodes spread over several chunks.
+    let arena = Arena::with_capacity(2);
+    let mut list = LinkedList4::new(&arena);
+    assert!(list.is_empty());
+    assert!(list.tail().is_none());
+    list.append(1);
+    let first = list.first().unwrap();
+    for i in 2..=10 {
+        list.append(i);
+    }
+    list.prepend(0);
+    assert_eq!(list.len(), 11);
+    assert_eq!(list.to_vec(), (0..=10).collect::<Vec<_>>());
+    assert_eq!(list.tail().unwrap().value(), 10);
+    assert_eq!(list.first().unwrap().len(), 11);
+
+    // The node we got after the first append is still there, even though
+    // the arena grew many times since, and it's linked to the rest now.
+    assert_eq!(first.value(), 1);
+    assert_eq!(first.next().unwrap().value(), 2);
+    // One node per value, appended or prepended: nothing was copied.
+    assert_eq!(arena.len(), 11);
+}
+
+#[test]
+fn test_linked_list4_prepend_shares() {
+    let arena = Arena::new();
+    let mut list = LinkedList4::new(&arena);
+    for i in (0..100).rev() {
+        list.prepend(i);
+    }
+    assert_eq!(list.to_vec(), (0..100).collect::<Vec<_>>());
+    // No copies: one node per value.
+    assert_eq!(arena.len(), 100);
+    let old = list.first().unwrap();
+    list.prepend(-1);
+    assert!(std::ptr::eq(list.first().unwrap().next().unwrap(), old));
+}
+
+#[test]
+fn test_linked_list2_eq_debug_to_vec() {
+    let c = LinkedList2::new(1, None);
+    let b = LinkedList2::new(8, Some(&c));
+    let a = LinkedList2::new(3, Some(&b));
+    assert_eq!(a.to_vec(), vec![3, 8, 1]);
+    assert_eq!(format!("{:?}", a), "[3, 8, 1]");
+    chain!(3, 8, 1 => |head| {
+        assert_eq!(*head, a);
+        assert_ne!(*head, b);
+    });
+    let words = LinkedList2::new(String::from("hi"), None);
+    assert_eq!(words.to_vec(), vec![String::from("hi")]);
+    assert_eq!(format!("{:?}", words), r#"["hi"]"#);
+}
+
+#[test]
+fn test_linked_list3_eq_debug_to_vec() {
+    let mut c = LinkedList3::new(1, None);
+    let mut b = LinkedList3::new(8, Some(&mut c));
+    let a = LinkedList3::new(3, Some(&mut b));
+    assert_eq!(a.to_vec(), vec![3, 8, 1]);
+    assert_eq!(format!("{:?}", a), "[3, 8, 1]");
+
+    let mut z = LinkedList3::new(1, None);
+    let mut y = LinkedList3::new(8, Some(&mut z));
+    let x = LinkedList3::new(3, Some(&mut y));
+    assert_eq!(a, x);
+    assert_ne!(*a.next().unwrap(), x);
+}
+
+#[test]
+fn test_long_chain_debug() {
+    // Built from the end with an arena, as the stack can't hold this many.
+    let arena = Arena::new();
+    let mut list = LinkedList4::new(&arena);
+    for i in 0..100_000 {
+        list.prepend(i);
+    }
+    let first = list.first().unwrap();
+    assert_eq!(format!("{:?}", first).len(), format!("{:?}", list.to_vec()).len());
+    assert_eq!(first, first);
+}
+
+#[test]
+fn test_linked_list3_tail_mut() {
+    let mut d = LinkedList3::new(4, None);
+    let mut c = LinkedList3::new(1, Some(&mut d));
+    let mut b = LinkedList3::new(8, Some(&mut c));
+    let mut a = LinkedList3::new(3, Some(&mut b));
+
+    let tail = a.tail_mut();
+    assert_eq!(tail.value(), 4);
+    assert!(tail.next().is_none());
+    tail.set_value(40);
+    // The chain is intact, nothing moved, and the change went to the tail.
+    assert_eq!(a.to_vec(), vec![3, 8, 1, 40]);
+    assert_eq!(a.tail().value(), 40);
+
+    // Calling it again finds the same node.
+    let first_call: *const LinkedList3 = a.tail_mut();
+    let second_call: *const LinkedList3 = a.tail_mut();
+    assert_eq!(first_call, second_call);
+    assert_eq!(a.value(), 3);
+
+    let mut single = LinkedList3::new(7, None);
+    single.tail_mut().set_value(70);
+    assert_eq!(single.to_vec(), vec![70]);
+}
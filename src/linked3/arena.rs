@@ -0,0 +1,70 @@
+/*
+A tiny arena: it owns values and hands out shared references to them that
+live as long as the arena itself.
+
+The values are stored in chunks, which are plain Vecs. A Vec only moves its
+contents when it grows past its capacity, so we never let a chunk do that:
+when the last chunk is full we start a new one, twice as big. Old chunks are
+never touched again until the arena is dropped, and then everything goes away
+at once.
+
+alloc() takes &self, because the whole point is to keep allocating while
+references from earlier allocations are still alive. The list of chunks sits
+in a RefCell so it can grow anyway; that borrow only lasts inside alloc().
+*/
+
+use std::cell::RefCell;
+use std::panic::RefUnwindSafe;
+
+pub struct Arena<T> {
+    chunks: RefCell<Vec<Vec<T>>>,
+}
+
+/* The RefCell makes the compiler refuse a &Arena across catch_unwind(). But
+the only borrow_mut() is in alloc(), and nothing in there runs code of yours:
+the value is moved in whole, so a panic can't stop a push halfway. The
+references it hands out are &T, which is as safe as T says it is. */
+impl<T: RefUnwindSafe> RefUnwindSafe for Arena<T> {}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self::with_capacity(16)
+    }
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Arena {
+            chunks: RefCell::new(vec![Vec::with_capacity(capacity.max(1))]),
+        }
+    }
+
+    pub fn alloc(&self, value: T) -> &T {
+        let mut chunks = self.chunks.borrow_mut();
+        let last = chunks.last().unwrap();
+        if last.len() == last.capacity() {
+            let capacity = last.capacity() * 2;
+            chunks.push(Vec::with_capacity(capacity));
+        }
+        let chunk = chunks.last_mut().unwrap();
+        chunk.push(value);
+        let value: *const T = chunk.last().unwrap();
+        /* The chunk never reallocates (we checked the capacity above) and it's
+        only freed with the arena, so the value stays put for as long as &self
+        is borrowed. */
+        unsafe { &*value }
+    }
+
+    /* How many values were allocated so far. */
+    pub fn len(&self) -> usize {
+        self.chunks.borrow().iter().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
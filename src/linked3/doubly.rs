@@ -0,0 +1,223 @@
+/*
+How far can Cell go?
+===========================================================================
+
+linked4 needed a raw pointer for `prev`, because the node before us already
+owns us through a Box. Here nobody owns anybody: nodes sit in an arena and
+the links are plain shared references. So `prev` can be exactly the same kind
+of thing as `next`, a Cell<Option<&Node>>, and there's no unsafe at all.
+
+Every node now points to its neighbours in both directions, which makes
+unlinking a node O(1) given just a reference to it: nothing to walk.
+*/
+
+use super::Arena;
+use std::cell::Cell;
+use std::iter::FusedIterator;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub struct Node<'a, T> {
+    value: T,
+    next: Cell<Option<&'a Node<'a, T>>>,
+    prev: Cell<Option<&'a Node<'a, T>>>,
+    /* The id of the list the node is in, or 0 once it's unlinked. Two lists
+    can share an arena, so the arena doesn't tell them apart, and the lists
+    can move, so their address doesn't either. Same trick as intrusive. */
+    owner: Cell<usize>,
+}
+
+impl<'a, T: Copy> Node<'a, T> {
+    pub fn value(&self) -> T {
+        self.value
+    }
+    pub fn next(&self) -> Option<&'a Node<'a, T>> {
+        self.next.get()
+    }
+    pub fn prev(&self) -> Option<&'a Node<'a, T>> {
+        self.prev.get()
+    }
+}
+
+static NEXT_LIST_ID: AtomicUsize = AtomicUsize::new(1);
+
+/* Everything takes &self, like ManagedList. The length lives in a Cell too. */
+pub struct DoublyLinkedList<'a, T> {
+    id: usize,
+    arena: &'a Arena<Node<'a, T>>,
+    first: Cell<Option<&'a Node<'a, T>>>,
+    last: Cell<Option<&'a Node<'a, T>>>,
+    len: Cell<usize>,
+}
+
+impl<'a, T: Copy> DoublyLinkedList<'a, T> {
+    pub fn new(arena: &'a Arena<Node<'a, T>>) -> Self {
+        DoublyLinkedList {
+            id: NEXT_LIST_ID.fetch_add(1, Ordering::Relaxed),
+            arena,
+            first: Cell::new(None),
+            last: Cell::new(None),
+            len: Cell::new(0),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len.get() == 0
+    }
+
+    pub fn first(&self) -> Option<&'a Node<'a, T>> {
+        self.first.get()
+    }
+
+    pub fn last(&self) -> Option<&'a Node<'a, T>> {
+        self.last.get()
+    }
+
+    pub fn push_back(&self, value: T) -> &'a Node<'a, T> {
+        let node = self.arena.alloc(Node {
+            value,
+            next: Cell::new(None),
+            prev: Cell::new(self.last.get()),
+            owner: Cell::new(self.id),
+        });
+        match self.last.get() {
+            Some(last) => last.next.set(Some(node)),
+            None => self.first.set(Some(node)),
+        }
+        self.last.set(Some(node));
+        self.len.set(self.len.get() + 1);
+        node
+    }
+
+    pub fn push_front(&self, value: T) -> &'a Node<'a, T> {
+        let node = self.arena.alloc(Node {
+            value,
+            next: Cell::new(self.first.get()),
+            prev: Cell::new(None),
+            owner: Cell::new(self.id),
+        });
+        match self.first.get() {
+            Some(first) => first.prev.set(Some(node)),
+            None => self.last.set(Some(node)),
+        }
+        self.first.set(Some(node));
+        self.len.set(self.len.get() + 1);
+        node
+    }
+
+    pub fn pop_front(&self) -> Option<T> {
+        let node = self.first.get()?;
+        self.unlink(node);
+        Some(node.value)
+    }
+
+    pub fn pop_back(&self) -> Option<T> {
+        let node = self.last.get()?;
+        self.unlink(node);
+        Some(node.value)
+    }
+
+    /* O(1): the node knows which list it's in. */
+    pub fn contains(&self, node: &Node<'a, T>) -> bool {
+        node.owner.get() == self.id
+    }
+
+    /* Takes a node out of the chain. Its neighbours are joined together, and
+    its own links are cleared so it doesn't keep pointing into the list. The
+    node stays in the arena, as always.
+
+    Returns false, and changes nothing, if the node isn't in this list: it
+    was unlinked already, or it's in another one. Without that check, a
+    node with no prev would look like our first one, and unlinking it again
+    would set first to None and lose the whole list. */
+    pub fn unlink(&self, node: &'a Node<'a, T>) -> bool {
+        if !self.contains(node) {
+            return false;
+        }
+        node.owner.set(0);
+        let prev = node.prev.take();
+        let next = node.next.take();
+        match prev {
+            Some(prev) => prev.next.set(next),
+            None => self.first.set(next),
+        }
+        match next {
+            Some(next) => next.prev.set(prev),
+            None => self.last.set(prev),
+        }
+        self.len.set(self.len.get() - 1);
+        true
+    }
+
+    pub fn iter(&self) -> Iter<'a, T> {
+        Iter {
+            front: self.first.get(),
+            back: self.last.get(),
+            remaining: self.len.get(),
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().collect()
+    }
+}
+
+/* Unlike linked4::doubly::Iter, both ends are plain references, no
+PhantomData needed. We still count, so the ends don't cross. */
+pub struct Iter<'a, T> {
+    front: Option<&'a Node<'a, T>>,
+    back: Option<&'a Node<'a, T>>,
+    remaining: usize,
+}
+
+impl<'a, T: Copy> Iterator for Iter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.front?;
+        self.front = node.next();
+        self.remaining -= 1;
+        Some(node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T: Copy> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.back?;
+        self.back = node.prev();
+        self.remaining -= 1;
+        Some(node.value)
+    }
+}
+
+impl<'a, T: Copy> ExactSizeIterator for Iter<'a, T> {}
+impl<'a, T: Copy> FusedIterator for Iter<'a, T> {}
+
+/*
+So it works, with no unsafe, no Rc and no RefCell. But look at what it cost:
+
+- The list can't own its nodes. The arena has to be created first, outside,
+  and the list borrows it. A function can't build one of these and return it.
+- Nodes are never freed until the arena goes, no matter how many we unlink.
+- The values must be Copy, as nobody can lend a reference longer than a Cell
+  read.
+- Because of the Cells, 'a is invariant: a list of nodes from one arena can't
+  be mixed with nodes from another, not even a longer-lived one.
+
+That's about as far as plain references stretch. To get a list that owns its
+data and can be moved around freely, we go back to Box (linked4), or give up
+and count references (linked5).
+*/
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 use std::cell::Cell;
+use std::fmt;
 /*
 Using `Cell<T>` to handle interior mutability of next
 ===========================================================================
@@ -45,21 +46,24 @@ pub fn test_cell() {
     println!("Cell get: x: {:#?}, y: {:#?}", x.get(), y.get());
 }
 
-/* Seems it's not a problem! So let's get to it: */
-#[derive(Debug)]
-pub struct LinkedList1<'a> {
-    value: i64,
-    next: Cell<Option<&'a LinkedList1<'a>>>,
+/* Seems it's not a problem! So let's get to it:
+
+The value doesn't have to be an i64, but it has to be Copy: the iterator
+and value() hand out copies, as there's no owner to lend references from
+for longer than the chain borrow. */
+pub struct LinkedList1<'a, T> {
+    value: T,
+    next: Cell<Option<&'a LinkedList1<'a, T>>>,
 }
 
-pub struct IterLinkedList1<'a> {
-    cursor: Option<&'a LinkedList1<'a>>,
+pub struct IterLinkedList1<'a, T> {
+    cursor: Option<&'a LinkedList1<'a, T>>,
 }
 
 /* Now I'll copy the implementation from linked2/LinkedList2 here: */
 
-impl<'a> Iterator for IterLinkedList1<'a> {
-    type Item = i64;
+impl<'a, T: Copy> Iterator for IterLinkedList1<'a, T> {
+    type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
         let ret = self.cursor.map(|c| c.value);
@@ -69,9 +73,9 @@ impl<'a> Iterator for IterLinkedList1<'a> {
     }
 }
 
-impl<'a> LinkedList1<'a> {
+impl<'a, T: Copy> LinkedList1<'a, T> {
     /* The constructor is quite simple: */
-    pub fn new(value: i64, next: Option<&'a LinkedList1<'a>>) -> Self {
+    pub fn new(value: T, next: Option<&'a LinkedList1<'a, T>>) -> Self {
         LinkedList1 {
             value,
             next: Cell::new(next),
@@ -79,10 +83,10 @@ impl<'a> LinkedList1<'a> {
     }
 
     /* Some getters and setters for public access: */
-    pub fn value(&self) -> i64 {
+    pub fn value(&self) -> T {
         self.value
     }
-    pub fn set_value(&mut self, value: i64) {
+    pub fn set_value(&mut self, value: T) {
         self.value = value;
     }
     pub fn next(&self) -> Option<&Self> {
@@ -90,19 +94,21 @@ impl<'a> LinkedList1<'a> {
         will be just a nullable pointer being copied.*/
         self.next.get()
     }
-    pub fn set_next(&self, next: Option<&'a LinkedList1<'a>>) -> Option<&LinkedList1<'a>> {
+    pub fn set_next(&self, next: Option<&'a LinkedList1<'a, T>>) -> Option<&LinkedList1<'a, T>> {
         /* Here we use replace instead to be able to write. Notice we no longer
         need a `&mut self`, an immutable reference is enough now. Also, we can
         return the old value easily, so why not? */
         self.next.replace(next)
     }
-    pub fn iter(&'a self) -> IterLinkedList1 {
+    pub fn iter(&'a self) -> IterLinkedList1<'a, T> {
         /* I had to add the lifetime &'a to self to avoid confusion for Rust */
+        debug_assert!(!self.has_cycle(), "iter() on a chain with a cycle");
         IterLinkedList1 {
             cursor: Some(&self),
         }
     }
-    fn tail(&self) -> &Self {
+    pub fn tail(&self) -> &Self {
+        debug_assert!(!self.has_cycle(), "tail() on a chain with a cycle");
         let mut cur = self;
         /*while cur.next().is_some() {
             cur = cur.next().unwrap()
@@ -115,11 +121,43 @@ impl<'a> LinkedList1<'a> {
         cur
     }
 
+    /* set_next() only needs &self, so nothing stops anyone from pointing the
+    last node back to the first. Then tail() never finds the end and spins
+    forever. In debug builds tail() and iter() check for that first.
+
+    This is Brent's algorithm. The hare walks ahead one node at a time, and
+    the tortoise teleports to the hare every time the number of steps hits a
+    power of two. If there's a loop, at some point the power is bigger than
+    the loop, and the hare comes back around to the tortoise. It compares
+    pointers, not values, and needs no memory at all. */
+    pub fn has_cycle(&self) -> bool {
+        let mut power = 1;
+        let mut steps = 1;
+        let mut tortoise = self;
+        let mut hare = match self.next() {
+            Some(next) => next,
+            None => return false,
+        };
+        while !std::ptr::eq(tortoise, hare) {
+            if steps == power {
+                tortoise = hare;
+                power *= 2;
+                steps = 0;
+            }
+            hare = match hare.next() {
+                Some(next) => next,
+                None => return false,
+            };
+            steps += 1;
+        }
+        true
+    }
+
     /* Now there's no point of having a mutable tail function. If you want
     to mutate a page, replace it! */
     // fn tail_mut(&mut self) -> &mut Self { unimplemented!(); }
 
-    fn insert(&self, item: &'a LinkedList1<'a>) {
+    pub fn insert(&self, item: &'a LinkedList1<'a, T>) {
         /* Instead of Option::replace we use Cell::replace, Some(x) is needed
         now to match the types  */
         let oldnext = self.next.replace(Some(item));
@@ -135,7 +173,7 @@ impl<'a> LinkedList1<'a> {
     returning the old item discarded. Anyway the signature is the same, because
     we would return always one item, in one case with next populated, and in the
     other next would always be None */
-    fn replace(&self, item: &'a LinkedList1<'a>, chain: bool) -> Option<&'a LinkedList1<'a>> {
+    pub fn replace(&self, item: &'a LinkedList1<'a, T>, chain: bool) -> Option<&'a LinkedList1<'a, T>> {
         let oldnext = self.next.replace(Some(item));
         if chain {
             let tail = item.tail();
@@ -147,12 +185,12 @@ impl<'a> LinkedList1<'a> {
     }
 
     /* Append should be just tail + insert */
-    fn append(&self, item: &'a LinkedList1<'a>) {
+    pub fn append(&self, item: &'a LinkedList1<'a, T>) {
         self.tail().insert(item)
     }
 
     /* Remove next should be using next.take */
-    fn remove_next(&self) -> Option<&'a LinkedList1<'a>> {
+    pub fn remove_next(&self) -> Option<&'a LinkedList1<'a, T>> {
         let ret = self.next.take();
         if let Some(r) = ret {
             /* We remove the "next" value from the return object and place it
@@ -167,6 +205,196 @@ impl<'a> LinkedList1<'a> {
         }
         ret
     }
+
+    /* iter() needs &'a self, and because `next` is a Cell, 'a can't be
+    shortened to fit a shorter borrow (Cell makes the type invariant). Traits
+    like Display only get a short &self, so they walk with this instead. */
+    fn values(&self) -> Values<'_, 'a, T> {
+        Values { cursor: Some(self) }
+    }
+
+    /* There's no list struct to keep a counter in, so it's a walk. */
+    pub fn len(&self) -> usize {
+        self.values().count()
+    }
+
+    /* A chain always has at least the node we're calling it on. */
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /* Searching is just the tail() loop with a condition. */
+    pub fn find(&self, value: T) -> Option<&Self>
+    where
+        T: PartialEq,
+    {
+        let mut cur = self;
+        loop {
+            if cur.value == value {
+                return Some(cur);
+            }
+            cur = cur.next()?;
+        }
+    }
+
+    /* To unlink a node we need the one before it, as that's the one holding
+    the Cell to rewrite. So we walk one node behind and look ahead. Nobody
+    owns the node, so "removing" it only means nothing points to it anymore;
+    we give it back in case the caller wants to put it somewhere else.
+
+    It only looks after self: self can't remove itself, it has no previous. */
+    pub fn remove_next_matching(&self, value: T) -> Option<&'a LinkedList1<'a, T>>
+    where
+        T: PartialEq,
+    {
+        let mut prev = self;
+        while let Some(next) = prev.next() {
+            if next.value == value {
+                return prev.remove_next();
+            }
+            prev = next;
+        }
+        None
+    }
+
+    /* Node number n, counting self as 0. Like find(), but counting. */
+    pub fn nth(&self, n: usize) -> Option<&Self> {
+        let mut cur = self;
+        for _ in 0..n {
+            cur = cur.next()?;
+        }
+        Some(cur)
+    }
+
+    /* The node in the middle, in one pass: the hare jumps two nodes for
+    every one of the tortoise, so when the hare hits the end, the tortoise is
+    halfway. With an even length we get the first of the two middle nodes. */
+    pub fn middle(&self) -> &Self {
+        let mut slow = self;
+        let mut fast = self;
+        while let Some(next) = fast.next().and_then(|n| n.next()) {
+            fast = next;
+            slow = slow.next().unwrap();
+        }
+        slow
+    }
+}
+
+impl<'a, T: Copy> LinkedList1<'a, T> {
+    /* Reversing in place would work too, it's just rewriting Cells. But that
+    changes the chain for everyone else holding a reference into it. Here we
+    leave it alone and build a reversed copy in an arena instead: walking
+    forward and pushing each value to the front of the new chain is all it
+    takes. There's always at least one node, so there's always a first one. */
+    pub fn reverse_into(&self, arena: &'a Arena<LinkedList1<'a, T>>) -> &'a LinkedList1<'a, T> {
+        let mut first = None;
+        for value in self.values() {
+            first = Some(arena.alloc(LinkedList1::new(value, first)));
+        }
+        first.unwrap()
+    }
+
+    /* Sorting without moving a single value: only the `next` Cells get
+    rewritten, which shows that Cell alone is enough for any pointer surgery
+    we want, no &mut needed.
+
+    The catch is that self may not be the first node anymore afterwards, and
+    whoever held a reference to it still does. So the new first node is
+    returned, and that's the one to use from now on.
+
+    It's a merge sort, same as linked4: cut the chain in halves, sort them,
+    and merge them by relinking. Stable, O(n log n), and the recursion depth is
+    only log n, because the halves are found by counting. */
+    pub fn sort_links(&'a self) -> &'a LinkedList1<'a, T>
+    where
+        T: Ord,
+    {
+        debug_assert!(!self.has_cycle(), "sort_links() on a chain with a cycle");
+        sort_links(self, self.len())
+    }
+}
+
+/* Sorts the first `len` nodes from `first`, which must be all of them: the
+chain has to end right after. */
+fn sort_links<'a, T: Copy + Ord>(first: &'a LinkedList1<'a, T>, len: usize) -> &'a LinkedList1<'a, T> {
+    if len < 2 {
+        return first;
+    }
+    let half = len / 2;
+    let right = first.nth(half - 1).unwrap().next.take().unwrap();
+    let left = sort_links(first, half);
+    let right = sort_links(right, len - half);
+    merge_links(left, right)
+}
+
+fn merge_links<'a, T: Copy + Ord>(
+    left: &'a LinkedList1<'a, T>,
+    right: &'a LinkedList1<'a, T>,
+) -> &'a LinkedList1<'a, T> {
+    /* Taking from the left on ties is what keeps it stable. */
+    let (first, mut left, mut right) = if right.value < left.value {
+        (right, Some(left), right.next())
+    } else {
+        (left, left.next(), Some(right))
+    };
+    let mut tail = first;
+    while let (Some(l), Some(r)) = (left, right) {
+        let node = if r.value < l.value {
+            right = r.next();
+            r
+        } else {
+            left = l.next();
+            l
+        };
+        tail.next.set(Some(node));
+        tail = node;
+    }
+    /* One side ran out; the other is already chained, hang it at the end. */
+    tail.next.set(left.or(right));
+    first
+}
+
+/* The same as IterLinkedList1, but the borrow of the chain ('s) and the
+lifetime of the nodes ('a) are kept apart. */
+struct Values<'s, 'a, T> {
+    cursor: Option<&'s LinkedList1<'a, T>>,
+}
+
+impl<'s, 'a, T: Copy> Iterator for Values<'s, 'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.cursor?;
+        self.cursor = node.next();
+        Some(node.value)
+    }
+}
+
+/* The derived Debug printed `next` as a nested Cell, which recursed once per
+node. These walk the chain in a loop instead. Same format as linked4:
+1 -> 2 -> 3 -> ∅ */
+impl<'a, T: Copy + fmt::Debug> fmt::Debug for LinkedList1<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.values()).finish()
+    }
+}
+
+impl<'a, T: Copy + fmt::Display> fmt::Display for LinkedList1<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for value in self.values() {
+            write!(f, "{} -> ", value)?;
+        }
+        write!(f, "∅")
+    }
+}
+
+/* Equal if the chains hold the same values in the same order, no matter
+where the nodes live. As the lifetime can't be shortened, two chains built in
+different places have different types, so we compare across lifetimes. */
+impl<'a, 'b, T: Copy + PartialEq> PartialEq<LinkedList1<'b, T>> for LinkedList1<'a, T> {
+    fn eq(&self, other: &LinkedList1<'b, T>) -> bool {
+        self.values().eq(other.values())
+    }
 }
 
 /* Success! This is the first "complete" implementation of a linked list!. As we
@@ -190,7 +418,7 @@ end having a static lifetime on the objects.
 To showcase this problem, let's build a "manager" for this LinkedList1.
 */
 
-type Node2<'a> = LinkedList1<'a>;
+type Node2<'a> = LinkedList1<'a, i64>;
 
 struct LinkedList2<'a> {
     data: Vec<Node2<'a>>,
@@ -257,3 +485,104 @@ exercise is to avoid Rc<T> as much as possible to experience the "real" Rust.
 
 So, we will go for another approach!
 */
+
+/*
+Well, before leaving, there's one way to rescue this one. The real problem is
+that nobody owns the nodes for long enough. The manager can't, because it
+could move or drop them while the chain still points to them. But something
+that never moves or frees a node until it's gone itself can: an arena.
+
+If the arena lives outside the manager, every node it hands out is valid for
+as long as the arena is borrowed. That's the 'arena lifetime, and it's exactly
+the 'a our LinkedList1 wants.
+*/
+mod arena;
+pub use arena::Arena;
+
+pub struct ManagedList<'arena, T> {
+    arena: &'arena Arena<LinkedList1<'arena, T>>,
+    first: Cell<Option<&'arena LinkedList1<'arena, T>>>,
+    /* Cached, so appending doesn't walk the whole chain. Anyone can call
+    set_next() on a node, so we still walk from here to the real tail. */
+    tail: Cell<Option<&'arena LinkedList1<'arena, T>>>,
+}
+
+impl<'arena, T: Copy> ManagedList<'arena, T> {
+    pub fn new(arena: &'arena Arena<LinkedList1<'arena, T>>) -> Self {
+        ManagedList {
+            arena,
+            first: Cell::new(None),
+            tail: Cell::new(None),
+        }
+    }
+
+    /* And now append() works, with &self even. The node goes into the arena,
+    and the reference we get back lives as long as the arena does. */
+    pub fn append(&self, value: T) -> &'arena LinkedList1<'arena, T> {
+        let node = self.arena.alloc(LinkedList1::new(value, None));
+        match self.tail.get() {
+            Some(tail) => {
+                tail.tail().set_next(Some(node));
+            }
+            None => self.first.set(Some(node)),
+        }
+        self.tail.set(Some(node));
+        node
+    }
+
+    pub fn push_front(&self, value: T) -> &'arena LinkedList1<'arena, T> {
+        let node = self.arena.alloc(LinkedList1::new(value, self.first.get()));
+        if self.first.get().is_none() {
+            self.tail.set(Some(node));
+        }
+        self.first.set(Some(node));
+        node
+    }
+
+    pub fn first(&self) -> Option<&'arena LinkedList1<'arena, T>> {
+        self.first.get()
+    }
+
+    pub fn iter(&self) -> IterLinkedList1<'arena, T> {
+        IterLinkedList1 {
+            cursor: self.first.get(),
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().collect()
+    }
+}
+
+/* With an arena we can also offer a scoped builder. The chain is built in an
+arena that lives only during the call, and the closure gets the first node
+(plus the arena, to allocate more nodes to insert). Everything inside the
+closure shares one lifetime, so insert(), replace() and remove_next() just
+work, without the caller having to prove anything about lifetimes.
+
+The closure can return anything that doesn't borrow the chain. If there are
+no values there's no first node, and the closure isn't called. */
+impl<'a, T: Copy> LinkedList1<'a, T> {
+    pub fn with_list<R, F>(values: &[T], f: F) -> Option<R>
+    where
+        F: for<'b> FnOnce(&'b LinkedList1<'b, T>, &'b Arena<LinkedList1<'b, T>>) -> R,
+    {
+        let arena = Arena::with_capacity(values.len());
+        let list = ManagedList::new(&arena);
+        for value in values {
+            list.append(*value);
+        }
+        list.first().map(|first| f(first, &arena))
+    }
+}
+
+/* Nodes are never freed one by one. Removing a node from the chain just
+leaves it in the arena until the arena goes away. That's the price. */
+
+/* With the arena doing the owning, nothing stops us from adding a `prev`
+link too. */
+mod doubly;
+pub use doubly::{DoublyLinkedList, Iter as DoublyIter, Node};
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,285 @@
+use super::*;
+use crate::differential::Lcg;
+
+#[test]
+fn test_u8_chain() {
+    let c = LinkedList1::new(3u8, None);
+    let b = LinkedList1::new(2u8, Some(&c));
+    let a = LinkedList1::new(1u8, Some(&b));
+    assert_eq!(a.iter().collect::<Vec<u8>>(), vec![1, 2, 3]);
+    assert_eq!(a.tail().value(), 3);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[test]
+fn test_copy_struct_operations() {
+    let p = |x, y| Point { x, y };
+    let a = LinkedList1::new(p(0, 0), None);
+    let b = LinkedList1::new(p(1, 1), None);
+    let c = LinkedList1::new(p(2, 2), None);
+    let d = LinkedList1::new(p(3, 3), None);
+    a.append(&b);
+    a.append(&c);
+    // a -> d -> b -> c
+    a.insert(&d);
+    assert_eq!(a.iter().map(|v| v.x).collect::<Vec<_>>(), vec![0, 3, 1, 2]);
+    // a -> b -> c, d comes back alone
+    let removed = a.remove_next().unwrap();
+    assert_eq!(removed.value(), p(3, 3));
+    assert!(removed.next().is_none());
+    assert_eq!(a.iter().map(|v| v.x).collect::<Vec<_>>(), vec![0, 1, 2]);
+    // a -> d -> c, b is dropped from the chain
+    let old = a.replace(&d, true).unwrap();
+    assert_eq!(old.value(), p(1, 1));
+    assert_eq!(a.iter().map(|v| v.y).collect::<Vec<_>>(), vec![0, 3, 2]);
+}
+
+#[test]
+fn test_arena() {
+    let arena = Arena::with_capacity(2);
+    let refs: Vec<&i64> = (0..100).map(|i| arena.alloc(i)).collect();
+    // Growing the arena never moves what was already allocated.
+    for (i, r) in refs.iter().enumerate() {
+        assert_eq!(**r, i as i64);
+    }
+    assert_eq!(arena.len(), 100);
+}
+
+#[test]
+fn test_managed_list() {
+    let arena = Arena::new();
+    let list = ManagedList::new(&arena);
+    assert_eq!(list.to_vec(), vec![]);
+    for i in 1..=40 {
+        list.append(i);
+    }
+    list.push_front(0);
+    let want: Vec<i64> = (0..=40).collect();
+    assert_eq!(list.to_vec(), want);
+
+    // The nodes are ordinary linked3 nodes, so the Cell operations work too.
+    let first = list.first().unwrap();
+    assert_eq!(first.remove_next().unwrap().value(), 1);
+    first.insert(arena.alloc(LinkedList1::new(100, None)));
+    list.append(41);
+    assert_eq!(list.to_vec()[..3], [0, 100, 2]);
+    assert_eq!(list.to_vec().last(), Some(&41));
+    assert_eq!(arena.len(), 43);
+}
+
+#[test]
+fn test_with_list() {
+    let got = LinkedList1::with_list(&[1, 2, 3], |first, arena| {
+        first.remove_next();
+        first.insert(arena.alloc(LinkedList1::new(9, None)));
+        first.append(arena.alloc(LinkedList1::new(4, None)));
+        first.iter().collect::<Vec<i64>>()
+    });
+    assert_eq!(got, Some(vec![1, 9, 3, 4]));
+    assert_eq!(LinkedList1::with_list(&[] as &[i64], |_, _| ()), None);
+}
+
+#[test]
+fn test_find_and_remove_matching() {
+    LinkedList1::with_list(&[1, 2, 3, 2, 4], |first, _| {
+        assert_eq!(first.find(1).map(|n| n.value()), Some(1));
+        let found = first.find(2).unwrap();
+        assert_eq!(found.next().unwrap().value(), 3);
+        assert!(first.find(7).is_none());
+
+        let removed = first.remove_next_matching(2).unwrap();
+        assert_eq!(removed.value(), 2);
+        assert!(removed.next().is_none());
+        assert_eq!(first.iter().collect::<Vec<_>>(), vec![1, 3, 2, 4]);
+        assert!(first.remove_next_matching(1).is_none());
+        first.remove_next_matching(4);
+        assert_eq!(first.iter().collect::<Vec<_>>(), vec![1, 3, 2]);
+    });
+}
+
+#[test]
+fn test_len_display_eq() {
+    LinkedList1::with_list(&[3, 8, 1], |first, _| {
+        assert_eq!(first.len(), 3);
+        assert_eq!(format!("{}", first), "3 -> 8 -> 1 -> ∅");
+        assert_eq!(format!("{:?}", first), "[3, 8, 1]");
+        let c = LinkedList1::new(1, None);
+        let b = LinkedList1::new(8, Some(&c));
+        let a = LinkedList1::new(3, Some(&b));
+        assert_eq!(*first, a);
+        assert_ne!(*first, b);
+        assert_eq!(b.len(), 2);
+    });
+}
+
+#[test]
+fn test_has_cycle() {
+    let a = LinkedList1::new(1, None);
+    let b = LinkedList1::new(2, None);
+    let c = LinkedList1::new(3, None);
+    a.set_next(Some(&b));
+    b.set_next(Some(&c));
+    assert!(!a.has_cycle());
+    assert!(!c.has_cycle());
+    c.set_next(Some(&a));
+    assert!(a.has_cycle());
+    assert!(b.has_cycle());
+    // A loop that doesn't include the first node.
+    c.set_next(Some(&b));
+    assert!(a.has_cycle());
+    // And a node pointing to itself.
+    c.set_next(Some(&c));
+    assert!(a.has_cycle());
+    c.set_next(None);
+    assert!(!a.has_cycle());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "tail() on a chain with a cycle")]
+fn test_tail_on_cycle_panics() {
+    let a = LinkedList1::new(1, None);
+    let b = LinkedList1::new(2, Some(&a));
+    a.set_next(Some(&b));
+    a.tail();
+}
+
+#[test]
+fn test_reverse_middle_nth() {
+    LinkedList1::with_list(&[1, 2, 3, 4, 5], |first, arena| {
+        let reversed = first.reverse_into(arena);
+        assert_eq!(reversed.iter().collect::<Vec<_>>(), vec![5, 4, 3, 2, 1]);
+        // The original chain is untouched.
+        assert_eq!(first.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+
+        assert_eq!(first.middle().value(), 3);
+        assert_eq!(first.nth(0).unwrap().value(), 1);
+        assert_eq!(first.nth(4).unwrap().value(), 5);
+        assert!(first.nth(5).is_none());
+
+        first.nth(3).unwrap().remove_next();
+        assert_eq!(first.middle().value(), 2);
+        assert_eq!(first.nth(3).unwrap().value(), 4);
+    });
+    let single = LinkedList1::new(7, None);
+    assert!(std::ptr::eq(single.middle(), &single));
+    let arena = Arena::new();
+    assert_eq!(single.reverse_into(&arena).iter().collect::<Vec<_>>(), vec![7]);
+}
+
+#[test]
+fn test_sort_links() {
+    let mut rng = Lcg::new(42);
+    for n in 1..50 {
+        let data: Vec<i64> = (0..n).map(|_| (rng.next_u64() >> 33) as i64 % 20).collect();
+        LinkedList1::with_list(&data, |first, _| {
+            let old_first = first.value();
+            let sorted = first.sort_links();
+            let mut expected = data.clone();
+            expected.sort();
+            assert_eq!(sorted.iter().collect::<Vec<_>>(), expected);
+            // No value moved: the node we had still holds the same one.
+            assert_eq!(first.value(), old_first);
+            assert!(!sorted.has_cycle());
+        });
+    }
+}
+
+#[test]
+fn test_sort_links_is_stable() {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Key(u8);
+    let a = LinkedList1::new(Key(1), None);
+    let b = LinkedList1::new(Key(0), Some(&a));
+    let c = LinkedList1::new(Key(1), Some(&b));
+    let d = LinkedList1::new(Key(0), Some(&c));
+    let sorted = d.sort_links();
+    assert!(std::ptr::eq(sorted, &d));
+    assert!(std::ptr::eq(sorted.nth(1).unwrap(), &b));
+    assert!(std::ptr::eq(sorted.nth(2).unwrap(), &c));
+    assert!(std::ptr::eq(sorted.nth(3).unwrap(), &a));
+}
+
+#[test]
+fn test_doubly_linked_list() {
+    let arena = Arena::new();
+    let list = DoublyLinkedList::new(&arena);
+    assert!(list.is_empty());
+    assert_eq!(list.pop_back(), None);
+    list.push_back(2);
+    let three = list.push_back(3);
+    list.push_back(4);
+    list.push_front(1);
+    assert_eq!(list.len(), 4);
+    assert_eq!(list.to_vec(), vec![1, 2, 3, 4]);
+    assert_eq!(list.iter().rev().collect::<Vec<_>>(), vec![4, 3, 2, 1]);
+    assert_eq!(three.prev().unwrap().value(), 2);
+    assert_eq!(three.next().unwrap().value(), 4);
+
+    let mut iter = list.iter();
+    assert_eq!(iter.next(), Some(1));
+    assert_eq!(iter.next_back(), Some(4));
+    assert_eq!(iter.len(), 2);
+    assert_eq!(iter.collect::<Vec<_>>(), vec![2, 3]);
+
+    assert!(list.unlink(three));
+    assert!(three.next().is_none() && three.prev().is_none());
+    assert_eq!(list.to_vec(), vec![1, 2, 4]);
+    assert_eq!(list.iter().rev().collect::<Vec<_>>(), vec![4, 2, 1]);
+
+    assert_eq!(list.pop_front(), Some(1));
+    assert_eq!(list.pop_back(), Some(4));
+    assert_eq!(list.first().unwrap().value(), 2);
+    assert!(std::ptr::eq(list.first().unwrap(), list.last().unwrap()));
+    assert_eq!(list.pop_back(), Some(2));
+    assert!(list.is_empty());
+    assert!(list.first().is_none() && list.last().is_none());
+    // The nodes are still in the arena.
+    assert_eq!(arena.len(), 4);
+}
+
+#[test]
+fn test_doubly_unlink_twice() {
+    let arena = Arena::new();
+    let list = DoublyLinkedList::new(&arena);
+    let one = list.push_back(1);
+    list.push_back(2);
+    let three = list.push_back(3);
+    assert!(list.unlink(one));
+    assert!(!list.contains(one));
+    // Its prev is None like the first node's, but it isn't the first node.
+    assert!(!list.unlink(one));
+    assert!(list.unlink(three));
+    assert!(!list.unlink(three));
+    assert_eq!(list.len(), 1);
+    assert_eq!(list.to_vec(), vec![2]);
+    assert_eq!(list.iter().rev().collect::<Vec<_>>(), vec![2]);
+    assert_eq!(list.pop_back(), Some(2));
+    assert!(!list.unlink(one));
+    assert_eq!(list.len(), 0);
+}
+
+#[test]
+fn test_doubly_unlink_from_another_list() {
+    let arena = Arena::new();
+    let a = DoublyLinkedList::new(&arena);
+    let b = DoublyLinkedList::new(&arena);
+    let a1 = a.push_back(1);
+    let a2 = a.push_back(2);
+    a.push_back(3);
+    let b1 = b.push_back(10);
+    b.push_back(20);
+    assert!(!b.unlink(a1));
+    assert!(!b.unlink(a2));
+    assert!(!a.unlink(b1));
+    assert_eq!(a.to_vec(), vec![1, 2, 3]);
+    assert_eq!(b.to_vec(), vec![10, 20]);
+    assert!(a.unlink(a2));
+    assert_eq!(a.iter().rev().collect::<Vec<_>>(), vec![3, 1]);
+    assert_eq!((a.len(), b.len()), (2, 2));
+}
@@ -0,0 +1,181 @@
+/*
+A cursor: a position inside the list that we can move around and edit at.
+
+With plain references this is a fight. A `&mut LinkedList1` to the current
+node borrows the whole chain from there on, so we can't also keep the node
+before it (which we need to unlink the current one), and every method that
+moves the cursor has to convince the borrow checker all over again.
+
+So the cursor borrows the whole List mutably, once, for as long as it lives.
+Nobody else can touch the list meanwhile, and inside that borrow we keep raw
+pointers to the current node and the one before it. The borrow checker only
+checks the outer &mut; the pointers are our problem. Like the tail cache,
+they're taken from the Boxes with node_ptr(), so they stay good while we
+reach the same nodes through the chain.
+
+Like std's LinkedList cursors, there's a "ghost" position between the last and
+the first node. That's where `current` is null. Moving next from the last node
+lands there, and moving next again goes back to the first one.
+*/
+
+use super::{node_ptr, LinkedList1In, ListIn, ListState};
+use crate::arena::{Allocator, Global};
+use crate::invariants::debug_check;
+use std::ptr::{self, NonNull};
+
+pub struct CursorMut<'a, T, A: Allocator = Global> {
+    list: &'a mut ListIn<T, A>,
+    current: *mut LinkedList1In<T, A>,
+    /* The node before current, or null if current is the first one (or the
+    ghost). */
+    prev: *mut LinkedList1In<T, A>,
+    index: usize,
+}
+
+/* current and prev point into the Box chain of the list we borrow
+mutably, and nothing else can reach those nodes while we do. So sending
+the cursor is sending the &mut List<T>, and sharing it lets others read
+values through it. */
+unsafe impl<'a, T: Send, A: Allocator + Send> Send for CursorMut<'a, T, A> {}
+unsafe impl<'a, T: Sync, A: Allocator + Sync> Sync for CursorMut<'a, T, A> {}
+
+impl<T, A: Allocator> ListIn<T, A> {
+    /* The cursor starts at the first node, or at the ghost if the list is
+    empty. */
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T, A> {
+        let mut cursor = CursorMut {
+            list: self,
+            current: ptr::null_mut(),
+            prev: ptr::null_mut(),
+            index: 0,
+        };
+        cursor.move_next();
+        cursor
+    }
+
+    fn first_ptr(&mut self) -> *mut LinkedList1In<T, A> {
+        match &mut self.state {
+            ListState::First(list) => node_ptr(list).as_ptr(),
+            ListState::Empty => ptr::null_mut(),
+        }
+    }
+}
+
+impl<'a, T, A: Allocator> CursorMut<'a, T, A> {
+    /* None on the ghost. */
+    pub fn index(&self) -> Option<usize> {
+        if self.current.is_null() {
+            None
+        } else {
+            Some(self.index)
+        }
+    }
+
+    pub fn move_next(&mut self) {
+        if self.current.is_null() {
+            self.prev = ptr::null_mut();
+            self.current = self.list.first_ptr();
+            self.index = 0;
+        } else {
+            let node = unsafe { &mut *self.current };
+            self.prev = self.current;
+            self.current = next_ptr(node);
+            self.index += 1;
+        }
+    }
+
+    /* The reference borrows the cursor, so it can't outlive the next move. */
+    pub fn current(&mut self) -> Option<&mut T> {
+        unsafe { self.current.as_mut() }.map(|node| &mut node.value)
+    }
+
+    /* Takes the current node out and returns its value. The cursor moves to
+    the node that came after it (or the ghost). */
+    pub fn remove_current(&mut self) -> Option<T> {
+        if self.current.is_null() {
+            return None;
+        }
+        if self.prev.is_null() {
+            /* The first node is owned by the list itself, not by a `next`. */
+            let value = self.list.pop_front();
+            self.current = self.list.first_ptr();
+            return value;
+        }
+        let prev = unsafe { &mut *self.prev };
+        let (value, next) = prev.next.take().unwrap().into_parts();
+        prev.next = next;
+        /* If that was the cached tail, the node before it is the new one.
+        Unless it's the first node, which is never cached. */
+        if self.list.tail == NonNull::new(self.current) {
+            self.list.tail = NonNull::new(self.prev);
+            self.list.uncache_first();
+        } else if let Some(next) = prev.next.as_mut() {
+            /* Same as in insert_after(): the next Box moved. */
+            if self.list.tail == Some(NonNull::from(&**next)) {
+                self.list.tail = Some(node_ptr(next));
+            }
+        }
+        let prev = unsafe { &mut *self.prev };
+        self.current = next_ptr(prev);
+        self.list.len -= 1;
+        debug_check(self.list, self.list.len);
+        Some(value)
+    }
+}
+
+/* These two make a node or a list, and they get the list's allocator. */
+impl<'a, T, A: Allocator + Clone> CursorMut<'a, T, A> {
+    /* On the ghost, "after" means at the front of the list. The cursor
+    doesn't move. */
+    pub fn insert_after(&mut self, value: T) {
+        let node = match unsafe { self.current.as_mut() } {
+            Some(node) => node,
+            None => return self.list.push_front(value),
+        };
+        let next = node.next.take();
+        let is_last = next.is_none();
+        let alloc = self.list.alloc.clone();
+        let new = node.next.insert(LinkedList1In::new_box_in(value, next, alloc));
+        if is_last {
+            self.list.tail = Some(node_ptr(new));
+        } else if let Some(next) = new.next.as_mut() {
+            /* The old next has moved into the new node. If it was the tail,
+            the cache has to be taken again. */
+            if self.list.tail == Some(NonNull::from(&**next)) {
+                self.list.tail = Some(node_ptr(next));
+            }
+        }
+        self.list.len += 1;
+        debug_check(self.list, self.list.len);
+    }
+
+    /* Cuts the list after the current node and returns everything that was
+    after it as a new List. On the ghost that's the whole list. */
+    pub fn split_after(&mut self) -> ListIn<T, A> {
+        let node = match unsafe { self.current.as_mut() } {
+            Some(node) => node,
+            None => {
+                let empty = ListIn::new_in(self.list.alloc.clone());
+                return std::mem::replace(self.list, empty);
+            }
+        };
+        let rest = node.next.take();
+        /* The first node is never cached. */
+        self.list.tail = NonNull::new(self.current).filter(|_| self.index > 0);
+        self.list.len = self.index + 1;
+        debug_check(self.list, self.list.len);
+        let alloc = self.list.alloc.clone();
+        match rest {
+            Some(rest) => ListIn::from_state(ListState::First(rest), alloc),
+            None => ListIn::new_in(alloc),
+        }
+    }
+}
+
+/* The node after `node`, for `current`, or null for the ghost. */
+fn next_ptr<T, A: Allocator>(node: &mut LinkedList1In<T, A>) -> *mut LinkedList1In<T, A> {
+    match node.next.as_mut() {
+        Some(next) => node_ptr(next).as_ptr(),
+        None => ptr::null_mut(),
+    }
+}
@@ -0,0 +1,313 @@
+/*
+Going backwards
+===========================================================================
+
+IterLinkedList1 can't be reversed: nodes only know who comes next. To walk
+backwards we need every node to also know who comes before it.
+
+We can't give it a second Box, because the previous node already owns it.
+Two owners is what Rc is for, and that's linked5. But there's a middle step:
+the `next` Box keeps owning the chain exactly like before, and `prev` is just
+a raw pointer that owns nothing. It's only valid because the node it points to
+owns us (directly, through its `next`), so it can't go away before we do.
+
+Raw pointers mean unsafe. The deal is simple though: only this file touches
+`prev` and `tail`, and every method keeps them pointing to live nodes.
+
+Live isn't enough, as Miri pointed out. A Box promises, like &mut, to be the
+only way to its node: moving it kills every raw pointer taken from it before.
+Hence the finer print:
+- The first node's Box is a field of the list, and moves whenever the list
+  does. So nothing points to the first node: the second node's `prev` is null
+  too, and `tail` is null while there's at most one node. Whoever finds a null
+  there and still wants the first node gets it from `first`.
+- The pointers are taken from the Box, with node_ptr(), never from a
+  reference to the node. A reference would be a borrow of its own, and the
+  next read through the Box would leave it read-only (Tree Borrows says so).
+- When a node's Box moves, the pointers to it are taken again.
+*/
+
+use crate::arena::{Allocator, Global};
+use crate::footprint::HeapSize;
+use crate::topology::{id_of, NodeLinks, Topology};
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ptr;
+
+/* Boxes in an allocator, like List's (see LinkedList1In). */
+#[cfg(feature = "allocator_api")]
+type NodeBox<T, A> = Box<Node<T, A>, A>;
+#[cfg(not(feature = "allocator_api"))]
+type NodeBox<T, A> = Box<Node<T, A>>;
+
+struct Node<T, A: Allocator> {
+    value: T,
+    next: Option<NodeBox<T, A>>,
+    prev: *mut Node<T, A>,
+    #[cfg(not(feature = "allocator_api"))]
+    _alloc: PhantomData<A>,
+}
+
+impl<T, A: Allocator> Node<T, A> {
+    #[cfg(feature = "allocator_api")]
+    fn new_box(value: T, next: Option<NodeBox<T, A>>, prev: *mut Self, alloc: A) -> NodeBox<T, A> {
+        Box::new_in(Node { value, next, prev }, alloc)
+    }
+    #[cfg(not(feature = "allocator_api"))]
+    fn new_box(value: T, next: Option<NodeBox<T, A>>, prev: *mut Self, _alloc: A) -> NodeBox<T, A> {
+        Box::new(Node {
+            value,
+            next,
+            prev,
+            _alloc: PhantomData,
+        })
+    }
+}
+
+/* DoublyList<T, A> with the allocator_api feature, DoublyList<T> on Global
+without it, like List. */
+#[cfg(feature = "allocator_api")]
+pub type DoublyList<T, A = Global> = DoublyListIn<T, A>;
+#[cfg(not(feature = "allocator_api"))]
+pub type DoublyList<T> = DoublyListIn<T, Global>;
+
+pub struct DoublyListIn<T, A: Allocator = Global> {
+    first: Option<NodeBox<T, A>>,
+    tail: *mut Node<T, A>,
+    len: usize,
+    alloc: A,
+}
+
+/* Same reasoning as List: raw pointers turn off Send and Sync, but they only
+point inside our own chain of Boxes. */
+unsafe impl<T: Send, A: Allocator + Send> Send for DoublyListIn<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for DoublyListIn<T, A> {}
+
+impl<T> Default for DoublyList<T> {
+    fn default() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T> DoublyList<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_slice(slice: &[T]) -> Self
+    where
+        T: Clone,
+    {
+        let mut list = Self::new();
+        for value in slice {
+            list.push_back(value.clone());
+        }
+        list
+    }
+}
+
+impl<T, A: Allocator> DoublyListIn<T, A> {
+    pub fn new_in(alloc: A) -> Self {
+        DoublyListIn {
+            first: None,
+            tail: ptr::null_mut(),
+            len: 0,
+            alloc,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let mut node = self.first.take()?;
+        self.first = node.next.take();
+        self.len -= 1;
+        /* The second node's Box has just moved into `first`, so the pointers
+        to it are gone: tail if it was the last node, otherwise the prev of
+        the node after it. */
+        if self.len <= 1 {
+            self.tail = ptr::null_mut();
+        } else {
+            let first = self.first.as_deref_mut().unwrap();
+            first.next.as_deref_mut().unwrap().prev = ptr::null_mut();
+        }
+        Some(node.value)
+    }
+
+    /* This is what `prev` buys us: linked4::List::pop_back() has to walk the
+    whole list to find the node before the tail. Here we just ask the tail. */
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.tail.is_null() {
+            return self.pop_front();
+        }
+        let prev = unsafe { (*self.tail).prev };
+        let owner = if prev.is_null() {
+            &mut self.first.as_mut().unwrap().next
+        } else {
+            unsafe { &mut (*prev).next }
+        };
+        let node = owner.take().unwrap();
+        self.tail = prev;
+        self.len -= 1;
+        Some(node.value)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, A> {
+        Iter {
+            front: self.first.as_deref(),
+            first: self.first.as_deref(),
+            back: self.tail,
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+
+    pub fn to_vec_rev(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().rev().cloned().collect()
+    }
+}
+
+/* Pushing makes a Box, which gets a clone of the list's allocator. */
+impl<T, A: Allocator + Clone> DoublyListIn<T, A> {
+    /* The pointer to the new node is taken once its Box is in the chain, not
+    before: moving the Box would kill it. */
+    pub fn push_back(&mut self, value: T) {
+        let node = Node::new_box(value, None, self.tail, self.alloc.clone());
+        let owner = match self.first.as_deref_mut() {
+            None => {
+                /* The only node, so tail stays null. */
+                self.first = Some(node);
+                self.len += 1;
+                return;
+            }
+            Some(first) if self.tail.is_null() => &mut first.next,
+            Some(_) => unsafe { &mut (*self.tail).next },
+        };
+        self.tail = node_ptr(owner.insert(node));
+        self.len += 1;
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        let next = self.first.take();
+        let new = Node::new_box(value, next, ptr::null_mut(), self.alloc.clone());
+        let first = self.first.insert(new);
+        /* The old first node is second now, and its Box is in its final
+        place, so it can be pointed to: by tail if it's also the last one, or
+        by the node after it. */
+        if let Some(second) = first.next.as_mut() {
+            let second_ptr = node_ptr(second);
+            match second.next.as_deref_mut() {
+                None => self.tail = second_ptr,
+                Some(third) => third.prev = second_ptr,
+            }
+        }
+        self.len += 1;
+    }
+}
+
+/* Through the Box, without making a reference first: see the top. */
+fn node_ptr<T, A: Allocator>(owner: &mut NodeBox<T, A>) -> *mut Node<T, A> {
+    ptr::addr_of_mut!(**owner)
+}
+
+/* Node has no Drop of its own (that's what lets pop_front() move the value
+out), so the chain is unlinked here, in a loop, like LinkedList1 does. */
+impl<T, A: Allocator> Drop for DoublyListIn<T, A> {
+    fn drop(&mut self) {
+        let mut cur = self.first.take();
+        while let Some(mut node) = cur {
+            cur = node.next.take();
+        }
+    }
+}
+
+/* The prev pointers are written down as they are: the second node's is
+null, see the rules at the top. */
+impl<T, A: Allocator> Topology for DoublyListIn<T, A> {
+    fn topology(&self) -> Vec<NodeLinks> {
+        let id = |node: &Node<T, A>| id_of(node);
+        std::iter::successors(self.first.as_deref(), |node| node.next.as_deref())
+            .take(self.len)
+            .map(|node| NodeLinks {
+                id: id(node),
+                next: node.next.as_deref().map(id),
+                prev: (!node.prev.is_null()).then(|| id_of(node.prev)),
+                counts: None,
+            })
+            .collect()
+    }
+}
+
+impl<T, A: Allocator> HeapSize for DoublyListIn<T, A> {
+    fn heap_size(&self) -> usize {
+        self.len * size_of::<Node<T, A>>()
+    }
+}
+
+/* The front walks through the Boxes as usual. The back follows `prev`, which
+is a raw pointer, so the borrow checker doesn't know it's borrowing the list.
+PhantomData tells it: this iterator behaves as if it held a &'a T.
+
+Both ends could meet in the middle and walk past each other, so we count how
+many values are left instead of comparing pointers. A null `back` means the
+first node, which nothing points to. */
+pub struct Iter<'a, T, A: Allocator = Global> {
+    front: Option<&'a Node<T, A>>,
+    first: Option<&'a Node<T, A>>,
+    back: *const Node<T, A>,
+    remaining: usize,
+    _list: PhantomData<&'a T>,
+}
+
+impl<'a, T, A: Allocator> Iterator for Iter<'a, T, A> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.front?;
+        self.front = node.next.as_deref();
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T, A: Allocator> DoubleEndedIterator for Iter<'a, T, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = match unsafe { self.back.as_ref() } {
+            Some(node) => node,
+            None => self.first?,
+        };
+        self.back = node.prev;
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+}
+
+impl<'a, T, A: Allocator> ExactSizeIterator for Iter<'a, T, A> {}
+impl<'a, T, A: Allocator> FusedIterator for Iter<'a, T, A> {}
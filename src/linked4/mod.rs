@@ -17,24 +17,62 @@ checked solution which implies counting how many borrows are there. I want
 to avoid this if possible, so let's go without Cell for now.
 */
 
-#[derive(Debug)]
-pub struct LinkedList1 {
-    pub value: i64,
-    pub next: Option<Box<LinkedList1>>,
+use crate::arena::{Allocator, Global};
+use crate::footprint::HeapSize;
+use crate::invariants::{debug_check, CheckInvariants, InvariantError};
+use crate::topology::{id_of, NodeLinks, Topology};
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FusedIterator;
+#[cfg(not(feature = "allocator_api"))]
+use std::marker::PhantomData;
+use std::mem::{size_of, ManuallyDrop};
+use std::ops::{Index, IndexMut};
+use std::ptr::{self, NonNull};
+
+/* Now that we have something that works, it doesn't need to be i64 only.
+Everything is generic over the value type T from here on.
+
+And, with the allocator_api feature, over where the Boxes come from (see
+arena). A Box<T, A> needs that A in the node type, so the node is
+LinkedList1In on the inside and LinkedList1 on the outside, like linked6's
+List: LinkedList1<T, A> with the feature on, and LinkedList1<T>, always on
+Global, without it. */
+#[cfg(feature = "allocator_api")]
+pub type LinkedList1<T, A = Global> = LinkedList1In<T, A>;
+#[cfg(not(feature = "allocator_api"))]
+pub type LinkedList1<T> = LinkedList1In<T, Global>;
+
+/* The Box a `next` is kept in. Stable has no Box<T, A>, only Box<T>. */
+#[cfg(feature = "allocator_api")]
+type NodeBox<T, A> = Box<LinkedList1In<T, A>, A>;
+#[cfg(not(feature = "allocator_api"))]
+type NodeBox<T, A> = Box<LinkedList1In<T, A>>;
+
+pub struct LinkedList1In<T, A: Allocator = Global> {
+    pub value: T,
+    pub next: Option<NodeBox<T, A>>,
+    /* On stable the Box doesn't mention A, so something else has to. It also
+    means a node can't be built with `LinkedList1 { value, next }` there, only
+    with new(). */
+    #[cfg(not(feature = "allocator_api"))]
+    _alloc: PhantomData<A>,
 }
 
-pub struct IterLinkedList1<'a> {
+pub struct IterLinkedList1<'a, T, A: Allocator = Global> {
     /* Notice this one is still a reference. Why? Iterators are expected to be
     consumed. It doesn't make much sense to leave an iterator floating around
     permanently. */
-    cursor: Option<&'a LinkedList1>,
+    cursor: Option<&'a LinkedList1In<T, A>>,
 }
 
-impl<'a> Iterator for IterLinkedList1<'a> {
-    type Item = i64;
+/* The iterator hands out references now. Copying was fine for i64, but
+for a generic T we can't assume it's cheap (or even possible) to copy. */
+impl<'a, T, A: Allocator> Iterator for IterLinkedList1<'a, T, A> {
+    type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let ret = self.cursor.map(|c| c.value);
+        let ret = self.cursor.map(|c| &c.value);
         /* Now we have to use Option::as_deref() so it swaps the Box with
         a reference */
         self.cursor = match self.cursor {
@@ -45,19 +83,48 @@ impl<'a> Iterator for IterLinkedList1<'a> {
     }
 }
 
-impl LinkedList1 {
+/* A bare chain doesn't know its length, so there's no exact size_hint()
+here (List::iter() has one). Once the cursor is None it stays None, so it's
+fused at least. */
+impl<'a, T, A: Allocator> FusedIterator for IterLinkedList1<'a, T, A> {}
+
+impl<T> LinkedList1<T> {
     /* This new function is now a bit pointless. But I'll keep it. */
-    pub fn new(value: i64, next: Option<Box<LinkedList1>>) -> Self {
-        LinkedList1 { value, next }
+    pub fn new(value: T, next: Option<Box<LinkedList1<T>>>) -> Self {
+        Self::node(value, next)
     }
     /* This will come handy sometime later */
-    pub fn new_box(value: i64, next: Option<Box<LinkedList1>>) -> Box<Self> {
-        Box::new(LinkedList1 { value, next })
+    pub fn new_box(value: T, next: Option<Box<LinkedList1<T>>>) -> Box<Self> {
+        Box::new(Self::node(value, next))
     }
-    pub fn value(&self) -> i64 {
-        self.value
+    pub fn insert_into(&mut self, item: LinkedList1<T>) {
+        let newnext = Box::new(item);
+        self.insert(newnext);
     }
-    pub fn set_value(&mut self, value: i64) {
+}
+
+impl<T, A: Allocator> LinkedList1In<T, A> {
+    fn node(value: T, next: Option<NodeBox<T, A>>) -> Self {
+        LinkedList1In {
+            value,
+            next,
+            #[cfg(not(feature = "allocator_api"))]
+            _alloc: PhantomData,
+        }
+    }
+    /* new_box(), with the Box in `alloc`. */
+    #[cfg(feature = "allocator_api")]
+    pub fn new_box_in(value: T, next: Option<NodeBox<T, A>>, alloc: A) -> NodeBox<T, A> {
+        Box::new_in(Self::node(value, next), alloc)
+    }
+    #[cfg(not(feature = "allocator_api"))]
+    pub fn new_box_in(value: T, next: Option<NodeBox<T, A>>, _alloc: A) -> NodeBox<T, A> {
+        Box::new(Self::node(value, next))
+    }
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+    pub fn set_value(&mut self, value: T) {
         self.value = value;
     }
     pub fn next(&self) -> Option<&Self> {
@@ -66,12 +133,12 @@ impl LinkedList1 {
         self.next.as_deref()
     }
     /* This function now needs to be mutable because we lost the Cell */
-    pub fn set_next(&mut self, next: Option<Box<LinkedList1>>) -> Option<Box<LinkedList1>> {
+    pub fn set_next(&mut self, next: Option<NodeBox<T, A>>) -> Option<NodeBox<T, A>> {
         /* Not needed, as we could do two steps here. But I'll use replace anyways. */
         use std::mem::replace;
         replace(&mut self.next, next)
     }
-    pub fn iter(&self) -> IterLinkedList1 {
+    pub fn iter(&self) -> IterLinkedList1<T, A> {
         IterLinkedList1 {
             cursor: Some(&self),
         }
@@ -124,13 +191,11 @@ impl LinkedList1 {
     means the caller loses the value into the function.
 
     For convenience I'll split this into two, one takes ownership, the other
-    takes already a box. This might be convenient for later.
+    takes already a box. This might be convenient for later. (The one that
+    takes ownership has to make the Box, so it's up with new_box(), on
+    Global.)
      */
-    pub fn insert_into(&mut self, item: LinkedList1) {
-        let newnext = Box::new(item);
-        self.insert(newnext);
-    }
-    pub fn insert(&mut self, item: Box<LinkedList1>) {
+    pub fn insert(&mut self, item: NodeBox<T, A>) {
         let oldnext = self.next.replace(item);
         /* Here because the mutable pointer is unique, we need to be smarter and
         realize that once the item is in our chain, its tail is actually now our
@@ -139,7 +204,7 @@ impl LinkedList1 {
         tail.next = oldnext;
     }
 
-    pub fn replace(&mut self, item: Box<LinkedList1>, chain: bool) -> Option<Box<LinkedList1>> {
+    pub fn replace(&mut self, item: NodeBox<T, A>, chain: bool) -> Option<NodeBox<T, A>> {
         let oldnext = self.next.replace(item);
         if chain {
             let tail = self.tail_mut();
@@ -160,11 +225,11 @@ impl LinkedList1 {
         }
     }
 
-    pub fn append(&mut self, item: Box<LinkedList1>) {
+    pub fn append(&mut self, item: NodeBox<T, A>) {
         self.tail_mut().insert(item)
     }
 
-    pub fn remove_next(&mut self) -> Option<Box<LinkedList1>> {
+    pub fn remove_next(&mut self) -> Option<NodeBox<T, A>> {
         let ret = self.next.take();
         /* Some(r) now needs to be mutable in order to perform r.next.take() */
         if let Some(mut r) = ret {
@@ -178,6 +243,21 @@ impl LinkedList1 {
             None
         }
     }
+
+    /* Because LinkedList1 implements Drop, Rust doesn't let us destructure it
+    and move the value out: `let LinkedList1 { value, .. } = node` fails with
+    "cannot move out of type which implements the Drop trait".
+
+    The way around it is to never run the drop. ManuallyDrop keeps the node
+    from being dropped, so we can copy the value out bit by bit with ptr::read
+    and nobody will drop it a second time. `next` is taken beforehand, so we
+    don't leak the rest of the chain. */
+    pub fn into_parts(mut self) -> (T, Option<NodeBox<T, A>>) {
+        let next = self.next.take();
+        let node = ManuallyDrop::new(self);
+        let value = unsafe { ptr::read(&node.value) };
+        (value, next)
+    }
 }
 
 /*
@@ -193,90 +273,910 @@ a list of 1 or more items. Can we code this as a Rust enum?
 
 /* Was going to use "None", but for practice, I guess we can reinvent the wheel */
 #[derive(Debug)]
-pub enum List {
-    First(Box<LinkedList1>),
+enum ListState<T, A: Allocator = Global> {
+    First(NodeBox<T, A>),
     Empty,
 }
 
-impl List {
-    pub fn new_slow(slice: &[i64]) -> Self {
+/* The enum works, but every add_item() has to walk the whole list to find the
+tail, so appending is O(n) and concatenating in a loop is O(n^2). The profiler
+was quite clear about it.
+
+So List becomes a struct: the enum above still holds the nodes, and next to it
+we remember where the last node is. This can't be a reference, because it
+would be borrowing from `state`, which we also own. It has to be a raw pointer,
+and we promise to keep it pointing to a node that lives inside `state`.
+
+That promise is why the fields are private now. If anyone could take the Box
+out of the enum they could free the node under our feet.
+
+For the same reason, tail_mut() hands out the last value, not the last node.
+The node's `next` is public, and anyone holding it could hang more nodes
+after our tail, where neither the cache nor the count would know about them.
+(It used to hand out the node, and every method had to walk past the cache
+first, in case someone had.)
+
+While we're at it, we also keep count of the nodes, so len() doesn't have to
+walk the list either. Same deal: every method that adds or removes nodes has
+to keep it right.
+
+Miri taught us a few more rules. A Box promises, like &mut, to be the only
+way to its node, and moving the Box kills every raw pointer taken from it
+before. A pointer taken from a reference to the node, like
+NonNull::from(&mut *node), is even more fragile: it's a new borrow of its
+own, and under Tree Borrows the next read through the Box (iter() does
+nothing else) leaves it read-only. So:
+- The cache is taken from the Box itself, with node_ptr(). Then it carries
+  the Box's own permission, and reading or writing the node through the Box
+  doesn't hurt it.
+- When the tail's Box moves within the chain, we take the pointer again from
+  where the Box is now.
+- The first node's Box lives in `state`, so it moves every time the List
+  does. We can't retake anything then, so the cache never points to the first
+  node. None with nodes in the list means the first node is the tail.
+*/
+#[cfg(feature = "allocator_api")]
+pub type List<T, A = Global> = ListIn<T, A>;
+#[cfg(not(feature = "allocator_api"))]
+pub type List<T> = ListIn<T, Global>;
+
+pub struct ListIn<T, A: Allocator = Global> {
+    state: ListState<T, A>,
+    tail: Option<NonNull<LinkedList1In<T, A>>>,
+    len: usize,
+    /* Every Box keeps its own allocator, but an empty list has no Box, and
+    new ones get a clone of this. */
+    alloc: A,
+}
+
+/* A raw pointer makes the compiler give up on Send and Sync. The pointer only
+ever points inside our own Box chain, so we're as thread safe as the Boxes
+are, allocators and all. */
+unsafe impl<T: Send, A: Allocator + Send> Send for ListIn<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for ListIn<T, A> {}
+
+/* The old i64 list is still there, just with a longer name. */
+pub type I64List = List<i64>;
+
+/* A pointer to the node in `owner`, with the Box's permission and not a new
+one: addr_of_mut! goes through the Box without making a reference first. */
+fn node_ptr<T, A: Allocator>(owner: &mut NodeBox<T, A>) -> NonNull<LinkedList1In<T, A>> {
+    unsafe { NonNull::new_unchecked(ptr::addr_of_mut!(**owner)) }
+}
+
+impl<T: Clone> List<T> {
+    pub fn new_slow(slice: &[T]) -> Self {
         let mut iter = slice.iter();
         /* Because we don't know the length of the slice, the only way to get
         the 1st value, then the remaining, is to use an iterator. Consume the
         first item, then iterate the remaining. */
         let opt_value = iter.next();
         if opt_value.is_none() {
-            return List::Empty;
+            return List::from_state(ListState::Empty, Global);
         }
         let value = opt_value.unwrap();
         /* value needs to be de-referenced to do a copy, since i64 implements
         copy, this is possible. If the type instead of i64 was non-copyable, we
         would have to choose, either .clone() it (if it allows) or take full
-        ownership. */
-        let mut first = LinkedList1::new_box(*value, None);
+        ownership.
+
+        Now that the list is generic, that's exactly our situation: T might
+        not be Copy, so we clone. */
+        let mut first = LinkedList1::new_box(value.clone(), None);
         for value in iter {
             /* This is not really efficient as it will iterate the list each time */
-            first.append(LinkedList1::new_box(*value, None))
+            first.append(LinkedList1::new_box(value.clone(), None))
         }
-        List::First(first)
+        List::from_state(ListState::First(first), Global)
     }
     /* Let's try a faster version */
-    pub fn new_bad(slice: &[i64]) -> Self {
+    pub fn new_bad(slice: &[T]) -> Self {
         let mut iter = slice.iter();
         let opt_value = iter.next();
         if opt_value.is_none() {
-            return List::Empty;
+            return List::from_state(ListState::Empty, Global);
         }
         let value = opt_value.unwrap();
-        let mut first = LinkedList1::new_box(*value, None);
+        let mut first = LinkedList1::new_box(value.clone(), None);
         let cur = &mut first;
         for value in iter {
-            cur.next = Some(LinkedList1::new_box(*value, None));
+            cur.next = Some(LinkedList1::new_box(value.clone(), None));
             /* this doesn't seem possible because Rust thinks we have access now
             to two pointers at the same time */
             // cur = &mut cur.next.unwrap();
             unimplemented!();
         }
-        List::First(first)
+        List::from_state(ListState::First(first), Global)
+    }
+    /* It is possible after all. The problem was `cur.next.unwrap()`: unwrap()
+    takes the Option by value, so it tries to move the Box out of the list.
+    What we want is a reference to the node we just put in `next`, and that's
+    exactly what Option::insert() returns.
+
+    So we keep `link` pointing to the empty `next` at the end of the chain,
+    and each new node goes there. We also grab the tail, and the slice tells
+    us how many nodes there are, so there's no second walk to find them. */
+    pub fn new_fast(slice: &[T]) -> Self {
+        let mut first = None;
+        let mut link = &mut first;
+        let mut tail = None;
+        if let Some((last, init)) = slice.split_last() {
+            for value in init {
+                link = &mut link.insert(LinkedList1::new_box(value.clone(), None)).next;
+            }
+            let last = link.insert(LinkedList1::new_box(last.clone(), None));
+            tail = Some(node_ptr(last));
+        }
+        let state = match first {
+            Some(list) => ListState::First(list),
+            None => ListState::Empty,
+        };
+        /* A single node is the first one, which the cache doesn't point to. */
+        if slice.len() < 2 {
+            tail = None;
+        }
+        List {
+            state,
+            tail,
+            len: slice.len(),
+            alloc: Global,
+        }
     }
     /* We need to construct it backwards, from tail to head... */
-    pub fn new(slice: &[i64]) -> Self {
-        let mut cur = None::<Box<LinkedList1>>;
+    pub fn new(slice: &[T]) -> Self {
+        let mut cur = None::<Box<LinkedList1<T>>>;
         for elem in slice.iter().rev() {
-            let mut new = LinkedList1::new_box(*elem, None);
+            let mut new = LinkedList1::new_box(elem.clone(), None);
             if let Some(prev) = cur {
                 new.next = Some(prev);
             }
             cur = Some(new);
         }
         match cur {
-            Some(list) => List::First(list),
-            None => List::Empty,
+            Some(list) => List::from_state(ListState::First(list), Global),
+            None => List::from_state(ListState::Empty, Global),
         }
     }
-    /* We'll try a simply add_item... */
-    pub fn add_item(&mut self, value: i64) {
-        let new = LinkedList1::new_box(value, None);
-        if let List::First(list) = self {
-            let tail = list.tail_mut();
-            tail.next = Some(new);
+}
+
+/* new() takes a slice, so it needs T: Clone even for an empty one. This
+doesn't. */
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T, A: Allocator> ListIn<T, A> {
+    /* An empty list whose nodes will be Boxes in `alloc`, see arena. */
+    pub fn new_in(alloc: A) -> Self {
+        ListIn {
+            state: ListState::Empty,
+            tail: None,
+            len: 0,
+            alloc,
+        }
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    /* Every constructor goes through here, so the tail is found (and the
+    nodes counted) once, and from then on we keep them updated. */
+    fn from_state(state: ListState<T, A>, alloc: A) -> Self {
+        let mut list = ListIn {
+            state,
+            tail: None,
+            len: 0,
+            alloc,
+        };
+        list.last_node();
+        debug_check(&list, list.len);
+        list
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /* Dropping the first Box runs the iterative drop of LinkedList1, so this
+    is safe for huge lists too. */
+    pub fn clear(&mut self) {
+        self.state = ListState::Empty;
+        self.tail = None;
+        self.len = 0;
+    }
+
+    pub fn first(&self) -> Option<&LinkedList1In<T, A>> {
+        match &self.state {
+            ListState::First(list) => Some(list),
+            ListState::Empty => None,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, A> {
+        Iter {
+            chain: IterLinkedList1 {
+                cursor: self.first(),
+            },
+            remaining: self.len(),
+        }
+    }
+
+    /* Positional access has to walk from the first node, so it's O(n). It's
+    here for convenience (and for comparing against a Vec), not for speed. */
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        self.iter().nth(idx)
+    }
+
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+        self.node_mut(idx).map(|node| &mut node.value)
+    }
+
+    fn node_mut(&mut self, idx: usize) -> Option<&mut LinkedList1In<T, A>> {
+        if idx >= self.len {
+            return None;
+        }
+        /* Index len - 1 is the cached one, no need to walk there. */
+        if let (Some(mut tail), true) = (self.tail, idx + 1 == self.len) {
+            return Some(unsafe { tail.as_mut() });
+        }
+        self.box_mut(idx).map(|node| &mut **node)
+    }
+
+    /* The Box that owns the node at idx, walking from the first one. For
+    when we need to take a pointer to the node, with node_ptr(). */
+    fn box_mut(&mut self, idx: usize) -> Option<&mut NodeBox<T, A>> {
+        let mut cur = match &mut self.state {
+            ListState::First(list) => list,
+            ListState::Empty => return None,
+        };
+        for _ in 0..idx {
+            cur = cur.next.as_mut()?;
+        }
+        Some(cur)
+    }
+
+    /* To swap two values we need two &mut into the same chain at once. The
+    trick is that `value` and `next` are different fields, so we can borrow
+    both of them from the first node at the same time, and then find the
+    second node through `next`. */
+    pub fn swap(&mut self, i: usize, j: usize) {
+        let (lo, hi) = (i.min(j), i.max(j));
+        if hi >= self.len {
+            panic!("swap: index out of range");
+        }
+        if lo == hi {
+            return;
+        }
+        let tail = self.tail.filter(|_| hi + 1 == self.len);
+        let LinkedList1In { value, next, .. } = self.node_mut(lo).unwrap();
+        let other = match tail {
+            /* The last node goes through the cache, as in node_mut(). */
+            Some(mut tail) => unsafe { tail.as_mut() },
+            None => {
+                let mut other = next.as_deref_mut().unwrap();
+                for _ in lo + 1..hi {
+                    other = other.next.as_deref_mut().unwrap();
+                }
+                other
+            }
+        };
+        std::mem::swap(value, &mut other.value);
+    }
+
+    /* No values move here. The node before the pivot becomes the tail, the
+    node after it becomes the first one, and the old tail gets the old first
+    node as its `next`. */
+    pub fn rotate_left(&mut self, n: usize) {
+        if self.len == 0 {
+            return;
+        }
+        let n = n % self.len;
+        if n == 0 {
+            return;
+        }
+        let pivot = self.box_mut(n - 1).unwrap();
+        let rest = pivot.next.take().unwrap();
+        let new_tail = node_ptr(pivot);
+        let old_first = match std::mem::replace(&mut self.state, ListState::First(rest)) {
+            ListState::First(list) => list,
+            ListState::Empty => unreachable!(),
+        };
+        /* The cache still points to the old tail, which is now in the middle,
+        or even first. */
+        self.uncache_first();
+        let old_first = self.last_node().unwrap().next.insert(old_first);
+        /* If the pivot was the old first node, its Box has just moved. */
+        self.tail = Some(if n == 1 {
+            node_ptr(old_first)
         } else {
-            // This feels strange. We can "replace" the contents just by
-            // de-referencing. I was expecting this to fail:
-            *self = List::First(new);
+            new_tail
+        });
+        debug_check(self, self.len);
+    }
+
+    pub fn rotate_right(&mut self, n: usize) {
+        if self.len == 0 {
+            return;
+        }
+        self.rotate_left(self.len - n % self.len);
+    }
+
+    /* Since we keep the length, this could just be get(len - 1 - n). But the
+    runner technique works on any chain without knowing its length, which is
+    more fun: `lead` goes n nodes ahead, and then both walk together. When
+    lead reaches the last node, `follow` is n nodes before it. */
+    pub fn nth_from_end(&self, n: usize) -> Option<&T> {
+        let mut lead = self.first()?;
+        for _ in 0..n {
+            lead = lead.next()?;
+        }
+        let mut follow = self.first()?;
+        while let Some(next) = lead.next() {
+            lead = next;
+            follow = follow.next()?;
+        }
+        Some(&follow.value)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let first = match std::mem::replace(&mut self.state, ListState::Empty) {
+            ListState::First(list) => list,
+            ListState::Empty => return None,
+        };
+        let (value, next) = first.into_parts();
+        self.len -= 1;
+        if let Some(next) = next {
+            self.state = ListState::First(next);
+            /* The second node may have been the cached tail, and now it's
+            first. */
+            self.uncache_first();
+        }
+        debug_check(self, self.len);
+        Some(value)
+    }
+
+    /* The tail cache doesn't help here. To unlink the last node we need the
+    one before it, and nodes don't know who points to them. So this is still a
+    walk, O(n). For a cheap pop_back we'd need a doubly linked list. */
+    pub fn pop_back(&mut self) -> Option<T> {
+        let mut cur = match &mut self.state {
+            ListState::First(list) if list.next.is_some() => list,
+            _ => return self.pop_front(),
+        };
+        while cur.next.as_ref().is_some_and(|next| next.next.is_some()) {
+            cur = cur.next.as_mut().unwrap();
+        }
+        let last = cur.next.take().unwrap();
+        self.tail = Some(node_ptr(cur));
+        self.uncache_first();
+        self.len -= 1;
+        debug_check(self, self.len);
+        Some(last.into_parts().0)
+    }
+
+    /* The classic three pointers: prev, cur and next. Each node gets its
+    `next` pointed back to the previous one. Everything is moved with take(),
+    so no node is allocated or copied, only the Boxes change hands. */
+    pub fn reverse(&mut self) {
+        let mut cur = match std::mem::replace(&mut self.state, ListState::Empty) {
+            ListState::First(list) => Some(list),
+            ListState::Empty => return,
+        };
+        /* The first node will be the last one. Its Box moves once it gets a
+        node in front of it, and from then on it stays put, so that's when we
+        take the pointer. A list of one node has no tail to cache. */
+        self.tail = None;
+        let mut prev = None;
+        while let Some(mut node) = cur {
+            cur = node.next.take();
+            node.next = prev;
+            if let Some(next) = node.next.as_mut() {
+                if next.next.is_none() {
+                    self.tail = Some(node_ptr(next));
+                }
+            }
+            prev = Some(node);
+        }
+        if let Some(first) = prev {
+            self.state = ListState::First(first);
+        }
+        debug_check(self, self.len);
+    }
+
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(|a, b| a.cmp(b))
+    }
+
+    /* Merge sort, done on the links themselves. No Vec, no new nodes: the
+    chain is cut in halves, each half is sorted, and the Boxes are moved one
+    by one into the merged chain. It's stable, like slice::sort_by.
+
+    We take the chain out of the list before starting. If `compare` panics
+    halfway, the nodes that the sort was holding are dropped while unwinding
+    and the list is left empty, instead of with half a chain and a tail
+    pointing to who knows where. */
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let first = match self.take_chain() {
+            Some(first) => first,
+            None => return,
+        };
+        self.put_chain(Some(merge_sort(first, &mut compare)));
+    }
+
+    /* retain() is where the `Option<Box<_>>` idiom shines. `link` is a
+    reference to the Option that points to the current node: either the head
+    of the chain or the `next` of the last node we kept. To drop a node we
+    take() its next and put it in the place of the node; the node goes away
+    on its own. To keep it we just move `link` to its `next`. */
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut chain = self.take_chain();
+        let mut link = &mut chain;
+        while let Some(node) = link {
+            if keep(&node.value) {
+                link = &mut link.as_mut().unwrap().next;
+            } else {
+                let next = node.next.take();
+                *link = next;
+            }
+        }
+        self.put_chain(chain);
+    }
+
+    /* Same walk as retain(), but it stops at the first match and gives the
+    value back. */
+    pub fn remove_first_value(&mut self, value: &T) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let mut chain = self.take_chain();
+        let mut link = &mut chain;
+        while link.as_ref().is_some_and(|node| node.value != *value) {
+            link = &mut link.as_mut().unwrap().next;
+        }
+        let removed = link.take().map(|node| {
+            let (removed, next) = node.into_parts();
+            *link = next;
+            removed
+        });
+        self.put_chain(chain);
+        removed
+    }
+
+    /* Returns how many were removed. */
+    pub fn remove_all_value(&mut self, value: &T) -> usize
+    where
+        T: PartialEq,
+    {
+        let before = self.len();
+        self.retain(|v| v != value);
+        before - self.len
+    }
+
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|next, kept| next == kept)
+    }
+
+    /* Removes consecutive nodes when `same(next, kept)` says so, like
+    Vec::dedup_by. One walk: `kept` is the last node we kept, and we look at
+    the one after it. While we're there we count the survivors and end up at
+    the tail, so there's nothing to recompute at the end.
+
+    The chain is out of the list while `same` runs, in case it panics. */
+    pub fn dedup_by<F>(&mut self, mut same: F)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        let mut chain = match self.take_chain() {
+            Some(chain) => chain,
+            None => return,
+        };
+        let mut len = 1;
+        let mut kept = &mut chain;
+        while let Some(mut next) = kept.next.take() {
+            if same(&next.value, &kept.value) {
+                kept.next = next.next.take();
+            } else {
+                kept = kept.next.insert(next);
+                len += 1;
+            }
+        }
+        let tail = node_ptr(kept);
+        self.state = ListState::First(chain);
+        /* If we only kept the first node, its Box has just moved. */
+        self.tail = Some(tail).filter(|_| len > 1);
+        self.len = len;
+        debug_check(self, self.len);
+    }
+
+    pub fn is_sorted(&self) -> bool
+    where
+        T: PartialOrd,
+    {
+        self.iter().zip(self.iter().skip(1)).all(|(a, b)| a <= b)
+    }
+
+    /* Some operations are easier on a plain Option<Box<_>> chain than on our
+    enum. These two move the chain out of the list and back in. While the
+    chain is out, the list is empty, so if something panics in between we
+    don't leave a tail pointing to a node that is gone. */
+    fn take_chain(&mut self) -> Option<NodeBox<T, A>> {
+        self.tail = None;
+        self.len = 0;
+        match std::mem::replace(&mut self.state, ListState::Empty) {
+            ListState::First(list) => Some(list),
+            ListState::Empty => None,
+        }
+    }
+
+    fn put_chain(&mut self, chain: Option<NodeBox<T, A>>) {
+        if let Some(first) = chain {
+            self.state = ListState::First(first);
+            self.uncache_first();
+            /* Walking from the first node finds the tail and counts the nodes
+            again. */
+            self.last_node();
+        }
+        debug_check(self, self.len);
+    }
+
+    /* Hangs a whole chain after our tail. The second last_node() call walks
+    over the new nodes, counting them and caching the chain's tail as ours. */
+    fn append_chain(&mut self, chain: NodeBox<T, A>) {
+        match self.last_node() {
+            Some(tail) => tail.next = Some(chain),
+            None => self.state = ListState::First(chain),
+        }
+        self.last_node();
+        debug_check(self, self.len);
+    }
+
+    /* concat_copy() without the copying: if we own the other list, its chain
+    of Boxes can hang from our tail as it is. Nothing gets cloned, so T
+    doesn't have to be Clone either. With both tails cached that's O(1), no
+    matter how long either list is, and not a single allocation.
+
+    Its cached tail stays valid, as only the Box of its first node moves
+    (see the comment on List). If it had no cache, its first node was its
+    tail, and we take the pointer again from where that Box ends up. */
+    pub fn concat(&mut self, mut other: Self) {
+        let chain = match std::mem::replace(&mut other.state, ListState::Empty) {
+            ListState::First(chain) => chain,
+            ListState::Empty => return,
+        };
+        let (other_tail, other_len) = (other.tail, other.len);
+        match self.last_node() {
+            Some(tail) => {
+                let first = tail.next.insert(chain);
+                let new_tail = other_tail.unwrap_or_else(|| node_ptr(first));
+                self.tail = Some(new_tail);
+            }
+            None => {
+                self.state = ListState::First(chain);
+                self.tail = other_tail;
+            }
+        }
+        self.len += other_len;
+        debug_check(self, self.len);
+    }
+
+    /* The last value, through the cache: O(1). */
+    pub fn tail_mut(&mut self) -> Option<&mut T> {
+        self.last_node().map(|node| &mut node.value)
+    }
+
+    /* The last node. It's also how the cache gets built, for a chain we were
+    just handed (from_state(), put_chain(), append_chain()): any node we step
+    onto after the cached one is one we didn't know about, so it gets
+    counted. No cache means the first node is the tail, and with len 0 that
+    it wasn't counted yet either. */
+    fn last_node(&mut self) -> Option<&mut LinkedList1In<T, A>> {
+        let mut last = match self.tail {
+            /* The cached pointer always points to a node owned by `state`, and
+            we hold &mut self, so nobody else can be looking at it. */
+            Some(tail) => tail,
+            None => match &mut self.state {
+                ListState::First(list) => {
+                    self.len = self.len.max(1);
+                    node_ptr(list)
+                }
+                ListState::Empty => return None,
+            },
+        };
+        let mut walked = false;
+        while let Some(next) = unsafe { &mut (*last.as_ptr()).next } {
+            last = node_ptr(next);
+            self.len += 1;
+            walked = true;
+        }
+        if walked {
+            self.tail = Some(last);
+        }
+        Some(unsafe { &mut *last.as_ptr() })
+    }
+
+    /* The cache never points to the first node, see the comment on List. */
+    fn uncache_first(&mut self) {
+        if let ListState::First(first) = &self.state {
+            if self.tail == Some(NonNull::from(&**first)) {
+                self.tail = None;
+            }
         }
     }
+}
+
+/* Everything that makes a Box, or a second list, needs an allocator to put
+in it: a clone of ours, which can free what ours allocated. */
+impl<T, A: Allocator + Clone> ListIn<T, A> {
+    fn new_box(&self, value: T, next: Option<NodeBox<T, A>>) -> NodeBox<T, A> {
+        LinkedList1In::new_box_in(value, next, self.alloc.clone())
+    }
+
+    /* Keeps [0, at) and returns the rest as a new List. Finding the cut walks
+    at nodes; the rest keeps our old tail and its share of len, so it doesn't
+    have to be walked at all. */
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(
+            at <= self.len,
+            "split_off: the len is {} but the index is {}",
+            self.len,
+            at
+        );
+        if at == 0 {
+            let empty = Self::new_in(self.alloc.clone());
+            return std::mem::replace(self, empty);
+        }
+        let (tail, len) = (self.tail, self.len);
+        let last = self.box_mut(at - 1).unwrap();
+        let rest = match last.next.take() {
+            Some(rest) => rest,
+            None => return Self::new_in(self.alloc.clone()),
+        };
+        self.tail = Some(node_ptr(last));
+        self.len = at;
+        self.uncache_first();
+        let mut rest = ListIn {
+            state: ListState::First(rest),
+            tail,
+            len: len - at,
+            alloc: self.alloc.clone(),
+        };
+        rest.uncache_first();
+        debug_check(self, self.len);
+        debug_check(&rest, rest.len);
+        rest
+    }
+
+    /* We'll try a simply add_item... */
+    pub fn add_item(&mut self, value: T) {
+        self.append_chain(self.new_box(value, None));
+    }
+
+    /* Adding at the front is what a singly linked list is good at. The tail
+    doesn't move, unless the list had one node: that one was first and
+    uncached, and now it's second, so the cache can point to it. */
+    pub fn push_front(&mut self, value: T) {
+        let next = match std::mem::replace(&mut self.state, ListState::Empty) {
+            ListState::First(list) => Some(list),
+            ListState::Empty => None,
+        };
+        self.state = ListState::First(self.new_box(value, next));
+        if let (None, ListState::First(first)) = (self.tail, &mut self.state) {
+            self.tail = first.next.as_mut().map(node_ptr);
+        }
+        self.len += 1;
+        debug_check(self, self.len);
+    }
+}
+
+/* The cached tail has to be reachable from the first node, it has to be
+the last one, and len has to count the nodes up to it. A chain of Boxes
+can't have a cycle, so there's no need to bound the walk.
+
+Without a cache the first node is the tail, so len is 1. With one, it must
+be some node after the first. */
+impl<T, A: Allocator> CheckInvariants for ListIn<T, A> {
+    fn check(&self) -> Result<(), InvariantError> {
+        let (first, tail) = match (self.first(), self.tail) {
+            (None, None) if self.len == 0 => return Ok(()),
+            (None, None) => {
+                return Err(InvariantError::WrongLen {
+                    len: self.len,
+                    counted: 0,
+                })
+            }
+            (None, Some(_)) => return Err(InvariantError::TailWithoutHead),
+            (Some(first), None) if self.len == 1 => {
+                return match first.next() {
+                    Some(_) => Err(InvariantError::NextAfterTail),
+                    None => Ok(()),
+                }
+            }
+            (Some(_), None) => {
+                return Err(InvariantError::WrongLen {
+                    len: self.len,
+                    counted: 1,
+                })
+            }
+            (Some(first), Some(tail)) => (first, tail),
+        };
+        let mut counted = 1;
+        let mut cur = first.next();
+        while let Some(node) = cur {
+            counted += 1;
+            if NonNull::from(node) == tail {
+                if counted != self.len {
+                    return Err(InvariantError::WrongLen {
+                        len: self.len,
+                        counted,
+                    });
+                }
+                if node.next().is_some() {
+                    return Err(InvariantError::NextAfterTail);
+                }
+                return Ok(());
+            }
+            cur = node.next();
+        }
+        Err(InvariantError::WrongTail)
+    }
+}
+
+/* Each node is its Box's address. Reading through the Boxes, like iter()
+does, so the tail cache stays valid. */
+impl<T, A: Allocator> Topology for ListIn<T, A> {
+    fn topology(&self) -> Vec<NodeLinks> {
+        let id = |node: &LinkedList1In<T, A>| id_of(node);
+        std::iter::successors(self.first(), |node| node.next())
+            .take(self.len())
+            .map(|node| NodeLinks::singly(id(node), node.next().map(id)))
+            .collect()
+    }
+}
+
+impl<T, A: Allocator> HeapSize for ListIn<T, A> {
+    fn heap_size(&self) -> usize {
+        self.len() * size_of::<LinkedList1In<T, A>>()
+    }
+}
+
+/* The runner technique: `fast` moves two nodes each time `slow` moves one, so
+when fast reaches the end, slow is in the middle.
+
+With Boxes we can't have a &mut on slow while fast is borrowing the same
+chain, so fast runs first and just counts how many steps slow has to take. */
+fn split_half<T, A: Allocator>(first: &mut LinkedList1In<T, A>) -> Option<NodeBox<T, A>> {
+    let mut steps = 0;
+    let mut fast: &LinkedList1In<T, A> = first;
+    while let Some(next2) = fast.next.as_deref().and_then(|n| n.next.as_deref()) {
+        fast = next2;
+        steps += 1;
+    }
+    let mut slow = first;
+    for _ in 0..steps {
+        slow = slow.next.as_deref_mut().unwrap();
+    }
+    slow.next.take()
+}
+
+fn merge_sort<T, A, F>(mut first: NodeBox<T, A>, compare: &mut F) -> NodeBox<T, A>
+where
+    A: Allocator,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let back = match split_half(&mut first) {
+        Some(back) => back,
+        None => return first,
+    };
+    let front = merge_sort(first, compare);
+    let back = merge_sort(back, compare);
+    merge(front, back, compare)
+}
 
-    pub fn tail_mut(&mut self) -> Option<&mut LinkedList1> {
-        match self {
-            List::First(list) => Some(list.tail_mut()),
-            List::Empty => None,
+/* `tail` is a reference to the `next` of the last merged node (or to `first`
+at the beginning). Option::insert() puts the node there and gives us a
+reference to it, so we can move on to its `next`. */
+fn merge<T, A, F>(
+    front: NodeBox<T, A>,
+    back: NodeBox<T, A>,
+    compare: &mut F,
+) -> NodeBox<T, A>
+where
+    A: Allocator,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut front = Some(front);
+    let mut back = Some(back);
+    let mut first = None;
+    let mut tail = &mut first;
+    while let (Some(a), Some(b)) = (&front, &back) {
+        /* On ties the front half wins, that's what keeps it stable. */
+        let src = if compare(&b.value, &a.value) == Ordering::Less {
+            &mut back
+        } else {
+            &mut front
+        };
+        let mut node = src.take().unwrap();
+        *src = node.next.take();
+        tail = &mut tail.insert(node).next;
+    }
+    *tail = front.or(back);
+    first.unwrap()
+}
+
+impl<T: Clone, A: Allocator + Clone> ListIn<T, A> {
+    /* Like new_fast(): each new node goes straight into the `next` of the one
+    before it, starting with our tail's. We know how many nodes there are from
+    the slice, and the last one we build is our new tail. No walking at all.
+
+    Building a chain of its own first and hanging it afterwards would move the
+    Box of its first node, and if that's also its last node, the pointer we
+    took to it would be dead (see the comment on List).
+
+    The values are cloned before any of that, though. clone() is the caller's
+    code and may panic, and halfway through the loop below there are nodes
+    after our tail that neither `tail` nor `len` know about yet. */
+    pub fn append_slice(&mut self, slice: &[T]) {
+        let values: Vec<T> = slice.to_vec();
+        self.append_values(values.into_iter());
+    }
+
+    /* The Boxes get their allocator from a clone made up front, as `last`
+    borrows the list all the way through. */
+    fn append_values(&mut self, mut values: impl ExactSizeIterator<Item = T>) {
+        let count = values.len();
+        let alloc = self.alloc.clone();
+        let first = match values.next() {
+            Some(first) => LinkedList1In::new_box_in(first, None, alloc.clone()),
+            None => return,
+        };
+        let mut last = match self.last_node() {
+            Some(tail) => tail.next.insert(first),
+            None => {
+                self.state = ListState::First(first);
+                match &mut self.state {
+                    ListState::First(first) => first,
+                    ListState::Empty => unreachable!(),
+                }
+            }
+        };
+        for value in values {
+            last = last
+                .next
+                .insert(LinkedList1In::new_box_in(value, None, alloc.clone()));
         }
+        self.tail = Some(node_ptr(last));
+        self.uncache_first();
+        self.len += count;
+        debug_check(self, self.len);
+    }
+
+    /* The name Vec uses for the same thing. */
+    pub fn extend_from_slice(&mut self, slice: &[T]) {
+        self.append_slice(slice)
     }
 
     /* let's try a concatenate! We will copy the values as we iterate. */
     pub fn concat_copy(&mut self, other: &Self) {
-        if let List::First(list) = other {
+        if let ListState::First(list) = &other.state {
             /* in order to do this efficiently we should create it in reverse
             order, as doing tail each time would be a waste: */
             /*for elem in list.iter() {
@@ -289,51 +1189,316 @@ impl List {
             // }
 
             /* Turns out that for this we would need the full array anyway, so ... */
-            let array: Vec<i64> = list.iter().collect();
-            let mut cur: Option<LinkedList1> = None;
-            for elem in array.iter().rev() {
-                cur = Some(LinkedList1::new(*elem, cur.map(Box::new)))
+            let array: Vec<T> = list.iter().cloned().collect();
+            let mut cur: Option<NodeBox<T, A>> = None;
+            for elem in array.into_iter().rev() {
+                cur = Some(self.new_box(elem, cur))
             }
-            let boxval = cur.map(Box::new);
-            /* TODO: Add comments here... it's quite complex. */
-            match self {
-                List::First(self_list) => {
-                    // This is expensive. It recurses finding the last item and takes long for big arrays
-                    let tail = self_list.tail_mut();
-                    tail.next = boxval;
-                }
-                List::Empty => {
-                    if let Some(v) = boxval {
-                        *self = List::First(v);
-                    }
-                }
+            /* Finding our own tail used to be the expensive part here, as it
+            walked the whole list for every concat. Now it comes from the cache. */
+            if let Some(chain) = cur {
+                self.append_chain(chain);
             }
         }
     }
 
-    pub fn to_vec(&self) -> Vec<i64> {
-        match self {
-            List::First(l) => l.iter().collect(),
-            List::Empty => vec![],
+    pub fn to_vec(&self) -> Vec<T> {
+        match &self.state {
+            ListState::First(l) => l.iter().cloned().collect(),
+            ListState::Empty => vec![],
         }
     }
 }
 
-// If drop is not implemented, does stack overflow when freeing big lists
-impl Drop for LinkedList1 {
+/* The chain's iterator, plus the List's len(), so that this one knows its
+exact size. Knowing it lets collect() allocate the Vec once. */
+pub struct Iter<'a, T, A: Allocator = Global> {
+    chain: IterLinkedList1<'a, T, A>,
+    remaining: usize,
+}
+
+impl<'a, T, A: Allocator> Iterator for Iter<'a, T, A> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ret = self.chain.next();
+        if ret.is_some() {
+            self.remaining = self.remaining.saturating_sub(1);
+        }
+        ret
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T, A: Allocator> ExactSizeIterator for Iter<'a, T, A> {}
+impl<'a, T, A: Allocator> FusedIterator for Iter<'a, T, A> {}
+
+/* A consuming iterator. It owns the rest of the chain and pops one Box at a
+time, so each node is freed as soon as its value is handed out. If the loop
+stops early, whatever is left goes away with the iterative drop below. */
+pub struct IntoIter<T, A: Allocator = Global> {
+    next: Option<NodeBox<T, A>>,
+    remaining: usize,
+}
+
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        let (value, next) = node.into_parts();
+        self.next = next;
+        self.remaining = self.remaining.saturating_sub(1);
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+impl<T, A: Allocator> FusedIterator for IntoIter<T, A> {}
+
+impl<T, A: Allocator> IntoIterator for ListIn<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let remaining = self.len();
+        /* No Drop on List, so we can just move the Box out of it. */
+        let next = match self.state {
+            ListState::First(list) => Some(list),
+            ListState::Empty => None,
+        };
+        IntoIter { next, remaining }
+    }
+}
+
+impl<T, A: Allocator> ListIn<T, A> {
+    /* Like into_iter(), but the list stays ours: it's just empty afterwards. */
+    pub fn drain(&mut self) -> Drain<'_, T, A> {
+        Drain { list: self }
+    }
+
+    /* Hands out the values for which `pred` returns true, taking their nodes
+    out of the list, and leaves the rest linked as they were. If the iterator
+    is dropped early, whatever it didn't look at stays in the list. */
+    pub fn drain_filter<F>(&mut self, pred: F) -> DrainFilter<'_, T, F, A>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        DrainFilter {
+            cursor: self.cursor_front_mut(),
+            pred,
+        }
+    }
+}
+
+/* Each next() pops the first Box. Dropping the Drain empties the list even
+if it wasn't consumed to the end, like Vec::drain does. */
+pub struct Drain<'a, T, A: Allocator = Global> {
+    list: &'a mut ListIn<T, A>,
+}
+
+impl<'a, T, A: Allocator> Iterator for Drain<'a, T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<'a, T, A: Allocator> ExactSizeIterator for Drain<'a, T, A> {}
+impl<'a, T, A: Allocator> FusedIterator for Drain<'a, T, A> {}
+
+impl<'a, T, A: Allocator> Drop for Drain<'a, T, A> {
     fn drop(&mut self) {
-        let cur = self;
-        /* Just iterate, doing cur.next.take() will consume the item at the end
-        of the loop. */
-        while let Some(curnext) = cur.next.take() {
-            if curnext.next.is_some() {
-                *cur = *curnext;
-            } else {
-                return;
+        self.list.clear();
+    }
+}
+
+/* The cursor already knows how to unlink a node in the middle of the chain
+and keep the tail and the length right, so this just drives it. */
+pub struct DrainFilter<'a, T, F, A: Allocator = Global> {
+    cursor: CursorMut<'a, T, A>,
+    pred: F,
+}
+
+impl<'a, T, F, A> Iterator for DrainFilter<'a, T, F, A>
+where
+    A: Allocator,
+    F: FnMut(&T) -> bool,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let value = self.cursor.current()?;
+            if (self.pred)(value) {
+                return self.cursor.remove_current();
             }
+            self.cursor.move_next();
+        }
+    }
+}
+
+/* Not ExactSize, we can't know how many will match. But once the cursor
+reaches the ghost position it stays there. */
+impl<'a, T, F, A> FusedIterator for DrainFilter<'a, T, F, A>
+where
+    A: Allocator,
+    F: FnMut(&T) -> bool,
+{
+}
+
+// If drop is not implemented, does stack overflow when freeing big lists
+/* The default drop is recursive: dropping a node drops its Box, which drops
+the next node, which drops its Box... and a million nodes later the stack is
+gone. So we unlink the chain ourselves.
+
+The previous version of this moved each node into `self` (`*cur = *curnext`),
+which works, but it copies every value around on the way. It's simpler to
+just take() the next box out of each node before letting it go: by the time
+a node is dropped its `next` is None, so its own drop() does nothing. */
+impl<T, A: Allocator> Drop for LinkedList1In<T, A> {
+    fn drop(&mut self) {
+        let mut cur = self.next.take();
+        while let Some(mut node) = cur {
+            cur = node.next.take();
+        }
+    }
+}
+
+/* Same problem as Drop: #[derive(Clone)] would clone `next`, which clones its
+`next`... one stack frame per node. So we copy the values in a loop, hanging
+each new node at the end of the chain we're building. */
+impl<T: Clone> Clone for LinkedList1<T> {
+    fn clone(&self) -> Self {
+        let mut first = LinkedList1::new(self.value.clone(), None);
+        let mut tail = &mut first.next;
+        for value in self.iter().skip(1) {
+            tail = &mut tail.insert(LinkedList1::new_box(value.clone(), None)).next;
         }
+        first
+    }
+}
+
+/* The list knows its allocator, so it can clone into it. */
+impl<T: Clone, A: Allocator + Clone> Clone for ListIn<T, A> {
+    fn clone(&self) -> Self {
+        let mut list = Self::new_in(self.alloc.clone());
+        list.append_values(self.iter().cloned());
+        list
     }
 }
 
+/* The derived Debug had the same recursion problem as Drop and Clone: it
+printed `next`, which printed its `next`... Also the output was a tower of
+`LinkedList1 { value: 3, next: Some(LinkedList1 { ...`, which nobody wants to
+read. debug_list() prints it like a Vec, and takes an iterator, so it loops. */
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for LinkedList1In<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for ListIn<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/* Prints the chain with arrows, like 3 -> 8 -> 1 -> ∅ */
+impl<T: fmt::Display, A: Allocator> fmt::Display for LinkedList1In<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for value in self.iter() {
+            write!(f, "{} -> ", value)?;
+        }
+        write!(f, "∅")
+    }
+}
+
+impl<T: fmt::Display, A: Allocator> fmt::Display for ListIn<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.first() {
+            Some(list) => list.fmt(f),
+            None => write!(f, "∅"),
+        }
+    }
+}
+
+/* Two chains are equal if they have the same values in the same order. Both
+iterators walk in lockstep, and Iterator::eq stops at the first difference. */
+impl<T: PartialEq, A: Allocator> PartialEq for LinkedList1In<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for LinkedList1In<T, A> {}
+
+/* For List we know the lengths, so different sizes don't need a walk. */
+impl<T: PartialEq, A: Allocator> PartialEq for ListIn<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for ListIn<T, A> {}
+
+impl<T, A: Allocator> Index<usize> for ListIn<T, A> {
+    type Output = T;
+
+    fn index(&self, idx: usize) -> &T {
+        match self.get(idx) {
+            Some(value) => value,
+            None => panic!(
+                "index out of range: the len is {} but the index is {}",
+                self.len(),
+                idx
+            ),
+        }
+    }
+}
+
+impl<T, A: Allocator> IndexMut<usize> for ListIn<T, A> {
+    fn index_mut(&mut self, idx: usize) -> &mut T {
+        let len = self.len();
+        match self.get_mut(idx) {
+            Some(value) => value,
+            None => panic!(
+                "index out of range: the len is {} but the index is {}",
+                len, idx
+            ),
+        }
+    }
+}
+
+/* List doesn't need its own Drop. It only owns the first Box, and dropping
+that runs the loop above. Adding a Drop to List would also forbid moving the
+Box out of it with a match, which we're going to want. */
+
+/* And a doubly linked version, so we can finally iterate backwards. */
+mod doubly;
+pub use doubly::{DoublyList, DoublyListIn, Iter as DoublyIter};
+
+/* A cursor to walk the list and edit it on the way. */
+mod cursor;
+pub use cursor::CursorMut;
+
+/* Splitting for rayon, with split_off(). */
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(feature = "rayon")]
+pub use par::{IntoParIter, ParIter};
+
 #[cfg(test)]
 mod test;
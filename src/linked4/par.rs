@@ -0,0 +1,124 @@
+/*
+Parallel iterators, with rayon (behind the `rayon` feature).
+
+rayon splits the work in halves for as long as it has idle threads. A
+singly linked list can only find its middle by walking to it, so every split
+costs half the piece being split, and all of them together O(n log n). That
+pays off when each value takes real work; for summing numbers, the plain
+iter() on one thread is faster.
+
+Owned lists split with split_off(): each half is a List of its own, and the
+threads consume them with into_iter(). Borrowed lists can't be cut, but what
+an iterator needs is only where to start and how many values to take, so a
+half of a borrowed list is just that pair.
+
+The halves are joined back in order, so collect() keeps the list order.
+*/
+use super::{IterLinkedList1, LinkedList1, List};
+use rayon::iter::plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+pub struct IntoParIter<T> {
+    list: List<T>,
+}
+
+impl<T: Send> IntoParallelIterator for List<T> {
+    type Iter = IntoParIter<T>;
+    type Item = T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        IntoParIter { list: self }
+    }
+}
+
+impl<T: Send> ParallelIterator for IntoParIter<T> {
+    type Item = T;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        bridge_unindexed(self, consumer)
+    }
+}
+
+impl<T: Send> UnindexedProducer for IntoParIter<T> {
+    type Item = T;
+
+    fn split(mut self) -> (Self, Option<Self>) {
+        if self.list.len() < 2 {
+            return (self, None);
+        }
+        let rest = self.list.split_off(self.list.len() / 2);
+        (self, Some(IntoParIter { list: rest }))
+    }
+
+    fn fold_with<F>(self, folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        folder.consume_iter(self.list)
+    }
+}
+
+pub struct ParIter<'a, T> {
+    first: Option<&'a LinkedList1<T>>,
+    len: usize,
+}
+
+impl<'a, T: Sync> IntoParallelIterator for &'a List<T> {
+    type Iter = ParIter<'a, T>;
+    type Item = &'a T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        ParIter {
+            first: self.first(),
+            len: self.len(),
+        }
+    }
+}
+
+impl<'a, T: Sync> ParallelIterator for ParIter<'a, T> {
+    type Item = &'a T;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        bridge_unindexed(self, consumer)
+    }
+}
+
+impl<'a, T: Sync> UnindexedProducer for ParIter<'a, T> {
+    type Item = &'a T;
+
+    fn split(self) -> (Self, Option<Self>) {
+        if self.len < 2 {
+            return (self, None);
+        }
+        let mid = self.len / 2;
+        let mut node = self.first;
+        for _ in 0..mid {
+            node = node.and_then(|node| node.next.as_deref());
+        }
+        let left = ParIter {
+            first: self.first,
+            len: mid,
+        };
+        let right = ParIter {
+            first: node,
+            len: self.len - mid,
+        };
+        (left, Some(right))
+    }
+
+    /* The left halves must stop at their len: the next node is still there,
+    it's just somebody else's. */
+    fn fold_with<F>(self, folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        let iter = IterLinkedList1 { cursor: self.first };
+        folder.consume_iter(iter.take(self.len))
+    }
+}
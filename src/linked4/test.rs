@@ -1,4 +1,5 @@
 use super::*;
+use crate::differential::Lcg;
 
 #[test]
 fn test_create() {
@@ -57,3 +58,707 @@ fn test_concat_huge() {
     let lvec = l.to_vec();
     assert_eq!(test, lvec);
 }
+
+/* Every combination of empty, one node (no cached tail) and more, on both
+sides. The tail has to be right afterwards, so we keep using the list. */
+#[test]
+fn test_concat_move() {
+    let lists: [&[i64]; 3] = [&[], &[1], &[1, 2, 3]];
+    for left in lists.iter() {
+        for right in lists.iter() {
+            let mut l = List::new(left);
+            l.concat(List::new(right));
+            let mut expected = [*left, *right].concat();
+            assert_eq!(l.to_vec(), expected);
+            assert_eq!(l.len(), expected.len());
+            l.add_item(9);
+            l.concat(List::new(&[10]));
+            expected.extend([9, 10]);
+            assert_eq!(l.to_vec(), expected);
+            assert_eq!(l.pop_back(), Some(10));
+            assert_eq!(l.len(), expected.len() - 1);
+        }
+    }
+}
+
+/* concat() only moves Boxes, so it works for values that can't be cloned. */
+#[test]
+fn test_concat_not_clone() {
+    struct Token(i64);
+    let list_of = |values: &[i64]| {
+        let mut list = List::default();
+        for &v in values {
+            list.add_item(Token(v));
+        }
+        list
+    };
+    let mut l = list_of(&[1, 2]);
+    l.concat(list_of(&[3]));
+    l.concat(list_of(&[]));
+    l.concat(list_of(&[4, 5]));
+    l.add_item(Token(6));
+    assert_eq!(
+        l.iter().map(|t| t.0).collect::<Vec<_>>(),
+        vec![1, 2, 3, 4, 5, 6]
+    );
+    assert_eq!(l.len(), 6);
+    assert_eq!(l.pop_back().map(|t| t.0), Some(6));
+}
+
+#[test]
+fn test_drop_huge_node_chain() {
+    // A million nodes would overflow the stack with a recursive drop.
+    let mut first = LinkedList1::new_box(0, None);
+    for i in 1..1_000_000 {
+        first = LinkedList1::new_box(i, Some(first));
+    }
+    assert_eq!(first.iter().count(), 1_000_000);
+    drop(first);
+}
+
+#[test]
+fn test_drop_huge_list() {
+    let data: Vec<i64> = (0..1_000_000).collect();
+    let l = List::new(&data);
+    assert_eq!(l.to_vec().len(), data.len());
+    drop(l);
+}
+
+#[test]
+fn test_add_item() {
+    let mut l = List::new(&[]);
+    for i in 0..1000 {
+        l.add_item(i);
+    }
+    let want: Vec<i64> = (0..1000).collect();
+    assert_eq!(l.to_vec(), want);
+    assert_eq!(l.tail_mut().copied(), Some(999));
+}
+
+/* tail_mut() only lends the value: there's no node to hang anything on. */
+#[test]
+fn test_tail_mut() {
+    let mut l: List<i64> = List::default();
+    assert_eq!(l.tail_mut(), None);
+    l.add_item(1);
+    *l.tail_mut().unwrap() = 2;
+    l.add_item(3);
+    *l.tail_mut().unwrap() += 1;
+    assert_eq!(l.to_vec(), vec![2, 4]);
+    assert_eq!(l.check(), Ok(()));
+}
+
+#[test]
+fn test_concat_copy_into_empty() {
+    let mut l = List::new(&[]);
+    l.concat_copy(&List::new(&[]));
+    assert!(l.first().is_none());
+    l.concat_copy(&List::new(&[5, 6]));
+    l.add_item(7);
+    assert_eq!(l.to_vec(), vec![5, 6, 7]);
+}
+
+#[test]
+fn test_into_iter() {
+    let data = vec![3, 8, 1, 2];
+    let mut out = vec![];
+    for v in List::new(&data) {
+        out.push(v);
+    }
+    assert_eq!(out, data);
+    assert_eq!(List::<i64>::new(&[]).into_iter().next(), None);
+}
+
+#[test]
+fn test_into_iter_moves_values() {
+    let data = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    let mut it = List::new(&data).into_iter();
+    assert_eq!(it.next(), Some("a".to_string()));
+    // The rest is dropped together with the iterator.
+    drop(it);
+    let out: Vec<String> = List::new(&data).into_iter().collect();
+    assert_eq!(out, data);
+}
+
+#[test]
+fn test_push_pop_front() {
+    let mut l = List::new(&[]);
+    l.push_front(2);
+    l.push_front(1);
+    l.add_item(3);
+    assert_eq!(l.to_vec(), vec![1, 2, 3]);
+    assert_eq!(l.pop_front(), Some(1));
+    assert_eq!(l.pop_front(), Some(2));
+    assert_eq!(l.pop_front(), Some(3));
+    assert_eq!(l.pop_front(), None);
+    // The tail must have been forgotten with the last node.
+    l.add_item(4);
+    l.push_front(5);
+    assert_eq!(l.to_vec(), vec![5, 4]);
+}
+
+#[test]
+fn test_pop_back() {
+    let mut l = List::new(&[1, 2, 3]);
+    assert_eq!(l.pop_back(), Some(3));
+    l.add_item(4);
+    assert_eq!(l.to_vec(), vec![1, 2, 4]);
+    assert_eq!(l.pop_back(), Some(4));
+    assert_eq!(l.pop_back(), Some(2));
+    assert_eq!(l.pop_back(), Some(1));
+    assert_eq!(l.pop_back(), None);
+    l.add_item(5);
+    assert_eq!(l.to_vec(), vec![5]);
+}
+
+#[test]
+fn test_len() {
+    let mut l = List::new(&[1, 2, 3]);
+    assert_eq!(l.len(), 3);
+    l.add_item(4);
+    l.push_front(0);
+    l.concat_copy(&List::new(&[5, 6]));
+    assert_eq!(l.len(), 7);
+    l.pop_front();
+    l.pop_back();
+    assert_eq!(l.len(), 5);
+    l.add_item(10);
+    assert_eq!(l.len(), 6);
+    assert_eq!(l.len(), l.to_vec().len());
+}
+
+#[test]
+fn test_clear() {
+    let data: Vec<i64> = (0..1_000_000).collect();
+    let mut l = List::new(&data);
+    assert!(!l.is_empty());
+    l.clear();
+    assert!(l.is_empty());
+    assert_eq!(l.len(), 0);
+    assert_eq!(l.to_vec(), vec![]);
+    l.add_item(1);
+    assert_eq!(l.to_vec(), vec![1]);
+}
+
+#[test]
+fn test_reverse() {
+    for n in 0..10 {
+        let mut data: Vec<i64> = (0..n).collect();
+        let mut l = List::new(&data);
+        l.reverse();
+        data.reverse();
+        assert_eq!(l.to_vec(), data);
+        assert_eq!(l.len(), data.len());
+        // The tail has to be the old first node now.
+        l.add_item(100);
+        data.push(100);
+        assert_eq!(l.to_vec(), data);
+    }
+}
+
+#[test]
+fn test_sort() {
+    let mut rng = Lcg::new(42);
+    for n in 0..50 {
+        let mut data: Vec<i64> = (0..n).map(|_| (rng.next_u64() >> 33) as i64 % 20).collect();
+        let mut l = List::new(&data);
+        l.sort();
+        data.sort();
+        assert_eq!(l.to_vec(), data);
+        assert_eq!(l.len(), data.len());
+        l.add_item(100);
+        data.push(100);
+        assert_eq!(l.to_vec(), data);
+    }
+}
+
+#[test]
+fn test_sort_by_is_stable() {
+    let data = vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd'), (0, 'e'), (2, 'f')];
+    let mut l = List::new(&data);
+    l.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut want = data.clone();
+    want.sort_by_key(|a| a.0);
+    assert_eq!(l.to_vec(), want);
+}
+
+#[test]
+fn test_sort_by_panic_leaves_empty_list() {
+    let mut l = List::new(&[3, 1, 2]);
+    let r = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        l.sort_by(|_, _| panic!("no comparing today"));
+    }));
+    assert!(r.is_err());
+    assert!(l.is_empty());
+    l.add_item(1);
+    assert_eq!(l.to_vec(), vec![1]);
+}
+
+#[test]
+fn test_retain() {
+    let data: Vec<i64> = (0..20).collect();
+    let mut l = List::new(&data);
+    l.retain(|v| v % 3 == 0);
+    assert_eq!(l.to_vec(), vec![0, 3, 6, 9, 12, 15, 18]);
+    assert_eq!(l.len(), 7);
+    l.retain(|v| *v < 10);
+    l.add_item(1);
+    assert_eq!(l.to_vec(), vec![0, 3, 6, 9, 1]);
+    l.retain(|_| false);
+    assert!(l.is_empty());
+    l.add_item(2);
+    assert_eq!(l.to_vec(), vec![2]);
+}
+
+#[test]
+fn test_remove_value() {
+    let mut l = List::new(&[1, 2, 3, 2, 4, 2]);
+    assert_eq!(l.remove_first_value(&2), Some(2));
+    assert_eq!(l.to_vec(), vec![1, 3, 2, 4, 2]);
+    assert_eq!(l.remove_first_value(&7), None);
+    assert_eq!(l.remove_all_value(&2), 2);
+    assert_eq!(l.to_vec(), vec![1, 3, 4]);
+    // Removing the last node must move the tail back.
+    assert_eq!(l.remove_first_value(&4), Some(4));
+    l.add_item(5);
+    assert_eq!(l.to_vec(), vec![1, 3, 5]);
+    assert_eq!(l.len(), 3);
+}
+
+#[test]
+fn test_clone() {
+    let mut l = List::new(&[1, 2, 3]);
+    let mut c = l.clone();
+    l.add_item(4);
+    c.pop_front();
+    c.add_item(5);
+    assert_eq!(l.to_vec(), vec![1, 2, 3, 4]);
+    assert_eq!(c.to_vec(), vec![2, 3, 5]);
+    assert_eq!(c.len(), 3);
+    assert!(List::<i64>::new(&[]).clone().is_empty());
+}
+
+#[test]
+fn test_clone_huge() {
+    let data: Vec<i64> = (0..1_000_000).collect();
+    let l = List::new(&data);
+    let c = l.clone();
+    drop(l);
+    assert_eq!(c.len(), data.len());
+    assert_eq!(c.to_vec(), data);
+}
+
+#[test]
+fn test_eq() {
+    let l = List::new(&[3, 8, 1]);
+    assert_eq!(l, List::new(&[3, 8, 1]));
+    assert_ne!(l, List::new(&[3, 8]));
+    assert_ne!(l, List::new(&[3, 8, 2]));
+    assert_eq!(List::<i64>::new(&[]), List::new(&[]));
+    assert_eq!(l.first(), List::new(&[3, 8, 1]).first());
+}
+
+#[test]
+fn test_debug_display() {
+    let l = List::new(&[3, 8, 1]);
+    assert_eq!(format!("{:?}", l), "[3, 8, 1]");
+    assert_eq!(format!("{}", l), "3 -> 8 -> 1 -> ∅");
+    assert_eq!(format!("{}", List::<i64>::new(&[])), "∅");
+    assert_eq!(format!("{:?}", l.first().unwrap()), "[3, 8, 1]");
+}
+
+#[test]
+fn test_debug_huge() {
+    // The derived Debug overflowed the stack here.
+    let data: Vec<i64> = (0..1_000_000).collect();
+    let l = List::new(&data);
+    assert_eq!(format!("{:?}", l), format!("{:?}", data));
+}
+
+#[test]
+fn test_doubly_iter_both_ways() {
+    let l = DoublyList::from_slice(&[1, 2, 3, 4]);
+    assert_eq!(l.to_vec(), vec![1, 2, 3, 4]);
+    assert_eq!(l.to_vec_rev(), vec![4, 3, 2, 1]);
+    let mut it = l.iter();
+    assert_eq!(it.next(), Some(&1));
+    assert_eq!(it.next_back(), Some(&4));
+    assert_eq!(it.next_back(), Some(&3));
+    assert_eq!(it.next(), Some(&2));
+    assert_eq!(it.next(), None);
+    assert_eq!(it.next_back(), None);
+}
+
+#[test]
+fn test_doubly_push_pop() {
+    let mut l = DoublyList::new();
+    l.push_back(2);
+    l.push_front(1);
+    l.push_back(3);
+    assert_eq!(l.len(), 3);
+    assert_eq!(l.pop_back(), Some(3));
+    assert_eq!(l.pop_front(), Some(1));
+    assert_eq!(l.to_vec_rev(), vec![2]);
+    assert_eq!(l.pop_back(), Some(2));
+    assert_eq!(l.pop_back(), None);
+    assert_eq!(l.pop_front(), None);
+    assert!(l.is_empty());
+    l.push_front(4);
+    l.push_back(5);
+    assert_eq!(l.to_vec_rev(), vec![5, 4]);
+}
+
+#[test]
+fn test_doubly_drop_huge() {
+    let mut l = DoublyList::new();
+    for i in 0..1_000_000 {
+        l.push_back(i);
+    }
+    assert_eq!(l.iter().next_back(), Some(&999_999));
+    drop(l);
+}
+
+#[test]
+fn test_cursor_walk() {
+    let mut l = List::new(&[1, 2, 3]);
+    let mut c = l.cursor_front_mut();
+    assert_eq!(c.index(), Some(0));
+    *c.current().unwrap() *= 10;
+    c.move_next();
+    c.move_next();
+    assert_eq!(c.current(), Some(&mut 3));
+    c.move_next();
+    assert_eq!(c.index(), None);
+    assert_eq!(c.current(), None);
+    c.move_next();
+    assert_eq!(c.current(), Some(&mut 10));
+    assert_eq!(l.to_vec(), vec![10, 2, 3]);
+}
+
+#[test]
+fn test_cursor_insert_remove() {
+    let mut l = List::new(&[1, 2, 3]);
+    let mut c = l.cursor_front_mut();
+    c.insert_after(15);
+    assert_eq!(c.remove_current(), Some(1));
+    assert_eq!(c.current(), Some(&mut 15));
+    c.move_next();
+    c.move_next();
+    // Removing the last node moves the tail back.
+    assert_eq!(c.remove_current(), Some(3));
+    assert_eq!(c.current(), None);
+    assert_eq!(c.remove_current(), None);
+    // On the ghost, insert_after() pushes to the front.
+    c.insert_after(0);
+    c.move_next();
+    c.move_next();
+    c.move_next();
+    // On the last node, insert_after() adds a new tail.
+    c.insert_after(4);
+    assert_eq!(l.to_vec(), vec![0, 15, 2, 4]);
+    assert_eq!(l.len(), 4);
+    l.add_item(5);
+    assert_eq!(l.to_vec(), vec![0, 15, 2, 4, 5]);
+}
+
+#[test]
+fn test_cursor_split_after() {
+    let mut l = List::new(&[1, 2, 3, 4]);
+    let mut c = l.cursor_front_mut();
+    c.move_next();
+    let mut rest = c.split_after();
+    assert_eq!(l.to_vec(), vec![1, 2]);
+    assert_eq!(rest.to_vec(), vec![3, 4]);
+    assert_eq!((l.len(), rest.len()), (2, 2));
+    l.add_item(5);
+    rest.add_item(6);
+    assert_eq!(l.to_vec(), vec![1, 2, 5]);
+    assert_eq!(rest.to_vec(), vec![3, 4, 6]);
+
+    let mut c = rest.cursor_front_mut();
+    for _ in 0..3 {
+        c.move_next();
+    }
+    let all = c.split_after();
+    assert!(rest.is_empty());
+    assert_eq!(all.to_vec(), vec![3, 4, 6]);
+}
+
+#[test]
+fn test_new_fast() {
+    for n in 0..5 {
+        let data: Vec<i64> = (0..n).collect();
+        let mut l = List::new_fast(&data);
+        assert_eq!(l, List::new(&data));
+        assert_eq!(l.len(), data.len());
+        l.add_item(10);
+        assert_eq!(l.tail_mut().copied(), Some(10));
+    }
+}
+
+#[test]
+fn test_get() {
+    let mut l = List::new(&[3, 8, 1]);
+    assert_eq!(l.get(0), Some(&3));
+    assert_eq!(l.get(2), Some(&1));
+    assert_eq!(l.get(3), None);
+    *l.get_mut(1).unwrap() = 9;
+    assert_eq!(l.get_mut(3), None);
+    l[2] += 1;
+    assert_eq!((l[0], l[1], l[2]), (3, 9, 2));
+}
+
+#[test]
+#[should_panic(expected = "index out of range: the len is 3 but the index is 3")]
+fn test_index_out_of_range() {
+    let l = List::new(&[3, 8, 1]);
+    let _ = l[3];
+}
+
+#[test]
+fn test_append_slice() {
+    let mut l = List::new(&[]);
+    l.append_slice(&[1, 2]);
+    l.append_slice(&[]);
+    l.extend_from_slice(&[3, 4, 5]);
+    assert_eq!(l.to_vec(), vec![1, 2, 3, 4, 5]);
+    assert_eq!(l.len(), 5);
+    l.add_item(6);
+    assert_eq!(l.to_vec(), vec![1, 2, 3, 4, 5, 6]);
+    assert_eq!(l.len(), 6);
+}
+
+#[test]
+fn test_swap() {
+    let mut l = List::new(&[1, 2, 3, 4]);
+    l.swap(0, 3);
+    l.swap(2, 1);
+    l.swap(2, 2);
+    assert_eq!(l.to_vec(), vec![4, 3, 2, 1]);
+}
+
+#[test]
+#[should_panic(expected = "swap: index out of range")]
+fn test_swap_out_of_range() {
+    let mut l = List::new(&[1, 2]);
+    l.swap(0, 2);
+}
+
+#[test]
+fn test_rotate() {
+    let data: Vec<i64> = (0..5).collect();
+    for n in 0..12 {
+        let mut l = List::new(&data);
+        let mut want = data.clone();
+        l.rotate_left(n);
+        want.rotate_left(n % data.len());
+        assert_eq!(l.to_vec(), want);
+        l.rotate_right(n + 1);
+        want.rotate_right((n + 1) % data.len());
+        assert_eq!(l.to_vec(), want);
+        l.add_item(9);
+        want.push(9);
+        assert_eq!(l.to_vec(), want);
+    }
+    let mut l = List::<i64>::new(&[]);
+    l.rotate_left(3);
+    l.rotate_right(3);
+    assert!(l.is_empty());
+}
+
+#[test]
+fn test_nth_from_end() {
+    let l = List::new(&[1, 2, 3, 4]);
+    assert_eq!(l.nth_from_end(0), Some(&4));
+    assert_eq!(l.nth_from_end(3), Some(&1));
+    assert_eq!(l.nth_from_end(4), None);
+    assert_eq!(List::<i64>::new(&[]).nth_from_end(0), None);
+}
+
+#[test]
+fn test_dedup() {
+    let mut l = List::new(&[1, 1, 2, 3, 3, 3, 1, 4, 4]);
+    l.dedup();
+    assert_eq!(l.to_vec(), vec![1, 2, 3, 1, 4]);
+    assert_eq!(l.len(), 5);
+    l.add_item(5);
+    assert_eq!(l.to_vec(), vec![1, 2, 3, 1, 4, 5]);
+    let mut l = List::new(&[1, 2, 4, 5, 7, 10]);
+    l.dedup_by(|next, kept| next - kept == 1);
+    assert_eq!(l.to_vec(), vec![1, 4, 7, 10]);
+    let mut l = List::<i64>::new(&[]);
+    l.dedup();
+    assert!(l.is_empty());
+}
+
+#[test]
+fn test_is_sorted() {
+    assert!(List::<i64>::new(&[]).is_sorted());
+    assert!(List::new(&[1]).is_sorted());
+    assert!(List::new(&[1, 2, 2, 5]).is_sorted());
+    assert!(!List::new(&[1, 3, 2]).is_sorted());
+    let mut l = List::new(&[5, 1, 4, 1]);
+    l.sort();
+    assert!(l.is_sorted());
+}
+
+#[test]
+fn test_drain() {
+    let mut l = List::new(&[1, 2, 3]);
+    assert_eq!(l.drain().collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert!(l.is_empty());
+    l.append_slice(&[4, 5, 6]);
+    let mut d = l.drain();
+    assert_eq!(d.next(), Some(4));
+    drop(d);
+    assert!(l.is_empty());
+    l.add_item(7);
+    assert_eq!(l.to_vec(), vec![7]);
+}
+
+#[test]
+fn test_drain_filter() {
+    let data: Vec<i64> = (0..10).collect();
+    let mut l = List::new(&data);
+    let evens: Vec<i64> = l.drain_filter(|v| v % 2 == 0).collect();
+    assert_eq!(evens, vec![0, 2, 4, 6, 8]);
+    assert_eq!(l.to_vec(), vec![1, 3, 5, 7, 9]);
+    assert_eq!(l.len(), 5);
+    l.add_item(11);
+    assert_eq!(l.to_vec(), vec![1, 3, 5, 7, 9, 11]);
+
+    // Stopping early keeps everything that wasn't visited.
+    assert_eq!(l.drain_filter(|v| *v > 4).next(), Some(5));
+    assert_eq!(l.to_vec(), vec![1, 3, 7, 9, 11]);
+
+    // Removing the tail moves it back.
+    assert_eq!(l.drain_filter(|v| *v == 11).count(), 1);
+    l.add_item(12);
+    assert_eq!(l.to_vec(), vec![1, 3, 7, 9, 12]);
+}
+
+#[test]
+fn test_exact_size() {
+    let mut l = List::new(&[1, 2, 3, 4]);
+    let mut it = l.iter();
+    assert_eq!(it.len(), 4);
+    it.next();
+    assert_eq!(it.size_hint(), (3, Some(3)));
+    /* The bare chain doesn't know, and doesn't walk itself to find out. */
+    assert_eq!(l.first().unwrap().iter().size_hint(), (0, None));
+    assert_eq!(l.drain().len(), 4);
+
+    let mut it = List::new(&[1, 2, 3]).into_iter();
+    it.next();
+    assert_eq!(it.len(), 2);
+
+    let d = DoublyList::from_slice(&[1, 2, 3]);
+    let mut it = d.iter();
+    it.next_back();
+    assert_eq!(it.len(), 2);
+    it.next();
+    it.next();
+    assert_eq!(it.len(), 0);
+    assert_eq!(it.next(), None);
+    assert_eq!(it.next(), None);
+}
+
+#[test]
+fn test_split_off() {
+    for len in 0..8 {
+        for at in 0..=len {
+            let values: Vec<usize> = (0..len).collect();
+            let mut list = List::new(&values);
+            let mut rest = list.split_off(at);
+            assert_eq!(list.iter().copied().collect::<Vec<_>>(), &values[..at]);
+            assert_eq!(rest.iter().copied().collect::<Vec<_>>(), &values[at..]);
+            assert_eq!(list.len(), at);
+            assert_eq!(rest.len(), len - at);
+            // Both tails must still be right, or appending goes astray.
+            list.append_slice(&[100]);
+            rest.append_slice(&[200]);
+            assert_eq!(list.iter().last(), Some(&100));
+            assert_eq!(list.len(), at + 1);
+            assert_eq!(rest.iter().last(), Some(&200));
+            assert_eq!(rest.len(), len - at + 1);
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "split_off: the len is 2 but the index is 3")]
+fn test_split_off_out_of_range() {
+    let mut list = List::new(&[1, 2]);
+    list.split_off(3);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_par_iter() {
+    use rayon::prelude::*;
+    let values: Vec<u64> = (0..10_000).collect();
+    let list = List::new(&values);
+    let squares: Vec<u64> = list.par_iter().map(|v| v * v).collect();
+    assert_eq!(squares, values.iter().map(|v| v * v).collect::<Vec<_>>());
+    assert_eq!(list.par_iter().sum::<u64>(), values.iter().sum());
+    assert_eq!(list.len(), 10_000);
+
+    let strings: Vec<String> = List::new(&values)
+        .into_par_iter()
+        .filter(|v| v & 1 == 1)
+        .map(|v| v.to_string())
+        .collect();
+    assert_eq!(strings.len(), 5_000);
+    assert_eq!(strings[0], "1");
+    assert_eq!(strings[4_999], "9999");
+
+    let empty: List<u64> = List::new(&[]);
+    assert_eq!(empty.par_iter().count(), 0);
+    assert_eq!(List::new(&[7u64]).into_par_iter().sum::<u64>(), 7);
+}
+
+#[test]
+fn test_check_invariants() {
+    let mut l = List::new(&[3, 4, 0]);
+    assert_eq!(l.check(), Ok(()));
+    /* Nothing can hang nodes after the tail from outside anymore, but
+    check() would see them. Each write goes through the cached pointer
+    again, as last_node() does: check() reads through the Boxes, and a &mut
+    kept across that read would be read-only afterwards. */
+    let tail = l.tail.unwrap().as_ptr();
+    unsafe { (*tail).set_next(Some(LinkedList1::new_box(1, None))) };
+    assert_eq!(l.check(), Err(InvariantError::NextAfterTail));
+    unsafe { (*tail).set_next(None) };
+
+    l.len = 5;
+    assert_eq!(l.check(), Err(InvariantError::WrongLen { len: 5, counted: 3 }));
+    l.len = 3;
+    let other = List::new(&[7, 8]);
+    l.tail = other.tail;
+    assert_eq!(l.check(), Err(InvariantError::WrongTail));
+    /* The cache never points to the first node... */
+    l.tail = l.first().map(NonNull::from);
+    assert_eq!(l.check(), Err(InvariantError::WrongTail));
+    /* ...and without it, the first node is the only one counted, and the
+    last one. */
+    l.tail = None;
+    assert_eq!(l.check(), Err(InvariantError::WrongLen { len: 3, counted: 1 }));
+    l.len = 1;
+    assert_eq!(l.check(), Err(InvariantError::NextAfterTail));
+    if let ListState::First(first) = &mut l.state {
+        first.next = None;
+    }
+    assert_eq!(l.check(), Ok(()));
+    l.clear();
+    l.len = 1;
+    assert_eq!(l.check(), Err(InvariantError::WrongLen { len: 1, counted: 0 }));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "broken list: len is 6 but there are 4 nodes")]
+fn test_debug_check_catches_corruption() {
+    let mut l = List::new(&[3, 4, 0]);
+    l.len = 5;
+    l.push_front(1);
+}
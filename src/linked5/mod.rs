@@ -20,8 +20,13 @@ copy, but not useful at all.
 
 So the only sane way is going with "next: Rc<RefCell<Node>>"
 */
+use crate::footprint::{rc_size, HeapSize};
+use crate::invariants::{debug_check, CheckInvariants, InvariantError};
+use crate::topology::{id_of, NodeLinks, Topology};
 use std::cell::Ref;
 use std::cell::RefCell;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
 use std::rc::Rc;
 use std::rc::Weak;
 
@@ -34,6 +39,7 @@ pub struct Node {
 pub struct List {
     first: Option<Rc<RefCell<Node>>>,
     tail: Weak<RefCell<Node>>,
+    len: usize,
 }
 
 impl Node {
@@ -63,10 +69,20 @@ impl Default for List {
         Self {
             first: None,
             tail: Weak::new(),
+            len: 0,
         }
     }
 }
 
+/* The compiler says a List can't cross catch_unwind(), because of the
+RefCells, and it's right. The handles iter_mut() gives out are the nodes
+themselves, and they don't borrow the list. Hold a.borrow() while calling
+swap_nodes(&a, &b), or pop_first() while the second node is borrowed, and
+link() panics on borrow_mut() with one side of a pair relinked and the other
+not. Whatever catches that has a list check() rejects. So there's no
+UnwindSafe here: to carry a List across catch_unwind(), wrap it in
+AssertUnwindSafe and take the blame. */
+
 impl List {
     pub fn new() -> Self {
         Default::default()
@@ -79,11 +95,15 @@ impl List {
         l
     }
 
-    pub fn from_vec(v: &[i64]) -> Self {
+    /* Every node first, in a Vec, and then a second pass to link them, with
+    a borrow_mut() on each side of every link and an Rc clone for each next.
+    The Vec itself is one more allocation, and dropping it at the end takes
+    back all the counts it held. */
+    pub fn from_vec_two_passes(v: &[i64]) -> Self {
         if v.is_empty() {
-            return List {first: None, tail: Weak::new()};
+            return List::new();
         }
-        let mut nodes: Vec<Rc<RefCell<Node>>> = v
+        let nodes: Vec<Rc<RefCell<Node>>> = v
             .iter()
             .map(|n| Node {
                 value: *n,
@@ -99,7 +119,37 @@ impl List {
         List {
             first: Some(nodes[0].clone()),
             tail: Rc::downgrade(&nodes[nodes.len()-1]),
+            len: nodes.len(),
+        }
+    }
+
+    /* One pass, from the back. Every node is made already owning the one
+    after it, so the Rc moves in instead of being cloned, and there's no Vec
+    to hold them meanwhile. The only thing left to fix is the prev of the
+    node after, which we can't know until the new one exists: one
+    borrow_mut() per node, and a Weak. */
+    pub fn from_vec(v: &[i64]) -> Self {
+        let mut first: Option<Rc<RefCell<Node>>> = None;
+        let mut tail = Weak::new();
+        for &value in v.iter().rev() {
+            let node = Rc::new(RefCell::new(Node {
+                value,
+                prev: Weak::new(),
+                next: first.take(),
+            }));
+            match &node.borrow().next {
+                Some(after) => after.borrow_mut().prev = Rc::downgrade(&node),
+                None => tail = Rc::downgrade(&node),
+            }
+            first = Some(node);
         }
+        let list = List {
+            first,
+            tail,
+            len: v.len(),
+        };
+        debug_check(&list, list.len);
+        list
     }
 
     pub fn to_vec(&self) -> Vec<i64> {
@@ -110,10 +160,22 @@ impl List {
         self.iter().rev().collect()
     }
 
+    /* Taking other_list by value means `l.concat(l)` can't even compile, but
+    two List values could still end up sharing nodes (everything is an Rc
+    after all). Linking a list after itself would create a cycle, so in that
+    case we copy the values instead.
+
+    Checking this is O(1): if two chains share any node, they also share the
+    last one, because every node has a single next. If it ever got that
+    wrong, debug_check() would see the first node with a prev. */
     pub fn concat(&mut self, other_list: List) {
         if other_list.first.is_none() {
             return;
         }
+        if self.shares_tail_with(&other_list) {
+            return self.concat_ref(&other_list);
+        }
+        self.len += other_list.len;
         let other = other_list.first.unwrap();
         if let Some(tail) = self.tail.upgrade() {
             let mut muttail = tail.borrow_mut();
@@ -124,6 +186,21 @@ impl List {
             self.tail = other_list.tail.clone();
             self.first = Some(other);
         }
+        debug_check(self, self.len);
+    }
+
+    /* Deep copy. Other stays untouched, and it can be any list, even one
+    sharing nodes with this one: the values are collected before appending
+    anything. */
+    pub fn concat_ref(&mut self, other: &List) {
+        self.concat(List::from_vec(&other.to_vec()));
+    }
+
+    fn shares_tail_with(&self, other: &List) -> bool {
+        match (self.tail.upgrade(), other.tail.upgrade()) {
+            (Some(a), Some(b)) => Rc::ptr_eq(&a, &b),
+            _ => false,
+        }
     }
 
     pub fn append(&mut self, value: i64) {
@@ -132,6 +209,7 @@ impl List {
             next: None,
             prev: Weak::new(),
         };
+        self.len += 1;
 
         if let Some(tail) = self.tail.upgrade() {
             let mut muttail = tail.borrow_mut();
@@ -144,6 +222,7 @@ impl List {
             self.first = Some(otherref.clone());
             self.tail = Rc::downgrade(&otherref);
         }
+        debug_check(self, self.len);
     }
 
     pub fn insert_first(&mut self, value: i64) {
@@ -152,6 +231,7 @@ impl List {
             next: None,
             prev: Weak::new(),
         };
+        self.len += 1;
 
         if let Some(first) = self.first.clone() {
             let mut mutfirst = first.borrow_mut();
@@ -164,6 +244,7 @@ impl List {
             self.first = Some(otherref.clone());
             self.tail = Rc::downgrade(&otherref);
         }
+        debug_check(self, self.len);
     }
 
     pub fn peek_front(&self) -> Option<i64> {
@@ -174,10 +255,12 @@ impl List {
         self.tail.upgrade().map(|f| f.borrow().value)
     }
 
-    pub fn iter(&self) -> IterList {
+    pub fn iter(&self) -> IterList<'_> {
         IterList {
             cursor: self.first.clone(),
             revcursor: self.tail.upgrade(),
+            remaining: self.len,
+            list: PhantomData,
         }
     }
 
@@ -192,7 +275,11 @@ impl List {
                 self.first = None;
             }
             tail.prev = Weak::new();
-            Some(tail.value)
+            self.len -= 1;
+            let value = tail.value;
+            drop(tail);
+            debug_check(self, self.len);
+            Some(value)
         } else {
             None
         }
@@ -200,15 +287,20 @@ impl List {
     pub fn pop_first(&mut self) -> Option<i64> {
         if let Some(firstref) = self.first.clone() {
             let mut first = firstref.borrow_mut();
-            self.first = first.next.clone();
-            first.next = None;
+            self.first = first.next.take();
             if self.first.is_none() {
                 self.tail = Weak::new();
             }
-            if let Some(newfirst) = first.next.clone() {
+            /* The new first still had a prev pointing to us. It would fail to
+            upgrade once we're gone... unless someone else keeps us alive. */
+            if let Some(newfirst) = self.first.as_ref() {
                 newfirst.borrow_mut().prev = Weak::new();
             }
-            Some(first.value)
+            self.len -= 1;
+            let value = first.value;
+            drop(first);
+            debug_check(self, self.len);
+            Some(value)
         } else {
             None
         }
@@ -220,18 +312,745 @@ impl List {
             cursor,                    
         }
     }
+
+    /* This used to walk the whole list. Now every method that links or
+    unlinks nodes keeps a counter up to date. */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.first.is_none()
+    }
+
+    /* Cutting the chain after the nth node is enough: whatever hangs from
+    there loses its last strong reference and gets freed by Drop for Node. */
+    pub fn truncate(&mut self, len: usize) {
+        if len == 0 {
+            self.first = None;
+            self.tail = Weak::new();
+            self.len = 0;
+            return;
+        }
+        if let Some(last) = self.node_at(len - 1) {
+            last.borrow_mut().next = None;
+            self.tail = Rc::downgrade(&last);
+            self.len = len;
+        }
+        debug_check(self, self.len);
+    }
+
+    pub fn resize(&mut self, len: usize, fill: i64) {
+        let cur_len = self.len();
+        if len <= cur_len {
+            self.truncate(len);
+        } else {
+            for _ in cur_len..len {
+                self.append(fill);
+            }
+        }
+    }
+
+    /* Queries are just the iterator in disguise, but they save the caller a
+    to_vec() every time. */
+    pub fn contains(&self, value: i64) -> bool {
+        self.iter().any(|v| v == value)
+    }
+
+    pub fn position(&self, value: i64) -> Option<usize> {
+        self.iter().position(|v| v == value)
+    }
+
+    pub fn find<P>(&self, mut predicate: P) -> Option<i64>
+    where
+        P: FnMut(i64) -> bool,
+    {
+        self.iter().find(|v| predicate(*v))
+    }
+
+    pub fn find_map<B, F>(&self, f: F) -> Option<B>
+    where
+        F: FnMut(i64) -> Option<B>,
+    {
+        self.iter().find_map(f)
+    }
+
+    /* A sorted insert doesn't need a binary search (we couldn't do one on a
+    linked list anyway). As we have prev links we can start walking from
+    whichever end has the closest value, which halves the walk on average.
+
+    Equal values always go after the existing ones, so both directions agree
+    on where the new node goes. */
+    pub fn insert_sorted(&mut self, value: i64) {
+        let (front, end) = match (self.peek_front(), self.peek_end()) {
+            (Some(front), Some(end)) => (front as i128, end as i128),
+            _ => return self.append(value),
+        };
+        if (value as i128 - front).abs() <= (end - value as i128).abs() {
+            // Forward: stop at the first node that is bigger than value.
+            let mut cur = self.first.clone();
+            while let Some(node) = cur.clone() {
+                if node.borrow().value > value {
+                    break;
+                }
+                cur = node.borrow().next.clone();
+            }
+            let prev = match &cur {
+                Some(node) => node.borrow().prev.upgrade(),
+                None => self.tail.upgrade(),
+            };
+            self.insert_between(prev.as_ref(), cur.as_ref(), value);
+        } else {
+            // Backward: stop at the last node that is smaller or equal.
+            let mut cur = self.tail.upgrade();
+            while let Some(node) = cur.clone() {
+                if node.borrow().value <= value {
+                    break;
+                }
+                cur = node.borrow().prev.upgrade();
+            }
+            let next = match &cur {
+                Some(node) => node.borrow().next.clone(),
+                None => self.first.clone(),
+            };
+            self.insert_between(cur.as_ref(), next.as_ref(), value);
+        }
+    }
+
+    pub fn is_sorted(&self) -> bool {
+        let mut iter = self.iter();
+        let mut prev = match iter.next() {
+            Some(v) => v,
+            None => return true,
+        };
+        for v in iter {
+            if v < prev {
+                return false;
+            }
+            prev = v;
+        }
+        true
+    }
+
+    /* Consumes the list and cuts it in two: [0, idx) and [idx, len). Both
+    halves are complete lists, with their own first, tail and prev links, so
+    they can be used on their own right away. Panics if idx > len, just like
+    slice::split_at. */
+    pub fn split_at(self, idx: usize) -> (List, List) {
+        if idx == 0 {
+            return (List::new(), self);
+        }
+        let last = match self.node_at(idx - 1) {
+            Some(node) => node,
+            None => panic!("split_at: index {} out of range", idx),
+        };
+        let right_first = last.borrow_mut().next.take();
+        let right = match right_first {
+            Some(first) => {
+                first.borrow_mut().prev = Weak::new();
+                List {
+                    first: Some(first),
+                    tail: self.tail.clone(),
+                    len: self.len - idx,
+                }
+            }
+            None => List::new(),
+        };
+        let left = List {
+            first: self.first,
+            tail: Rc::downgrade(&last),
+            len: idx,
+        };
+        debug_check(&left, left.len);
+        debug_check(&right, right.len);
+        (left, right)
+    }
+
+    pub fn zip<'a>(&'a self, other: &'a List) -> impl Iterator<Item = (i64, i64)> + 'a {
+        self.iter().zip(other.iter())
+    }
+
+    /* Alternates the nodes of both lists: self, other, self, other... When
+    one runs out, the rest of the other one goes at the end. No node is
+    created here, they are moved from one chain to the other. */
+    pub fn interleave(mut self, mut other: List) -> List {
+        let mut result = List::new();
+        loop {
+            match (self.pop_first_rc(), other.pop_first_rc()) {
+                (None, None) => break,
+                (a, b) => {
+                    if let Some(a) = a {
+                        result.push_back_rc(a);
+                    }
+                    if let Some(b) = b {
+                        result.push_back_rc(b);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /* Splits the nodes in two lists: the ones matching the predicate go to
+    the first one, the rest to the second. Relative order is kept, and the
+    nodes are moved over, not copied. */
+    pub fn partition<P>(mut self, mut predicate: P) -> (List, List)
+    where
+        P: FnMut(i64) -> bool,
+    {
+        let mut yes = List::new();
+        let mut no = List::new();
+        while let Some(node) = self.pop_first_rc() {
+            let value = node.borrow().value;
+            if predicate(value) {
+                yes.push_back_rc(node);
+            } else {
+                no.push_back_rc(node);
+            }
+        }
+        (yes, no)
+    }
+
+    /* The classic interview exercise: reverse every group of k nodes. A last
+    group shorter than k is left in its original order.
+
+    Each group is built by pushing nodes at the front of a temporary list,
+    which reverses them for free, and then linked at the end of self. */
+    pub fn reverse_chunks(&mut self, k: usize) {
+        if k < 2 {
+            return;
+        }
+        let mut rest = std::mem::take(self);
+        loop {
+            let mut chunk = List::new();
+            let mut count = 0;
+            while count < k {
+                match rest.pop_first_rc() {
+                    Some(node) => chunk.push_front_rc(node),
+                    None => break,
+                }
+                count += 1;
+            }
+            if count < k {
+                // Short group: flip it back before putting it in place.
+                let mut unreversed = List::new();
+                while let Some(node) = chunk.pop_first_rc() {
+                    unreversed.push_front_rc(node);
+                }
+                self.concat(unreversed);
+                break;
+            }
+            self.concat(chunk);
+        }
+    }
+
+    /* With prev links, counting from the end is just walking backwards.
+    nth_from_end(0) is the last value. */
+    pub fn nth_from_end(&self, n: usize) -> Option<i64> {
+        self.iter().rev().nth(n)
+    }
+
+    /* The two cursors trick: fast moves two nodes per step, slow moves one,
+    so slow is halfway when fast runs out. It doesn't need prev links at all.
+    For even lengths this returns the second of the two middle values. */
+    pub fn middle(&self) -> Option<i64> {
+        let mut slow = self.iter();
+        let mut fast = self.iter();
+        let mut mid = slow.next();
+        while fast.next().is_some() && fast.next().is_some() {
+            mid = slow.next();
+        }
+        mid
+    }
+
+    /* Walks from both ends at the same time and stops when the cursors
+    meet in the middle. No Vec needed, the DoubleEndedIterator does it all. */
+    pub fn is_palindrome(&self) -> bool {
+        let mut iter = self.iter();
+        while let (Some(front), Some(back)) = (iter.next(), iter.next_back()) {
+            if front != back {
+                return false;
+            }
+        }
+        true
+    }
+
+    /* Both lists must be sorted already. The result takes the nodes from the
+    front of whichever list has the smallest value (self wins on ties, so it
+    is stable), and once one of them runs out the rest of the other is linked
+    in one go. */
+    pub fn merge_sorted(mut self, mut other: List) -> List {
+        let mut result = List::new();
+        loop {
+            let node = match (self.peek_front(), other.peek_front()) {
+                (Some(a), Some(b)) if a <= b => self.pop_first_rc(),
+                (Some(_), Some(_)) => other.pop_first_rc(),
+                (Some(_), None) => {
+                    result.concat(self);
+                    break;
+                }
+                (None, _) => {
+                    result.concat(other);
+                    break;
+                }
+            };
+            if let Some(node) = node {
+                result.push_back_rc(node);
+            }
+        }
+        result
+    }
+
+    /* And with merge_sorted and split_at, a merge sort is almost free. The
+    values never move, only the links. */
+    pub fn sort(&mut self) {
+        let len = self.len();
+        if len < 2 {
+            return;
+        }
+        let (mut left, mut right) = std::mem::take(self).split_at(len / 2);
+        left.sort();
+        right.sort();
+        *self = left.merge_sorted(right);
+    }
+
+    /* Fisher-Yates needs random access, which we don't have. So first we
+    collect a cursor (an Rc) to every node, and then the shuffle swaps the
+    values through them. The links don't change at all. */
+    #[cfg(feature = "rand")]
+    pub fn shuffle<R: rand::Rng>(&mut self, rng: &mut R) {
+        let nodes: Vec<Rc<RefCell<Node>>> = self.iter_mut().collect();
+        for i in (1..nodes.len()).rev() {
+            let j = rng.gen_range(0..=i);
+            if i != j {
+                std::mem::swap(
+                    &mut nodes[i].borrow_mut().value,
+                    &mut nodes[j].borrow_mut().value,
+                );
+            }
+        }
+    }
+
+    /* Aggregates, so nobody needs to collect into a Vec just to add things
+    up. An empty list sums 0 and has no min or max. */
+    pub fn sum(&self) -> i64 {
+        self.iter().sum()
+    }
+
+    pub fn min(&self) -> Option<i64> {
+        self.iter().min()
+    }
+
+    pub fn max(&self) -> Option<i64> {
+        self.iter().max()
+    }
+
+    pub fn fold<B, F>(&self, init: B, f: F) -> B
+    where
+        F: FnMut(B, i64) -> B,
+    {
+        self.iter().fold(init, f)
+    }
+
+    /* These pop the whole node instead of just the value. Once unlinked, the
+    list no longer owns it, so if nobody else holds an Rc we can unwrap it
+    and return a plain Node.
+
+    If there are outstanding handles (e.g. from iter_mut) Rc::try_unwrap
+    fails, and we give back the Rc instead. The node is out of the list
+    either way; the error only tells you that it's still shared. */
+    pub fn pop_first_node(&mut self) -> Option<Result<Node, Rc<RefCell<Node>>>> {
+        self.pop_first_rc().map(|rc| Rc::try_unwrap(rc).map(RefCell::into_inner))
+    }
+
+    pub fn pop_tail_node(&mut self) -> Option<Result<Node, Rc<RefCell<Node>>>> {
+        self.pop_tail_rc().map(|rc| Rc::try_unwrap(rc).map(RefCell::into_inner))
+    }
+
+    /* Audit helpers. strong_counts() returns (strong, weak) for every node,
+    not counting the Rc we hold while walking. A healthy list with no handles
+    around has (1, 1) everywhere: the strong one comes from the previous
+    node (or first), the weak one from the next node's prev (or tail). */
+    pub fn strong_counts(&self) -> Vec<(usize, usize)> {
+        self.nodes()
+            .map(|rc| (Rc::strong_count(&rc) - 1, Rc::weak_count(&rc)))
+            .collect()
+    }
+
+    /* A Weak to every node. They don't keep anything alive, so once the list
+    is dropped none of them should upgrade anymore. One that still does is a
+    node nobody can reach and nobody will free: a leak, most likely a cycle. */
+    pub fn weak_nodes(&self) -> Vec<Weak<RefCell<Node>>> {
+        self.nodes().map(|rc| Rc::downgrade(&rc)).collect()
+    }
+
+    /* Panics if the links don't add up (see check() below). It also
+    complains about nodes with more than one strong reference, so don't call
+    it while you keep handles from iter_mut() around. */
+    pub fn assert_invariants(&self) {
+        self.assert_check();
+        for (idx, node) in self.nodes().enumerate() {
+            let strong = Rc::strong_count(&node) - 1;
+            assert!(strong <= 1, "node {} has {} strong references", idx, strong);
+        }
+    }
+
+    /* Floyd's tortoise and hare: fast jumps two nodes for each one of slow.
+    If there's a loop, fast ends up lapping slow and they meet; if not, fast
+    falls off the end. Only the Rc pointers are compared, values don't matter.
+
+    A cycle means the nodes own each other and will never be freed, and also
+    that any loop looking for the end of `next` never finishes. */
+    pub fn detect_cycle(&self) -> bool {
+        let mut slow = self.first.clone();
+        let mut fast = self.first.clone();
+        loop {
+            fast = match fast.as_ref().and_then(Self::next_of) {
+                Some(node) => Self::next_of(&node),
+                None => return false,
+            };
+            slow = slow.as_ref().and_then(Self::next_of);
+            match (&slow, &fast) {
+                (_, None) => return false,
+                (Some(slow), Some(fast)) if Rc::ptr_eq(slow, fast) => return true,
+                _ => (),
+            }
+        }
+    }
+
+    /* Positional access. It's O(n), this is a linked list after all.
+
+    There's no Index, so no list[3]. index() has to return a plain &i64, and
+    our values live inside RefCells, which only lend them through Ref guards.
+    Peeking through the RefCell with a raw pointer would compile, but a handle
+    from iter_mut() could then write the value while that &i64 is alive,
+    which is undefined behaviour. An i64 is cheap to copy, so we copy it. */
+    pub fn get(&self, idx: usize) -> Option<i64> {
+        self.iter().nth(idx)
+    }
+
+    /* IndexMut would need to hand out a &mut i64 from inside a RefCell,
+    which can't outlive the RefMut guard. So writes go through here. */
+    pub fn set(&mut self, idx: usize, value: i64) {
+        match self.node_at(idx) {
+            Some(node) => node.borrow_mut().value = value,
+            None => panic!(
+                "index out of range: the len is {} but the index is {}",
+                self.len(),
+                idx
+            ),
+        }
+    }
+
+    /* Swapping values is the easy way: find both nodes and exchange what's
+    inside. We must not borrow_mut the same RefCell twice, so i == j returns
+    early (after the bounds check, to behave like Vec::swap). */
+    pub fn swap(&mut self, i: usize, j: usize) {
+        let a = self.node_at(i);
+        let b = self.node_at(j);
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                if i != j {
+                    std::mem::swap(&mut a.borrow_mut().value, &mut b.borrow_mut().value);
+                }
+            }
+            _ => panic!("swap: index out of range ({}, {})", i, j),
+        }
+    }
+
+    /* Swapping the nodes themselves is the real pointer surgery. The handles
+    are the ones returned by iter_mut(). Returns false, and changes nothing,
+    if either of them isn't in this list: it was popped already, or it's in
+    another one. Relinking those would rewire both lists and leave first,
+    tail and len wrong. Checking costs a walk back to the first node, so
+    this is O(n) after all.
+
+    When both nodes are adjacent the four neighbours collapse into two, so
+    that case has to be done separately or we would link a node to itself. */
+    pub fn swap_nodes(&mut self, a: &Rc<RefCell<Node>>, b: &Rc<RefCell<Node>>) -> bool {
+        if !self.contains_node(a) || !self.contains_node(b) {
+            return false;
+        }
+        if Rc::ptr_eq(a, b) {
+            return true;
+        }
+        // Normalize so that if they're adjacent, a always goes before b.
+        let (a, b) = if Self::is_next(b, a) { (b, a) } else { (a, b) };
+        let a_prev = a.borrow().prev.upgrade();
+        let a_next = a.borrow().next.clone();
+        let b_prev = b.borrow().prev.upgrade();
+        let b_next = b.borrow().next.clone();
+        if Self::is_next(a, b) {
+            // prev -> a -> b -> next  ==>  prev -> b -> a -> next
+            self.link(a_prev.as_ref(), Some(b));
+            self.link(Some(b), Some(a));
+            self.link(Some(a), b_next.as_ref());
+        } else {
+            self.link(a_prev.as_ref(), Some(b));
+            self.link(Some(b), a_next.as_ref());
+            self.link(b_prev.as_ref(), Some(a));
+            self.link(Some(a), b_next.as_ref());
+        }
+        debug_check(self, self.len);
+        true
+    }
+
+    /* Nodes don't know which list they're in: concat() moves them in O(1),
+    so there's nowhere to keep it. Following prev back, a node of ours ends
+    at our first node, in at most len steps. */
+    pub fn contains_node(&self, node: &Rc<RefCell<Node>>) -> bool {
+        let mut cur = node.clone();
+        for _ in 0..self.len {
+            let prev = cur.borrow().prev.upgrade();
+            match prev {
+                Some(prev) => cur = prev,
+                None => return self.first.as_ref().is_some_and(|first| Rc::ptr_eq(first, &cur)),
+            }
+        }
+        false
+    }
+
+    /* Removes consecutive repeated values, like Vec::dedup. */
+    pub fn dedup(&mut self) {
+        self.dedup_by(|a, b| a == b)
+    }
+
+    /* Same argument order as Vec::dedup_by: the closure gets the candidate
+    for removal first and the value that stays in the list second. The nodes
+    are unlinked from the chain, so tail gets fixed for free if the last one
+    goes away. */
+    pub fn dedup_by<F>(&mut self, mut same_bucket: F)
+    where
+        F: FnMut(i64, i64) -> bool,
+    {
+        let mut cur = self.first.clone();
+        while let Some(node) = cur {
+            let next = match node.borrow().next.clone() {
+                Some(next) => next,
+                None => break,
+            };
+            if same_bucket(next.borrow().value, node.borrow().value) {
+                self.unlink(&next);
+                cur = Some(node);
+            } else {
+                cur = Some(next);
+            }
+        }
+    }
+
+    fn nodes(&self) -> IterListMut {
+        IterListMut {
+            cursor: self.first.clone(),
+        }
+    }
+
+    /* Walks to the node at position idx. Cloning the Rc on each step keeps
+    the borrow short, so no Ref<Node> is held across iterations. */
+    fn node_at(&self, idx: usize) -> Option<Rc<RefCell<Node>>> {
+        let mut cur = self.first.clone();
+        for _ in 0..idx {
+            cur = cur?.borrow().next.clone();
+        }
+        cur
+    }
+
+    fn next_of(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+        node.borrow().next.clone()
+    }
+
+    fn is_next(node: &Rc<RefCell<Node>>, other: &Rc<RefCell<Node>>) -> bool {
+        match node.borrow().next.as_ref() {
+            Some(next) => Rc::ptr_eq(next, other),
+            None => false,
+        }
+    }
+
+    /* Creates a new node between prev and next, which must be neighbours
+    (None meaning that end of the list). */
+    fn insert_between(
+        &mut self,
+        prev: Option<&Rc<RefCell<Node>>>,
+        next: Option<&Rc<RefCell<Node>>>,
+        value: i64,
+    ) {
+        let node = Rc::new(RefCell::new(Node {
+            value,
+            prev: Weak::new(),
+            next: None,
+        }));
+        self.link(prev, Some(&node));
+        self.link(Some(&node), next);
+        self.len += 1;
+        debug_check(self, self.len);
+    }
+
+    /* Node level versions of pop_first and append. These move the Rc around
+    instead of the value, so the allocation is reused. */
+    fn pop_first_rc(&mut self) -> Option<Rc<RefCell<Node>>> {
+        let first = self.first.clone()?;
+        self.unlink(&first);
+        Some(first)
+    }
+
+    fn pop_tail_rc(&mut self) -> Option<Rc<RefCell<Node>>> {
+        let tail = self.tail.upgrade()?;
+        self.unlink(&tail);
+        Some(tail)
+    }
+
+    fn push_front_rc(&mut self, node: Rc<RefCell<Node>>) {
+        let first = self.first.clone();
+        self.link(Some(&node), first.as_ref());
+        self.link(None, Some(&node));
+        self.len += 1;
+        debug_check(self, self.len);
+    }
+
+    fn push_back_rc(&mut self, node: Rc<RefCell<Node>>) {
+        let tail = self.tail.upgrade();
+        self.link(tail.as_ref(), Some(&node));
+        self.link(Some(&node), None);
+        self.len += 1;
+        debug_check(self, self.len);
+    }
+
+    /* Takes a node out of the chain and joins its neighbours together. The
+    node itself keeps living as long as someone holds an Rc to it. */
+    fn unlink(&mut self, node: &Rc<RefCell<Node>>) {
+        let prev = node.borrow().prev.upgrade();
+        let next = node.borrow().next.clone();
+        self.link(prev.as_ref(), next.as_ref());
+        let mut node = node.borrow_mut();
+        node.prev = Weak::new();
+        node.next = None;
+        drop(node);
+        self.len -= 1;
+        debug_check(self, self.len);
+    }
+
+    /* Makes prev and next point to each other. None on either side means
+    that end of the list, so first or tail get updated instead.
+
+    The callers must hold their own Rc to any node that is being unlinked,
+    otherwise overwriting `next` could drop it halfway through the surgery. */
+    fn link(&mut self, prev: Option<&Rc<RefCell<Node>>>, next: Option<&Rc<RefCell<Node>>>) {
+        match prev {
+            Some(p) => p.borrow_mut().next = next.cloned(),
+            None => self.first = next.cloned(),
+        }
+        let weak_prev = prev.map(Rc::downgrade).unwrap_or_default();
+        match next {
+            Some(n) => n.borrow_mut().prev = weak_prev,
+            None => self.tail = weak_prev,
+        }
+    }
 }
 
-pub struct IterList {
+/* The same counts as strong_counts(), per node. A prev whose node is gone
+(it can't be, in a healthy list) shows up as None, like no prev at all. */
+impl Topology for List {
+    fn topology(&self) -> Vec<NodeLinks> {
+        self.nodes()
+            .take(self.len)
+            .map(|rc| {
+                let counts = (Rc::strong_count(&rc) - 1, Rc::weak_count(&rc));
+                let node = rc.borrow();
+                NodeLinks {
+                    id: id_of(Rc::as_ptr(&rc)),
+                    next: node.next.as_ref().map(|next| id_of(Rc::as_ptr(next))),
+                    prev: node.prev.upgrade().map(|prev| id_of(Rc::as_ptr(&prev))),
+                    counts: Some(counts),
+                }
+            })
+            .collect()
+    }
+}
+
+/* Each node is an Rc, with the two counts in front. */
+impl HeapSize for List {
+    fn heap_size(&self) -> usize {
+        self.len * rc_size::<RefCell<Node>>()
+    }
+}
+
+/* Walks forward from first, checking each next against the prev coming
+back, and stops after len + 1 nodes so that a cycle can't keep it going
+forever. */
+impl CheckInvariants for List {
+    fn check(&self) -> Result<(), InvariantError> {
+        let first = match self.first.clone() {
+            Some(first) => first,
+            None if self.tail.upgrade().is_some() => return Err(InvariantError::TailWithoutHead),
+            None if self.len != 0 => {
+                return Err(InvariantError::WrongLen {
+                    len: self.len,
+                    counted: 0,
+                })
+            }
+            None => return Ok(()),
+        };
+        if first.borrow().prev.upgrade().is_some() {
+            return Err(InvariantError::PrevBeforeHead);
+        }
+        let mut node = first;
+        let mut counted = 1;
+        while let Some(next) = Self::next_of(&node) {
+            let back = next.borrow().prev.upgrade();
+            if !back.is_some_and(|back| Rc::ptr_eq(&back, &node)) {
+                return Err(InvariantError::BrokenPrev { index: counted - 1 });
+            }
+            if counted > self.len {
+                break;
+            }
+            node = next;
+            counted += 1;
+        }
+        if counted != self.len {
+            return Err(InvariantError::WrongLen {
+                len: self.len,
+                counted,
+            });
+        }
+        match self.tail.upgrade() {
+            Some(tail) if Rc::ptr_eq(&tail, &node) => Ok(()),
+            Some(_) => Err(InvariantError::WrongTail),
+            None => Err(InvariantError::HeadWithoutTail),
+        }
+    }
+}
+
+/* Just the values. A derived one would print every Rc and RefCell on the
+way, each node nested inside the one before. */
+impl std::fmt::Debug for List {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/* The iterator holds Rcs, but it borrows the list too, so the list can't
+change while we walk it and `remaining` stays true. It still counts down,
+so that the two ends stop where they meet. */
+pub struct IterList<'a> {
     cursor: Option<Rc<RefCell<Node>>>,
     revcursor: Option<Rc<RefCell<Node>>>,
+    remaining: usize,
+    list: PhantomData<&'a List>,
 }
 
-impl Iterator for IterList {
+impl Iterator for IterList<'_> {
     type Item = i64;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            self.cursor = None;
+            self.revcursor = None;
+            return None;
+        }
         let ret = self.cursor.as_ref().map(|c| c.borrow().value);
+        match ret {
+            Some(_) => self.remaining -= 1,
+            None => self.remaining = 0,
+        }
 
         self.cursor = match self.cursor.as_ref() {
             Some(node) => {
@@ -243,6 +1062,9 @@ impl Iterator for IterList {
                     false
                 };
                 if reached_rcursor {
+                    /* Both cursors met, so the other end is done as well.
+                    Otherwise next_back() would return this value again. */
+                    self.revcursor = None;
                     None
                 } else {
                     let bnode = node.borrow();
@@ -253,11 +1075,24 @@ impl Iterator for IterList {
         };
         ret
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 }
 
-impl DoubleEndedIterator for IterList {
+impl DoubleEndedIterator for IterList<'_> {
     fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            self.cursor = None;
+            self.revcursor = None;
+            return None;
+        }
         let ret = self.revcursor.as_ref().map(|c| c.borrow().value);
+        match ret {
+            Some(_) => self.remaining -= 1,
+            None => self.remaining = 0,
+        }
         self.revcursor = match self.revcursor.as_ref() {
             Some(node) => {
                 let reached_lcursor = if let Some(lnode) = self.cursor.clone() {
@@ -268,6 +1103,7 @@ impl DoubleEndedIterator for IterList {
                     false
                 };
                 if reached_lcursor {
+                    self.cursor = None;
                     None
                 } else {
                     let bnode = node.borrow();
@@ -280,6 +1116,9 @@ impl DoubleEndedIterator for IterList {
     }
 }
 
+/* Once both cursors are None they stay None, so it's fused already. */
+impl ExactSizeIterator for IterList<'_> {}
+impl FusedIterator for IterList<'_> {}
 
 // If drop is not implemented, does stack overflow when freeing big lists
 impl Drop for Node {
@@ -316,5 +1155,10 @@ impl Iterator for IterListMut {
 
     }
 }
+
+/* No ExactSizeIterator here: the handles don't borrow the list, so it can
+change while we walk it and any count we give could be wrong. */
+impl FusedIterator for IterListMut {}
+
 #[cfg(test)]
 mod test;
@@ -8,6 +8,21 @@ fn test_create() {
     assert_eq!(want, got);
 }
 
+/* Both builders make the same list, links both ways included. */
+#[test]
+fn test_from_vec_builders() {
+    for len in 0..6 {
+        let v: Vec<i64> = (0..len).collect();
+        for l in [List::from_vec(&v), List::from_vec_two_passes(&v)] {
+            assert_eq!(l.to_vec(), v);
+            assert_eq!(l.to_vec_rev(), v.iter().rev().copied().collect::<Vec<_>>());
+            assert_eq!(l.len(), v.len());
+            assert_eq!(l.peek_end(), v.last().copied());
+            l.assert_check();
+        }
+    }
+}
+
 #[test]
 fn test_rev_iter() {
     let v = vec![3, 4, 0, 1, 2, 5, 6, 7, 8];
@@ -98,3 +113,816 @@ fn test_insert_first() {
     let want: Vec<i64> = want.iter().rev().cloned().collect();
     assert_eq!(want, got);
 }
+
+fn check_both_ways(l: &List, want: &[i64]) {
+    assert_eq!(want.to_vec(), l.to_vec());
+    let rev: Vec<i64> = want.iter().rev().cloned().collect();
+    assert_eq!(rev, l.to_vec_rev());
+}
+
+#[test]
+fn test_swap() {
+    let mut l = List::from_vec(&[3, 4, 0, 1, 2]);
+    l.swap(1, 3);
+    check_both_ways(&l, &[3, 1, 0, 4, 2]);
+    l.swap(0, 4);
+    check_both_ways(&l, &[2, 1, 0, 4, 3]);
+    l.swap(2, 2);
+    check_both_ways(&l, &[2, 1, 0, 4, 3]);
+}
+
+#[test]
+#[should_panic]
+fn test_swap_out_of_range() {
+    let mut l = List::from_vec(&[3, 4, 0]);
+    l.swap(1, 3);
+}
+
+#[test]
+fn test_swap_nodes_adjacent() {
+    let mut l = List::from_vec(&[3, 4, 0, 1, 2]);
+    let a = l.iter_mut().nth(1).unwrap();
+    let b = l.iter_mut().nth(2).unwrap();
+    assert!(l.swap_nodes(&a, &b));
+    check_both_ways(&l, &[3, 0, 4, 1, 2]);
+    // Same nodes again, but now b goes before a.
+    assert!(l.swap_nodes(&a, &b));
+    check_both_ways(&l, &[3, 4, 0, 1, 2]);
+}
+
+#[test]
+fn test_swap_nodes_head_tail() {
+    let mut l = List::from_vec(&[3, 4, 0, 1, 2]);
+    let first = l.iter_mut().next().unwrap();
+    let last = l.iter_mut().last().unwrap();
+    assert!(l.swap_nodes(&first, &last));
+    check_both_ways(&l, &[2, 4, 0, 1, 3]);
+    assert_eq!(Some(2), l.peek_front());
+    assert_eq!(Some(3), l.peek_end());
+
+    let mut l = List::from_vec(&[3, 4]);
+    let first = l.iter_mut().next().unwrap();
+    let last = l.iter_mut().last().unwrap();
+    assert!(l.swap_nodes(&last, &first));
+    check_both_ways(&l, &[4, 3]);
+}
+
+#[test]
+fn test_swap_nodes_same() {
+    let mut l = List::from_vec(&[3, 4, 0]);
+    let a = l.iter_mut().nth(1).unwrap();
+    assert!(l.swap_nodes(&a, &a));
+    check_both_ways(&l, &[3, 4, 0]);
+}
+
+#[test]
+fn test_swap_nodes_keeps_values() {
+    let mut l = List::from_vec(&[3, 4, 0, 1, 2, 5]);
+    let a = l.iter_mut().nth(1).unwrap();
+    let b = l.iter_mut().nth(4).unwrap();
+    assert!(l.swap_nodes(&a, &b));
+    check_both_ways(&l, &[3, 2, 0, 1, 4, 5]);
+    // The handles still point to the same nodes, wherever they are now.
+    assert_eq!(4, a.borrow().value);
+    assert_eq!(2, b.borrow().value);
+}
+
+/* A handle from another list, or one that was popped, is turned away
+before anything is relinked. */
+#[test]
+fn test_swap_nodes_foreign() {
+    let mut l = List::from_vec(&[1, 2, 3]);
+    let mut other = List::from_vec(&[7, 8]);
+    let mine = l.iter_mut().nth(1).unwrap();
+    let theirs = other.iter_mut().next().unwrap();
+    assert!(l.contains_node(&mine));
+    assert!(!l.contains_node(&theirs));
+    assert!(!l.swap_nodes(&mine, &theirs));
+    assert!(!other.swap_nodes(&mine, &theirs));
+    check_both_ways(&l, &[1, 2, 3]);
+    check_both_ways(&other, &[7, 8]);
+
+    let first = l.iter_mut().next().unwrap();
+    l.pop_first();
+    assert!(!l.contains_node(&first));
+    assert!(!l.swap_nodes(&first, &mine));
+    check_both_ways(&l, &[2, 3]);
+
+    // After a concat, the other list's nodes are ours.
+    l.concat(other);
+    assert!(l.swap_nodes(&mine, &theirs));
+    check_both_ways(&l, &[7, 3, 2, 8]);
+}
+
+#[test]
+fn test_dedup() {
+    let mut l = List::from_vec(&[1, 1, 1, 2, 3, 3, 4, 3, 5, 5]);
+    l.dedup();
+    check_both_ways(&l, &[1, 2, 3, 4, 3, 5]);
+    assert_eq!(Some(1), l.peek_front());
+    assert_eq!(Some(5), l.peek_end());
+
+    let mut l = List::from_vec(&[7, 7, 7]);
+    l.dedup();
+    check_both_ways(&l, &[7]);
+
+    let mut l = List::new();
+    l.dedup();
+    check_both_ways(&l, &[]);
+}
+
+#[test]
+fn test_dedup_tail_run() {
+    let mut l = List::from_vec(&[1, 2, 3, 3, 3]);
+    l.dedup();
+    check_both_ways(&l, &[1, 2, 3]);
+    l.append(4);
+    check_both_ways(&l, &[1, 2, 3, 4]);
+}
+
+#[test]
+fn test_dedup_by() {
+    let mut l = List::from_vec(&[10, 15, 21, 29, 22, 30, 31, 45]);
+    l.dedup_by(|a, b| a / 10 == b / 10);
+    check_both_ways(&l, &[10, 21, 30, 45]);
+
+    // The first argument is the candidate for removal, like in Vec::dedup_by
+    let mut l = List::from_vec(&[1, 2, 5, 6, 7]);
+    l.dedup_by(|next, kept| next - kept == 1);
+    check_both_ways(&l, &[1, 5, 7]);
+}
+
+#[test]
+fn test_truncate() {
+    let mut l = List::from_vec(&[3, 4, 0, 1, 2]);
+    l.truncate(10);
+    check_both_ways(&l, &[3, 4, 0, 1, 2]);
+    l.truncate(5);
+    check_both_ways(&l, &[3, 4, 0, 1, 2]);
+    l.truncate(3);
+    check_both_ways(&l, &[3, 4, 0]);
+    assert_eq!(Some(0), l.peek_end());
+    l.append(7);
+    check_both_ways(&l, &[3, 4, 0, 7]);
+    l.truncate(0);
+    check_both_ways(&l, &[]);
+    assert!(l.is_empty());
+    assert_eq!(None, l.peek_end());
+}
+
+#[test]
+fn test_resize() {
+    let mut l = List::from_vec(&[3, 4]);
+    l.resize(5, 9);
+    check_both_ways(&l, &[3, 4, 9, 9, 9]);
+    assert_eq!(5, l.len());
+    l.resize(1, 0);
+    check_both_ways(&l, &[3]);
+    l.resize(0, 0);
+    check_both_ways(&l, &[]);
+    l.resize(2, 6);
+    check_both_ways(&l, &[6, 6]);
+}
+
+#[test]
+fn test_contains_position() {
+    let l = List::from_vec(&[3, 4, 0, 1, 4]);
+    assert!(l.contains(0));
+    assert!(!l.contains(9));
+    assert_eq!(Some(1), l.position(4));
+    assert_eq!(Some(3), l.position(1));
+    assert_eq!(None, l.position(9));
+    assert!(!List::new().contains(0));
+    assert_eq!(None, List::new().position(0));
+}
+
+#[test]
+fn test_find() {
+    let l = List::from_vec(&[3, 4, 0, 1, 8]);
+    assert_eq!(Some(4), l.find(|v| v % 2 == 0));
+    assert_eq!(None, l.find(|v| v > 10));
+    assert_eq!(Some(2), l.find_map(|v| if v > 3 { Some(v / 2) } else { None }));
+    assert_eq!(None::<i64>, l.find_map(|_| None));
+}
+
+#[test]
+fn test_insert_sorted() {
+    let data = vec![50, 3, 99, -7, 50, 12, 0, 100, 49, 51, -7, 98, 1];
+    let mut l = List::new();
+    let mut want: Vec<i64> = Vec::new();
+    for v in data {
+        l.insert_sorted(v);
+        want.push(v);
+        want.sort();
+        check_both_ways(&l, &want);
+        assert!(l.is_sorted());
+    }
+}
+
+#[test]
+fn test_insert_sorted_ends() {
+    let mut l = List::from_vec(&[10, 20, 30]);
+    l.insert_sorted(5);
+    l.insert_sorted(35);
+    l.insert_sorted(29);
+    l.insert_sorted(11);
+    check_both_ways(&l, &[5, 10, 11, 20, 29, 30, 35]);
+    l.insert_sorted(i64::MIN);
+    l.insert_sorted(i64::MAX);
+    check_both_ways(&l, &[i64::MIN, 5, 10, 11, 20, 29, 30, 35, i64::MAX]);
+}
+
+#[test]
+fn test_insert_sorted_as_priority_queue() {
+    let mut l = List::new();
+    for v in &[4, 1, 3, 5, 2] {
+        l.insert_sorted(*v);
+    }
+    let mut got = Vec::new();
+    while let Some(v) = l.pop_first() {
+        got.push(v);
+    }
+    assert_eq!(vec![1, 2, 3, 4, 5], got);
+}
+
+#[test]
+fn test_is_sorted() {
+    assert!(List::new().is_sorted());
+    assert!(List::from_vec(&[1]).is_sorted());
+    assert!(List::from_vec(&[1, 1, 2, 5]).is_sorted());
+    assert!(!List::from_vec(&[1, 3, 2]).is_sorted());
+}
+
+#[test]
+fn test_split_at() {
+    let data = [3, 4, 0, 1, 2];
+    for idx in 0..=data.len() {
+        let (mut left, mut right) = List::from_vec(&data).split_at(idx);
+        check_both_ways(&left, &data[..idx]);
+        check_both_ways(&right, &data[idx..]);
+        // Both halves must be fully usable on their own.
+        left.append(10);
+        right.insert_first(20);
+        right.append(30);
+        let mut want_left = data[..idx].to_vec();
+        want_left.push(10);
+        let mut want_right = vec![20];
+        want_right.extend(&data[idx..]);
+        want_right.push(30);
+        check_both_ways(&left, &want_left);
+        check_both_ways(&right, &want_right);
+    }
+}
+
+#[test]
+fn test_split_at_empty() {
+    let (left, right) = List::new().split_at(0);
+    check_both_ways(&left, &[]);
+    check_both_ways(&right, &[]);
+}
+
+#[test]
+#[should_panic]
+fn test_split_at_out_of_range() {
+    List::from_vec(&[3, 4, 0]).split_at(4);
+}
+
+#[test]
+fn test_concat_empty() {
+    let mut l = List::new();
+    l.concat(List::new());
+    check_both_ways(&l, &[]);
+    assert_eq!(None, l.peek_end());
+
+    l.concat(List::from_vec(&[3, 4]));
+    check_both_ways(&l, &[3, 4]);
+    l.concat(List::new());
+    check_both_ways(&l, &[3, 4]);
+    l.append(5);
+    check_both_ways(&l, &[3, 4, 5]);
+}
+
+#[test]
+fn test_concat_ref() {
+    let mut l = List::from_vec(&[3, 4]);
+    let other = List::from_vec(&[0, 1]);
+    l.concat_ref(&other);
+    l.concat_ref(&other);
+    check_both_ways(&l, &[3, 4, 0, 1, 0, 1]);
+    check_both_ways(&other, &[0, 1]);
+
+    let mut empty = List::new();
+    empty.concat_ref(&List::new());
+    check_both_ways(&empty, &[]);
+    empty.concat_ref(&other);
+    check_both_ways(&empty, &[0, 1]);
+}
+
+#[test]
+fn test_concat_aliased() {
+    // Two lists sharing the same nodes can't be built with the public API,
+    // so we cheat from here to check that concat doesn't create a cycle.
+    let mut l = List::from_vec(&[3, 4, 0]);
+    let alias = List {
+        first: l.first.clone(),
+        tail: l.tail.clone(),
+        len: 3,
+    };
+    l.concat(alias);
+    check_both_ways(&l, &[3, 4, 0, 3, 4, 0]);
+
+    let mut l = List::from_vec(&[3, 4, 0, 1]);
+    let partial = List {
+        first: l.node_at(2),
+        tail: l.tail.clone(),
+        len: 2,
+    };
+    l.concat(partial);
+    check_both_ways(&l, &[3, 4, 0, 1, 0, 1]);
+}
+
+fn node_ptrs(l: &mut List) -> Vec<*const RefCell<Node>> {
+    l.iter_mut().map(|n| Rc::as_ptr(&n)).collect()
+}
+
+#[test]
+fn test_zip() {
+    let a = List::from_vec(&[3, 4, 0]);
+    let b = List::from_vec(&[1, 2]);
+    let got: Vec<(i64, i64)> = a.zip(&b).collect();
+    assert_eq!(vec![(3, 1), (4, 2)], got);
+    assert_eq!(0, a.zip(&List::new()).count());
+}
+
+#[test]
+fn test_interleave() {
+    let a = List::from_vec(&[1, 3, 5]);
+    let b = List::from_vec(&[2, 4, 6, 8, 10]);
+    let l = a.interleave(b);
+    check_both_ways(&l, &[1, 2, 3, 4, 5, 6, 8, 10]);
+
+    let a = List::from_vec(&[1, 3, 5, 7]);
+    let l = a.interleave(List::from_vec(&[2]));
+    check_both_ways(&l, &[1, 2, 3, 5, 7]);
+
+    let l = List::new().interleave(List::new());
+    check_both_ways(&l, &[]);
+}
+
+#[test]
+fn test_interleave_reuses_nodes() {
+    let mut a = List::from_vec(&[1, 3]);
+    let mut b = List::from_vec(&[2, 4]);
+    let pa = node_ptrs(&mut a);
+    let pb = node_ptrs(&mut b);
+    let mut l = a.interleave(b);
+    assert_eq!(vec![pa[0], pb[0], pa[1], pb[1]], node_ptrs(&mut l));
+}
+
+#[test]
+fn test_partition() {
+    let l = List::from_vec(&[3, 4, 0, 1, 2, 5, 6, 7, 8]);
+    let (even, odd) = l.partition(|v| v % 2 == 0);
+    check_both_ways(&even, &[4, 0, 2, 6, 8]);
+    check_both_ways(&odd, &[3, 1, 5, 7]);
+
+    let (all, none) = List::from_vec(&[1, 2]).partition(|_| true);
+    check_both_ways(&all, &[1, 2]);
+    check_both_ways(&none, &[]);
+
+    let (a, b) = List::new().partition(|_| true);
+    check_both_ways(&a, &[]);
+    check_both_ways(&b, &[]);
+}
+
+#[test]
+fn test_partition_reuses_nodes() {
+    let mut l = List::from_vec(&[3, 4, 0, 1]);
+    let p = node_ptrs(&mut l);
+    let (mut big, mut small) = l.partition(|v| v > 2);
+    assert_eq!(vec![p[0], p[1]], node_ptrs(&mut big));
+    assert_eq!(vec![p[2], p[3]], node_ptrs(&mut small));
+}
+
+#[test]
+fn test_reverse_chunks() {
+    let mut l = List::from_vec(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    l.reverse_chunks(3);
+    check_both_ways(&l, &[3, 2, 1, 6, 5, 4, 7, 8]);
+
+    let mut l = List::from_vec(&[1, 2, 3, 4, 5, 6]);
+    l.reverse_chunks(2);
+    check_both_ways(&l, &[2, 1, 4, 3, 6, 5]);
+
+    let mut l = List::from_vec(&[1, 2, 3, 4, 5]);
+    l.reverse_chunks(5);
+    check_both_ways(&l, &[5, 4, 3, 2, 1]);
+
+    let mut l = List::from_vec(&[1, 2, 3]);
+    l.reverse_chunks(4);
+    check_both_ways(&l, &[1, 2, 3]);
+    l.reverse_chunks(1);
+    check_both_ways(&l, &[1, 2, 3]);
+    l.reverse_chunks(0);
+    check_both_ways(&l, &[1, 2, 3]);
+
+    let mut l = List::new();
+    l.reverse_chunks(3);
+    check_both_ways(&l, &[]);
+}
+
+#[test]
+fn test_reverse_chunks_reuses_nodes() {
+    let mut l = List::from_vec(&[1, 2, 3, 4, 5]);
+    let p = node_ptrs(&mut l);
+    l.reverse_chunks(2);
+    assert_eq!(vec![p[1], p[0], p[3], p[2], p[4]], node_ptrs(&mut l));
+    l.append(6);
+    check_both_ways(&l, &[2, 1, 4, 3, 5, 6]);
+}
+
+#[test]
+fn test_nth_from_end() {
+    let l = List::from_vec(&[3, 4, 0, 1, 2]);
+    assert_eq!(Some(2), l.nth_from_end(0));
+    assert_eq!(Some(0), l.nth_from_end(2));
+    assert_eq!(Some(3), l.nth_from_end(4));
+    assert_eq!(None, l.nth_from_end(5));
+    assert_eq!(None, List::new().nth_from_end(0));
+}
+
+#[test]
+fn test_middle() {
+    assert_eq!(None, List::new().middle());
+    assert_eq!(Some(3), List::from_vec(&[3]).middle());
+    assert_eq!(Some(4), List::from_vec(&[3, 4]).middle());
+    assert_eq!(Some(4), List::from_vec(&[3, 4, 0]).middle());
+    assert_eq!(Some(0), List::from_vec(&[3, 4, 0, 1]).middle());
+    assert_eq!(Some(0), List::from_vec(&[3, 4, 0, 1, 2]).middle());
+}
+
+#[test]
+fn test_iter_both_ends() {
+    let l = List::from_vec(&[1, 2, 3, 4, 5]);
+    let mut iter = l.iter();
+    let mut got = Vec::new();
+    loop {
+        match (iter.next(), iter.next_back()) {
+            (None, None) => break,
+            (a, b) => got.extend(a.into_iter().chain(b)),
+        }
+    }
+    // Every value exactly once, no matter where the cursors meet.
+    assert_eq!(vec![1, 5, 2, 4, 3], got);
+    assert_eq!(None, iter.next());
+    assert_eq!(None, iter.next_back());
+}
+
+#[test]
+fn test_is_palindrome() {
+    assert!(List::new().is_palindrome());
+    assert!(List::from_vec(&[7]).is_palindrome());
+    assert!(List::from_vec(&[7, 7]).is_palindrome());
+    assert!(List::from_vec(&[1, 2, 1]).is_palindrome());
+    assert!(List::from_vec(&[1, 2, 2, 1]).is_palindrome());
+    assert!(List::from_vec(&[3, 1, 4, 1, 3]).is_palindrome());
+    assert!(!List::from_vec(&[1, 2]).is_palindrome());
+    assert!(!List::from_vec(&[1, 2, 3]).is_palindrome());
+    assert!(!List::from_vec(&[1, 2, 3, 1]).is_palindrome());
+}
+
+#[test]
+fn test_merge_sorted() {
+    let a = List::from_vec(&[1, 4, 4, 9]);
+    let b = List::from_vec(&[0, 2, 4, 10, 11]);
+    let l = a.merge_sorted(b);
+    check_both_ways(&l, &[0, 1, 2, 4, 4, 4, 9, 10, 11]);
+
+    let l = List::new().merge_sorted(List::from_vec(&[1, 2]));
+    check_both_ways(&l, &[1, 2]);
+    let l = List::from_vec(&[1, 2]).merge_sorted(List::new());
+    check_both_ways(&l, &[1, 2]);
+    let l = List::new().merge_sorted(List::new());
+    check_both_ways(&l, &[]);
+}
+
+#[test]
+fn test_merge_sorted_reuses_nodes() {
+    let mut a = List::from_vec(&[1, 3]);
+    let mut b = List::from_vec(&[1, 2]);
+    let pa = node_ptrs(&mut a);
+    let pb = node_ptrs(&mut b);
+    let mut l = a.merge_sorted(b);
+    // On ties the node from self goes first.
+    assert_eq!(vec![pa[0], pb[0], pb[1], pa[1]], node_ptrs(&mut l));
+}
+
+#[test]
+fn test_sort() {
+    let data = vec![3, 4, 0, 1, 2, 5, 6, 7, 8, -1, 4, 4, 100, 0];
+    let mut l = List::from_vec(&data);
+    l.sort();
+    let mut want = data.clone();
+    want.sort();
+    check_both_ways(&l, &want);
+    assert!(l.is_sorted());
+
+    let mut l = List::new();
+    l.sort();
+    check_both_ways(&l, &[]);
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn test_shuffle() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    let data: Vec<i64> = (0..50).collect();
+    let mut a = List::from_vec(&data);
+    let mut b = List::from_vec(&data);
+    a.shuffle(&mut StdRng::seed_from_u64(1234));
+    b.shuffle(&mut StdRng::seed_from_u64(1234));
+    // Same seed, same permutation.
+    assert_eq!(a.to_vec(), b.to_vec());
+    assert_ne!(data, a.to_vec());
+    let mut got = a.to_vec();
+    check_both_ways(&a, &got);
+    got.sort();
+    assert_eq!(data, got);
+
+    let mut empty = List::new();
+    empty.shuffle(&mut StdRng::seed_from_u64(1234));
+    check_both_ways(&empty, &[]);
+}
+
+#[test]
+fn test_sum_min_max() {
+    let l = List::from_vec(&[3, -4, 0, 10, 2]);
+    assert_eq!(11, l.sum());
+    assert_eq!(Some(-4), l.min());
+    assert_eq!(Some(10), l.max());
+
+    let empty = List::new();
+    assert_eq!(0, empty.sum());
+    assert_eq!(None, empty.min());
+    assert_eq!(None, empty.max());
+}
+
+#[test]
+fn test_fold() {
+    let l = List::from_vec(&[3, 4, 0, 1]);
+    assert_eq!(0, l.fold(1, |acc, v| acc * v));
+    assert_eq!("3401", l.fold(String::new(), |acc, v| acc + &v.to_string()));
+    assert_eq!(7, List::new().fold(7, |acc, v| acc + v));
+}
+
+#[test]
+fn test_pop_nodes() {
+    let mut l = List::from_vec(&[3, 4, 0, 1]);
+    let first = l.pop_first_node().unwrap().ok().unwrap();
+    assert_eq!(3, first.value);
+    assert!(first.next.is_none());
+    let tail = l.pop_tail_node().unwrap().ok().unwrap();
+    assert_eq!(1, tail.value);
+    assert!(tail.prev.upgrade().is_none());
+    check_both_ways(&l, &[4, 0]);
+
+    l.pop_tail_node().unwrap().ok().unwrap();
+    l.pop_first_node().unwrap().ok().unwrap();
+    check_both_ways(&l, &[]);
+    assert!(l.pop_first_node().is_none());
+    assert!(l.pop_tail_node().is_none());
+}
+
+#[test]
+fn test_pop_nodes_shared() {
+    let mut l = List::from_vec(&[3, 4, 0]);
+    let handle = l.iter_mut().next().unwrap();
+    let err = l.pop_first_node().unwrap().err().unwrap();
+    assert!(Rc::ptr_eq(&handle, &err));
+    // It's unlinked anyway.
+    check_both_ways(&l, &[4, 0]);
+    drop(err);
+    // Now the handle is the only owner left.
+    let node = Rc::try_unwrap(handle).ok().unwrap().into_inner();
+    assert_eq!(3, node.value);
+    assert!(node.next.is_none());
+}
+
+#[test]
+fn test_strong_counts() {
+    let mut l = List::from_vec(&[3, 4, 0]);
+    assert_eq!(vec![(1, 1), (1, 1), (1, 1)], l.strong_counts());
+    let handle = l.iter_mut().nth(1).unwrap();
+    let weak = Rc::downgrade(&handle);
+    assert_eq!(vec![(1, 1), (2, 2), (1, 1)], l.strong_counts());
+    drop(handle);
+    drop(weak);
+    assert_eq!(vec![(1, 1), (1, 1), (1, 1)], l.strong_counts());
+    assert_eq!(Vec::<(usize, usize)>::new(), List::new().strong_counts());
+}
+
+#[test]
+fn test_assert_invariants() {
+    List::new().assert_invariants();
+    let mut l = List::from_vec(&[3, 4, 0, 1, 2]);
+    l.assert_invariants();
+    l.pop_first();
+    l.pop_tail();
+    l.insert_first(9);
+    l.append(8);
+    l.assert_invariants();
+    l.sort();
+    l.reverse_chunks(2);
+    l.dedup();
+    l.assert_invariants();
+    let (a, b) = l.split_at(2);
+    a.assert_invariants();
+    b.assert_invariants();
+}
+
+#[test]
+fn test_pop_first_resets_prev() {
+    let mut l = List::from_vec(&[3, 4, 0]);
+    let handle = l.iter_mut().next().unwrap();
+    l.pop_first();
+    // With the old node still alive, a stale prev would be upgradable.
+    l.assert_invariants();
+    drop(handle);
+}
+
+#[test]
+#[should_panic(expected = "is not the prev of its next")]
+fn test_assert_invariants_broken_prev() {
+    let l = List::from_vec(&[3, 4, 0]);
+    l.node_at(2).unwrap().borrow_mut().prev = Weak::new();
+    l.assert_invariants();
+}
+
+#[test]
+#[should_panic(expected = "tail is not the last node")]
+fn test_assert_invariants_broken_tail() {
+    let mut l = List::from_vec(&[3, 4, 0]);
+    l.tail = Rc::downgrade(&l.node_at(1).unwrap());
+    l.assert_invariants();
+}
+
+#[test]
+#[should_panic(expected = "strong references")]
+fn test_assert_invariants_shared() {
+    let mut l = List::from_vec(&[3, 4, 0]);
+    let _handle = l.iter_mut().next().unwrap();
+    l.assert_invariants();
+}
+
+#[test]
+fn test_detect_cycle() {
+    assert!(!List::new().detect_cycle());
+    assert!(!List::from_vec(&[3]).detect_cycle());
+    assert!(!List::from_vec(&[3, 4, 0, 1, 2]).detect_cycle());
+
+    for (from, to) in &[(0, 0), (1, 0), (4, 1), (4, 4), (3, 2)] {
+        let l = List::from_vec(&[3, 4, 0, 1, 2]);
+        let from_node = l.node_at(*from).unwrap();
+        from_node.borrow_mut().next = l.node_at(*to);
+        assert!(l.detect_cycle(), "{} -> {}", from, to);
+        // Break it again, or the nodes would leak.
+        from_node.borrow_mut().next = None;
+    }
+
+    /* The cycle a bad concat() would make: linked both ways, and check()
+    catches it without detect_cycle(), by the first node's prev. */
+    let l = List::from_vec(&[3, 4, 0]);
+    let (first, last) = (l.node_at(0).unwrap(), l.node_at(2).unwrap());
+    last.borrow_mut().next = Some(first.clone());
+    first.borrow_mut().prev = Rc::downgrade(&last);
+    assert!(l.detect_cycle());
+    assert_eq!(l.check(), Err(InvariantError::PrevBeforeHead));
+    last.borrow_mut().next = None;
+}
+
+#[test]
+fn test_get_set() {
+    let mut l = List::from_vec(&[3, 4, 0, 1]);
+    assert_eq!(Some(3), l.get(0));
+    assert_eq!(Some(0), l.get(2));
+    assert_eq!(Some(1), l.get(3));
+    assert_eq!(Some(4), l.get(1));
+    assert_eq!(None, l.get(4));
+    l.set(2, 9);
+    l.set(0, -1);
+    assert_eq!(Some(9), l.get(2));
+    check_both_ways(&l, &[-1, 4, 9, 1]);
+}
+
+#[test]
+#[should_panic(expected = "index out of range: the len is 0 but the index is 0")]
+fn test_set_out_of_range() {
+    let mut l = List::new();
+    l.set(0, 1);
+}
+
+/* What we get is a copy, so a handle writing the node afterwards can't
+change it under our feet. */
+#[test]
+fn test_get_then_write_through_handle() {
+    let mut l = List::from_vec(&[3, 4, 0, 1]);
+    let value = l.get(0).unwrap();
+    let handle = l.iter_mut().next().unwrap();
+    handle.borrow_mut().value = 99;
+    assert_eq!(3, value);
+    assert_eq!(Some(99), l.get(0));
+}
+
+#[test]
+#[should_panic(expected = "mutably borrowed")]
+fn test_get_while_borrowed() {
+    let mut l = List::from_vec(&[3, 4, 0, 1]);
+    let handle = l.iter_mut().nth(1).unwrap();
+    let _guard = handle.borrow_mut();
+    let _ = l.get(1);
+}
+
+#[test]
+fn test_exact_size() {
+    let l = List::from_vec(&[1, 2, 3, 4]);
+    let mut it = l.iter();
+    assert_eq!(it.len(), 4);
+    it.next();
+    it.next_back();
+    assert_eq!(it.size_hint(), (2, Some(2)));
+    it.next();
+    it.next_back();
+    assert_eq!(it.len(), 0);
+    assert_eq!(it.next(), None);
+    assert_eq!(it.next_back(), None);
+}
+
+#[test]
+fn test_len_is_tracked() {
+    let mut l = List::from_vec(&[5, 1, 4]);
+    l.append(2);
+    l.insert_first(3);
+    l.insert_sorted(0);
+    l.pop_tail();
+    l.dedup();
+    l.sort();
+    assert_eq!(l.len(), l.to_vec().len());
+    let (a, b) = l.split_at(2);
+    assert_eq!((a.len(), b.len()), (2, 3));
+    let mut l = a.interleave(b);
+    l.truncate(3);
+    l.assert_invariants();
+    assert_eq!(l.len(), 3);
+}
+
+#[test]
+fn test_check_invariants() {
+    let mut l = List::from_vec(&[3, 4, 0]);
+    assert_eq!(l.check(), Ok(()));
+
+    l.node_at(2).unwrap().borrow_mut().prev = Weak::new();
+    assert_eq!(l.check(), Err(InvariantError::BrokenPrev { index: 1 }));
+    l.node_at(2).unwrap().borrow_mut().prev = Rc::downgrade(&l.node_at(1).unwrap());
+    assert_eq!(l.check(), Ok(()));
+
+    let first = l.node_at(0).unwrap();
+    first.borrow_mut().prev = Rc::downgrade(&l.node_at(2).unwrap());
+    assert_eq!(l.check(), Err(InvariantError::PrevBeforeHead));
+    first.borrow_mut().prev = Weak::new();
+
+    l.len = 2;
+    assert_eq!(l.check(), Err(InvariantError::WrongLen { len: 2, counted: 3 }));
+    l.len = 3;
+    l.tail = Weak::new();
+    assert_eq!(l.check(), Err(InvariantError::HeadWithoutTail));
+    l.tail = Rc::downgrade(&l.node_at(1).unwrap());
+    assert_eq!(l.check(), Err(InvariantError::WrongTail));
+
+    let mut empty = List::new();
+    empty.tail = Rc::downgrade(&first);
+    assert_eq!(empty.check(), Err(InvariantError::TailWithoutHead));
+}
+
+/* A cycle doesn't send check() around forever. */
+#[test]
+fn test_check_invariants_cycle() {
+    let l = List::from_vec(&[3, 4, 0]);
+    let (first, last) = (l.node_at(0).unwrap(), l.node_at(2).unwrap());
+    last.borrow_mut().next = Some(first.clone());
+    first.borrow_mut().prev = Rc::downgrade(&last);
+    assert_eq!(l.check(), Err(InvariantError::PrevBeforeHead));
+    first.borrow_mut().prev = Weak::new();
+    assert_eq!(l.check(), Err(InvariantError::BrokenPrev { index: 2 }));
+    /* Break it again, or the nodes never get freed. */
+    last.borrow_mut().next = None;
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "broken list: node 0 is not the prev of its next")]
+fn test_debug_check_catches_corruption() {
+    let mut l = List::from_vec(&[3, 4, 0]);
+    l.node_at(1).unwrap().borrow_mut().prev = Weak::new();
+    l.append(1);
+}
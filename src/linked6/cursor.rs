@@ -0,0 +1,223 @@
+/*
+The cursor is where raw pointers pay off the most. linked4's CursorMut had
+to remember the node before the current one, because its nodes only point
+forward. Here every node knows both neighbours, so the cursor is just one
+pointer, and it can move both ways.
+
+Like std's, there's a "ghost" position between the tail and the head, where
+`current` is None. Its index is len, as if it were one past the end.
+*/
+
+use super::{Link, List, ListIn};
+use crate::arena::{Allocator, Global};
+use crate::invariants::debug_check;
+use std::mem;
+
+pub struct CursorMut<'a, T, A: Allocator = Global> {
+    list: &'a mut ListIn<T, A>,
+    current: Link<T>,
+    index: usize,
+}
+
+/* current is one of the list's own NonNull nodes, and the list is
+borrowed mutably for as long as the cursor lives. The cursor can insert
+and remove, so the allocator goes wherever it goes: it's Send when T and
+A are, and Sync when both are, like std's CursorMut<T, A>. */
+unsafe impl<'a, T: Send, A: Allocator + Send> Send for CursorMut<'a, T, A> {}
+unsafe impl<'a, T: Sync, A: Allocator + Sync> Sync for CursorMut<'a, T, A> {}
+
+impl<T, A: Allocator> ListIn<T, A> {
+    /* At the head, or at the ghost if the list is empty. */
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T, A> {
+        CursorMut {
+            current: self.head,
+            index: 0,
+            list: self,
+        }
+    }
+
+    /* At the tail, or at the ghost if the list is empty. */
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T, A> {
+        CursorMut {
+            current: self.tail,
+            index: self.len.saturating_sub(1),
+            list: self,
+        }
+    }
+}
+
+impl<'a, T, A: Allocator> CursorMut<'a, T, A> {
+    /* None on the ghost. */
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    pub fn move_next(&mut self) {
+        match self.current {
+            Some(current) => {
+                self.current = unsafe { (*current.as_ptr()).next };
+                self.index += 1;
+            }
+            None => {
+                self.current = self.list.head;
+                self.index = 0;
+            }
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        match self.current {
+            Some(current) => {
+                self.current = unsafe { (*current.as_ptr()).prev };
+                self.index = self.index.checked_sub(1).unwrap_or(self.list.len);
+            }
+            None => {
+                self.current = self.list.tail;
+                self.index = self.list.len.saturating_sub(1);
+            }
+        }
+    }
+
+    /* The reference borrows the cursor, so it can't outlive the next move. */
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.current
+            .map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        let next = match self.current {
+            Some(current) => unsafe { (*current.as_ptr()).next },
+            None => self.list.head,
+        };
+        next.map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        let prev = match self.current {
+            Some(current) => unsafe { (*current.as_ptr()).prev },
+            None => self.list.tail,
+        };
+        prev.map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    /* On the ghost, "after" is the front of the list. The cursor stays where
+    it is. */
+    pub fn insert_after(&mut self, value: T) {
+        let current = match self.current {
+            Some(current) => current,
+            None => {
+                self.list.push_front(value);
+                self.index = self.list.len;
+                return;
+            }
+        };
+        unsafe {
+            let next = (*current.as_ptr()).next;
+            let node = self.list.new_node(value, Some(current), next);
+            (*current.as_ptr()).next = Some(node);
+            match next {
+                Some(next) => (*next.as_ptr()).prev = Some(node),
+                None => self.list.tail = Some(node),
+            }
+        }
+        self.list.len += 1;
+        debug_check(self.list, self.list.len);
+    }
+
+    /* On the ghost, "before" is the back of the list. */
+    pub fn insert_before(&mut self, value: T) {
+        let current = match self.current {
+            Some(current) => current,
+            None => {
+                self.list.push_back(value);
+                self.index = self.list.len;
+                return;
+            }
+        };
+        unsafe {
+            let prev = (*current.as_ptr()).prev;
+            let node = self.list.new_node(value, prev, Some(current));
+            (*current.as_ptr()).prev = Some(node);
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = Some(node),
+                None => self.list.head = Some(node),
+            }
+        }
+        self.list.len += 1;
+        self.index += 1;
+        debug_check(self.list, self.list.len);
+    }
+
+    /* Takes the current node out and returns its value. The cursor moves to
+    the next node, or the ghost, which keeps the same index either way. */
+    pub fn remove_current(&mut self) -> Option<T> {
+        let current = self.current?;
+        let (prev, next) = unsafe { ((*current.as_ptr()).prev, (*current.as_ptr()).next) };
+        unsafe {
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = next,
+                None => self.list.head = next,
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).prev = prev,
+                None => self.list.tail = prev,
+            }
+        }
+        self.current = next;
+        self.list.len -= 1;
+        let value = unsafe { self.list.free_node(current) };
+        debug_check(self.list, self.list.len);
+        Some(value)
+    }
+}
+
+impl<'a, T, A: Allocator + Clone> CursorMut<'a, T, A> {
+    /* Everything after the current node becomes a new list, in O(1). On the
+    ghost that's the whole list. */
+    pub fn split_after(&mut self) -> ListIn<T, A> {
+        let rest = match self.current {
+            Some(current) => unsafe { self.list.split_after_node(current, self.index + 1) },
+            None => {
+                self.index = 0;
+                self.list.take_nodes()
+            }
+        };
+        debug_check(self.list, self.list.len);
+        debug_check(&rest, rest.len);
+        rest
+    }
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    /* Puts all of other right after the current node, in O(1). On the ghost
+    it goes at the front. The cursor doesn't move. Like List::append(), only
+    on the global allocator. */
+    pub fn splice_after(&mut self, mut other: List<T>) {
+        let (other_head, other_tail) = match (other.head.take(), other.tail.take()) {
+            (Some(head), Some(tail)) => (head, tail),
+            _ => return,
+        };
+        let other_len = mem::replace(&mut other.len, 0);
+        let next = match self.current {
+            Some(current) => unsafe { (*current.as_ptr()).next },
+            None => self.list.head,
+        };
+        unsafe {
+            (*other_head.as_ptr()).prev = self.current;
+            match self.current {
+                Some(current) => (*current.as_ptr()).next = Some(other_head),
+                None => self.list.head = Some(other_head),
+            }
+            (*other_tail.as_ptr()).next = next;
+            match next {
+                Some(next) => (*next.as_ptr()).prev = Some(other_tail),
+                None => self.list.tail = Some(other_tail),
+            }
+        }
+        self.list.len += other_len;
+        if self.current.is_none() {
+            self.index = self.list.len;
+        }
+        debug_check(self.list, self.list.len);
+    }
+}
@@ -0,0 +1,736 @@
+#![allow(dead_code)]
+/*
+Raw pointers all the way: a list like std's
+===========================================================================
+
+linked5 paid for every link with a reference count and a RefCell flag, and
+checked them at runtime all the time. linked4's DoublyList went halfway: the
+`next` Box owns the chain, `prev` is a raw pointer.
+
+This is the last step: nobody owns anybody. Every link is a NonNull, which is
+a raw pointer that can't be null (so Option<NonNull> is still one word, see
+linked1). Nodes are allocated one by one, like a Box would be, and freed one
+by one when they're removed. The List itself is the owner of all of them,
+even if no type says so.
+
+This is how std::collections::LinkedList is built, and it gives the same
+things: push and pop at both ends in O(1), appending a whole list in O(1),
+and a cursor that can insert, remove and split anywhere in O(1).
+
+The deal with unsafe is the same as in linked4::DoublyList:
+- head and tail are None together, or both point to live nodes.
+- A node's prev is None only for head, and next is None only for tail.
+- Following next from head reaches tail after exactly len nodes, and prev
+  walks the same nodes backwards.
+- Every node was allocated by the list's allocator, and is freed exactly
+  once, by that allocator, when it leaves the list: the value is moved out,
+  and the rest deallocated. A list with a NodePool may reuse the node
+  instead (see pool.rs).
+Every method here keeps those true before it returns, and check() (see
+invariants) walks the list to make sure.
+
+One allocation per node is the price, and unlike linked7 there's no
+with_capacity() to pay it in bulk. reserve() only pays it early: one
+allocation per node still, made before the pushes that need them. Nodes here
+change lists: append() and splice_after() take them from another list,
+split_off() hands them to a new one. If a hundred nodes came from one
+allocation, none of it could be freed until the last of them was gone, from
+whichever list it ended up in. linked7 can do it because its nodes never
+leave its Vec.
+
+What it can do is not give them back. with_pool() makes a list that keeps
+the nodes it frees in a NodePool and reuses them, which turns a queue's
+malloc and free per value into a few pointer writes. Or, on nightly with the
+allocator_api feature, the nodes can all come from an arena, and go with it:
+new_in() takes an allocator, like std's collections do (see arena).
+*/
+use crate::arena::{Allocator, Global};
+use crate::footprint::HeapSize;
+use crate::invariants::{debug_check, CheckInvariants, InvariantError};
+use crate::topology::{id_of, NodeLinks, Topology};
+use std::alloc::{handle_alloc_error, Layout};
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::marker::PhantomData;
+use std::mem::{self, size_of};
+use std::ptr::{self, NonNull};
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+struct Node<T> {
+    value: T,
+    prev: Link<T>,
+    next: Link<T>,
+}
+
+/* The list, and the allocator its nodes come from. That second parameter is
+only there for the allocator_api feature, so the list is ListIn on the
+inside and List on the outside: List<T, A> with the feature on, and List<T>,
+always on Global, without it. */
+#[cfg(feature = "allocator_api")]
+pub type List<T, A = Global> = ListIn<T, A>;
+#[cfg(not(feature = "allocator_api"))]
+pub type List<T> = ListIn<T, Global>;
+
+pub struct ListIn<T, A: Allocator = Global> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    /* We own Boxes of nodes, even if we only keep raw pointers to them. This
+    tells the compiler so: it matters for the drop check, and it makes List
+    covariant in T, like Box. */
+    _boxes: PhantomData<Box<Node<T>>>,
+    /* Boxed, so that a list without one is only a word bigger. */
+    pool: Option<Box<NodePool<T>>>,
+    /* Global is zero sized, so a list that doesn't choose pays nothing. */
+    alloc: A,
+}
+
+/* Raw pointers aren't Send or Sync, but these ones only point to nodes the
+list owns, so it's as thread-safe as T is, and as its allocator. */
+unsafe impl<T: Send, A: Allocator + Send> Send for ListIn<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for ListIn<T, A> {}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /* A list that frees its nodes into the pool, and takes them from there
+    first when it needs one. The nodes of one list can be handed to the next
+    with take_pool(). Lists made from this one by split_off() don't get a
+    pool: a pool belongs to one list. Only on the global allocator, which
+    is where the pool gets its nodes and where it frees them. */
+    pub fn with_pool(pool: NodePool<T>) -> Self {
+        let mut list = Self::new();
+        list.pool = Some(Box::new(pool));
+        list
+    }
+
+    /* So that the next `additional` pushes don't call the allocator. The
+    nodes are allocated now, one by one, into the pool: this moves the
+    allocator calls ahead, it doesn't save any. A pool whose limit is lower
+    than `additional` gets its limit raised to it. A list without a pool
+    gets one limited to `additional`, not an unlimited one that would keep
+    every node it's ever given back. */
+    pub fn reserve(&mut self, additional: usize) {
+        let pool = self
+            .pool
+            .get_or_insert_with(|| Box::new(NodePool::with_limit(additional)));
+        if pool.limit() < additional {
+            pool.set_limit(additional);
+        }
+        pool.reserve(additional.saturating_sub(pool.len()));
+    }
+
+    /* Values it can hold before a push calls the allocator. */
+    pub fn capacity(&self) -> usize {
+        self.len + self.pool.as_ref().map_or(0, |pool| pool.len())
+    }
+
+    pub fn pool(&self) -> Option<&NodePool<T>> {
+        self.pool.as_deref()
+    }
+
+    /* The list keeps its nodes and goes on without a pool. */
+    pub fn take_pool(&mut self) -> Option<NodePool<T>> {
+        self.pool.take().map(|pool| *pool)
+    }
+
+    /* Moves all of other to the end of this list, in O(1): two pointers to
+    rewrite, no matter how long the lists are. other is left empty. Only on
+    the global allocator: another list's allocator might not be one this
+    list's can free from (see arena). */
+    pub fn append(&mut self, other: &mut Self) {
+        let tail = match self.tail {
+            Some(tail) => tail,
+            None => return self.swap_nodes(other),
+        };
+        if let Some(other_head) = other.head.take() {
+            unsafe {
+                (*tail.as_ptr()).next = Some(other_head);
+                (*other_head.as_ptr()).prev = Some(tail);
+            }
+            self.tail = other.tail.take();
+            self.len += mem::replace(&mut other.len, 0);
+        }
+        debug_check(self, self.len);
+    }
+}
+
+impl<T, A: Allocator> ListIn<T, A> {
+    /* An empty list that gets its nodes from `alloc`, see arena. */
+    pub fn new_in(alloc: A) -> Self {
+        ListIn {
+            head: None,
+            tail: None,
+            len: 0,
+            _boxes: PhantomData,
+            pool: None,
+            alloc,
+        }
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.head.map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.tail.map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.tail.map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        let node = self.new_node(value, None, self.head);
+        match self.head {
+            Some(head) => unsafe { (*head.as_ptr()).prev = Some(node) },
+            None => self.tail = Some(node),
+        }
+        self.head = Some(node);
+        self.len += 1;
+        debug_check(self, self.len);
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        let node = self.new_node(value, self.tail, None);
+        match self.tail {
+            Some(tail) => unsafe { (*tail.as_ptr()).next = Some(node) },
+            None => self.head = Some(node),
+        }
+        self.tail = Some(node);
+        self.len += 1;
+        debug_check(self, self.len);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let node = self.head?;
+        self.head = unsafe { (*node.as_ptr()).next };
+        match self.head {
+            Some(head) => unsafe { (*head.as_ptr()).prev = None },
+            None => self.tail = None,
+        }
+        self.len -= 1;
+        /* The node has left the list, so we take it back. */
+        let value = unsafe { self.free_node(node) };
+        debug_check(self, self.len);
+        Some(value)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let node = self.tail?;
+        self.tail = unsafe { (*node.as_ptr()).prev };
+        match self.tail {
+            Some(tail) => unsafe { (*tail.as_ptr()).next = None },
+            None => self.head = None,
+        }
+        self.len -= 1;
+        let value = unsafe { self.free_node(node) };
+        debug_check(self, self.len);
+        Some(value)
+    }
+
+    /* Every node is made here, and every node that leaves a list for good
+    goes through free_node(), so these two are all a pool or an allocator
+    needs. */
+    fn new_node(&mut self, value: T, prev: Link<T>, next: Link<T>) -> NonNull<Node<T>> {
+        if let Some(pool) = &mut self.pool {
+            return pool.alloc(value, prev, next);
+        }
+        let layout = Layout::new::<Node<T>>();
+        let node = match self.alloc.allocate(layout) {
+            Ok(node) => node.cast::<Node<T>>(),
+            Err(_) => handle_alloc_error(layout),
+        };
+        unsafe { ptr::write(node.as_ptr(), Node { value, prev, next }) };
+        node
+    }
+
+    /* Returns the value of a node that's no longer linked from anywhere. */
+    unsafe fn free_node(&mut self, node: NonNull<Node<T>>) -> T {
+        let value = ptr::read(&(*node.as_ptr()).value);
+        match &mut self.pool {
+            Some(pool) => pool.recycle(node),
+            None => self.alloc.deallocate(node.cast(), Layout::new::<Node<T>>()),
+        }
+        value
+    }
+
+    /* mem::swap(), but each pool and allocator stays with its list. */
+    fn swap_nodes(&mut self, other: &mut Self) {
+        mem::swap(&mut self.head, &mut other.head);
+        mem::swap(&mut self.tail, &mut other.tail);
+        mem::swap(&mut self.len, &mut other.len);
+    }
+
+    /* With links both ways, reversing doesn't move anything: every node
+    swaps its prev and next, and the list swaps head and tail. */
+    pub fn reverse(&mut self) {
+        let mut cur = self.head;
+        while let Some(node) = cur {
+            unsafe {
+                let node = &mut *node.as_ptr();
+                mem::swap(&mut node.prev, &mut node.next);
+                cur = node.prev;
+            }
+        }
+        mem::swap(&mut self.head, &mut self.tail);
+        debug_check(self, self.len);
+    }
+
+    /* Every node goes to a new allocation, made in the order of the list, so
+    that walking it walks forward through memory, as far as the allocator
+    lets us. A list built while the program was allocating other things, or
+    one that had nodes inserted all over it, ends up with its nodes scattered
+    around the heap, and every step of a walk is a likely cache miss: the
+    hardware prefetcher can guess the next line of an array, but not where a
+    pointer goes before it's loaded.
+
+    The old nodes are all kept until the new ones are made, or the allocator
+    would hand the same places back to us. Holes that other things left
+    behind are fair game for it, though: compact() only helps when the
+    allocator has a long enough free run to give. The values are moved, not cloned:
+    read out of the old nodes, which are then freed without dropping them.
+    The pool, if there's one, is left out of it: its nodes are as scattered
+    as the ones we're getting rid of. */
+    pub fn compact(&mut self) {
+        let pool = self.pool.take();
+        let old = self.head.take();
+        self.tail = None;
+        self.len = 0;
+        /* self is empty now, as far as Drop knows, and the old chain is only
+        reachable from `old`. */
+        let mut cur = old;
+        while let Some(node) = cur {
+            unsafe {
+                self.push_back(ptr::read(&(*node.as_ptr()).value));
+                cur = (*node.as_ptr()).next;
+            }
+        }
+        let layout = Layout::new::<Node<T>>();
+        let mut cur = old;
+        while let Some(node) = cur {
+            unsafe {
+                cur = (*node.as_ptr()).next;
+                self.alloc.deallocate(node.cast(), layout);
+            }
+        }
+        self.pool = pool;
+    }
+
+    /* The node at that index, which must exist. */
+    fn node_at(&self, index: usize) -> NonNull<Node<T>> {
+        debug_assert!(index < self.len);
+        unsafe {
+            if index < self.len / 2 {
+                let mut cur = self.head.unwrap();
+                for _ in 0..index {
+                    cur = (*cur.as_ptr()).next.unwrap();
+                }
+                cur
+            } else {
+                let mut cur = self.tail.unwrap();
+                for _ in index + 1..self.len {
+                    cur = (*cur.as_ptr()).prev.unwrap();
+                }
+                cur
+            }
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            _list: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            _list: PhantomData,
+        }
+    }
+
+    pub fn contains(&self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|v| v == value)
+    }
+
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+/* Making a second list needs a second allocator, and a clone of ours is the
+one that can free the nodes we hand it. */
+impl<T, A: Allocator + Clone> ListIn<T, A> {
+    /* mem::take(), the same way. */
+    fn take_nodes(&mut self) -> Self {
+        let mut nodes = Self::new_in(self.alloc.clone());
+        self.swap_nodes(&mut nodes);
+        nodes
+    }
+
+    /* Splits the list in two at the index: this one keeps [0, at), and the
+    rest is returned. Finding the spot walks from whichever end is closer;
+    the cut itself is O(1). With a cursor already there, see
+    CursorMut::split_after(), there's no walk at all. */
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(
+            at <= self.len,
+            "split_off: the len is {} but the index is {}",
+            self.len,
+            at
+        );
+        if at == 0 {
+            return self.take_nodes();
+        }
+        let new_tail = self.node_at(at - 1);
+        let rest = unsafe { self.split_after_node(new_tail, at) };
+        debug_check(self, self.len);
+        debug_check(&rest, rest.len);
+        rest
+    }
+
+    /* Cuts the chain after `node`, which must be in this list with `kept`
+    nodes up to and including it. */
+    unsafe fn split_after_node(&mut self, node: NonNull<Node<T>>, kept: usize) -> Self {
+        let mut other = Self::new_in(self.alloc.clone());
+        if let Some(rest) = (*node.as_ptr()).next.take() {
+            (*rest.as_ptr()).prev = None;
+            other.head = Some(rest);
+            other.tail = self.tail;
+            other.len = self.len - kept;
+            self.tail = Some(node);
+            self.len = kept;
+        }
+        other
+    }
+}
+
+/* No recursion here: every node is freed by pop_front(), one at a time. The
+pool goes first, so that they're freed right away instead of going through
+it. */
+impl<T, A: Allocator> Drop for ListIn<T, A> {
+    fn drop(&mut self) {
+        self.pool = None;
+        self.clear();
+    }
+}
+
+/* Both ends walk towards each other, and `len` says when they've met. The
+PhantomData ties the iterator to the borrow of the list, as it only holds raw
+pointers. */
+pub struct Iter<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    _list: PhantomData<&'a Node<T>>,
+}
+
+/* It stands for a &List<T>, so it can go wherever that can. */
+unsafe impl<'a, T: Sync> Send for Iter<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for Iter<'a, T> {}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = unsafe { &*self.head?.as_ptr() };
+        self.head = node.next;
+        self.len -= 1;
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = unsafe { &*self.tail?.as_ptr() };
+        self.tail = node.prev;
+        self.len -= 1;
+        Some(&node.value)
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+/* Same thing, but handing out &mut. Each node is yielded once, from one end
+or the other, so no two of them ever alias. */
+pub struct IterMut<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    _list: PhantomData<&'a mut Node<T>>,
+}
+
+/* And this one for a &mut List<T>. */
+unsafe impl<'a, T: Send> Send for IterMut<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for IterMut<'a, T> {}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = unsafe { &mut *self.head?.as_ptr() };
+        self.head = node.next;
+        self.len -= 1;
+        Some(&mut node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = unsafe { &mut *self.tail?.as_ptr() };
+        self.tail = node.prev;
+        self.len -= 1;
+        Some(&mut node.value)
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+impl<'a, T> FusedIterator for IterMut<'a, T> {}
+
+pub struct IntoIter<T, A: Allocator = Global> {
+    list: ListIn<T, A>,
+}
+
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+impl<T, A: Allocator> FusedIterator for IntoIter<T, A> {}
+
+impl<T, A: Allocator> IntoIterator for ListIn<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a ListIn<T, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a mut ListIn<T, A> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T, A: Allocator> Extend<T> for ListIn<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T: Clone, A: Allocator + Clone> Clone for ListIn<T, A> {
+    fn clone(&self) -> Self {
+        let mut list = Self::new_in(self.alloc.clone());
+        list.extend(self.iter().cloned());
+        list
+    }
+}
+
+/* Walks next from head, and checks each step against prev. A cycle would
+make that walk endless, so it stops as soon as there are more nodes than
+len says. */
+impl<T, A: Allocator> CheckInvariants for ListIn<T, A> {
+    fn check(&self) -> Result<(), InvariantError> {
+        let (head, tail) = match (self.head, self.tail) {
+            (None, None) if self.len == 0 => return Ok(()),
+            (None, None) => {
+                return Err(InvariantError::WrongLen {
+                    len: self.len,
+                    counted: 0,
+                })
+            }
+            (Some(_), None) => return Err(InvariantError::HeadWithoutTail),
+            (None, Some(_)) => return Err(InvariantError::TailWithoutHead),
+            (Some(head), Some(tail)) => (head, tail),
+        };
+        unsafe {
+            if (*head.as_ptr()).prev.is_some() {
+                return Err(InvariantError::PrevBeforeHead);
+            }
+            if (*tail.as_ptr()).next.is_some() {
+                return Err(InvariantError::NextAfterTail);
+            }
+            let mut node = head;
+            let mut counted = 1;
+            while let Some(next) = (*node.as_ptr()).next {
+                if (*next.as_ptr()).prev != Some(node) {
+                    return Err(InvariantError::BrokenPrev { index: counted - 1 });
+                }
+                if counted == self.len {
+                    return Err(InvariantError::WrongLen {
+                        len: self.len,
+                        counted: counted + 1,
+                    });
+                }
+                node = next;
+                counted += 1;
+            }
+            if node != tail {
+                return Err(InvariantError::WrongTail);
+            }
+            if counted != self.len {
+                return Err(InvariantError::WrongLen {
+                    len: self.len,
+                    counted,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T, A: Allocator> Topology for ListIn<T, A> {
+    fn topology(&self) -> Vec<NodeLinks> {
+        let id = |node: NonNull<Node<T>>| id_of(node.as_ptr());
+        let node = |node: NonNull<Node<T>>| unsafe { &*node.as_ptr() };
+        std::iter::successors(self.head, |&cur| node(cur).next)
+            .take(self.len)
+            .map(|cur| NodeLinks {
+                id: id(cur),
+                next: node(cur).next.map(id),
+                prev: node(cur).prev.map(id),
+                counts: None,
+            })
+            .collect()
+    }
+}
+
+impl<T, A: Allocator> HeapSize for ListIn<T, A> {
+    fn heap_size(&self) -> usize {
+        let pool = self
+            .pool
+            .as_ref()
+            .map_or(0, |pool| size_of::<NodePool<T>>() + pool.heap_size());
+        self.len * size_of::<Node<T>>() + pool
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for ListIn<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, A: Allocator> PartialEq for ListIn<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for ListIn<T, A> {}
+
+mod cursor;
+pub use cursor::CursorMut;
+
+mod pool;
+pub use pool::{NodePool, PoolStats};
+
+mod sentinel;
+pub use sentinel::{Iter as SentinelIter, SentinelList};
+
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(feature = "rayon")]
+pub use par::{IntoParIter, ParIter};
+
+#[cfg(test)]
+mod test;
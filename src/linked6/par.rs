@@ -0,0 +1,135 @@
+/*
+Parallel iterators, with rayon (behind the `rayon` feature).
+
+rayon works by splitting: it cuts the work in two, hands one half to another
+thread, and keeps cutting while there are idle threads to take the halves.
+For a Vec a cut is free. For a list, finding the middle means walking to it,
+so each cut is O(n), and the whole recursion O(n log n) on top of the work
+itself. Worth it when the work per value is more than following a pointer;
+for a plain sum, a single thread wins.
+
+An owned List is cut with split_off(), the same one anybody can call: both
+halves are real Lists, each thread consumes its own. A borrowed one can't be
+cut, but its Iter can: it's just a pair of ends and a count, so the two
+halves are two Iters, and the nodes stay where they are.
+
+Either way the order is kept, collect() gives back the values in list order.
+
+The benchmarks (linked6_iter_* and linked6_par_iter_* in benches/benchmark.rs,
+with --features rayon) were run on a single core, where rayon can only lose:
+the sum of 100k values takes 3 times as long, and 200 multiplications per
+value still 1.3 times. That's what the splitting and the thread pool cost on
+their own. With more cores, the busy one is the kind that can win; the sum
+isn't.
+*/
+use super::{Iter, List};
+use rayon::iter::plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::marker::PhantomData;
+
+pub struct IntoParIter<T> {
+    list: List<T>,
+}
+
+impl<T: Send> IntoParallelIterator for List<T> {
+    type Iter = IntoParIter<T>;
+    type Item = T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        IntoParIter { list: self }
+    }
+}
+
+impl<T: Send> ParallelIterator for IntoParIter<T> {
+    type Item = T;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        bridge_unindexed(self, consumer)
+    }
+}
+
+impl<T: Send> UnindexedProducer for IntoParIter<T> {
+    type Item = T;
+
+    fn split(mut self) -> (Self, Option<Self>) {
+        if self.list.len() < 2 {
+            return (self, None);
+        }
+        let rest = self.list.split_off(self.list.len() / 2);
+        (self, Some(IntoParIter { list: rest }))
+    }
+
+    fn fold_with<F>(self, folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        folder.consume_iter(self.list)
+    }
+}
+
+pub struct ParIter<'a, T> {
+    iter: Iter<'a, T>,
+}
+
+impl<'a, T: Sync> IntoParallelIterator for &'a List<T> {
+    type Iter = ParIter<'a, T>;
+    type Item = &'a T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        ParIter { iter: self.iter() }
+    }
+}
+
+impl<'a, T: Sync> ParallelIterator for ParIter<'a, T> {
+    type Item = &'a T;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        bridge_unindexed(self, consumer)
+    }
+}
+
+impl<'a, T: Sync> UnindexedProducer for ParIter<'a, T> {
+    type Item = &'a T;
+
+    /* Walks to the middle and gives each half its own ends. The list is
+    borrowed for 'a, so no node can go away while either half is out. */
+    fn split(self) -> (Self, Option<Self>) {
+        let Iter {
+            head, tail, len, ..
+        } = self.iter;
+        if len < 2 {
+            return (self, None);
+        }
+        let mid = len / 2;
+        let mut last = head.unwrap();
+        for _ in 1..mid {
+            last = unsafe { (*last.as_ptr()).next.unwrap() };
+        }
+        let left = Iter {
+            head,
+            tail: Some(last),
+            len: mid,
+            _list: PhantomData,
+        };
+        let right = Iter {
+            head: unsafe { (*last.as_ptr()).next },
+            tail,
+            len: len - mid,
+            _list: PhantomData,
+        };
+        (ParIter { iter: left }, Some(ParIter { iter: right }))
+    }
+
+    fn fold_with<F>(self, folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        folder.consume_iter(self.iter)
+    }
+}
@@ -0,0 +1,200 @@
+/*
+Recycling nodes
+===========================================================================
+
+A list used as a queue allocates a node on every push and frees one on every
+pop, forever, even if it never holds more than a hundred values. The
+allocator is fast, but it's still a function call, a lock-free dance with
+its thread cache, and a node that lands wherever there happened to be room.
+
+NodePool keeps the nodes instead. A List made with_pool() gives every node
+it frees to its pool, and takes one back from it on every push, so once the
+list has been as long as it's going to get, it doesn't allocate any more.
+The free nodes are kept in a linked list, of course: a node whose value has
+been moved out still has its `next`, and that's all a free list needs. It
+costs nothing to keep, not even a Vec.
+
+A List<T, A> gets its nodes from its allocator A, and a node must be freed
+by the allocator that allocated it (or a clone of it), never by another one
+(see arena). The pool gets its nodes from Global and gives them back to
+Global, which is why pools only exist on List<T>, that is List<T, Global>:
+with_pool() isn't there for any other A. So the pool doesn't care where a
+node came from. A node can be pushed through a pool, appended to a list
+without one, and deallocated there, or the other way around, and it's the
+same allocator every time. The pool only ever holds nodes with no value in
+them, and deallocates them when it drops.
+
+A pool that never gives anything back is a leak with extra steps: a queue
+that once held a million values would keep a million empty nodes around
+after it shrank. with_limit() caps how many free nodes it keeps, and any
+above that go back to the allocator. new() keeps them all.
+
+On my machine the pool benchmarks (the "pool" group) come out three times
+faster for the queue and six times for the bursts, with no allocations at
+all once the pool is warm.
+*/
+
+use super::{Link, Node};
+use crate::arena::{Allocator, Global};
+use crate::footprint::HeapSize;
+use std::alloc::{handle_alloc_error, Layout};
+use std::mem::size_of;
+use std::ptr::{self, NonNull};
+
+/* What a pool has done since it was made. allocated + reused is every node
+it handed out, recycled + released is every node it was given back. */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /* Asked the allocator for a new node, because there was none free. */
+    pub allocated: usize,
+    /* Handed out a free node instead. */
+    pub reused: usize,
+    /* Kept a node it was given back. */
+    pub recycled: usize,
+    /* Gave a node back to the allocator: over the limit, in shrink_to(),
+    or when the pool dropped. */
+    pub released: usize,
+}
+
+pub struct NodePool<T> {
+    /* Chained through `next`. The values are gone, and prev is garbage. */
+    free: Link<T>,
+    len: usize,
+    limit: usize,
+    stats: PoolStats,
+}
+
+/* The pool only holds empty memory, but it's memory for T, so it goes where
+a List<T> could go. */
+unsafe impl<T: Send> Send for NodePool<T> {}
+unsafe impl<T: Sync> Sync for NodePool<T> {}
+
+impl<T> Default for NodePool<T> {
+    fn default() -> Self {
+        Self::with_limit(usize::MAX)
+    }
+}
+
+impl<T> NodePool<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limit(limit: usize) -> Self {
+        NodePool {
+            free: None,
+            len: 0,
+            limit,
+            stats: PoolStats::default(),
+        }
+    }
+
+    /* How many free nodes it holds right now. */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /* A lower limit frees the nodes above it right away. */
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.shrink_to(limit);
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        self.stats
+    }
+
+    /* Allocates free nodes up front, up to the limit, so that the first
+    `additional` pushes don't have to. */
+    pub fn reserve(&mut self, additional: usize) {
+        for _ in 0..additional.min(self.limit - self.len) {
+            let node = Self::allocate();
+            self.stats.allocated += 1;
+            unsafe { self.push_free(node) };
+        }
+    }
+
+    /* Frees nodes until it holds no more than `len`. */
+    pub fn shrink_to(&mut self, len: usize) {
+        while self.len > len {
+            let node = self.free.unwrap();
+            unsafe {
+                self.free = (*node.as_ptr()).next;
+                Self::deallocate(node);
+            }
+            self.len -= 1;
+            self.stats.released += 1;
+        }
+    }
+
+    /* A node with these contents, from the free list if there's one. */
+    pub(super) fn alloc(&mut self, value: T, prev: Link<T>, next: Link<T>) -> NonNull<Node<T>> {
+        let node = match self.free {
+            Some(node) => {
+                self.free = unsafe { (*node.as_ptr()).next };
+                self.len -= 1;
+                self.stats.reused += 1;
+                node
+            }
+            None => {
+                self.stats.allocated += 1;
+                Self::allocate()
+            }
+        };
+        /* write, not assignment: there's no old value to drop. */
+        unsafe { ptr::write(node.as_ptr(), Node { value, prev, next }) };
+        node
+    }
+
+    /* Takes a node whose value has already been moved out. It must come
+    from Global, and nothing else may point to it anymore. */
+    pub(super) unsafe fn recycle(&mut self, node: NonNull<Node<T>>) {
+        if self.len < self.limit {
+            self.stats.recycled += 1;
+            self.push_free(node);
+        } else {
+            self.stats.released += 1;
+            Self::deallocate(node);
+        }
+    }
+
+    /* Uninitialized: whoever gets it writes the node. */
+    fn allocate() -> NonNull<Node<T>> {
+        let layout = Layout::new::<Node<T>>();
+        match Global.allocate(layout) {
+            Ok(node) => node.cast(),
+            Err(_) => handle_alloc_error(layout),
+        }
+    }
+
+    unsafe fn deallocate(node: NonNull<Node<T>>) {
+        Global.deallocate(node.cast(), Layout::new::<Node<T>>());
+    }
+
+    /* Only `next` is written: the rest of the node may never have been. */
+    unsafe fn push_free(&mut self, node: NonNull<Node<T>>) {
+        ptr::addr_of_mut!((*node.as_ptr()).next).write(self.free);
+        self.free = Some(node);
+        self.len += 1;
+    }
+}
+
+impl<T> Drop for NodePool<T> {
+    fn drop(&mut self) {
+        self.shrink_to(0);
+    }
+}
+
+impl<T> HeapSize for NodePool<T> {
+    fn heap_size(&self) -> usize {
+        self.len * size_of::<Node<T>>()
+    }
+}
@@ -0,0 +1,258 @@
+/*
+The same list, with a sentinel
+===========================================================================
+
+Count the matches on Option in List: push_front has one (is there a head?),
+push_back has one, both pops have one more each, append and split have their
+own. They're all the same question, "is this the end of the list?", asked
+again and again, and each one is a branch the CPU has to guess.
+
+SentinelList removes the question. There's one extra node, allocated once in
+new(), that holds no value and closes the list into a ring: its next is the
+first node, its prev is the last one, and when the list is empty it points to
+itself. Now every node always has a real neighbour on both sides, so
+inserting is always "between prev and next" and removing is always "join
+prev and next", with no ifs at all. That's the circular module's ring, and
+SentinelList is built on it (see circular/ring.rs, rules included): every
+method here comes down to its insert_before() and unlink().
+
+The price: new() allocates even if nothing is ever pushed, and the list is a
+ring, so the iterators have to count (or watch for the sentinel) to stop.
+Whether the missing branches are worth it is what the linked6 benchmarks in
+benches/benchmark.rs are for. The circular module takes the same idea one
+step further.
+
+Spoiler: on my machine they aren't, not for speed. The mixed push/pop
+benchmark comes out about even, because those branches are very easy to
+predict and the allocation costs much more than any of them. In the queue
+benchmark the Option version even wins by a lot: the compiler can see that
+each node is freed right after it's allocated and skips the allocator, and
+it can't see through the sentinel's ring to do the same. What the sentinel
+does buy is shorter code with fewer cases to get wrong.
+*/
+use crate::circular::ring::{Node, Ring};
+use crate::footprint::HeapSize;
+use crate::topology::{NodeLinks, Topology};
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ptr::NonNull;
+
+pub struct SentinelList<T> {
+    ring: Ring<T>,
+}
+
+impl<T> Default for SentinelList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SentinelList<T> {
+    pub fn new() -> Self {
+        SentinelList { ring: Ring::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    fn first(&self) -> NonNull<Node<T>> {
+        self.ring.next_of(self.ring.sentinel())
+    }
+
+    fn last(&self) -> NonNull<Node<T>> {
+        self.ring.prev_of(self.ring.sentinel())
+    }
+
+    /* The node at `index`, or the sentinel when index == len. Walks from the
+    nearer end. */
+    fn node_at(&self, index: usize) -> NonNull<Node<T>> {
+        let len = self.len();
+        debug_assert!(index <= len);
+        let mut cur = self.ring.sentinel();
+        if index < len / 2 {
+            for _ in 0..=index {
+                cur = self.ring.next_of(cur);
+            }
+        } else {
+            for _ in index..len {
+                cur = self.ring.prev_of(cur);
+            }
+        }
+        cur
+    }
+
+    /* The sentinel's value is uninitialized, so these check for empty
+    first. That's the one branch we can't get rid of. */
+    pub fn front(&self) -> Option<&T> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(unsafe { (*self.first().as_ptr()).value.assume_init_ref() })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(unsafe { (*self.last().as_ptr()).value.assume_init_ref() })
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        self.ring.insert_before(self.first(), value);
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        self.ring.insert_before(self.ring.sentinel(), value);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(unsafe { self.ring.unlink(self.first()) })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(unsafe { self.ring.unlink(self.last()) })
+    }
+
+    /* Inserts so that the value ends up at `at`. at == len is push_back. */
+    pub fn insert(&mut self, at: usize, value: T) {
+        assert!(
+            at <= self.len(),
+            "insert: the len is {} but the index is {}",
+            self.len(),
+            at
+        );
+        self.ring.insert_before(self.node_at(at), value);
+    }
+
+    pub fn remove(&mut self, at: usize) -> Option<T> {
+        if at >= self.len() {
+            return None;
+        }
+        Some(unsafe { self.ring.unlink(self.node_at(at)) })
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            head: self.first(),
+            tail: self.last(),
+            len: self.len(),
+            _list: PhantomData,
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+/* The ring's: the sentinel isn't a node there. */
+impl<T> Topology for SentinelList<T> {
+    fn topology(&self) -> Vec<NodeLinks> {
+        self.ring.topology()
+    }
+}
+
+/* The sentinel is a whole node, even in an empty list. */
+impl<T> HeapSize for SentinelList<T> {
+    fn heap_size(&self) -> usize {
+        (self.len() + 1) * size_of::<Node<T>>()
+    }
+}
+
+/* Counting is what stops it: after len steps, head would be the sentinel. */
+pub struct Iter<'a, T> {
+    head: NonNull<Node<T>>,
+    tail: NonNull<Node<T>>,
+    len: usize,
+    _list: PhantomData<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = unsafe { &*self.head.as_ptr() };
+        self.head = node.next;
+        self.len -= 1;
+        Some(unsafe { node.value.assume_init_ref() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = unsafe { &*self.tail.as_ptr() };
+        self.tail = node.prev;
+        self.len -= 1;
+        Some(unsafe { node.value.assume_init_ref() })
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+impl<'a, T> IntoIterator for &'a SentinelList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> FromIterator<T> for SentinelList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for SentinelList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SentinelList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for SentinelList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
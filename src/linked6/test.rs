@@ -0,0 +1,560 @@
+use super::*;
+use crate::differential::{run_positional, Op};
+use std::rc::Rc;
+
+#[test]
+fn test_push_pop_both_ends() {
+    let mut list = List::new();
+    assert!(list.is_empty());
+    assert_eq!(list.pop_front(), None);
+    assert_eq!(list.pop_back(), None);
+    list.push_back(2);
+    list.push_back(3);
+    list.push_front(1);
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.front(), Some(&1));
+    assert_eq!(list.back(), Some(&3));
+    assert_eq!(list.to_vec(), vec![1, 2, 3]);
+    assert_eq!(list.pop_back(), Some(3));
+    assert_eq!(list.pop_front(), Some(1));
+    assert_eq!(list.pop_front(), Some(2));
+    assert_eq!(list.pop_back(), None);
+    assert!(list.front().is_none() && list.back().is_none());
+    list.push_front(7);
+    assert_eq!(list.back(), Some(&7));
+}
+
+#[test]
+fn test_front_back_mut() {
+    let mut list: List<i64> = (1..=3).collect();
+    *list.front_mut().unwrap() += 10;
+    *list.back_mut().unwrap() *= 10;
+    assert_eq!(list.to_vec(), vec![11, 2, 30]);
+}
+
+#[test]
+fn test_iter_both_ways() {
+    let mut list: List<i64> = (1..=5).collect();
+    assert_eq!(
+        list.iter().rev().copied().collect::<Vec<_>>(),
+        vec![5, 4, 3, 2, 1]
+    );
+    let mut iter = list.iter();
+    assert_eq!(iter.len(), 5);
+    assert_eq!(iter.next(), Some(&1));
+    assert_eq!(iter.next_back(), Some(&5));
+    assert_eq!(iter.copied().collect::<Vec<_>>(), vec![2, 3, 4]);
+
+    for v in list.iter_mut() {
+        *v *= 2;
+    }
+    let mut iter = list.iter_mut();
+    *iter.next_back().unwrap() = 0;
+    assert_eq!(list.to_vec(), vec![2, 4, 6, 8, 0]);
+    assert!(list.contains(&6));
+    assert!(!list.contains(&5));
+
+    assert_eq!(
+        list.into_iter().rev().collect::<Vec<_>>(),
+        vec![0, 8, 6, 4, 2]
+    );
+}
+
+#[test]
+fn test_append() {
+    let mut a: List<i64> = (1..=3).collect();
+    let mut b: List<i64> = (4..=6).collect();
+    a.append(&mut b);
+    assert!(b.is_empty());
+    assert_eq!(a.len(), 6);
+    assert_eq!(a.to_vec(), vec![1, 2, 3, 4, 5, 6]);
+    assert_eq!(a.iter().rev().count(), 6);
+
+    let mut empty = List::new();
+    empty.append(&mut a);
+    assert!(a.is_empty());
+    assert_eq!(empty.to_vec(), vec![1, 2, 3, 4, 5, 6]);
+    empty.append(&mut a);
+    assert_eq!(empty.len(), 6);
+    b.push_back(9);
+    assert_eq!(b.to_vec(), vec![9]);
+}
+
+#[test]
+fn test_split_off() {
+    for len in 0..8 {
+        for at in 0..=len {
+            let mut list: List<usize> = (0..len).collect();
+            let rest = list.split_off(at);
+            assert_eq!(list.to_vec(), (0..at).collect::<Vec<_>>());
+            assert_eq!(rest.to_vec(), (at..len).collect::<Vec<_>>());
+            assert_eq!(list.len() + rest.len(), len);
+            assert_eq!(list.iter().rev().count(), at);
+            assert_eq!(rest.iter().rev().count(), len - at);
+            assert_eq!(list.back().copied(), at.checked_sub(1));
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "split_off: the len is 2 but the index is 3")]
+fn test_split_off_out_of_range() {
+    let mut list: List<i64> = (0..2).collect();
+    list.split_off(3);
+}
+
+#[test]
+fn test_reverse() {
+    for len in 0..5 {
+        let mut list: List<usize> = (0..len).collect();
+        list.reverse();
+        assert_eq!(list.to_vec(), (0..len).rev().collect::<Vec<_>>());
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), (0..len).collect::<Vec<_>>());
+        list.push_back(len);
+        list.push_front(len + 1);
+        assert_eq!(list.len(), len + 2);
+        assert_eq!(list.front(), Some(&(len + 1)));
+        assert_eq!(list.back(), Some(&len));
+    }
+}
+
+#[test]
+fn test_cursor_moves() {
+    let mut list: List<i64> = (0..3).collect();
+    let mut cursor = list.cursor_front_mut();
+    assert_eq!(cursor.index(), Some(0));
+    cursor.move_prev();
+    assert_eq!(cursor.index(), None);
+    assert_eq!(cursor.peek_next(), Some(&mut 0));
+    assert_eq!(cursor.peek_prev(), Some(&mut 2));
+    cursor.move_prev();
+    assert_eq!(cursor.current(), Some(&mut 2));
+    assert_eq!(cursor.index(), Some(2));
+    cursor.move_next();
+    cursor.move_next();
+    assert_eq!(cursor.index(), Some(0));
+    *cursor.current().unwrap() = 10;
+
+    let mut cursor = list.cursor_back_mut();
+    assert_eq!(cursor.current(), Some(&mut 2));
+    assert_eq!(cursor.peek_prev(), Some(&mut 1));
+    assert_eq!(list.to_vec(), vec![10, 1, 2]);
+
+    let mut empty: List<i64> = List::new();
+    assert_eq!(empty.cursor_back_mut().index(), None);
+}
+
+#[test]
+fn test_cursor_insert_remove() {
+    let mut list: List<i64> = vec![1, 3].into_iter().collect();
+    let mut cursor = list.cursor_front_mut();
+    cursor.insert_after(2);
+    cursor.insert_before(0);
+    assert_eq!(cursor.index(), Some(1));
+    cursor.move_next();
+    cursor.move_next();
+    cursor.move_next();
+    // On the ghost: after is the front, before is the back.
+    cursor.insert_after(-1);
+    cursor.insert_before(4);
+    assert_eq!(cursor.index(), None);
+    assert_eq!(list.to_vec(), vec![-1, 0, 1, 2, 3, 4]);
+
+    let mut cursor = list.cursor_front_mut();
+    assert_eq!(cursor.remove_current(), Some(-1));
+    assert_eq!(cursor.current(), Some(&mut 0));
+    cursor.move_next();
+    cursor.move_next();
+    assert_eq!(cursor.remove_current(), Some(2));
+    assert_eq!(cursor.index(), Some(2));
+    cursor.move_next();
+    assert_eq!(cursor.remove_current(), Some(4));
+    assert_eq!(cursor.index(), None);
+    assert_eq!(cursor.remove_current(), None);
+    assert_eq!(list.to_vec(), vec![0, 1, 3]);
+    assert_eq!(
+        list.iter().rev().copied().collect::<Vec<_>>(),
+        vec![3, 1, 0]
+    );
+    assert_eq!(list.len(), 3);
+}
+
+#[test]
+fn test_cursor_split_and_splice() {
+    let mut list: List<i64> = (0..6).collect();
+    let mut cursor = list.cursor_front_mut();
+    cursor.move_next();
+    let rest = cursor.split_after();
+    assert_eq!(rest.to_vec(), vec![2, 3, 4, 5]);
+    cursor.splice_after((10..13).collect());
+    assert_eq!(cursor.current(), Some(&mut 1));
+    cursor.move_prev();
+    cursor.move_prev();
+    cursor.splice_after((20..22).collect());
+    cursor.splice_after(List::new());
+    assert_eq!(list.to_vec(), vec![20, 21, 0, 1, 10, 11, 12]);
+    assert_eq!(list.back(), Some(&12));
+    assert_eq!(list.iter().rev().count(), 7);
+
+    let mut cursor = list.cursor_back_mut();
+    cursor.splice_after(rest);
+    assert_eq!(list.len(), 11);
+    assert_eq!(list.back(), Some(&5));
+
+    let mut cursor = list.cursor_front_mut();
+    cursor.move_prev();
+    let all = cursor.split_after();
+    assert!(list.is_empty());
+    assert_eq!(all.len(), 11);
+}
+
+#[test]
+fn test_values_are_dropped() {
+    let counter = Rc::new(());
+    let mut list = List::new();
+    for _ in 0..10 {
+        list.push_back(Rc::clone(&counter));
+    }
+    let rest = list.split_off(4);
+    list.pop_back();
+    list.cursor_front_mut().remove_current();
+    assert_eq!(Rc::strong_count(&counter), 9);
+    drop(rest);
+    assert_eq!(Rc::strong_count(&counter), 3);
+    let mut iter = list.into_iter();
+    iter.next();
+    drop(iter);
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
+
+/* New nodes, all made while the old ones were still there, so none of them
+can be in an old node's place. The values move over: nothing is dropped or
+cloned, and the old nodes are freed. */
+#[test]
+fn test_compact() {
+    use crate::allocs::measure_allocs;
+    use crate::topology::Topology;
+    let counter = Rc::new(());
+    let mut list = List::new();
+    for i in 0..10 {
+        list.push_front((i, Rc::clone(&counter)));
+    }
+    let before = list.node_ids();
+    let (_, allocs) = measure_allocs(|| list.compact());
+    assert_eq!((allocs.allocs, allocs.frees), (10, 10));
+    assert_eq!(allocs.net_bytes(), 0);
+    /* On top of the old ones, all the new ones at once. */
+    assert_eq!(allocs.peak, 10 * size_of::<Node<(i32, Rc<()>)>>());
+    assert!(list.node_ids().iter().all(|id| !before.contains(id)));
+    assert_eq!(Rc::strong_count(&counter), 11);
+    let order: Vec<i32> = list.iter().map(|(i, _)| *i).collect();
+    assert_eq!(order, (0..10).rev().collect::<Vec<_>>());
+
+    list.push_back((10, Rc::clone(&counter)));
+    assert_eq!(list.pop_front().unwrap().0, 9);
+    drop(list);
+    assert_eq!(Rc::strong_count(&counter), 1);
+
+    let mut empty: List<i64> = List::new();
+    empty.compact();
+    assert!(empty.is_empty());
+}
+
+/* A queue that never holds more than 4 values allocates 4 nodes, once. */
+#[test]
+fn test_pool_reuses_nodes() {
+    use crate::allocs::measure_allocs;
+    let mut list = List::with_pool(NodePool::new());
+    let (_, allocs) = measure_allocs(|| {
+        for value in 0..100 {
+            list.push_back(value);
+            if list.len() > 4 {
+                list.pop_front();
+            }
+        }
+    });
+    assert_eq!((allocs.allocs, allocs.frees), (5, 0));
+    let stats = list.pool().unwrap().stats();
+    assert_eq!(
+        stats,
+        PoolStats {
+            allocated: 5,
+            reused: 95,
+            recycled: 96,
+            released: 0,
+        }
+    );
+    /* The fifth node, from the last pop. */
+    assert_eq!(list.pool().unwrap().len(), 1);
+    assert_eq!(list.to_vec(), vec![96, 97, 98, 99]);
+
+    /* clear() keeps them all, and dropping frees them all. */
+    list.clear();
+    assert_eq!(list.pool().unwrap().len(), 5);
+    assert_eq!(
+        list.heap_size(),
+        size_of::<NodePool<i32>>() + 5 * size_of::<Node<i32>>()
+    );
+    let (_, allocs) = measure_allocs(|| drop(list));
+    assert_eq!(allocs.frees, 6);
+}
+
+#[test]
+fn test_pool_limit() {
+    let mut pool = NodePool::with_limit(3);
+    pool.reserve(10);
+    assert_eq!(pool.len(), 3);
+    let mut list = List::with_pool(pool);
+    list.extend(0..8);
+    list.clear();
+    let stats = list.pool().unwrap().stats();
+    assert_eq!((stats.allocated, stats.reused), (8, 3));
+    assert_eq!((stats.recycled, stats.released), (3, 5));
+
+    let mut pool = list.take_pool().unwrap();
+    assert!(list.pool().is_none());
+    pool.shrink_to(1);
+    assert_eq!(pool.len(), 1);
+    assert_eq!(pool.stats().released, 7);
+}
+
+#[test]
+fn test_reserve() {
+    use crate::allocs::measure_allocs;
+    let mut list: List<i64> = (0..3).collect();
+    assert!(list.pool().is_none());
+    list.reserve(2);
+    assert_eq!(list.capacity(), 5);
+    let (_, allocs) = measure_allocs(|| list.extend(3..5));
+    assert_eq!(allocs.allocs, 0);
+    assert_eq!(list.to_vec(), vec![0, 1, 2, 3, 4]);
+
+    /* The pool only keeps what was reserved: the rest are freed. */
+    list.extend(5..10);
+    let (_, allocs) = measure_allocs(|| list.clear());
+    assert_eq!(allocs.frees, 10 - 2);
+    assert_eq!(list.pool().unwrap().len(), 2);
+    assert_eq!(list.capacity(), 2);
+
+    /* Two free nodes already, so it only allocates six more, past the
+    limit the pool had. */
+    list.reserve(8);
+    assert_eq!(list.pool().unwrap().limit(), 8);
+    assert_eq!(list.pool().unwrap().stats().allocated, 2 + 5 + 6);
+    assert_eq!(list.capacity(), 8);
+    let (_, allocs) = measure_allocs(|| list.extend(0..8));
+    assert_eq!(allocs.allocs, 0);
+
+    /* A pool someone gave it is raised too, and lowering it frees. */
+    let mut list: List<i64> = List::with_pool(NodePool::with_limit(1));
+    list.reserve(4);
+    assert_eq!(list.capacity(), 4);
+    let mut pool = list.take_pool().unwrap();
+    let (_, allocs) = measure_allocs(|| pool.set_limit(1));
+    assert_eq!((allocs.frees, pool.len()), (3, 1));
+}
+
+/* Nodes move between lists with and without pools, and end up freed by
+whichever one they're in. */
+#[test]
+fn test_pool_with_other_lists() {
+    let counter = Rc::new(());
+    let mut pooled = List::with_pool(NodePool::new());
+    let mut plain = List::new();
+    for i in 0..6 {
+        pooled.push_back((i, Rc::clone(&counter)));
+        plain.push_back((i + 10, Rc::clone(&counter)));
+    }
+    let mut rest = pooled.split_off(3);
+    assert!(rest.pool().is_none());
+    pooled.append(&mut plain);
+    plain.append(&mut rest);
+    assert!(pooled.pool().is_some() && plain.pool().is_none());
+
+    /* Four of plain's nodes end up in the pool. */
+    for _ in 0..4 {
+        pooled.pop_back();
+    }
+    assert_eq!(pooled.pool().unwrap().len(), 4);
+    pooled.extend((20..22).map(|i| (i, Rc::clone(&counter))));
+    assert_eq!(pooled.pool().unwrap().stats().reused, 2);
+    let order: Vec<i32> = pooled.iter().map(|(i, _)| *i).collect();
+    assert_eq!(order, vec![0, 1, 2, 10, 11, 20, 21]);
+
+    /* Emptying into another list keeps the pool where it was. */
+    let mut empty = List::new();
+    empty.append(&mut pooled);
+    assert!(empty.pool().is_none() && pooled.pool().is_some());
+    let mut cursor = empty.cursor_front_mut();
+    cursor.splice_after(plain);
+    let all = pooled.split_off(0);
+    assert!(all.is_empty() && pooled.pool().is_some());
+    pooled.compact();
+    assert_eq!(pooled.pool().unwrap().len(), 2);
+    drop((empty, pooled));
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
+
+#[test]
+fn test_pool_cursor() {
+    let mut list = List::with_pool(NodePool::new());
+    list.extend(vec![1, 3]);
+    let mut cursor = list.cursor_front_mut();
+    assert_eq!(cursor.remove_current(), Some(1));
+    cursor.insert_before(2);
+    cursor.insert_after(4);
+    assert_eq!(list.to_vec(), vec![2, 3, 4]);
+    let stats = list.pool().unwrap().stats();
+    assert_eq!((stats.allocated, stats.reused, stats.recycled), (3, 1, 1));
+}
+
+#[test]
+fn test_drop_long_list() {
+    let list: List<i64> = (0..1_000_000).collect();
+    drop(list);
+}
+
+#[test]
+fn test_traits() {
+    let list: List<i64> = vec![3, 8, 1].into_iter().collect();
+    let copy = list.clone();
+    assert_eq!(list, copy);
+    assert_eq!(format!("{:?}", list), "[3, 8, 1]");
+    let mut other = copy;
+    other.extend(vec![5]);
+    assert_ne!(list, other);
+    let sum: i64 = (&list).into_iter().sum();
+    assert_eq!(sum, 12);
+}
+
+#[test]
+fn test_send_sync() {
+    fn is_send_sync<T: Send + Sync>() {}
+    is_send_sync::<List<i64>>();
+    let list: List<i64> = (0..100).collect();
+    let handle = std::thread::spawn(move || list.iter().sum::<i64>());
+    assert_eq!(handle.join().unwrap(), 4950);
+}
+
+#[test]
+fn test_sentinel_list() {
+    let mut list = SentinelList::new();
+    assert_eq!(list.pop_front(), None);
+    assert_eq!(list.pop_back(), None);
+    assert_eq!(list.front(), None);
+    list.push_back(2);
+    list.push_front(1);
+    list.push_back(4);
+    list.insert(2, 3);
+    list.insert(0, 0);
+    list.insert(5, 5);
+    assert_eq!(list.to_vec(), vec![0, 1, 2, 3, 4, 5]);
+    assert_eq!(
+        list.iter().rev().copied().collect::<Vec<_>>(),
+        vec![5, 4, 3, 2, 1, 0]
+    );
+    assert_eq!(list.front(), Some(&0));
+    assert_eq!(list.back(), Some(&5));
+    assert_eq!(list.remove(3), Some(3));
+    assert_eq!(list.remove(5), None);
+    assert_eq!(list.remove(4), Some(5));
+    assert_eq!(list.pop_front(), Some(0));
+    assert_eq!(list.pop_back(), Some(4));
+    assert_eq!(format!("{:?}", list), "[1, 2]");
+    assert_eq!(list.len(), 2);
+}
+
+#[test]
+fn test_sentinel_list_matches_list() {
+    let ops = Op::random_sequence(3, 1000);
+    let list = run_positional::<List<i64>>(&ops);
+    let sentinel = run_positional::<SentinelList<i64>>(&ops);
+    assert_eq!(list.list().to_vec(), sentinel.list().to_vec());
+    let counter = Rc::new(());
+    let values: SentinelList<_> = (0..5).map(|_| Rc::clone(&counter)).collect();
+    drop(values);
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
+
+#[test]
+#[should_panic(expected = "insert: the len is 1 but the index is 2")]
+fn test_sentinel_insert_out_of_range() {
+    let mut list: SentinelList<i64> = (0..1).collect();
+    list.insert(2, 0);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_par_iter() {
+    use rayon::prelude::*;
+    let list: List<u64> = (0..10_000).collect();
+    let squares: Vec<u64> = list.par_iter().map(|v| v * v).collect();
+    assert_eq!(squares, (0..10_000).map(|v| v * v).collect::<Vec<_>>());
+    assert_eq!(list.par_iter().max(), Some(&9_999));
+    assert_eq!(list.len(), 10_000);
+
+    let odd: Vec<u64> = list.into_par_iter().filter(|v| v & 1 == 1).collect();
+    assert_eq!(odd, (0..10_000).filter(|v| v & 1 == 1).collect::<Vec<_>>());
+
+    let empty: List<u64> = List::new();
+    assert_eq!(empty.par_iter().count(), 0);
+    assert_eq!(empty.into_par_iter().count(), 0);
+}
+
+/* into_par_iter() hands every value to exactly one thread, and the ones
+nobody got to (a short-circuiting find) are still dropped. */
+#[cfg(feature = "rayon")]
+#[test]
+fn test_into_par_iter_drops_everything() {
+    use rayon::prelude::*;
+    use std::sync::Arc;
+    let counter = Arc::new(());
+    let list: List<Arc<()>> = (0..1_000).map(|_| Arc::clone(&counter)).collect();
+    let found = list.into_par_iter().find_any(|_| true);
+    assert!(found.is_some());
+    drop(found);
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+/* The corrupted lists are put back together before they're dropped: Drop
+trusts the links, and a broken list would free the wrong nodes. */
+#[test]
+fn test_check_invariants() {
+    let mut l: List<i64> = vec![3, 4, 0].into_iter().collect();
+    assert_eq!(l.check(), Ok(()));
+
+    let (middle, last) = (l.node_at(1), l.node_at(2));
+    unsafe { (*last.as_ptr()).prev = None };
+    assert_eq!(l.check(), Err(InvariantError::BrokenPrev { index: 1 }));
+    unsafe { (*last.as_ptr()).prev = Some(middle) };
+
+    l.tail = Some(middle);
+    assert_eq!(l.check(), Err(InvariantError::NextAfterTail));
+    l.tail = Some(last);
+
+    l.len = 2;
+    assert_eq!(l.check(), Err(InvariantError::WrongLen { len: 2, counted: 3 }));
+    l.len = 4;
+    assert_eq!(l.check(), Err(InvariantError::WrongLen { len: 4, counted: 3 }));
+    l.len = 3;
+
+    let head = l.head.take();
+    assert_eq!(l.check(), Err(InvariantError::TailWithoutHead));
+    l.head = head;
+    assert_eq!(l.check(), Ok(()));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_debug_check_catches_corruption() {
+    let mut l: List<i64> = vec![3, 4, 0].into_iter().collect();
+    l.len = 4;
+    let pushed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| l.push_back(1)));
+    let err = pushed.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<String>().unwrap(),
+        "broken list: len is 5 but there are 4 nodes"
+    );
+    l.len = 4;
+    assert_eq!(l.to_vec(), vec![3, 4, 0, 1]);
+}
@@ -0,0 +1,551 @@
+#![allow(dead_code)]
+/*
+Indices instead of pointers
+===========================================================================
+
+linked2 and linked3 fought the borrow checker because every link was a
+reference, and a reference borrows what it points to. linked2's LinkedList4
+tried to fix that by keeping all nodes in a Vec, and failed because the links
+were still references into that Vec.
+
+So what if the links aren't references at all? If every node lives in a Vec,
+a link can simply be the position of the next node in it: a usize. A usize
+borrows nothing. The Vec owns every node, the list owns the Vec, and the
+borrow checker only ever sees one owner. No unsafe, no Rc, no RefCell.
+
+This is how a lot of real code does graphs and lists in Rust (and how most
+ECS and arena crates work), so it's worth seeing.
+
+Removed nodes leave a hole in the Vec. Instead of shifting everything down,
+which would break every index pointing past it, the hole goes into a free
+list, and the next insert reuses it. The free list is itself a linked list
+through the holes, so it costs no extra memory.
+
+Inserting returns a Handle, the position of the new node, which can be used
+later to insert next to it, read it or remove it in O(1).
+
+A position alone isn't enough to find a node again, though: once the node is
+removed and its slot reused, the same position holds somebody else. So every
+slot also counts how many times it has been handed out, its generation, and
+a Handle carries both. When the generations don't match, the handle is stale
+and we say so with a None instead of reading the wrong node.
+*/
+use crate::footprint::HeapSize;
+use crate::invariants::{debug_check, CheckInvariants, InvariantError};
+use crate::topology::{NodeLinks, Topology};
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::mem::size_of;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: usize,
+    generation: u64,
+}
+
+struct Node<T> {
+    value: T,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+enum Entry<T> {
+    Occupied(Node<T>),
+    Free { next_free: Option<usize> },
+}
+
+/* The links between nodes stay plain indices: the list keeps those right
+itself, so they never go stale. Only the handles given out need checking. */
+struct Slot<T> {
+    generation: u64,
+    entry: Entry<T>,
+}
+
+pub struct List<T> {
+    slots: Vec<Slot<T>>,
+    head: Option<usize>,
+    tail: Option<usize>,
+    /* The first hole, if any. Each hole points to the next one. */
+    free: Option<usize>,
+    len: usize,
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        List {
+            slots: Vec::with_capacity(capacity),
+            head: None,
+            tail: None,
+            free: None,
+            len: 0,
+        }
+    }
+
+    /* Room for `additional` more nodes than there are now, counting the
+    holes there already are, so that the next that many inserts don't
+    allocate. That's what keeping the nodes in a Vec buys: one allocation
+    for all of them, instead of one per node like linked6. */
+    pub fn reserve(&mut self, additional: usize) {
+        let holes = self.slots.len() - self.len;
+        self.slots.reserve(additional.saturating_sub(holes));
+    }
+
+    /* Slots the Vec has room for, before it has to grow. */
+    pub fn capacity(&self) -> usize {
+        self.slots.capacity()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /* How many slots the Vec has, holes included. */
+    pub fn slots(&self) -> usize {
+        self.slots.len()
+    }
+
+    /* The slots stay, as holes, so that they keep their generations and the
+    handles from before the clear() don't come back to life. */
+    pub fn clear(&mut self) {
+        self.free = None;
+        for index in (0..self.slots.len()).rev() {
+            self.slots[index].entry = Entry::Free {
+                next_free: self.free,
+            };
+            self.free = Some(index);
+        }
+        self.head = None;
+        self.tail = None;
+        self.len = 0;
+        debug_check(self, self.len);
+    }
+
+    /* Moves the nodes so that the i-th one in the list is in slot i, and
+    all the holes after them. After enough inserts and removes, the order of
+    the list has nothing to do with the order of the slots, and a walk jumps
+    back and forth through the Vec like linked6 jumps through the heap. Once
+    compacted, it reads the Vec from start to end, which is what caches and
+    prefetchers are good at, and the next push_back() goes right after the
+    tail.
+
+    Every node moves, so every Handle from before is stale afterwards: all
+    the slots, holes included, get a generation newer than any of the old
+    ones had. That's also why the holes stay: a slot that was dropped and
+    pushed again would start from generation 0, and old handles to it would
+    come back. */
+    pub fn compact(&mut self) {
+        let generation = self.slots.iter().map(|slot| slot.generation + 1).max();
+        let generation = generation.unwrap_or(0);
+        let capacity = self.slots.capacity();
+        let mut old = std::mem::replace(&mut self.slots, Vec::with_capacity(capacity));
+        let mut cur = self.head;
+        while let Some(index) = cur {
+            let hole = Entry::Free { next_free: None };
+            let node = match std::mem::replace(&mut old[index].entry, hole) {
+                Entry::Occupied(node) => node,
+                Entry::Free { .. } => unreachable!("link to a free slot"),
+            };
+            cur = node.next;
+            let at = self.slots.len();
+            self.slots.push(Slot {
+                generation,
+                entry: Entry::Occupied(Node {
+                    value: node.value,
+                    prev: at.checked_sub(1),
+                    next: cur.map(|_| at + 1),
+                }),
+            });
+        }
+        for index in self.len..old.len() {
+            let next_free = Some(index + 1).filter(|&next| next < old.len());
+            self.slots.push(Slot {
+                generation,
+                entry: Entry::Free { next_free },
+            });
+        }
+        self.free = Some(self.len).filter(|&first| first < old.len());
+        self.head = if self.len > 0 { Some(0) } else { None };
+        self.tail = self.len.checked_sub(1);
+        debug_check(self, self.len);
+    }
+
+    fn node(&self, index: usize) -> &Node<T> {
+        match &self.slots[index].entry {
+            Entry::Occupied(node) => node,
+            Entry::Free { .. } => unreachable!("link to a free slot"),
+        }
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node<T> {
+        match &mut self.slots[index].entry {
+            Entry::Occupied(node) => node,
+            Entry::Free { .. } => unreachable!("link to a free slot"),
+        }
+    }
+
+    /* Puts the node in the first hole, or at the end of the Vec if there
+    are none, and returns its index. Linking it is up to the caller.
+
+    Reusing a hole bumps its generation, which is what makes every handle
+    to the node that was there before stale. */
+    fn alloc(&mut self, node: Node<T>) -> usize {
+        self.len += 1;
+        match self.free {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                self.free = match slot.entry {
+                    Entry::Free { next_free } => next_free,
+                    Entry::Occupied(_) => unreachable!("free list points to a node"),
+                };
+                slot.generation += 1;
+                slot.entry = Entry::Occupied(node);
+                index
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    entry: Entry::Occupied(node),
+                });
+                self.slots.len() - 1
+            }
+        }
+    }
+
+    /* The other way around: the slot becomes a hole at the front of the free
+    list, and we get the node that was there. */
+    fn release(&mut self, index: usize) -> Node<T> {
+        let hole = Entry::Free {
+            next_free: self.free,
+        };
+        self.free = Some(index);
+        self.len -= 1;
+        match std::mem::replace(&mut self.slots[index].entry, hole) {
+            Entry::Occupied(node) => node,
+            Entry::Free { .. } => unreachable!("released a free slot"),
+        }
+    }
+
+    /* Both neighbours get linked to the new node at `index`. */
+    fn link(&mut self, index: usize, prev: Option<usize>, next: Option<usize>) {
+        match prev {
+            Some(prev) => self.node_mut(prev).next = Some(index),
+            None => self.head = Some(index),
+        }
+        match next {
+            Some(next) => self.node_mut(next).prev = Some(index),
+            None => self.tail = Some(index),
+        }
+    }
+
+    /* And joined back together when the node at `index` goes. */
+    fn unlink(&mut self, index: usize) -> T {
+        let node = self.release(index);
+        match node.prev {
+            Some(prev) => self.node_mut(prev).next = node.next,
+            None => self.head = node.next,
+        }
+        match node.next {
+            Some(next) => self.node_mut(next).prev = node.prev,
+            None => self.tail = node.prev,
+        }
+        debug_check(self, self.len);
+        node.value
+    }
+
+    fn insert_between(&mut self, value: T, prev: Option<usize>, next: Option<usize>) -> Handle {
+        let index = self.alloc(Node { value, prev, next });
+        self.link(index, prev, next);
+        debug_check(self, self.len);
+        self.handle(index)
+    }
+
+    fn handle(&self, index: usize) -> Handle {
+        Handle {
+            index,
+            generation: self.slots[index].generation,
+        }
+    }
+
+    pub fn push_front(&mut self, value: T) -> Handle {
+        self.insert_between(value, None, self.head)
+    }
+
+    pub fn push_back(&mut self, value: T) -> Handle {
+        self.insert_between(value, self.tail, None)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let head = self.head?;
+        Some(self.unlink(head))
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail = self.tail?;
+        Some(self.unlink(tail))
+    }
+
+    pub fn front(&self) -> Option<Handle> {
+        self.head.map(|index| self.handle(index))
+    }
+
+    pub fn back(&self) -> Option<Handle> {
+        self.tail.map(|index| self.handle(index))
+    }
+
+    /* None if the handle points to a hole, or to a slot that has been
+    reused since the handle was made. */
+    fn index_of(&self, handle: Handle) -> Option<usize> {
+        let slot = self.slots.get(handle.index)?;
+        match slot.entry {
+            Entry::Occupied(_) if slot.generation == handle.generation => Some(handle.index),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        let index = self.index_of(handle)?;
+        Some(&self.node(index).value)
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        let index = self.index_of(handle)?;
+        Some(&mut self.node_mut(index).value)
+    }
+
+    pub fn next(&self, handle: Handle) -> Option<Handle> {
+        let index = self.index_of(handle)?;
+        self.node(index).next.map(|next| self.handle(next))
+    }
+
+    pub fn prev(&self, handle: Handle) -> Option<Handle> {
+        let index = self.index_of(handle)?;
+        self.node(index).prev.map(|prev| self.handle(prev))
+    }
+
+    /* O(1): the handle says where, the node says who its neighbours are.
+    None if the handle doesn't point to a node. */
+    pub fn insert_after(&mut self, handle: Handle, value: T) -> Option<Handle> {
+        let index = self.index_of(handle)?;
+        let next = self.node(index).next;
+        Some(self.insert_between(value, Some(index), next))
+    }
+
+    pub fn insert_before(&mut self, handle: Handle, value: T) -> Option<Handle> {
+        let index = self.index_of(handle)?;
+        let prev = self.node(index).prev;
+        Some(self.insert_between(value, prev, Some(index)))
+    }
+
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        let index = self.index_of(handle)?;
+        Some(self.unlink(index))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            list: self,
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+/* Two walks: the list from head, which must only step on nodes and find
+its way back through prev, and the free list, which must only step on holes.
+Between them they have to cover every slot exactly once. Links are followed
+with get(), so a bad index is reported instead of panicking, and both walks
+stop once they have seen more than there can be, in case of a cycle. */
+impl<T> CheckInvariants for List<T> {
+    fn check(&self) -> Result<(), InvariantError> {
+        let node_at = |index: usize| match self.slots.get(index).map(|slot| &slot.entry) {
+            Some(Entry::Occupied(node)) => Ok(node),
+            _ => Err(InvariantError::BrokenFreeList { slot: index }),
+        };
+        let mut last = match (self.head, self.tail) {
+            (None, None) => None,
+            (None, Some(_)) => return Err(InvariantError::TailWithoutHead),
+            (Some(_), None) => return Err(InvariantError::HeadWithoutTail),
+            (Some(head), Some(_)) => {
+                if node_at(head)?.prev.is_some() {
+                    return Err(InvariantError::PrevBeforeHead);
+                }
+                Some(head)
+            }
+        };
+        /* Every slot we step on, in either walk. */
+        let mut seen = vec![false; self.slots.len()];
+        let mut counted = last.map_or(0, |_| 1);
+        while let Some(index) = last {
+            seen[index] = true;
+            let next = match node_at(index)?.next {
+                Some(next) => next,
+                None => break,
+            };
+            if node_at(next)?.prev != Some(index) {
+                return Err(InvariantError::BrokenPrev { index: counted - 1 });
+            }
+            if counted > self.len {
+                break;
+            }
+            last = Some(next);
+            counted += 1;
+        }
+        if counted != self.len {
+            return Err(InvariantError::WrongLen {
+                len: self.len,
+                counted,
+            });
+        }
+        if last != self.tail {
+            return Err(InvariantError::WrongTail);
+        }
+
+        let mut hole = self.free;
+        while let Some(index) = hole {
+            hole = match self.slots.get(index).map(|slot| &slot.entry) {
+                Some(Entry::Free { next_free }) if !seen[index] => *next_free,
+                _ => return Err(InvariantError::BrokenFreeList { slot: index }),
+            };
+            seen[index] = true;
+        }
+        match seen.iter().position(|&seen| !seen) {
+            Some(slot) => Err(InvariantError::BrokenFreeList { slot }),
+            None => Ok(()),
+        }
+    }
+}
+
+/* The ids are the slots. A node keeps its slot for as long as it's in the
+list, and a new one gets the slot the last removed node left. */
+impl<T> Topology for List<T> {
+    fn topology(&self) -> Vec<NodeLinks> {
+        std::iter::successors(self.head, |&index| self.node(index).next)
+            .take(self.len)
+            .map(|index| NodeLinks {
+                id: index,
+                next: self.node(index).next,
+                prev: self.node(index).prev,
+                counts: None,
+            })
+            .collect()
+    }
+}
+
+/* Every slot the Vec has room for, free or not: that's what it allocated. */
+impl<T> HeapSize for List<T> {
+    fn heap_size(&self) -> usize {
+        self.slots.capacity() * size_of::<Slot<T>>()
+    }
+}
+
+/* The iterator borrows the whole list, and follows the indices through it.
+Nothing to prove to anyone: it's all plain indexing into a Vec. */
+pub struct Iter<'a, T> {
+    list: &'a List<T>,
+    front: Option<usize>,
+    back: Option<usize>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.list.node(self.front?);
+        self.front = node.next;
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.list.node(self.back?);
+        self.back = node.prev;
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut list = Self::with_capacity(iter.size_hint().0);
+        for value in iter {
+            list.push_back(value);
+        }
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+/*
+There's no Drop to write: the Vec drops every slot. And no recursion either,
+whatever the length.
+
+The catch was in the handles. With just an index, a handle to a removed node
+would happily point to whatever got its slot next: nothing unsafe, but the
+same bug as a dangling pointer, only quieter. The generation turns that into
+a None.
+
+The price is 8 bytes per slot, and that a u64 could wrap around, in theory,
+after 2^64 reuses of the same slot. Nobody will live to see it.
+*/
+
+#[cfg(test)]
+mod test;
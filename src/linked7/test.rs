@@ -0,0 +1,247 @@
+use super::*;
+use crate::differential::{run_deque, Op};
+
+#[test]
+fn test_push_pop() {
+    let mut list = List::new();
+    assert!(list.is_empty());
+    assert_eq!(list.pop_front(), None);
+    list.push_back(2);
+    list.push_back(3);
+    list.push_front(1);
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.to_vec(), vec![1, 2, 3]);
+    assert_eq!(
+        list.iter().rev().copied().collect::<Vec<_>>(),
+        vec![3, 2, 1]
+    );
+    assert_eq!(list.pop_back(), Some(3));
+    assert_eq!(list.pop_front(), Some(1));
+    assert_eq!(list.pop_front(), Some(2));
+    assert_eq!(list.pop_back(), None);
+    assert!(list.front().is_none() && list.back().is_none());
+}
+
+#[test]
+fn test_handles() {
+    let mut list = List::new();
+    let a = list.push_back("a");
+    let c = list.push_back("c");
+    let b = list.insert_after(a, "b").unwrap();
+    let d = list.insert_after(c, "d").unwrap();
+    list.insert_before(a, "0").unwrap();
+    assert_eq!(list.to_vec(), vec!["0", "a", "b", "c", "d"]);
+    assert_eq!(list.back(), Some(d));
+    assert_eq!(list.next(a), Some(b));
+    assert_eq!(list.prev(b), Some(a));
+    assert_eq!(list.get(c), Some(&"c"));
+    *list.get_mut(c).unwrap() = "C";
+
+    assert_eq!(list.remove(b), Some("b"));
+    assert_eq!(list.remove(b), None);
+    assert_eq!(list.get(b), None);
+    assert_eq!(list.insert_after(b, "x"), None);
+    assert_eq!(list.next(a), Some(c));
+    assert_eq!(list.to_vec(), vec!["0", "a", "C", "d"]);
+    assert_eq!(list.iter().rev().count(), 4);
+}
+
+#[test]
+fn test_free_list_reuses_slots() {
+    let mut list: List<i64> = (0..5).collect();
+    assert_eq!(list.slots(), 5);
+    let second = list.next(list.front().unwrap()).unwrap();
+    list.remove(second);
+    list.pop_back();
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.slots(), 5);
+    // Two holes, so the next two pushes don't grow the Vec.
+    list.push_back(10);
+    list.push_front(-1);
+    assert_eq!(list.slots(), 5);
+    list.push_back(11);
+    assert_eq!(list.slots(), 6);
+    assert_eq!(list.to_vec(), vec![-1, 0, 2, 3, 10, 11]);
+}
+
+/* Holes count as room already. */
+#[test]
+fn test_reserve() {
+    let mut list: List<i64> = List::new();
+    assert_eq!(list.capacity(), 0);
+    list.reserve(10);
+    assert!(list.capacity() >= 10);
+    let mut list: List<i64> = (0..10).collect();
+    list.pop_back();
+    list.pop_back();
+    let capacity = list.capacity();
+    list.reserve(2);
+    assert_eq!(list.capacity(), capacity);
+    list.reserve(100);
+    assert!(list.capacity() >= 108);
+}
+
+/* Popped slots are reused, so there are fewer slots than pushes. */
+#[test]
+fn test_many_operations() {
+    let ops = Op::random_sequence(7, 2000);
+    let pushes = ops
+        .iter()
+        .filter(|op| matches!(op, Op::PushFront(_) | Op::PushBack(_)))
+        .count();
+    let harness = run_deque::<List<i64>>(&ops);
+    assert!(harness.list().slots() < pushes);
+}
+
+#[test]
+fn test_traits() {
+    let list: List<i64> = vec![3, 8, 1].into_iter().collect();
+    assert_eq!(format!("{:?}", list), "[3, 8, 1]");
+    let mut other: List<i64> = vec![3, 8].into_iter().collect();
+    assert_ne!(list, other);
+    other.push_back(1);
+    assert_eq!(list, other);
+    assert_eq!((&list).into_iter().sum::<i64>(), 12);
+    other.clear();
+    assert!(other.is_empty());
+    assert_eq!(other.slots(), 3);
+    assert_eq!(other.iter().count(), 0);
+}
+
+#[test]
+fn test_stale_handles() {
+    let mut list = List::new();
+    let a = list.push_back(1);
+    let b = list.push_back(2);
+    assert_eq!(list.remove(a), Some(1));
+    // The slot of `a` is reused, but `a` must not see the new node.
+    let c = list.push_front(3);
+    assert_eq!(list.slots(), 2);
+    assert_ne!(a, c);
+    assert_eq!(list.get(a), None);
+    assert_eq!(list.get_mut(a), None);
+    assert_eq!(list.next(a), None);
+    assert_eq!(list.insert_after(a, 4), None);
+    assert_eq!(list.insert_before(a, 4), None);
+    assert_eq!(list.remove(a), None);
+    assert_eq!(list.get(c), Some(&3));
+    assert_eq!(list.to_vec(), vec![3, 2]);
+
+    // Same slot, reused many times: only the last handle works.
+    let mut handles = vec![b];
+    for i in 0..10 {
+        list.remove(*handles.last().unwrap());
+        handles.push(list.push_back(i));
+    }
+    let (last, stale) = handles.split_last().unwrap();
+    assert!(stale.iter().all(|h| list.get(*h).is_none()));
+    assert_eq!(list.get(*last), Some(&9));
+    assert_eq!(list.front(), Some(c));
+    assert_eq!(list.next(c), Some(*last));
+    assert_eq!(list.slots(), 2);
+}
+
+/* Inserted all over the place, the list order is all over the slots. */
+#[test]
+fn test_compact() {
+    use crate::topology::Topology;
+    let mut list = List::new();
+    let first = list.push_back(0);
+    let mut last = first;
+    for value in 1..10 {
+        last = list.insert_after(first, value).unwrap();
+    }
+    list.remove(last);
+    list.pop_back();
+    assert_eq!(list.to_vec(), vec![0, 8, 7, 6, 5, 4, 3, 2]);
+    assert_eq!(list.node_ids(), vec![0, 8, 7, 6, 5, 4, 3, 2]);
+
+    list.compact();
+    assert_eq!(list.to_vec(), vec![0, 8, 7, 6, 5, 4, 3, 2]);
+    assert_eq!(list.node_ids(), (0..8).collect::<Vec<_>>());
+    assert_eq!(list.slots(), 10);
+    assert_eq!(list.get(first), None);
+    assert_eq!(list.remove(last), None);
+    assert_eq!(list.get(list.back().unwrap()), Some(&2));
+
+    /* The holes are at the end, in order. */
+    list.push_back(1);
+    list.push_back(9);
+    assert_eq!(list.node_ids(), (0..10).collect::<Vec<_>>());
+    list.push_back(10);
+    assert_eq!(list.slots(), 11);
+
+    /* No slot comes back with a generation an old handle had. */
+    list.clear();
+    list.compact();
+    assert!(list.is_empty());
+    assert_eq!(list.slots(), 11);
+    list.push_back(0);
+    assert_eq!(list.get(first), None);
+
+    let mut empty: List<i64> = List::new();
+    empty.compact();
+    assert!(empty.is_empty() && empty.front().is_none());
+}
+
+#[test]
+fn test_stale_handles_after_clear() {
+    let mut list: List<i64> = (0..3).collect();
+    let first = list.front().unwrap();
+    let last = list.back().unwrap();
+    list.clear();
+    assert_eq!(list.get(first), None);
+    for v in 7..10 {
+        list.push_back(v);
+    }
+    assert_eq!(list.get(first), None);
+    assert_eq!(list.get(last), None);
+    assert_eq!(list.remove(last), None);
+    assert_eq!(list.to_vec(), vec![7, 8, 9]);
+    assert_eq!(list.slots(), 3);
+}
+
+#[test]
+fn test_check_invariants() {
+    let mut list: List<i64> = vec![3, 4, 0, 1].into_iter().collect();
+    let second = list.next(list.front().unwrap()).unwrap();
+    list.remove(second);
+    assert_eq!(list.check(), Ok(()));
+    /* Slot 1 is a hole now, and the only one on the free list. */
+    assert_eq!(list.free, Some(1));
+
+    list.free = None;
+    assert_eq!(list.check(), Err(InvariantError::BrokenFreeList { slot: 1 }));
+    list.free = Some(2);
+    assert_eq!(list.check(), Err(InvariantError::BrokenFreeList { slot: 2 }));
+    list.free = Some(1);
+
+    /* A hole pointing to itself is a cycle on the free list. */
+    list.slots[1].entry = Entry::Free { next_free: Some(1) };
+    assert_eq!(list.check(), Err(InvariantError::BrokenFreeList { slot: 1 }));
+    list.slots[1].entry = Entry::Free { next_free: None };
+
+    list.node_mut(3).prev = Some(1);
+    assert_eq!(list.check(), Err(InvariantError::BrokenPrev { index: 1 }));
+    list.node_mut(3).prev = Some(2);
+
+    list.len = 4;
+    assert_eq!(list.check(), Err(InvariantError::WrongLen { len: 4, counted: 3 }));
+    list.len = 3;
+    list.tail = Some(2);
+    assert_eq!(list.check(), Err(InvariantError::WrongTail));
+    list.tail = None;
+    assert_eq!(list.check(), Err(InvariantError::HeadWithoutTail));
+    list.tail = Some(3);
+    assert_eq!(list.check(), Ok(()));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "broken list: slot 0 is wrong on the free list")]
+fn test_debug_check_catches_corruption() {
+    let mut list: List<i64> = vec![3, 4].into_iter().collect();
+    list.pop_front();
+    list.free = None;
+    list.push_back(0);
+}
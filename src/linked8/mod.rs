@@ -0,0 +1,308 @@
+#![allow(dead_code)]
+/*
+A Box chain with a raw pointer to the end
+===========================================================================
+
+linked4 owns its nodes with Box, so it's all safe code, but adding at the
+back walks the whole list first: O(n). linked6 fixes everything with raw
+pointers everywhere, and pays for it with unsafe in every method.
+
+This is the step in between. The chain is still plain Boxes, each node owning
+the next one, exactly like linked4: dropping, iterating and popping at the
+front need no unsafe at all. The only addition is a raw pointer to the last
+node, so push_back can jump there instead of walking. With that, this is a
+proper queue: push at the back, pop at the front, both O(1). And a stack too,
+pushing and popping at the front.
+
+What it can't do is pop_back in O(1): the node before the last one doesn't
+know it is, and there's no prev pointer to ask. That's what linked6 is for.
+
+The tail pointer points into memory that a Box owns. That's allowed, but
+Rust gives Box the same "I'm the only way to reach this" promise as &mut, so
+the rules of the game are:
+- Moving a Box or writing through it makes every raw pointer taken before
+  that unusable (that's what Miri's Stacked Borrows checks). So tail is taken
+  from the Box of the last node only after that Box is in its final place,
+  and whenever the last node's Box moves, tail is taken again.
+- The head Box is a field of the List, so it moves every time the List does:
+  returned from a function, pushed into a Vec. We can't retake anything then,
+  so tail never points to the head node. It's null while len is 0 or 1, and
+  the last node is the head.
+- Otherwise, tail points to the last node, the one whose next is None.
+- The last node is only ever written to through tail, never through its Box.
+Run the tests with `cargo +nightly miri test linked8` to see that they hold.
+*/
+use crate::footprint::HeapSize;
+use crate::invariants::{debug_check, CheckInvariants, InvariantError};
+use crate::topology::{id_of, NodeLinks, Topology};
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::mem::size_of;
+use std::ptr;
+
+struct Node<T> {
+    value: T,
+    next: Option<Box<Node<T>>>,
+}
+
+pub struct List<T> {
+    head: Option<Box<Node<T>>>,
+    tail: *mut Node<T>,
+    len: usize,
+}
+
+/* Same story as in linked6: the raw pointer only points into nodes the list
+owns, so the list is as thread-safe as T. */
+unsafe impl<T: Send> Send for List<T> {}
+unsafe impl<T: Sync> Sync for List<T> {}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        List {
+            head: None,
+            tail: ptr::null_mut(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        let next = self.head.take();
+        let head = self.head.get_or_insert(Box::new(Node { value, next }));
+        /* If the list had one node, that node is now the second and last,
+        and its Box has just moved into the new one, which is its final
+        place. Before, tail was null; now it can point there. */
+        if let Some(next) = head.next.as_deref_mut() {
+            if next.next.is_none() {
+                self.tail = next;
+            }
+        }
+        self.len += 1;
+        debug_check(self, self.len);
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        let node = Box::new(Node { value, next: None });
+        let slot = if self.tail.is_null() {
+            match &mut self.head {
+                None => {
+                    /* The only node goes in head, and tail stays null. */
+                    self.head = Some(node);
+                    self.len += 1;
+                    debug_check(self, self.len);
+                    return;
+                }
+                Some(head) => &mut head.next,
+            }
+        } else {
+            /* tail points to the last node of our chain, and we have
+            &mut self, so nobody else is looking at it. */
+            unsafe { &mut (*self.tail).next }
+        };
+        /* Take the pointer only after the Box has been moved into the chain,
+        not before: moving it would have invalidated the pointer. */
+        self.tail = &mut **slot.get_or_insert(node);
+        self.len += 1;
+        debug_check(self, self.len);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let node = self.head.take()?;
+        let node = *node;
+        self.head = node.next;
+        self.len -= 1;
+        /* If the last node's Box just moved into head, tail pointed to it
+        and can't anymore. */
+        if self.len <= 1 {
+            self.tail = ptr::null_mut();
+        }
+        debug_check(self, self.len);
+        Some(node.value)
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.value)
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut node.value)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        if self.tail.is_null() {
+            self.front()
+        } else {
+            Some(unsafe { &(*self.tail).value })
+        }
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        if self.tail.is_null() {
+            self.front_mut()
+        } else {
+            Some(unsafe { &mut (*self.tail).value })
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            cursor: self.head.as_deref(),
+            remaining: self.len,
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+/* Reading through the Boxes is fine, reads don't invalidate tail. That's why
+there's iter() but no iter_mut(): it would write to the last node through
+its Box. */
+pub struct Iter<'a, T> {
+    cursor: Option<&'a Node<T>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.cursor?;
+        self.cursor = node.next.as_deref();
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/* Only reads, through the Boxes, which doesn't disturb tail. */
+impl<T> CheckInvariants for List<T> {
+    fn check(&self) -> Result<(), InvariantError> {
+        let mut node = match self.head.as_deref() {
+            None if !self.tail.is_null() => return Err(InvariantError::TailWithoutHead),
+            None if self.len == 0 => return Ok(()),
+            None => {
+                return Err(InvariantError::WrongLen {
+                    len: self.len,
+                    counted: 0,
+                })
+            }
+            Some(head) => head,
+        };
+        let head = node;
+        let mut counted = 1;
+        while let Some(next) = node.next.as_deref() {
+            node = next;
+            counted += 1;
+        }
+        /* Null when the head is the last node, never pointing to it. */
+        if ptr::eq(node, head) != self.tail.is_null() {
+            return Err(if self.tail.is_null() {
+                InvariantError::HeadWithoutTail
+            } else {
+                InvariantError::WrongTail
+            });
+        }
+        if !self.tail.is_null() && !ptr::eq(node, self.tail) {
+            return Err(InvariantError::WrongTail);
+        }
+        if counted != self.len {
+            return Err(InvariantError::WrongLen {
+                len: self.len,
+                counted,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<T> Topology for List<T> {
+    fn topology(&self) -> Vec<NodeLinks> {
+        let id = |node: &Node<T>| id_of(node);
+        std::iter::successors(self.head.as_deref(), |node| node.next.as_deref())
+            .take(self.len)
+            .map(|node| NodeLinks::singly(id(node), node.next.as_deref().map(id)))
+            .collect()
+    }
+}
+
+impl<T> HeapSize for List<T> {
+    fn heap_size(&self) -> usize {
+        self.len * size_of::<Node<T>>()
+    }
+}
+
+/* The default drop of a Box chain recurses once per node, and a long enough
+list overflows the stack. Popping in a loop drops one node at a time. */
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,149 @@
+use super::*;
+use crate::differential::{Harness, Op};
+use std::rc::Rc;
+
+/* These tests mix pushes and pops at both ends on purpose: every time the
+last node's Box moves, tail must be taken again, and Miri would flag any
+write through a stale one. They're kept small so Miri runs them quickly. */
+
+#[test]
+fn test_queue() {
+    let mut list = List::new();
+    assert!(list.is_empty());
+    assert_eq!(list.pop_front(), None);
+    assert_eq!(list.back(), None);
+    list.push_back(1);
+    list.push_back(2);
+    list.push_back(3);
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.pop_front(), Some(1));
+    list.push_back(4);
+    assert_eq!(list.to_vec(), vec![2, 3, 4]);
+    assert_eq!(list.pop_front(), Some(2));
+    assert_eq!(list.pop_front(), Some(3));
+    // The last node's Box has moved into head.
+    list.push_back(5);
+    assert_eq!(list.pop_front(), Some(4));
+    assert_eq!(list.pop_front(), Some(5));
+    assert_eq!(list.pop_front(), None);
+    assert!(list.is_empty());
+    list.push_back(6);
+    assert_eq!(list.front(), Some(&6));
+    assert_eq!(list.back(), Some(&6));
+}
+
+#[test]
+fn test_stack() {
+    let mut list = List::new();
+    list.push_front(1);
+    list.push_front(2);
+    list.push_front(3);
+    assert_eq!(list.back(), Some(&1));
+    assert_eq!(list.pop_front(), Some(3));
+    assert_eq!(list.pop_front(), Some(2));
+    list.push_back(4);
+    assert_eq!(list.to_vec(), vec![1, 4]);
+    assert_eq!(list.pop_front(), Some(1));
+    assert_eq!(list.pop_front(), Some(4));
+    assert_eq!(list.pop_front(), None);
+    // One node, whose Box moves under the new head.
+    list.push_back(1);
+    list.push_front(0);
+    list.push_back(2);
+    assert_eq!(list.to_vec(), vec![0, 1, 2]);
+}
+
+#[test]
+fn test_front_back_mut() {
+    let mut list = List::new();
+    list.push_back(1);
+    // One node: front and back are the same.
+    *list.front_mut().unwrap() += 10;
+    *list.back_mut().unwrap() += 10;
+    list.push_back(2);
+    *list.front_mut().unwrap() += 1;
+    *list.back_mut().unwrap() += 1;
+    list.push_back(3);
+    assert_eq!(list.to_vec(), vec![22, 3, 3]);
+    list.pop_front();
+    list.pop_front();
+    *list.front_mut().unwrap() = 0;
+    list.push_back(7);
+    assert_eq!(list.to_vec(), vec![0, 7]);
+}
+
+/* The harness checks the values through the Boxes after every step, and
+back() reads the last one through tail. */
+#[test]
+fn test_mixed_against_vecdeque() {
+    let mut harness: Harness<List<i64>> = Harness::new();
+    for op in Op::random_sequence(11, 300) {
+        harness.queue_op(op);
+        let list = harness.list();
+        assert_eq!(list.back(), list.iter().last());
+    }
+}
+
+#[test]
+fn test_values_are_dropped() {
+    let counter = Rc::new(());
+    let mut list = List::new();
+    for _ in 0..5 {
+        list.push_back(Rc::clone(&counter));
+        list.push_front(Rc::clone(&counter));
+    }
+    list.pop_front();
+    assert_eq!(Rc::strong_count(&counter), 10);
+    drop(list);
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_drop_long_list() {
+    let list: List<i64> = (0..1_000_000).collect();
+    drop(list);
+}
+
+#[test]
+fn test_traits() {
+    let list: List<i64> = vec![3, 8, 1].into_iter().collect();
+    assert_eq!(format!("{:?}", list), "[3, 8, 1]");
+    let mut other: List<i64> = vec![3, 8].into_iter().collect();
+    assert_ne!(list, other);
+    other.extend(vec![1]);
+    assert_eq!(list, other);
+    assert_eq!((&list).into_iter().sum::<i64>(), 12);
+    assert_eq!(list.iter().len(), 3);
+}
+
+/* Corrupted lists are fixed before they're dropped, Drop pops through them. */
+#[test]
+fn test_check_invariants() {
+    let mut list: List<i64> = vec![3, 4, 0].into_iter().collect();
+    assert_eq!(list.check(), Ok(()));
+
+    let tail = list.tail;
+    list.tail = &mut **list.head.as_mut().unwrap();
+    assert_eq!(list.check(), Err(InvariantError::WrongTail));
+    list.tail = ptr::null_mut();
+    assert_eq!(list.check(), Err(InvariantError::HeadWithoutTail));
+    list.tail = tail;
+
+    list.len = 2;
+    assert_eq!(list.check(), Err(InvariantError::WrongLen { len: 2, counted: 3 }));
+    list.len = 3;
+    assert_eq!(list.check(), Ok(()));
+
+    /* One node is the head, and tail never points there. */
+    let mut one: List<i64> = vec![5].into_iter().collect();
+    assert_eq!(one.check(), Ok(()));
+    one.tail = &mut **one.head.as_mut().unwrap();
+    assert_eq!(one.check(), Err(InvariantError::WrongTail));
+    one.tail = ptr::null_mut();
+
+    let mut empty: List<i64> = List::new();
+    empty.len = 1;
+    assert_eq!(empty.check(), Err(InvariantError::WrongLen { len: 1, counted: 0 }));
+    empty.len = 0;
+}
@@ -0,0 +1,105 @@
+/*
+The same Treiber stack, with crossbeam-epoch doing the reclamation.
+
+Each operation pins the current thread to the global epoch. A node popped
+while some thread is pinned is handed to defer_destroy(), and it's only
+freed once every thread that was pinned back then has unpinned. Unlike the
+counting Collector, one busy thread doesn't hold back everybody's garbage
+forever: the epoch keeps moving as long as each thread unpins now and then.
+*/
+use crossbeam_epoch::{self as epoch, Atomic, Owned};
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::Ordering;
+
+struct Node<T> {
+    value: ManuallyDrop<T>,
+    next: Atomic<Node<T>>,
+}
+
+pub struct EpochStack<T> {
+    head: Atomic<Node<T>>,
+}
+
+unsafe impl<T: Send> Send for EpochStack<T> {}
+unsafe impl<T: Send> Sync for EpochStack<T> {}
+
+impl<T> Default for EpochStack<T> {
+    fn default() -> Self {
+        EpochStack {
+            head: Atomic::null(),
+        }
+    }
+}
+
+impl<T> EpochStack<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let guard = epoch::pin();
+        self.head.load(Ordering::Acquire, &guard).is_null()
+    }
+
+    pub fn push(&self, value: T) {
+        let mut node = Owned::new(Node {
+            value: ManuallyDrop::new(value),
+            next: Atomic::null(),
+        });
+        let guard = epoch::pin();
+        let mut head = self.head.load(Ordering::Relaxed, &guard);
+        loop {
+            node.next.store(head, Ordering::Relaxed);
+            match self.head.compare_exchange_weak(
+                head,
+                node,
+                Ordering::Release,
+                Ordering::Relaxed,
+                &guard,
+            ) {
+                Ok(_) => return,
+                Err(err) => {
+                    head = err.current;
+                    node = err.new;
+                }
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = epoch::pin();
+        let mut head = self.head.load(Ordering::Acquire, &guard);
+        loop {
+            let node = unsafe { head.as_ref() }?;
+            let next = node.next.load(Ordering::Relaxed, &guard);
+            match self.head.compare_exchange_weak(
+                head,
+                next,
+                Ordering::Acquire,
+                Ordering::Acquire,
+                &guard,
+            ) {
+                Ok(_) => unsafe {
+                    let value = ptr::read(&*node.value);
+                    guard.defer_destroy(head);
+                    return Some(value);
+                },
+                Err(err) => head = err.current,
+            }
+        }
+    }
+}
+
+impl<T> Drop for EpochStack<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+impl<T> fmt::Debug for EpochStack<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EpochStack").finish_non_exhaustive()
+    }
+}
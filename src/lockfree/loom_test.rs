@@ -0,0 +1,151 @@
+/*
+Model checked with loom: every test runs once for each way its threads can
+interleave, up to a bound on preemptions, and for each value a weak atomic
+load is allowed to see. Run them with
+
+    RUSTFLAGS="--cfg loom" cargo test --release lockfree
+
+The usual tests in test.rs run the same code millions of times and hope to
+hit the bad interleaving; these hit all of them, just on tiny examples.
+*/
+use super::*;
+use loom::sync::Arc;
+use loom::thread;
+
+fn model<F: Fn() + Sync + Send + 'static>(f: F) {
+    let mut builder = loom::model::Builder::new();
+    builder.preemption_bound = Some(3);
+    builder.check(f);
+}
+
+fn sorted(values: impl IntoIterator<Item = Option<i32>>) -> Vec<i32> {
+    let mut values: Vec<i32> = values.into_iter().flatten().collect();
+    values.sort_unstable();
+    values
+}
+
+#[test]
+fn loom_stack_push_pop() {
+    model(|| {
+        let stack = Arc::new(Stack::new());
+        let other = Arc::clone(&stack);
+        let t = thread::spawn(move || {
+            other.push(1);
+            other.pop()
+        });
+        stack.push(2);
+        let mine = stack.pop();
+        let theirs = t.join().unwrap();
+        // Each thread pushed before popping, so neither can find it empty.
+        assert!(mine.is_some() && theirs.is_some());
+        assert_eq!(sorted(vec![mine, theirs]), vec![1, 2]);
+        assert_eq!(stack.pop(), None);
+    });
+}
+
+/* The ABA setup: one thread is about to pop the head while the other pops
+it, pops the next one and pushes a new node. If that node could reuse the
+first one's memory, the first thread's CAS would succeed with a stale next.
+The collector must keep both popped nodes alive while the first thread is
+still inside. */
+#[test]
+fn loom_stack_aba() {
+    model(|| {
+        let stack = Arc::new(Stack::new());
+        stack.push(1);
+        stack.push(2);
+        let other = Arc::clone(&stack);
+        let t = thread::spawn(move || other.pop());
+        let a = stack.pop();
+        let b = stack.pop();
+        stack.push(3);
+        let theirs = t.join().unwrap();
+        let rest = vec![stack.pop(), stack.pop()];
+        let all = sorted(vec![a, b, theirs].into_iter().chain(rest));
+        assert_eq!(all, vec![1, 2, 3]);
+    });
+}
+
+/* Every value is dropped exactly once, whoever ends up freeing its node. */
+#[test]
+fn loom_stack_values_are_dropped_once() {
+    model(|| {
+        let counter = std::sync::Arc::new(());
+        {
+            let stack = Arc::new(Stack::new());
+            stack.push(std::sync::Arc::clone(&counter));
+            stack.push(std::sync::Arc::clone(&counter));
+            let other = Arc::clone(&stack);
+            let t = thread::spawn(move || drop(other.pop()));
+            drop(stack.pop());
+            t.join().unwrap();
+        }
+        assert_eq!(std::sync::Arc::strong_count(&counter), 1);
+    });
+}
+
+#[test]
+fn loom_queue_is_fifo() {
+    model(|| {
+        let queue = Arc::new(Queue::new());
+        let producer = Arc::clone(&queue);
+        let t = thread::spawn(move || {
+            producer.push(1);
+            producer.push(2);
+        });
+        let first = queue.pop();
+        let second = queue.pop();
+        t.join().unwrap();
+        let seen: Vec<i32> = vec![first, second, queue.pop(), queue.pop()]
+            .into_iter()
+            .flatten()
+            .collect();
+        assert_eq!(seen, vec![1, 2]);
+        assert!(queue.is_empty());
+    });
+}
+
+#[test]
+fn loom_queue_two_producers() {
+    model(|| {
+        let queue = Arc::new(Queue::new());
+        let handles: Vec<_> = [1, 2]
+            .iter()
+            .map(|v| {
+                let queue = Arc::clone(&queue);
+                let v = *v;
+                thread::spawn(move || queue.push(v))
+            })
+            .collect();
+        let early = queue.pop();
+        for h in handles {
+            h.join().unwrap();
+        }
+        let rest = vec![queue.pop(), queue.pop()];
+        assert_eq!(sorted(vec![early].into_iter().chain(rest)), vec![1, 2]);
+        assert_eq!(queue.pop(), None);
+    });
+}
+
+/* recv() parks when the queue looks empty. Whatever the interleaving, both
+values arrive and the receiver isn't left asleep, and once the senders are
+gone recv() says so. The receiver gets a thread of its own, one that never
+joins anything: loom trips over a leftover unpark token on a thread that
+later calls join(). */
+#[test]
+fn loom_mpsc_recv_wakes_up() {
+    model(|| {
+        let (tx, rx) = mpsc::channel();
+        let receiver = thread::spawn(move || {
+            let got = vec![rx.recv(), rx.recv()];
+            assert_eq!(sorted(got), vec![1, 2]);
+            assert_eq!(rx.recv(), None);
+        });
+        let other = tx.clone();
+        let sender = thread::spawn(move || other.send(1));
+        tx.send(2);
+        drop(tx);
+        sender.join().unwrap();
+        receiver.join().unwrap();
+    });
+}
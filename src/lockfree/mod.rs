@@ -0,0 +1,74 @@
+#![allow(dead_code)]
+/*
+Lock-free lists: many threads, no locks
+===========================================================================
+
+Every list so far assumes one thread at a time, enforced by &mut. To share
+one between threads the easy answer is a Mutex around it, and it works, but
+every push and pop waits for whoever holds the lock. If that thread gets
+descheduled while holding it, everybody waits for the scheduler.
+
+A lock-free structure never waits for anybody. Instead of locking, every
+change is prepared on the side and then published with one atomic
+compare-and-swap (CAS): "set head to my node, but only if it's still the
+head I saw". If another thread got there first, the CAS fails, and we look
+again and retry. Some thread always makes progress, whatever the others are
+doing.
+
+The simplest one is the Treiber stack (R. K. Treiber, 1986): a singly-linked
+list where all the action happens at the head, so there's only one pointer
+to CAS.
+
+Pushing is easy. Popping is where it gets interesting, for two reasons:
+
+- To pop, we read head.next, then CAS head from the old head to that next.
+  But between reading and CAS-ing, some other thread could pop that same
+  node, pop another one, and push the first one back (or a new node that the
+  allocator put at the same address). The CAS sees the same pointer and
+  succeeds, with a next that's long gone. That's the ABA problem.
+
+- Once a node is popped, who frees it? Some other thread may have read the
+  head pointer just before and be about to read its next. Freeing it right
+  away would be a use after free. Garbage collected languages don't have
+  this problem; we do.
+
+Both problems have the same fix: never free (and so never reuse) a node
+while any thread could still be looking at it. Then the address can't come
+back while somebody holds it, and there's no ABA either.
+
+reclaim.rs does that with the simplest scheme there is, from "C++
+Concurrency in Action" (A. Williams): count the threads inside an operation,
+and only free the popped nodes once that count drops to zero. It's small and
+has no dependencies, but under constant traffic the count may never reach
+zero and the garbage piles up until it does. The real solutions are
+epoch-based reclamation and hazard pointers. With the `crossbeam` feature,
+EpochStack is the same stack on top of crossbeam-epoch, which only keeps
+garbage around for as long as some thread is actually pinned to an old
+epoch.
+
+queue.rs has the other classic, the Michael-Scott queue, which shares the
+same Collector: a FIFO with producers at one end and consumers at the other.
+And mpsc.rs has what's left when there's only one consumer: a channel where
+sending is a single atomic swap.
+*/
+
+pub mod mpsc;
+mod queue;
+mod reclaim;
+mod shim;
+mod stack;
+
+pub use queue::Queue;
+pub use stack::Stack;
+
+#[cfg(feature = "crossbeam")]
+mod epoch;
+
+#[cfg(feature = "crossbeam")]
+pub use epoch::EpochStack;
+
+#[cfg(all(test, not(loom)))]
+mod test;
+
+#[cfg(all(test, loom))]
+mod loom_test;
@@ -0,0 +1,276 @@
+/*
+A multi-producer, single-consumer queue, for channels (D. Vyukov's MPSC node
+queue).
+
+The Michael-Scott queue lets anybody pop, and that costs: consumers race on
+head, and nodes need a reclamation scheme. But a lot of queues have exactly
+one consumer: the receiving end of a channel, the mailbox of an actor, the
+run queue of one worker thread. With one consumer, everything gets simpler.
+
+- Pushing is a single atomic swap, not a CAS loop: swap head (the producers'
+  end) to the new node, which returns the previous last node, and then set
+  that one's next. No retries, no matter how many producers there are.
+
+- Popping needs no atomics on tail at all, only the consumer ever touches
+  it. It follows tail.next, and like in the Michael-Scott queue the old
+  front node is a dummy that gets freed, and the popped node becomes the new
+  dummy.
+
+- No reclamation problem: producers only ever touch the node they got back
+  from the swap, and the consumer can't get past a node whose next isn't set
+  yet, so it can't free one a producer is still about to write.
+
+The price of the single swap is a small window: between the swap and
+setting next, the new node is in the queue but not reachable from the
+front. A pop right then sees an empty queue even though a push has "happened".
+try_recv() just says empty; recv() waits for the producer to finish, which
+takes a few instructions.
+
+Vyukov's original is intrusive, the link lives inside the user's node, like
+in our intrusive module. This is the variant where push allocates the node,
+the one std itself used inside std::sync::mpsc for years: being intrusive
+across threads would need the nodes pinned and shared for as long as the
+queue lives, which is a whole chapter of its own.
+
+recv() blocks by parking the thread. The receiver says it's about to sleep,
+checks the queue once more (a push may have slipped in) and parks. Every
+push checks the flag after linking its node, and unparks the receiver if it
+was set. A park token from an unpark that comes before the park isn't lost,
+so there's no window for a missed wake up either.
+
+Against std::sync::mpsc (lockfree_mpsc_4x10k and std_mpsc_4x10k in
+benches/benchmark.rs) this loses, by 1.5 to 2 times. Since Rust 1.67 std's
+channel is crossbeam's, which allocates one block for every 31 messages
+instead of a node per message (the unrolled list trick again), while every
+send here pays an allocation and a SeqCst fence. The single swap is still
+the heart of most fast MPSC queues; it's everything around it that counts.
+*/
+use super::shim::thread::{self, Thread};
+use super::shim::{fence, Arc, AtomicBool, AtomicPtr, AtomicUsize, Mutex};
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::ptr;
+use std::sync::atomic::Ordering;
+
+struct Node<T> {
+    value: MaybeUninit<T>,
+    next: AtomicPtr<Node<T>>,
+}
+
+impl<T> Node<T> {
+    fn alloc(value: MaybeUninit<T>) -> *mut Node<T> {
+        Box::into_raw(Box::new(Node {
+            value,
+            next: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+struct Inner<T> {
+    /* The last node pushed. Producers swap it. */
+    head: AtomicPtr<Node<T>>,
+    /* The dummy node in front. Only the Receiver touches it. */
+    tail: UnsafeCell<*mut Node<T>>,
+    senders: AtomicUsize,
+    sleeping: AtomicBool,
+    /* Set by the receiver before it goes to sleep. Only the sender that
+    wakes it locks this, so pushes don't contend on it. */
+    receiver: Mutex<Option<Thread>>,
+}
+
+unsafe impl<T: Send> Send for Inner<T> {}
+unsafe impl<T: Send> Sync for Inner<T> {}
+
+impl<T> Inner<T> {
+    fn push(&self, value: T) {
+        let node = Node::alloc(MaybeUninit::new(value));
+        let prev = self.head.swap(node, Ordering::AcqRel);
+        unsafe { (*prev).next.store(node, Ordering::Release) };
+        /* Pairs with the fence in recv(): either the receiver sees our node,
+        or we see its flag. */
+        fence(Ordering::SeqCst);
+        self.wake();
+    }
+
+    fn wake(&self) {
+        /* A plain load first: writing on every push would bounce the flag's
+        cache line between every producer, even with nobody asleep. And a
+        compare_exchange, not a swap, so that only one of several producers
+        unparks, and the others don't write at all. */
+        if self.sleeping.load(Ordering::Relaxed)
+            && self
+                .sleeping
+                .compare_exchange(true, false, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+        {
+            let receiver = self.receiver.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(receiver) = &*receiver {
+                receiver.unpark();
+            }
+        }
+    }
+
+    /* Only the Receiver calls this. */
+    unsafe fn pop(&self) -> Option<T> {
+        let tail = *self.tail.get();
+        let next = (*tail).next.load(Ordering::Acquire);
+        if next.is_null() {
+            return None;
+        }
+        *self.tail.get() = next;
+        drop(Box::from_raw(tail));
+        Some(ptr::read((*next).value.as_ptr()))
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        let dummy = unsafe { Box::from_raw(*self.tail.get_mut()) };
+        let mut cur = dummy.next.load(Ordering::Relaxed);
+        while !cur.is_null() {
+            let mut node = unsafe { Box::from_raw(cur) };
+            unsafe { ptr::drop_in_place(node.value.as_mut_ptr()) };
+            cur = node.next.load(Ordering::Relaxed);
+        }
+    }
+}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let dummy = Node::alloc(MaybeUninit::uninit());
+    let inner = Arc::new(Inner {
+        head: AtomicPtr::new(dummy),
+        tail: UnsafeCell::new(dummy),
+        senders: AtomicUsize::new(1),
+        sleeping: AtomicBool::new(false),
+        receiver: Mutex::new(None),
+    });
+    (
+        Sender {
+            inner: Arc::clone(&inner),
+        },
+        Receiver {
+            inner,
+            _not_sync: PhantomData,
+        },
+    )
+}
+
+pub struct Sender<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Sender<T> {
+    pub fn send(&self, value: T) {
+        self.inner.push(value);
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.inner.senders.fetch_add(1, Ordering::Relaxed);
+        Sender {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+/* The last sender out wakes the receiver, so recv() can tell nothing else
+is coming. */
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.inner.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            /* Same pairing as in push(), with the count instead of a node. */
+            fence(Ordering::SeqCst);
+            self.inner.wake();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+/* There's only one, it's not Clone, and the Cell marker makes it !Sync:
+that's what makes it the single consumer. It can still move to another
+thread. */
+pub struct Receiver<T> {
+    inner: Arc<Inner<T>>,
+    _not_sync: PhantomData<std::cell::Cell<()>>,
+}
+
+/* Both ends share the UnsafeCell with the dummy node, so the compiler won't
+let either cross catch_unwind(). Only pop() writes to it, and nothing there
+can panic between moving the dummy forward and reading the value out. And
+like the Stack, the channel never lends out a T: values go in and come out
+whole. */
+impl<T> UnwindSafe for Sender<T> {}
+impl<T> RefUnwindSafe for Sender<T> {}
+impl<T> UnwindSafe for Receiver<T> {}
+impl<T> RefUnwindSafe for Receiver<T> {}
+
+impl<T> Receiver<T> {
+    /* Never blocks. None means empty, or a push right in the middle. */
+    pub fn try_recv(&self) -> Option<T> {
+        unsafe { self.inner.pop() }
+    }
+
+    /* Blocks until there's a value, or returns None once every Sender is
+    gone and the queue is empty. */
+    pub fn recv(&self) -> Option<T> {
+        loop {
+            if let Some(value) = self.try_recv() {
+                return Some(value);
+            }
+            if self.inner.senders.load(Ordering::Acquire) == 0 {
+                return self.try_recv();
+            }
+            *self
+                .inner
+                .receiver
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()) = Some(thread::current());
+            self.inner.sleeping.store(true, Ordering::Relaxed);
+            fence(Ordering::SeqCst);
+            /* Check again: a push, or the last sender leaving, may have come
+            in before the flag was up, and nobody would wake us. A push that
+            is still between its two steps will see the flag, so parking is
+            fine then too. */
+            if let Some(value) = self.try_recv() {
+                self.inner.sleeping.store(false, Ordering::Relaxed);
+                return Some(value);
+            }
+            if self.inner.senders.load(Ordering::Acquire) != 0 {
+                thread::park();
+            }
+            self.inner.sleeping.store(false, Ordering::Relaxed);
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { receiver: self }
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+/* Blocking: ends when every Sender is gone. */
+pub struct Iter<'a, T> {
+    receiver: &'a Receiver<T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv()
+    }
+}
@@ -0,0 +1,157 @@
+/*
+The Michael-Scott queue (M. M. Michael and M. L. Scott, 1996).
+
+A queue needs two ends, so two pointers to CAS: producers add after tail,
+consumers take from head. Two tricks make that work without a lock:
+
+- There's always a dummy node at the front. head points to it, and the first
+  real value is in the node after it. Dequeuing moves head one node along,
+  and the node that had the value becomes the new dummy. So head and tail
+  never have to change together, not even for the last value.
+
+- Enqueuing is two steps: link the node after the last one (a CAS on
+  last.next), then swing tail to it (another CAS). Between the two, tail lags
+  one node behind. Anybody who finds tail.next isn't null helps by swinging
+  tail forward before doing their own thing, so a thread stalled between its
+  two steps never blocks the others.
+
+Nodes are read by both sides (producers read tail, consumers read head and
+its next), so both enter the Collector, and a node is only retired once head
+has moved past it.
+*/
+use super::reclaim::Collector;
+use super::shim::AtomicPtr;
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::Ordering;
+
+/* The value is uninitialized in the dummy node, and gets moved out just as
+its node becomes the dummy. */
+struct Node<T> {
+    value: MaybeUninit<T>,
+    next: AtomicPtr<Node<T>>,
+}
+
+impl<T> Node<T> {
+    fn alloc(value: MaybeUninit<T>) -> *mut Node<T> {
+        Box::into_raw(Box::new(Node {
+            value,
+            next: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+pub struct Queue<T> {
+    head: AtomicPtr<Node<T>>,
+    tail: AtomicPtr<Node<T>>,
+    collector: Collector<Node<T>>,
+}
+
+unsafe impl<T: Send> Send for Queue<T> {}
+unsafe impl<T: Send> Sync for Queue<T> {}
+
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        let dummy = Node::alloc(MaybeUninit::uninit());
+        Queue {
+            head: AtomicPtr::new(dummy),
+            tail: AtomicPtr::new(dummy),
+            collector: Collector::new(),
+        }
+    }
+}
+
+impl<T> Queue<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let _guard = self.collector.enter();
+        let head = self.head.load(Ordering::Acquire);
+        unsafe { (*head).next.load(Ordering::Acquire).is_null() }
+    }
+
+    pub fn push(&self, value: T) {
+        let node = Node::alloc(MaybeUninit::new(value));
+        let _guard = self.collector.enter();
+        loop {
+            let tail = self.tail.load(Ordering::Acquire);
+            let next = unsafe { (*tail).next.load(Ordering::Acquire) };
+            if !next.is_null() {
+                /* tail is lagging: help it along and retry. */
+                let _ =
+                    self.tail
+                        .compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+                continue;
+            }
+            let linked = unsafe {
+                (*tail).next.compare_exchange(
+                    ptr::null_mut(),
+                    node,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                )
+            };
+            if linked.is_ok() {
+                /* If this fails, somebody already helped. */
+                let _ =
+                    self.tail
+                        .compare_exchange(tail, node, Ordering::Release, Ordering::Relaxed);
+                return;
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = self.collector.enter();
+        loop {
+            let head = self.head.load(Ordering::Acquire);
+            let tail = self.tail.load(Ordering::Acquire);
+            let next = unsafe { (*head).next.load(Ordering::Acquire) };
+            if next.is_null() {
+                return None;
+            }
+            /* Never let head get past tail, or tail would point to a node
+            that can be retired. */
+            if head == tail {
+                let _ =
+                    self.tail
+                        .compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+                continue;
+            }
+            if self
+                .head
+                .compare_exchange(head, next, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+            {
+                /* next is the dummy now. Only we won it, so only we take
+                its value. */
+                let value = unsafe { ptr::read((*next).value.as_ptr()) };
+                unsafe { guard.retire(head) };
+                return Some(value);
+            }
+        }
+    }
+}
+
+impl<T> Drop for Queue<T> {
+    fn drop(&mut self) {
+        let dummy = unsafe { Box::from_raw(self.head.load(Ordering::Relaxed)) };
+        let mut cur = dummy.next.load(Ordering::Relaxed);
+        while !cur.is_null() {
+            let mut node = unsafe { Box::from_raw(cur) };
+            unsafe { ptr::drop_in_place(node.value.as_mut_ptr()) };
+            cur = node.next.load(Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Queue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Queue")
+            .field("empty", &self.is_empty())
+            .finish()
+    }
+}
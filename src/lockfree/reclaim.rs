@@ -0,0 +1,118 @@
+/*
+Deferred freeing by counting threads.
+
+Every operation that may read a shared node enters the Collector first, and
+leaves it when the returned Guard is dropped. Nodes that were unlinked go to
+the retired list instead of being freed. When the last thread inside leaves,
+nobody can be holding any node that was retired before, because any thread
+that could have seen those nodes entered before they were unlinked, and has
+left. So they can all be freed.
+
+The subtle bit is in Guard::drop. Taking the retired list while we're still
+counted, and only then leaving, means that if someone entered in between
+(and so might have seen nodes retired in between), the leave tells us, and
+the batch goes back instead of being freed.
+
+All atomics here are SeqCst. The argument above needs every thread to agree
+on the order of enters, leaves and retires, and this isn't the place to be
+clever.
+*/
+use super::shim::{AtomicPtr, AtomicUsize};
+use std::marker::PhantomData;
+use std::ptr;
+use std::sync::atomic::Ordering::SeqCst;
+
+struct Retired<N> {
+    node: *mut N,
+    next: *mut Retired<N>,
+}
+
+pub(super) struct Collector<N> {
+    active: AtomicUsize,
+    retired: AtomicPtr<Retired<N>>,
+    _nodes: PhantomData<Box<N>>,
+}
+
+impl<N> Collector<N> {
+    pub(super) fn new() -> Self {
+        Collector {
+            active: AtomicUsize::new(0),
+            retired: AtomicPtr::new(ptr::null_mut()),
+            _nodes: PhantomData,
+        }
+    }
+
+    pub(super) fn enter(&self) -> Guard<'_, N> {
+        self.active.fetch_add(1, SeqCst);
+        Guard { collector: self }
+    }
+
+    /* Pushes a chain of retired entries, from first to last, back onto the
+    retired list: a Treiber push of its own. */
+    fn push_chain(&self, first: *mut Retired<N>, last: *mut Retired<N>) {
+        let mut head = self.retired.load(SeqCst);
+        loop {
+            unsafe { (*last).next = head };
+            match self
+                .retired
+                .compare_exchange_weak(head, first, SeqCst, SeqCst)
+            {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    unsafe fn free_chain(mut cur: *mut Retired<N>) {
+        while !cur.is_null() {
+            let retired = Box::from_raw(cur);
+            drop(Box::from_raw(retired.node));
+            cur = retired.next;
+        }
+    }
+}
+
+/* Nobody is inside any more when the collector itself goes away. */
+impl<N> Drop for Collector<N> {
+    fn drop(&mut self) {
+        unsafe { Self::free_chain(self.retired.load(SeqCst)) }
+    }
+}
+
+pub(super) struct Guard<'c, N> {
+    collector: &'c Collector<N>,
+}
+
+impl<'c, N> Guard<'c, N> {
+    /* The node must have come from a Box, be unreachable for any thread
+    that enters from now on, and be retired only once. */
+    pub(super) unsafe fn retire(&self, node: *mut N) {
+        let retired = Box::into_raw(Box::new(Retired {
+            node,
+            next: ptr::null_mut(),
+        }));
+        self.collector.push_chain(retired, retired);
+    }
+}
+
+impl<'c, N> Drop for Guard<'c, N> {
+    fn drop(&mut self) {
+        let collector = self.collector;
+        if collector.active.load(SeqCst) != 1 {
+            collector.active.fetch_sub(1, SeqCst);
+            return;
+        }
+        let batch = collector.retired.swap(ptr::null_mut(), SeqCst);
+        if collector.active.fetch_sub(1, SeqCst) == 1 {
+            unsafe { Collector::free_chain(batch) };
+        } else if !batch.is_null() {
+            let mut last = batch;
+            unsafe {
+                while !(*last).next.is_null() {
+                    last = (*last).next;
+                }
+            }
+            collector.push_chain(batch, last);
+        }
+    }
+}
@@ -0,0 +1,19 @@
+/*
+The atomics, locks and threads the lock-free code is built on. Normally
+they're std's. Built with RUSTFLAGS="--cfg loom", they're loom's instead,
+which record every atomic access so that loom_test.rs can run each test
+under every interleaving (and every weak memory outcome) loom can think of.
+*/
+#[cfg(not(loom))]
+pub(super) use std::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicUsize};
+#[cfg(not(loom))]
+pub(super) use std::sync::{Arc, Mutex};
+#[cfg(not(loom))]
+pub(super) use std::thread;
+
+#[cfg(loom)]
+pub(super) use loom::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicUsize};
+#[cfg(loom)]
+pub(super) use loom::sync::{Arc, Mutex};
+#[cfg(loom)]
+pub(super) use loom::thread;
@@ -0,0 +1,117 @@
+use super::reclaim::Collector;
+use super::shim::{AtomicPtr, AtomicUsize};
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::Ordering;
+
+/* The value is moved out by pop() long before the node is freed, so the
+node must not drop it again. */
+struct Node<T> {
+    value: ManuallyDrop<T>,
+    next: *mut Node<T>,
+}
+
+pub struct Stack<T> {
+    head: AtomicPtr<Node<T>>,
+    /* Only a hint: it's updated apart from the CAS. Pushes count before
+    theirs and pops after, so it can be too high for a moment, but it never
+    goes below zero. */
+    len: AtomicUsize,
+    collector: Collector<Node<T>>,
+}
+
+/* Values go in on one thread and come out on another, so T: Send is all
+that's needed, for both. Nobody ever gets a &T out of a shared Stack. */
+unsafe impl<T: Send> Send for Stack<T> {}
+unsafe impl<T: Send> Sync for Stack<T> {}
+
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Stack {
+            head: AtomicPtr::new(ptr::null_mut()),
+            len: AtomicUsize::new(0),
+            collector: Collector::new(),
+        }
+    }
+}
+
+impl<T> Stack<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /* With other threads pushing and popping, this may be out of date by
+    the time it returns. */
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+
+    /* Push never reads a node other than its own, so it needs no guard.
+    The Release on success publishes value and next along with the node. */
+    pub fn push(&self, value: T) {
+        let node = Box::into_raw(Box::new(Node {
+            value: ManuallyDrop::new(value),
+            next: ptr::null_mut(),
+        }));
+        self.len.fetch_add(1, Ordering::Relaxed);
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*node).next = head };
+            match self
+                .head
+                .compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = self.collector.enter();
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            if head.is_null() {
+                return None;
+            }
+            /* head may have been popped by now, but not freed: we're inside
+            the collector. */
+            let next = unsafe { (*head).next };
+            match self
+                .head
+                .compare_exchange_weak(head, next, Ordering::Acquire, Ordering::Acquire)
+            {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+        self.len.fetch_sub(1, Ordering::Relaxed);
+        /* Only the thread whose CAS succeeded gets here for this node. */
+        let value = unsafe { ptr::read(&*(*head).value) };
+        unsafe { guard.retire(head) };
+        Some(value)
+    }
+}
+
+/* With &mut self there's nobody else, so the nodes can go straight away. */
+impl<T> Drop for Stack<T> {
+    fn drop(&mut self) {
+        let mut cur = self.head.load(Ordering::Relaxed);
+        while !cur.is_null() {
+            let mut node = unsafe { Box::from_raw(cur) };
+            unsafe { ManuallyDrop::drop(&mut node.value) };
+            cur = node.next;
+        }
+    }
+}
+
+impl<T> fmt::Debug for Stack<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stack").field("len", &self.len()).finish()
+    }
+}
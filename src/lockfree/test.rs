@@ -0,0 +1,314 @@
+use super::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+/* Both stacks behind one interface, so the same tests run on each. */
+trait ConcurrentStack<T>: Default + Sync {
+    fn push(&self, value: T);
+    fn pop(&self) -> Option<T>;
+}
+
+impl<T: Send> ConcurrentStack<T> for Stack<T> {
+    fn push(&self, value: T) {
+        Stack::push(self, value)
+    }
+    fn pop(&self) -> Option<T> {
+        Stack::pop(self)
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+impl<T: Send> ConcurrentStack<T> for EpochStack<T> {
+    fn push(&self, value: T) {
+        EpochStack::push(self, value)
+    }
+    fn pop(&self) -> Option<T> {
+        EpochStack::pop(self)
+    }
+}
+
+fn lifo<S: ConcurrentStack<i64>>() {
+    let stack = S::default();
+    assert_eq!(stack.pop(), None);
+    stack.push(1);
+    stack.push(2);
+    stack.push(3);
+    assert_eq!(stack.pop(), Some(3));
+    stack.push(4);
+    assert_eq!(stack.pop(), Some(4));
+    assert_eq!(stack.pop(), Some(2));
+    assert_eq!(stack.pop(), Some(1));
+    assert_eq!(stack.pop(), None);
+}
+
+fn values_are_dropped<S: ConcurrentStack<Arc<()>>>() {
+    let counter = Arc::new(());
+    {
+        let stack = S::default();
+        for _ in 0..10 {
+            stack.push(Arc::clone(&counter));
+        }
+        stack.pop();
+        assert_eq!(Arc::strong_count(&counter), 10);
+    }
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+/* Pushers and poppers all at once: every value pushed comes out exactly
+once, none lost, none twice. */
+fn stress<S: ConcurrentStack<usize>>(pushers: usize, poppers: usize, per_thread: usize) {
+    let stack = S::default();
+    let total = pushers * per_thread;
+    let popped = AtomicUsize::new(0);
+    let mut seen: Vec<usize> = thread::scope(|s| {
+        for t in 0..pushers {
+            let stack = &stack;
+            s.spawn(move || {
+                for i in 0..per_thread {
+                    stack.push(t * per_thread + i);
+                }
+            });
+        }
+        let handles: Vec<_> = (0..poppers)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut mine = Vec::new();
+                    while popped.load(Ordering::Relaxed) < total {
+                        if let Some(v) = stack.pop() {
+                            popped.fetch_add(1, Ordering::Relaxed);
+                            mine.push(v);
+                        } else {
+                            thread::yield_now();
+                        }
+                    }
+                    mine
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    });
+    seen.sort_unstable();
+    assert_eq!(seen, (0..total).collect::<Vec<_>>());
+    assert!(stack.pop().is_none());
+}
+
+#[test]
+fn test_stack_lifo() {
+    lifo::<Stack<i64>>();
+    let stack = Stack::new();
+    stack.push(1);
+    assert_eq!(stack.len(), 1);
+    assert!(!stack.is_empty());
+}
+
+#[test]
+fn test_stack_values_are_dropped() {
+    values_are_dropped::<Stack<Arc<()>>>();
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_stack_stress() {
+    stress::<Stack<usize>>(4, 4, 20_000);
+}
+
+/* Values popped while other threads are inside get freed later, by the
+last one out. They must still be freed exactly once. */
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_stack_retired_values_are_dropped() {
+    let counter = Arc::new(());
+    let stack = Stack::new();
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..5000 {
+                    stack.push(Arc::clone(&counter));
+                    stack.pop();
+                }
+            });
+        }
+    });
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+#[cfg(feature = "crossbeam")]
+#[test]
+fn test_epoch_stack_lifo() {
+    lifo::<EpochStack<i64>>();
+}
+
+#[cfg(feature = "crossbeam")]
+#[test]
+fn test_epoch_stack_values_are_dropped() {
+    values_are_dropped::<EpochStack<Arc<()>>>();
+}
+
+#[cfg(feature = "crossbeam")]
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_epoch_stack_stress() {
+    stress::<EpochStack<usize>>(4, 4, 20_000);
+}
+
+#[test]
+fn test_queue_fifo() {
+    let queue = Queue::new();
+    assert!(queue.is_empty());
+    assert_eq!(queue.pop(), None);
+    queue.push(1);
+    queue.push(2);
+    assert!(!queue.is_empty());
+    assert_eq!(queue.pop(), Some(1));
+    queue.push(3);
+    assert_eq!(queue.pop(), Some(2));
+    assert_eq!(queue.pop(), Some(3));
+    assert_eq!(queue.pop(), None);
+    queue.push(4);
+    assert_eq!(queue.pop(), Some(4));
+}
+
+#[test]
+fn test_queue_values_are_dropped() {
+    let counter = Arc::new(());
+    {
+        let queue = Queue::new();
+        for _ in 0..10 {
+            queue.push(Arc::clone(&counter));
+        }
+        queue.pop();
+        assert_eq!(Arc::strong_count(&counter), 10);
+    }
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+/* N producers and M consumers. Nothing is lost or duplicated, and since
+it's a FIFO, every consumer sees each producer's values in the order they
+were pushed. */
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_queue_stress() {
+    let (producers, consumers, per_thread) = (4, 3, 20_000);
+    let total = producers * per_thread;
+    let queue = Queue::new();
+    let popped = AtomicUsize::new(0);
+    let mut seen: Vec<(usize, usize)> = thread::scope(|s| {
+        for p in 0..producers {
+            let queue = &queue;
+            s.spawn(move || {
+                for i in 0..per_thread {
+                    queue.push((p, i));
+                }
+            });
+        }
+        let handles: Vec<_> = (0..consumers)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut mine = Vec::new();
+                    let mut last = vec![None; producers];
+                    while popped.load(Ordering::Relaxed) < total {
+                        if let Some((p, i)) = queue.pop() {
+                            popped.fetch_add(1, Ordering::Relaxed);
+                            assert!(last[p] < Some(i), "out of order");
+                            last[p] = Some(i);
+                            mine.push((p, i));
+                        } else {
+                            thread::yield_now();
+                        }
+                    }
+                    mine
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    });
+    seen.sort_unstable();
+    let expected: Vec<_> = (0..producers)
+        .flat_map(|p| (0..per_thread).map(move |i| (p, i)))
+        .collect();
+    assert_eq!(seen, expected);
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn test_mpsc_try_recv() {
+    let (tx, rx) = mpsc::channel();
+    assert_eq!(rx.try_recv(), None);
+    tx.send(1);
+    tx.send(2);
+    assert_eq!(rx.try_recv(), Some(1));
+    assert_eq!(rx.try_recv(), Some(2));
+    assert_eq!(rx.try_recv(), None);
+    drop(tx);
+    assert_eq!(rx.recv(), None);
+}
+
+#[test]
+fn test_mpsc_values_are_dropped() {
+    let counter = Arc::new(());
+    {
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..10 {
+            tx.send(Arc::clone(&counter));
+        }
+        rx.try_recv();
+        assert_eq!(Arc::strong_count(&counter), 10);
+    }
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+/* The receiver blocks in recv(), and has to be woken up by every burst of
+sends, and finally by the last sender leaving. */
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_mpsc_many_producers() {
+    let (producers, per_thread) = (4, 20_000);
+    let (tx, rx) = mpsc::channel();
+    let handles: Vec<_> = (0..producers)
+        .map(|p| {
+            let tx = tx.clone();
+            thread::spawn(move || {
+                for i in 0..per_thread {
+                    tx.send((p, i));
+                    if i % 1000 == 1 {
+                        thread::yield_now();
+                    }
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+    let receiver = thread::spawn(move || {
+        let mut last = vec![None; producers];
+        let mut count = 0;
+        for (p, i) in rx.iter() {
+            assert!(last[p] < Some(i), "out of order");
+            last[p] = Some(i);
+            count += 1;
+        }
+        (count, last)
+    });
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let (count, last) = receiver.join().unwrap();
+    assert_eq!(count, producers * per_thread);
+    assert!(last.iter().all(|l| *l == Some(per_thread - 1)));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_mpsc_recv_waits() {
+    let (tx, rx) = mpsc::channel();
+    let receiver = thread::spawn(move || rx.recv());
+    thread::sleep(std::time::Duration::from_millis(20));
+    tx.send("late");
+    assert_eq!(receiver.join().unwrap(), Some("late"));
+}
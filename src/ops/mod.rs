@@ -0,0 +1,469 @@
+#![allow(dead_code)]
+/*
+One interface for all of them
+===========================================================================
+
+Every list in this crate has push_front() and len() and iter(), more or
+less, but each one grew its own: linked5 calls them insert_first() and
+pop_tail(), linked7 returns a Handle from every push, linked4's push_back()
+is still called add_item(). Fine for reading them one by one, not so fine
+for writing one test, or one benchmark, that runs against all of them.
+
+So here are a few traits, one on top of the other:
+
+- ReadOps: len(), iter() and to_vec(). Anything with values in order has
+  these, even the lists we can't (or shouldn't) push into from outside:
+  linked3's arena lists, the skip list that keeps itself sorted, the ring
+  that starts wherever its cursor is.
+
+- LinkedListOps: an empty list, from_slice(), push at both ends and
+  pop_front(). A queue, basically.
+
+- DoubleEndedOps: pop_back(). Not every list can, linked8 only knows where
+  its tail is, not what's before it. linked4 can, but it walks the whole
+  list to do it: the trait says what a list can do, not how fast.
+
+- PositionalOps: insert() and remove() at an index. Some have them already,
+  the cursor lists get them by walking a cursor there.
+
+iter() is the odd one. Most lists hand out &T, but linked5 can't (the value
+is behind a RefCell borrow that ends with next()) and linked3 copies its
+values out of the arena. So Iter is only required to yield something we can
+borrow an Item from: &T and T both are.
+
+The impls are all here, not next to each list. Each one is a few lines of
+"this method is called that there", and having them on one page shows how
+different the lists really are. Inherent methods win over trait methods, so
+`self.len()` inside an impl still calls the list's own len().
+*/
+use crate::{
+    circular, fixed, hybrid, indexed, linked3, linked4, linked5, linked6, linked7, linked8,
+    selforg, skiplist, unrolled,
+};
+use std::borrow::Borrow;
+
+pub trait ReadOps {
+    type Item;
+    type Iter<'a>: Iterator<Item: Borrow<Self::Item>>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn iter(&self) -> Self::Iter<'_>;
+
+    fn to_vec(&self) -> Vec<Self::Item>
+    where
+        Self::Item: Clone,
+    {
+        self.iter().map(|value| value.borrow().clone()).collect()
+    }
+}
+
+/* Default is the empty list. Every list that can be pushed into has one. */
+pub trait LinkedListOps: ReadOps + Default {
+    fn from_slice(values: &[Self::Item]) -> Self
+    where
+        Self::Item: Clone,
+    {
+        let mut list = Self::default();
+        for value in values {
+            list.push_back(value.clone());
+        }
+        list
+    }
+
+    fn push_front(&mut self, value: Self::Item);
+    fn push_back(&mut self, value: Self::Item);
+    fn pop_front(&mut self) -> Option<Self::Item>;
+}
+
+pub trait DoubleEndedOps: LinkedListOps {
+    fn pop_back(&mut self) -> Option<Self::Item>;
+}
+
+/* Like Vec: insert() panics past the end, remove() there returns None. */
+pub trait PositionalOps: LinkedListOps {
+    fn insert(&mut self, at: usize, value: Self::Item);
+    fn remove(&mut self, at: usize) -> Option<Self::Item>;
+}
+
+/* Most lists already have the exact same methods. This writes the three
+impls for those. */
+macro_rules! impl_ops {
+    ([$($gen:tt)*] $ty:ty, Iter = $iter:ty) => {
+        impl<$($gen)*> ReadOps for $ty {
+            type Item = T;
+            type Iter<'a> = $iter where Self: 'a;
+
+            fn len(&self) -> usize {
+                self.len()
+            }
+            fn iter(&self) -> Self::Iter<'_> {
+                self.iter()
+            }
+        }
+
+        impl<$($gen)*> LinkedListOps for $ty {
+            fn push_front(&mut self, value: T) {
+                self.push_front(value);
+            }
+            fn push_back(&mut self, value: T) {
+                self.push_back(value);
+            }
+            fn pop_front(&mut self) -> Option<T> {
+                self.pop_front()
+            }
+        }
+
+        impl<$($gen)*> DoubleEndedOps for $ty {
+            fn pop_back(&mut self) -> Option<T> {
+                self.pop_back()
+            }
+        }
+    };
+}
+
+impl_ops!([T] linked4::DoublyList<T>, Iter = linked4::DoublyIter<'a, T>);
+impl_ops!([T] linked6::List<T>, Iter = linked6::Iter<'a, T>);
+impl_ops!([T] linked6::SentinelList<T>, Iter = linked6::SentinelIter<'a, T>);
+impl_ops!([T, const N: usize] unrolled::UnrolledList<T, N>, Iter = unrolled::Iter<'a, T, N>);
+impl_ops!([T] indexed::IndexedList<T>, Iter = indexed::Iter<'a, T>);
+impl_ops!([T, const N: usize] hybrid::HybridList<T, N>, Iter = hybrid::Iter<'a, T, N>);
+
+/* linked4::List: push_back() is add_item(), and new() wants a slice. */
+impl<T> ReadOps for linked4::List<T> {
+    type Item = T;
+    type Iter<'a>
+        = linked4::Iter<'a, T>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+}
+
+impl<T> LinkedListOps for linked4::List<T> {
+    /* Builds the chain in one go, instead of one push at a time. */
+    fn from_slice(values: &[T]) -> Self
+    where
+        T: Clone,
+    {
+        linked4::List::new(values)
+    }
+    fn push_front(&mut self, value: T) {
+        self.push_front(value);
+    }
+    fn push_back(&mut self, value: T) {
+        self.add_item(value);
+    }
+    fn pop_front(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+}
+
+impl<T> DoubleEndedOps for linked4::List<T> {
+    fn pop_back(&mut self) -> Option<T> {
+        self.pop_back()
+    }
+}
+
+/* There's no cursor at a position in linked4, only at the front. For an
+insert we stop one node short, since its cursor only inserts after. */
+impl<T> PositionalOps for linked4::List<T> {
+    fn insert(&mut self, at: usize, value: T) {
+        assert!(
+            at <= self.len(),
+            "insert: the len is {} but the index is {}",
+            self.len(),
+            at
+        );
+        if at == 0 {
+            return self.push_front(value);
+        }
+        let mut cursor = self.cursor_front_mut();
+        for _ in 1..at {
+            cursor.move_next();
+        }
+        cursor.insert_after(value);
+    }
+    fn remove(&mut self, at: usize) -> Option<T> {
+        if at >= self.len() {
+            return None;
+        }
+        let mut cursor = self.cursor_front_mut();
+        for _ in 0..at {
+            cursor.move_next();
+        }
+        cursor.remove_current()
+    }
+}
+
+/* linked5 is i64 only, and names everything its own way. */
+impl ReadOps for linked5::List {
+    type Item = i64;
+    type Iter<'a> = linked5::IterList<'a>;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+}
+
+impl LinkedListOps for linked5::List {
+    fn from_slice(values: &[i64]) -> Self {
+        linked5::List::from_vec(values)
+    }
+    fn push_front(&mut self, value: i64) {
+        self.insert_first(value);
+    }
+    fn push_back(&mut self, value: i64) {
+        self.append(value);
+    }
+    fn pop_front(&mut self) -> Option<i64> {
+        self.pop_first()
+    }
+}
+
+impl DoubleEndedOps for linked5::List {
+    fn pop_back(&mut self) -> Option<i64> {
+        self.pop_tail()
+    }
+}
+
+/* linked6's cursor can insert before itself, and at == len is the ghost,
+where "before" means at the back. */
+impl<T> PositionalOps for linked6::List<T> {
+    fn insert(&mut self, at: usize, value: T) {
+        assert!(
+            at <= self.len(),
+            "insert: the len is {} but the index is {}",
+            self.len(),
+            at
+        );
+        let mut cursor = self.cursor_front_mut();
+        for _ in 0..at {
+            cursor.move_next();
+        }
+        cursor.insert_before(value);
+    }
+    fn remove(&mut self, at: usize) -> Option<T> {
+        if at >= self.len() {
+            return None;
+        }
+        let mut cursor = self.cursor_front_mut();
+        for _ in 0..at {
+            cursor.move_next();
+        }
+        cursor.remove_current()
+    }
+}
+
+impl<T> PositionalOps for linked6::SentinelList<T> {
+    fn insert(&mut self, at: usize, value: T) {
+        self.insert(at, value);
+    }
+    fn remove(&mut self, at: usize) -> Option<T> {
+        self.remove(at)
+    }
+}
+
+impl<T, const N: usize> PositionalOps for unrolled::UnrolledList<T, N> {
+    fn insert(&mut self, at: usize, value: T) {
+        self.insert(at, value);
+    }
+    fn remove(&mut self, at: usize) -> Option<T> {
+        self.remove(at)
+    }
+}
+
+impl<T> PositionalOps for indexed::IndexedList<T> {
+    fn insert(&mut self, at: usize, value: T) {
+        self.insert_at(at, value);
+    }
+    fn remove(&mut self, at: usize) -> Option<T> {
+        self.remove_at(at)
+    }
+}
+
+impl<T, const N: usize> PositionalOps for hybrid::HybridList<T, N> {
+    fn insert(&mut self, at: usize, value: T) {
+        self.insert(at, value);
+    }
+    fn remove(&mut self, at: usize) -> Option<T> {
+        self.remove(at)
+    }
+}
+
+/* linked7 hands back a Handle from every push. Nobody asked for it here. */
+impl<T> ReadOps for linked7::List<T> {
+    type Item = T;
+    type Iter<'a>
+        = linked7::Iter<'a, T>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+}
+
+impl<T> LinkedListOps for linked7::List<T> {
+    fn push_front(&mut self, value: T) {
+        self.push_front(value);
+    }
+    fn push_back(&mut self, value: T) {
+        self.push_back(value);
+    }
+    fn pop_front(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+}
+
+impl<T> DoubleEndedOps for linked7::List<T> {
+    fn pop_back(&mut self) -> Option<T> {
+        self.pop_back()
+    }
+}
+
+/* linked8 is singly linked: no pop_back(). */
+impl<T> ReadOps for linked8::List<T> {
+    type Item = T;
+    type Iter<'a>
+        = linked8::Iter<'a, T>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+}
+
+impl<T> LinkedListOps for linked8::List<T> {
+    fn push_front(&mut self, value: T) {
+        self.push_front(value);
+    }
+    fn push_back(&mut self, value: T) {
+        self.push_back(value);
+    }
+    fn pop_front(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+}
+
+/* The rest can only be read through the traits. linked3's lists push with
+&self and give back a node in the arena, and the values come out copied. */
+impl<'arena, T: Copy> ReadOps for linked3::ManagedList<'arena, T> {
+    type Item = T;
+    type Iter<'a>
+        = linked3::IterLinkedList1<'arena, T>
+    where
+        Self: 'a;
+
+    /* It doesn't count its nodes: anybody can hang more on the tail. */
+    fn len(&self) -> usize {
+        self.iter().count()
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+}
+
+impl<'arena, T: Copy> ReadOps for linked3::DoublyLinkedList<'arena, T> {
+    type Item = T;
+    type Iter<'a>
+        = linked3::DoublyIter<'arena, T>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+}
+
+/* A push_front() on the ring goes before its sentinel, but iter() starts at
+the current value, so what went in first doesn't have to come out first. */
+impl<T> ReadOps for circular::CircularList<T> {
+    type Item = T;
+    type Iter<'a>
+        = circular::Iter<'a, T>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+}
+
+/* Sorted: values go where they belong, not at an end. */
+impl<T> ReadOps for skiplist::SkipList<T> {
+    type Item = T;
+    type Iter<'a>
+        = skiplist::Iter<'a, T>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+}
+
+/* Reorders itself on every lookup, and has no pop at all. */
+impl<T> ReadOps for selforg::SelfOrgList<T> {
+    type Item = T;
+    type Iter<'a>
+        = selforg::Iter<'a, T>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+}
+
+/* Its pushes can fail when it's full, which push_back() here can't say. */
+impl<T, const N: usize> ReadOps for fixed::FixedList<T, N> {
+    type Item = T;
+    type Iter<'a>
+        = fixed::Iter<'a, T, N>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,111 @@
+use super::*;
+use crate::differential::{run_deque, Op};
+
+/* The point of the traits: one test, written once, run on every list. */
+fn check_queue<L: LinkedListOps<Item = i64>>() {
+    let mut list = L::default();
+    assert!(list.is_empty());
+    assert_eq!(list.pop_front(), None);
+    list.push_back(2);
+    list.push_back(3);
+    list.push_front(1);
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.to_vec(), vec![1, 2, 3]);
+    assert_eq!(list.pop_front(), Some(1));
+    assert_eq!(list.pop_front(), Some(2));
+    assert_eq!(list.pop_front(), Some(3));
+    assert_eq!(list.pop_front(), None);
+    assert!(list.is_empty());
+
+    let list = L::from_slice(&[5, 6, 7, 8]);
+    assert_eq!(list.len(), 4);
+    assert_eq!(list.to_vec(), vec![5, 6, 7, 8]);
+    let sum: i64 = list.iter().map(|value| *value.borrow()).sum();
+    assert_eq!(sum, 26);
+}
+
+fn check_deque<L: DoubleEndedOps<Item = i64>>() {
+    check_queue::<L>();
+    let mut list = L::from_slice(&[1, 2, 3]);
+    assert_eq!(list.pop_back(), Some(3));
+    list.push_back(4);
+    assert_eq!(list.pop_back(), Some(4));
+    assert_eq!(list.pop_front(), Some(1));
+    assert_eq!(list.pop_back(), Some(2));
+    assert_eq!(list.pop_back(), None);
+    assert_eq!(list.len(), 0);
+    list.push_front(9);
+    assert_eq!(list.to_vec(), vec![9]);
+}
+
+/* Drives a list and a VecDeque with the same pseudo-random operations,
+through the trait methods only. */
+fn check_against_vec<L: DoubleEndedOps<Item = i64>>() {
+    run_deque::<L>(&Op::random_sequence(7, 500));
+}
+
+#[test]
+fn test_double_ended_lists() {
+    check_deque::<linked4::List<i64>>();
+    check_deque::<linked4::DoublyList<i64>>();
+    check_deque::<linked5::List>();
+    check_deque::<linked6::List<i64>>();
+    check_deque::<linked6::SentinelList<i64>>();
+    check_deque::<linked7::List<i64>>();
+    check_deque::<unrolled::UnrolledList<i64, 4>>();
+    check_deque::<indexed::IndexedList<i64>>();
+    /* Small, so it spills halfway through. */
+    check_deque::<hybrid::HybridList<i64, 4>>();
+}
+
+#[test]
+fn test_queues() {
+    check_queue::<linked8::List<i64>>();
+}
+
+#[test]
+fn test_all_agree_with_a_vec() {
+    check_against_vec::<linked4::List<i64>>();
+    check_against_vec::<linked4::DoublyList<i64>>();
+    check_against_vec::<linked5::List>();
+    check_against_vec::<linked6::List<i64>>();
+    check_against_vec::<linked6::SentinelList<i64>>();
+    check_against_vec::<linked7::List<i64>>();
+    check_against_vec::<unrolled::UnrolledList<i64, 4>>();
+    check_against_vec::<indexed::IndexedList<i64>>();
+    check_against_vec::<hybrid::HybridList<i64, 4>>();
+}
+
+fn read<L: ReadOps<Item = i64>>(list: &L) -> (usize, Vec<i64>) {
+    (list.len(), list.to_vec())
+}
+
+#[test]
+fn test_read_only_lists() {
+    let arena = linked3::Arena::new();
+    let managed = linked3::ManagedList::new(&arena);
+    managed.append(2);
+    managed.push_front(1);
+    assert_eq!(read(&managed), (2, vec![1, 2]));
+
+    let arena = linked3::Arena::new();
+    let doubly = linked3::DoublyLinkedList::new(&arena);
+    doubly.push_back(2);
+    doubly.push_front(1);
+    assert_eq!(read(&doubly), (2, vec![1, 2]));
+
+    let skip: skiplist::SkipList<i64> = vec![3, 1, 2].into_iter().collect();
+    assert_eq!(read(&skip), (3, vec![1, 2, 3]));
+
+    let ring: circular::CircularList<i64> = (1..=3).collect();
+    assert_eq!(read(&ring).0, 3);
+
+    let mut selforg = selforg::SelfOrgList::new(selforg::Strategy::Static);
+    selforg.push_back(1);
+    assert_eq!(read(&selforg), (1, vec![1]));
+
+    let mut fixed: fixed::FixedList<i64, 2> = fixed::FixedList::new();
+    fixed.push_back(1).unwrap();
+    assert!(!ReadOps::is_empty(&fixed));
+    assert_eq!(read(&fixed), (1, vec![1]));
+}
@@ -0,0 +1,274 @@
+#![allow(dead_code)]
+/*
+Writing down a failing sequence, and playing it back
+===========================================================================
+
+When the differential harness finds a bug, what we have is a seed, or a
+fuzzer's crash file, or a proptest case in a terminal that scrolled away.
+Neither is something to paste in a bug report or keep as a test. A Vec<Op>
+is, once it can be written as text and read back, so here's a text form:
+
+    linked6: F5 B-1 f b I1,9 R7 N8
+
+First the list it ran on, then the operations, one short word each:
+
+    F5     PushFront(5)         f      PopFront
+    B-1    PushBack(-1)         b      PopBack
+    I1,9   Insert(1, 9)         R7     Remove(7)
+    N8     Nth(8)
+
+Capitals take arguments, lower case don't. Any whitespace separates them,
+line breaks too, so a long one can be wrapped to fit in a test.
+
+Replay::run() feeds the ops to the harness with that list, exactly as they
+came: positions wrap around len() when they run, same as always (see
+differential), so the text doesn't need to know anything about the list.
+
+To get one, run the sequence through find_failure(). It runs it on the list,
+and if the harness panics, it cuts the sequence after the step that failed:
+that prefix fails the same way, and everything after it is noise. For a
+fuzzer crash, the ops are `Op::from_bytes(&std::fs::read(path)?)`.
+
+The result goes in src/oputil/test.rs, as one more line in REGRESSIONS.
+*/
+use crate::differential::{run_deque, run_positional, run_queue, Op};
+use crate::{indexed, linked4, linked5, linked6, linked7, linked8, unrolled};
+use std::error::Error;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::PushFront(value) => write!(f, "F{}", value),
+            Op::PushBack(value) => write!(f, "B{}", value),
+            Op::PopFront => write!(f, "f"),
+            Op::PopBack => write!(f, "b"),
+            Op::Insert(at, value) => write!(f, "I{},{}", at, value),
+            Op::Remove(at) => write!(f, "R{}", at),
+            Op::Nth(at) => write!(f, "N{}", at),
+        }
+    }
+}
+
+/* Strict: "f1" or "I3" is an error, not a PopFront or an Insert at 3 of 0.
+Text is written by hand sometimes, and a typo should say so. */
+impl FromStr for Op {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bad = || ParseError::BadOp(text.to_string());
+        let mut chars = text.chars();
+        let kind = chars.next().ok_or_else(bad)?;
+        let args = chars.as_str();
+        let int = |arg: &str| arg.parse::<i64>().map_err(|_| bad());
+        let pos = |arg: &str| arg.parse::<usize>().map_err(|_| bad());
+        let op = match kind {
+            'F' => Op::PushFront(int(args)?),
+            'B' => Op::PushBack(int(args)?),
+            'f' if args.is_empty() => Op::PopFront,
+            'b' if args.is_empty() => Op::PopBack,
+            'I' => {
+                let (at, value) = args.split_once(',').ok_or_else(bad)?;
+                Op::Insert(pos(at)?, int(value)?)
+            }
+            'R' => Op::Remove(pos(args)?),
+            'N' => Op::Nth(pos(args)?),
+            _ => return Err(bad()),
+        };
+        Ok(op)
+    }
+}
+
+/* Every list the harness can drive, by the name the text uses. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Linked4,
+    Linked4Doubly,
+    Linked5,
+    Linked6,
+    Linked6Sentinel,
+    Linked7,
+    Linked8,
+    Unrolled,
+    Indexed,
+}
+
+impl Target {
+    pub const ALL: [Target; 9] = [
+        Target::Linked4,
+        Target::Linked4Doubly,
+        Target::Linked5,
+        Target::Linked6,
+        Target::Linked6Sentinel,
+        Target::Linked7,
+        Target::Linked8,
+        Target::Unrolled,
+        Target::Indexed,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Target::Linked4 => "linked4",
+            Target::Linked4Doubly => "linked4::doubly",
+            Target::Linked5 => "linked5",
+            Target::Linked6 => "linked6",
+            Target::Linked6Sentinel => "linked6::sentinel",
+            Target::Linked7 => "linked7",
+            Target::Linked8 => "linked8",
+            Target::Unrolled => "unrolled",
+            Target::Indexed => "indexed",
+        }
+    }
+
+    /* With the most operations each list can do, like the tests in
+    differential. Panics at the first step where it and the oracle disagree. */
+    pub fn run(self, ops: &[Op]) {
+        match self {
+            Target::Linked4 => {
+                run_positional::<linked4::List<i64>>(ops);
+            }
+            Target::Linked4Doubly => {
+                run_deque::<linked4::DoublyList<i64>>(ops);
+            }
+            Target::Linked5 => {
+                run_deque::<linked5::List>(ops);
+            }
+            Target::Linked6 => {
+                run_positional::<linked6::List<i64>>(ops);
+            }
+            Target::Linked6Sentinel => {
+                run_positional::<linked6::SentinelList<i64>>(ops);
+            }
+            Target::Linked7 => {
+                run_deque::<linked7::List<i64>>(ops);
+            }
+            Target::Linked8 => {
+                run_queue::<linked8::List<i64>>(ops);
+            }
+            /* Small chunks, so they split and merge all the time. */
+            Target::Unrolled => {
+                run_positional::<unrolled::UnrolledList<i64, 4>>(ops);
+            }
+            Target::Indexed => {
+                run_positional::<indexed::IndexedList<i64>>(ops);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Target {
+    type Err = ParseError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Target::ALL
+            .iter()
+            .copied()
+            .find(|target| target.name() == name)
+            .ok_or_else(|| ParseError::UnknownTarget(name.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub target: Target,
+    pub ops: Vec<Op>,
+}
+
+impl Replay {
+    pub fn new(target: Target, ops: &[Op]) -> Self {
+        Replay {
+            target,
+            ops: ops.to_vec(),
+        }
+    }
+
+    pub fn run(&self) {
+        self.target.run(&self.ops)
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.target)?;
+        for op in &self.ops {
+            write!(f, " {}", op)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = ParseError;
+
+    /* The last ':', the names have some too. */
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (target, ops) = text.rsplit_once(':').ok_or(ParseError::NoTarget)?;
+        Ok(Replay {
+            target: target.trim().parse()?,
+            ops: ops
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/* None if the whole sequence runs fine. Otherwise the shortest prefix that
+still fails. The harness checks after every step, so once a prefix fails
+every longer one does too, and a binary search finds the step: a few runs
+instead of one per step. */
+pub fn find_failure(target: Target, ops: &[Op]) -> Option<Replay> {
+    let len = failing_len(ops, |ops| target.run(ops))?;
+    Some(Replay::new(target, &ops[..len]))
+}
+
+/* How many ops it takes for `run` to panic. The panics are caught, but the
+hook still prints every one of them on the way. */
+fn failing_len(ops: &[Op], run: impl Fn(&[Op])) -> Option<usize> {
+    let fails = |ops: &[Op]| panic::catch_unwind(AssertUnwindSafe(|| run(ops))).is_err();
+    if !fails(ops) {
+        return None;
+    }
+    /* ops[..lo] passes, ops[..hi] fails. */
+    let (mut lo, mut hi) = (0, ops.len());
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if fails(&ops[..mid]) {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    Some(hi)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /* There's no "list:" in front. */
+    NoTarget,
+    UnknownTarget(String),
+    BadOp(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::NoTarget => write!(f, "no \"list:\" before the operations"),
+            ParseError::UnknownTarget(name) => write!(f, "no list called {:?}", name),
+            ParseError::BadOp(op) => write!(f, "can't read {:?} as an operation", op),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+#[cfg(test)]
+mod test;
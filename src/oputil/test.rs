@@ -0,0 +1,108 @@
+use super::*;
+
+/* Sequences that walk through the spots where bugs were: a list going down
+to one node and back, a tail that used to be left pointing at the first node,
+removes at both ends. New ones get added here, straight from find_failure(). */
+const REGRESSIONS: &[&str] = &[
+    "linked4: B1 b B2 F3 b b F4 B5 R1 R0 I0,6 I9,7 b f f",
+    "linked4: F1 F2 f B3 b f B4 R0 B5 I1,6 R1 b",
+    "linked4::doubly: B1 F2 b f B3 F4 f b F5 B6 b b",
+    "linked5: F1 b B2 f B3 B4 b b F5 F6 f f",
+    "linked6: I0,1 R0 I5,2 I0,3 R1 R1 N0 b",
+    "linked6::sentinel: I0,1 R0 I5,2 I0,3 R1 R1 N0 f",
+    "linked7: B1 b B2 B3 f b F4 B5 f f",
+    "linked8: B1 f B2 F3 f f F4 B5 N1 f B6 f f",
+    "unrolled: B1 B2 B3 B4 B5 R2 R2 R0 I1,6 f b b",
+    "indexed: B1 I0,2 R1 R0 I3,4 N0 f",
+];
+
+#[test]
+fn test_regressions() {
+    for text in REGRESSIONS {
+        let replay: Replay = text.parse().unwrap();
+        assert_eq!(&replay.to_string(), text);
+        replay.run();
+    }
+}
+
+#[test]
+fn test_text_form() {
+    let replay: Replay = "linked6: F5 B-1 f b I1,9 R7 N8".parse().unwrap();
+    assert_eq!(replay.target, Target::Linked6);
+    assert_eq!(
+        replay.ops,
+        vec![
+            Op::PushFront(5),
+            Op::PushBack(-1),
+            Op::PopFront,
+            Op::PopBack,
+            Op::Insert(1, 9),
+            Op::Remove(7),
+            Op::Nth(8),
+        ]
+    );
+    /* Any whitespace, and none needed after the colon. */
+    let wrapped: Replay = "linked6:F5\n  B-1 f\tb\n I1,9 R7 N8\n".parse().unwrap();
+    assert_eq!(wrapped, replay);
+    let empty: Replay = "linked8:".parse().unwrap();
+    assert_eq!(empty.to_string(), "linked8:");
+    assert!(empty.ops.is_empty());
+}
+
+#[test]
+fn test_round_trip() {
+    for seed in 0..20 {
+        let ops = Op::random_sequence(seed, 200);
+        for target in Target::ALL {
+            let replay = Replay::new(target, &ops);
+            assert_eq!(replay.to_string().parse(), Ok(replay));
+            assert_eq!(target.to_string().parse(), Ok(target));
+        }
+    }
+    let extremes = [
+        Op::PushFront(i64::MIN),
+        Op::PushBack(i64::MAX),
+        Op::Insert(usize::MAX, -1),
+    ];
+    let replay = Replay::new(Target::Indexed, &extremes);
+    assert_eq!(replay.to_string().parse(), Ok(replay));
+}
+
+#[test]
+fn test_parse_errors() {
+    let bad = |op: &str| Err(ParseError::BadOp(op.to_string()));
+    assert_eq!("f1".parse::<Op>(), bad("f1"));
+    assert_eq!("I3".parse::<Op>(), bad("I3"));
+    assert_eq!("I3,".parse::<Op>(), bad("I3,"));
+    assert_eq!("R-1".parse::<Op>(), bad("R-1"));
+    assert_eq!("F".parse::<Op>(), bad("F"));
+    assert_eq!("X1".parse::<Op>(), bad("X1"));
+    assert_eq!("".parse::<Op>(), bad(""));
+    assert_eq!("F1 B2".parse::<Replay>(), Err(ParseError::NoTarget));
+    assert_eq!(
+        "linked9: F1".parse::<Replay>(),
+        Err(ParseError::UnknownTarget("linked9".to_string()))
+    );
+    assert_eq!(
+        "linked6: F1 Bx".parse::<Replay>(),
+        Err(ParseError::BadOp("Bx".to_string()))
+    );
+    assert_eq!(
+        ParseError::BadOp("Bx".to_string()).to_string(),
+        "can't read \"Bx\" as an operation"
+    );
+}
+
+#[test]
+fn test_find_failure() {
+    let ops = Op::random_sequence(3, 400);
+    for target in Target::ALL {
+        assert_eq!(find_failure(target, &ops), None);
+    }
+    /* A run that blows up at the first Nth(13) is cut right after it. */
+    let ops: Vec<Op> = (0..100).map(Op::Nth).chain((0..100).map(Op::Nth)).collect();
+    let run = |ops: &[Op]| assert!(!ops.contains(&Op::Nth(13)), "found it");
+    assert_eq!(failing_len(&ops, run), Some(14));
+    assert_eq!(failing_len(&ops[14..], run), Some(100));
+    assert_eq!(failing_len(&ops[14..100], run), None);
+}
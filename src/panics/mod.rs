@@ -0,0 +1,107 @@
+#![allow(dead_code)]
+/*
+Panics in the middle of a list operation
+===========================================================================
+
+Most of a list's code is ours, and ours doesn't panic halfway through
+relinking (if it did, that would be the bug). But some of it calls yours: the
+closure given to retain(), sort_by() or drain_filter(), the clone() behind
+append_slice(), the Ord a sorted list compares with. Any of those can panic,
+in the middle of a walk, with some nodes already moved and others not. Unless
+someone catches the panic, the list goes away with everything else, and all
+it needs then is to be dropped properly. With catch_unwind() though, it's
+still there afterwards, and it gets used.
+
+Nothing here can become undefined behaviour, even with raw pointers in the
+lists: every unsafe block finishes before our code calls yours. What the
+tests below make sure of is the rest:
+
+- The list left behind passes check(), or for the lists without one, its
+  len() is what iterating it finds. Whatever was changed before the panic
+  stays changed, it doesn't need to be rolled back. It just has to be a
+  list.
+
+- Every value is dropped exactly once: the ones that were in nodes the
+  operation was holding when the panic came, too. DropTracker counts them
+  (see src/leaks).
+
+How each list gets there:
+
+- linked4 takes the chain out of the list (take_chain()) before sort_by(),
+  retain() and dedup_by() run the closure. If it panics, the chain is
+  dropped on the way out and the list is simply empty. drain_filter() is
+  a cursor, which relinks one node at a time, so it stops with the nodes it
+  didn't get to still in the list. append_slice() clones everything before
+  it links anything.
+
+- linked5's closures get values, copied out between one relink and the next.
+  What it can't survive is a node borrowed through a handle from
+  iter_mut() while it relinks: borrow_mut() panics halfway. That's not
+  something a test can fix, so linked5::List isn't UnwindSafe.
+
+- The constructors (from_slice(), clone()) build a list of their own, which
+  nobody has seen yet. If a clone panics, that list is dropped, and the one
+  it was copying from never changed.
+
+- The sorted lists (skiplist, indexed) and selforg compare first, and change
+  the links only when they know where. A panicking Ord stops the search,
+  not the insert.
+
+- SyncList's Mutex is poisoned by a panic while it's locked. lock() ignores
+  that: the List inside is fine, for the reasons above.
+
+Bomb is the panicking user code: a DropTracker that blows up when cloned or
+compared, if its value is negative.
+*/
+use crate::leaks::DropTracker;
+use std::cmp::Ordering;
+
+pub struct Bomb(pub DropTracker);
+
+impl Bomb {
+    pub fn value(&self) -> i64 {
+        self.0.value()
+    }
+
+    fn defuse(&self) -> &DropTracker {
+        if self.value() < 0 {
+            panic!("boom");
+        }
+        &self.0
+    }
+}
+
+impl Clone for Bomb {
+    fn clone(&self) -> Self {
+        Bomb(self.defuse().clone())
+    }
+}
+
+impl PartialEq for Bomb {
+    fn eq(&self, other: &Self) -> bool {
+        self.defuse() == other.defuse()
+    }
+}
+
+impl Eq for Bomb {}
+
+impl PartialOrd for Bomb {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Bomb {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.defuse().cmp(other.defuse())
+    }
+}
+
+impl std::fmt::Debug for Bomb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,328 @@
+use super::*;
+use crate::invariants::CheckInvariants;
+use crate::leaks::Tracker;
+use crate::ops::LinkedListOps;
+use crate::{branded, indexed, linked4, linked5, linked6, linked7, linked8};
+use crate::{selforg, skiplist, sync, unrolled};
+use std::panic::{self, AssertUnwindSafe};
+
+const LEN: usize = 100;
+const HALF: i64 = LEN as i64 / 2;
+
+/* The closures below capture the list by &mut, which is never UnwindSafe:
+that's us promising the list is fine afterwards, which is what the tests
+then check. */
+fn assert_panics<R>(f: impl FnOnce() -> R) {
+    assert!(panic::catch_unwind(AssertUnwindSafe(f)).is_err());
+}
+
+fn values<'a>(iter: impl Iterator<Item = &'a DropTracker>) -> Vec<i64> {
+    iter.map(DropTracker::value).collect()
+}
+
+/* Values 0..len, with a Bomb at `at`. */
+fn bombs(tracker: &Tracker, len: usize, at: usize) -> Vec<Bomb> {
+    (0..len as i64)
+        .map(|value| match value as usize == at {
+            true => Bomb(tracker.track(-value)),
+            false => Bomb(tracker.track(value)),
+        })
+        .collect()
+}
+
+/* A from_slice() that blows up halfway drops what it built. */
+fn check_from_slice<L: LinkedListOps<Item = Bomb>>() {
+    let tracker = Tracker::new();
+    let slice = bombs(&tracker, LEN, LEN / 2);
+    assert_panics(|| L::from_slice(&slice));
+    assert_eq!(tracker.alive(), LEN);
+    drop(slice);
+    tracker.assert_no_leaks();
+}
+
+#[test]
+fn test_from_slice() {
+    check_from_slice::<linked4::List<Bomb>>();
+    check_from_slice::<linked4::DoublyList<Bomb>>();
+    check_from_slice::<linked6::List<Bomb>>();
+    check_from_slice::<linked6::SentinelList<Bomb>>();
+    check_from_slice::<linked7::List<Bomb>>();
+    check_from_slice::<linked8::List<Bomb>>();
+    check_from_slice::<unrolled::UnrolledList<Bomb>>();
+    check_from_slice::<indexed::IndexedList<Bomb>>();
+}
+
+/* The chain is out of the list while the closure runs, so the list comes
+back empty, and every node the sort or the walk was holding is dropped. */
+#[test]
+fn test_linked4_take_chain() {
+    let tracker = Tracker::new();
+    let mut list = linked4::List::from_slice(&tracker.values(LEN));
+    assert_panics(|| {
+        list.sort_by(|a, b| {
+            assert_ne!(a.value(), HALF);
+            b.cmp(a)
+        })
+    });
+    list.assert_check();
+    assert!(list.is_empty());
+    assert_eq!(tracker.alive(), 0);
+
+    list.append_slice(&tracker.values(LEN));
+    assert_panics(|| list.retain(|value| value.value() != HALF || panic!("halfway")));
+    list.assert_check();
+    assert_eq!(tracker.alive(), list.len());
+
+    list.append_slice(&tracker.values(LEN));
+    assert_panics(|| {
+        list.dedup_by(|next, _| {
+            assert_ne!(next.value(), HALF);
+            false
+        })
+    });
+    list.assert_check();
+    assert_eq!(tracker.alive(), list.len());
+
+    /* And it's a list like any other afterwards. */
+    list.push_back(tracker.track(1));
+    list.push_front(tracker.track(0));
+    list.assert_check();
+    assert_eq!(values(list.iter()), vec![0, 1]);
+    drop(list);
+    tracker.assert_no_leaks();
+}
+
+/* sort() compares with Ord, which is also somebody else's code. */
+#[test]
+fn test_linked4_sort_bomb() {
+    let tracker = Tracker::new();
+    let mut list = linked4::List::new(&[]);
+    for value in bombs(&tracker, LEN, LEN / 2).into_iter().rev() {
+        list.push_back(value);
+    }
+    assert_panics(|| list.sort());
+    list.assert_check();
+    drop(list);
+    tracker.assert_no_leaks();
+
+    let mut list = linked4::List::new(&[]);
+    for value in bombs(&tracker, LEN, LEN / 4) {
+        list.push_back(value);
+    }
+    assert_panics(|| list.remove_first_value(&Bomb(tracker.track(HALF))));
+    list.assert_check();
+    drop(list);
+    tracker.assert_no_leaks();
+}
+
+/* drain_filter() relinks one node at a time, so it stops with what it took
+gone and everything else still there, in order. */
+#[test]
+fn test_linked4_drain_filter() {
+    let tracker = Tracker::new();
+    let mut list = linked4::List::from_slice(&tracker.values(LEN));
+    assert_panics(|| {
+        list.drain_filter(|value| {
+            assert_ne!(value.value(), HALF);
+            value.value() % 2 == 0
+        })
+        .for_each(drop)
+    });
+    list.assert_check();
+    let want: Vec<i64> = (0..LEN as i64)
+        .filter(|value| *value >= HALF || value % 2 == 1)
+        .collect();
+    assert_eq!(values(list.iter()), want);
+    assert_eq!(tracker.alive(), want.len());
+    list.push_back(tracker.track(LEN as i64));
+    list.assert_check();
+    drop(list);
+    tracker.assert_no_leaks();
+}
+
+/* This one used to leave nodes hanging after the tail, with a len that
+didn't count them. */
+#[test]
+fn test_linked4_append_slice() {
+    let tracker = Tracker::new();
+    let slice = bombs(&tracker, LEN, LEN / 2);
+    let mut list = linked4::List::new(&slice[..3]);
+    assert_panics(|| list.append_slice(&slice));
+    list.assert_check();
+    assert_eq!(list.len(), 3);
+    list.push_back(Bomb(tracker.track(-1)));
+    assert_panics(|| list.clone());
+    list.assert_check();
+
+    let mut empty = linked4::List::new(&[]);
+    assert_panics(|| empty.append_slice(&slice));
+    empty.assert_check();
+    assert!(empty.is_empty());
+
+    list.append_slice(&slice[..LEN / 2]);
+    list.assert_check();
+    assert_eq!(list.len(), 4 + LEN / 2);
+    drop(slice);
+    drop(list);
+    tracker.assert_no_leaks();
+}
+
+#[test]
+fn test_linked6_clone() {
+    let tracker = Tracker::new();
+    let mut list = linked6::List::from_slice(&bombs(&tracker, LEN - 1, LEN));
+    list.push_back(Bomb(tracker.track(-1)));
+    assert_panics(|| list.clone());
+    list.assert_check();
+    assert_eq!(list.len(), LEN);
+    drop(list);
+    tracker.assert_no_leaks();
+}
+
+/* A linked5::List isn't RefUnwindSafe (see test_linked5_held_borrow), so
+even find() and fold() need AssertUnwindSafe. Closures panicking is fine. */
+#[test]
+fn test_linked5() {
+    let list = linked5::List::from_vec(&(0..LEN as i64).collect::<Vec<_>>());
+    assert_panics(|| list.find(|value| value == HALF && panic!("halfway")));
+    assert_panics(|| list.fold(0, |sum, value| sum + value / (HALF - value)));
+    list.assert_check();
+    assert_eq!(list.to_vec(), (0..LEN as i64).collect::<Vec<_>>());
+
+    /* dedup_by() gets halfway: the pairs before the panic are gone. */
+    let doubled: Vec<i64> = (0..LEN as i64).flat_map(|value| [value, value]).collect();
+    let mut list = linked5::List::from_vec(&doubled);
+    assert_panics(|| {
+        list.dedup_by(|next, kept| {
+            assert_ne!(kept, HALF);
+            next == kept
+        })
+    });
+    list.assert_check();
+    let want: Vec<i64> = (0..HALF)
+        .chain(doubled[2 * HALF as usize..].iter().copied())
+        .collect();
+    assert_eq!(list.to_vec(), want);
+    list.append(LEN as i64);
+    list.insert_first(-1);
+    list.assert_check();
+
+    /* partition() owns the list. If it panics, every node goes: the ones
+    still in it, the ones already moved, and the one in between. */
+    let nodes = list.weak_nodes();
+    assert_panics(|| list.partition(|value| value != HALF || panic!("halfway")));
+    assert!(nodes.iter().all(|node| node.upgrade().is_none()));
+}
+
+/* What a node borrowed from outside does to it: the handles from iter_mut()
+don't borrow the list, so one can still be borrowed when the list relinks
+it. The relink panics halfway, and the list left behind is broken. This is
+why linked5::List isn't UnwindSafe. */
+#[test]
+fn test_linked5_held_borrow() {
+    let mut list = linked5::List::from_vec(&[0, 1, 2, 3]);
+    let nodes: Vec<_> = list.iter_mut().collect();
+    assert_panics(|| {
+        let _held = nodes[1].borrow();
+        list.swap_nodes(&nodes[1], &nodes[2]);
+    });
+    assert!(list.check().is_err());
+
+    let mut list = linked5::List::from_vec(&[0, 1, 2, 3]);
+    let second = list.iter_mut().nth(1).unwrap();
+    assert_panics(|| {
+        let _held = second.borrow();
+        list.pop_first();
+    });
+    assert!(list.check().is_err());
+}
+
+/* Searches compare first and relink after, so a panicking Ord leaves them
+as they were. */
+#[test]
+fn test_sorted() {
+    let tracker = Tracker::new();
+    let mut list = skiplist::SkipList::with_seed(5);
+    for value in bombs(&tracker, LEN, LEN) {
+        list.insert(value);
+    }
+    assert_panics(|| list.insert(Bomb(tracker.track(-HALF))));
+    assert_panics(|| list.contains(&Bomb(tracker.track(-1))));
+    assert_eq!(list.len(), LEN);
+    assert_eq!(list.iter().count(), LEN);
+    assert!(list.iter().zip(list.iter().skip(1)).all(|(a, b)| a < b));
+    assert!(list.insert(Bomb(tracker.track(LEN as i64))));
+    drop(list);
+    tracker.assert_no_leaks();
+
+    let mut list = indexed::IndexedList::with_seed(5);
+    for value in bombs(&tracker, LEN, LEN) {
+        list.push_back(value);
+    }
+    assert_panics(|| list.insert_sorted(Bomb(tracker.track(-HALF))));
+    assert_eq!(list.len(), LEN);
+    assert_eq!(list.iter().count(), LEN);
+    assert_eq!(list.insert_sorted(Bomb(tracker.track(HALF))), HALF as usize);
+    drop(list);
+    tracker.assert_no_leaks();
+
+    let mut list = selforg::SelfOrgList::new(selforg::Strategy::MoveToFront);
+    for value in bombs(&tracker, LEN, LEN) {
+        list.push_back(value);
+    }
+    assert_panics(|| list.find(&Bomb(tracker.track(-1))));
+    assert_eq!(list.len(), LEN);
+    assert_eq!(list.find(&Bomb(tracker.track(HALF))), Some(HALF as usize));
+    assert_eq!(list.iter().next().map(Bomb::value), Some(HALF));
+    drop(list);
+    tracker.assert_no_leaks();
+}
+
+/* for_each_mut() stops where the closure panicked: the values before it are
+the new ones, the old ones were dropped when they were replaced. */
+#[test]
+fn test_branded() {
+    let tracker = Tracker::new();
+    branded::GhostToken::new(|mut token| {
+        let mut list = branded::List::new();
+        for value in tracker.values(LEN) {
+            list.push_back(value, &mut token);
+        }
+        assert_panics(|| {
+            list.for_each_mut(&mut token, |value| {
+                assert_ne!(value.value(), HALF);
+                *value = tracker.track(-value.value());
+            })
+        });
+        assert_eq!(list.len(), LEN);
+        let want: Vec<i64> = (0..LEN as i64)
+            .map(|value| if value < HALF { -value } else { value })
+            .collect();
+        assert_eq!(values(list.iter(&token)), want);
+        assert_eq!(tracker.alive(), LEN);
+    });
+    tracker.assert_no_leaks();
+}
+
+/* A panic with the lock held poisons the Mutex, and the list inside is
+still whole. SyncList is RefUnwindSafe, like std's Mutex. */
+#[test]
+fn test_sync() {
+    let tracker = Tracker::new();
+    let list = sync::SyncList::new();
+    for value in tracker.values(LEN) {
+        list.push_back(value);
+    }
+    let result = panic::catch_unwind(|| {
+        let mut inner = list.lock();
+        inner.pop_front();
+        inner.push_back(tracker.track(LEN as i64));
+        panic!("with the lock held");
+    });
+    assert!(result.is_err());
+    list.lock().assert_check();
+    assert_eq!(list.len(), LEN);
+    assert_eq!(list.pop_front().map(|value| value.value()), Some(1));
+    drop(list);
+    tracker.assert_no_leaks();
+}
@@ -0,0 +1,263 @@
+#![allow(dead_code)]
+/*
+Workloads for a profiler
+===========================================================================
+
+linked4's List got its tail pointer because a profiler said so: concatenating
+in a loop spent all its time walking to the tail (see the comment above
+linked4::List). That run lived in main.rs, commented out, because it needed
+cpuprofiler, and cpuprofiler needs gperftools installed to even build:
+
+    sudo apt install google-perftools libgoogle-perftools-dev
+
+So it's optional now. Building with `--features profiling` brings it in, and
+the CLI can run any of the workloads here under it:
+
+    cargo run --release --features profiling -- profile linked5-concat
+    pprof --web target/release/crappylinkedlists linked5-concat.profile
+
+`-- profile` alone lists them. Without the feature the same command still
+runs the workload, and only says how long it took.
+
+Use --release. In debug builds every list checks itself after every change
+(see invariants), and that's all a profile would show.
+
+Each workload is the hot path of one list, a plain function that takes a
+scale and returns a checksum. The scale is what the tests turn down to run
+them all quickly; the checksum is there so the work can't be optimized away,
+and so the tests have something to check.
+*/
+use crate::differential::Lcg;
+use crate::ops::LinkedListOps;
+use crate::{circular, indexed, linked4, linked5, linked6, linked7, linked8};
+use crate::{selforg, skiplist, unrolled};
+use std::iter::FromIterator;
+
+pub struct Profile {
+    pub name: &'static str,
+    pub about: &'static str,
+    pub run: fn(usize) -> i64,
+}
+
+/* What the CLI runs with: about a second each, in release. cpuprofiler
+samples 100 times a second, so that's a hundred samples or so. */
+pub const DEFAULT_SCALE: usize = 100;
+
+pub const PROFILES: &[Profile] = &[
+    Profile {
+        name: "linked4-concat",
+        about: "concat_copy() in a loop, the original profile",
+        run: linked4_concat,
+    },
+    Profile {
+        name: "linked4-sort",
+        about: "merge sort by relinking",
+        run: linked4_sort,
+    },
+    Profile {
+        name: "linked5-concat",
+        about: "from_vec() and concat(), moving the Rc nodes over",
+        run: linked5_concat,
+    },
+    Profile {
+        name: "linked5-iter",
+        about: "walking the Rc<RefCell> chain",
+        run: linked5_iter,
+    },
+    Profile {
+        name: "linked6-queue",
+        about: "push_back() and pop_front(), a Box per value",
+        run: linked6_queue,
+    },
+    Profile {
+        name: "linked7-handles",
+        about: "insert_after() and remove() through handles",
+        run: linked7_handles,
+    },
+    Profile {
+        name: "linked8-queue",
+        about: "push_back() and pop_front() on the singly linked queue",
+        run: linked8_queue,
+    },
+    Profile {
+        name: "circular-rotate",
+        about: "rotate() around the ring",
+        run: circular_rotate,
+    },
+    Profile {
+        name: "unrolled-insert",
+        about: "insert() at scattered indices, splitting chunks",
+        run: unrolled_insert,
+    },
+    Profile {
+        name: "skiplist-search",
+        about: "insert() and contains() on random values",
+        run: skiplist_search,
+    },
+    Profile {
+        name: "indexed-get",
+        about: "get() at scattered indices",
+        run: indexed_get,
+    },
+    Profile {
+        name: "selforg-find",
+        about: "find() with skewed keys, moving them to the front",
+        run: selforg_find,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Profile> {
+    PROFILES.iter().find(|profile| profile.name == name)
+}
+
+fn shuffled(len: usize) -> Vec<i64> {
+    let mut rng = Lcg::new(len as u64);
+    (0..len)
+        .map(|_| ((rng.next_u64() >> 33) % 1_000_000) as i64)
+        .collect()
+}
+
+/* The one that was in main.rs: 29 values, repeated `scale` times, and
+concatenated to the end of the list `scale * 10` times. */
+fn linked4_concat(scale: usize) -> i64 {
+    let data_prev = vec![
+        3, 8, 1, 2, 9, 5, 12, 6, 3, 1, 0, 7, 6, 5, 4, 3, 1, 6, 8, 9, 5, 3, 2, 1, 5, 7, 8, 4, 6,
+    ];
+    let data = data_prev.repeat(scale);
+    let mut list = linked4::List::new(&data);
+    for _ in 0..scale * 10 {
+        list.concat_copy(&linked4::List::new(&data));
+    }
+    list.iter().sum()
+}
+
+fn linked4_sort(scale: usize) -> i64 {
+    let mut list = linked4::List::new(&shuffled(scale * 10_000));
+    list.sort();
+    list.get(list.len() / 2).copied().unwrap_or(0)
+}
+
+/* In rounds, or a million Rcs would be most of the memory of the machine
+by the end. */
+fn linked5_concat(scale: usize) -> i64 {
+    let data = shuffled(1000);
+    let mut len = 0;
+    for _ in 0..scale {
+        let mut list = linked5::List::new();
+        for _ in 0..100 {
+            list.concat(linked5::List::from_vec(&data));
+        }
+        len += list.len() as i64;
+    }
+    len
+}
+
+fn linked5_iter(scale: usize) -> i64 {
+    let list = linked5::List::from_vec(&shuffled(1000));
+    (0..scale * 1000).map(|_| list.iter().sum::<i64>()).sum()
+}
+
+/* A queue that stays at QUEUE_LEN values: what goes in at the back pushes
+the oldest one out at the front. */
+const QUEUE_LEN: usize = 1000;
+
+fn linked6_queue(scale: usize) -> i64 {
+    let mut list = linked6::List::new();
+    let mut sum = 0;
+    for value in 0..(scale * 400_000) as i64 {
+        list.push_back(value);
+        if list.len() > QUEUE_LEN {
+            sum += list.pop_front().unwrap_or(0);
+        }
+    }
+    sum
+}
+
+fn linked7_handles(scale: usize) -> i64 {
+    let mut rng = Lcg::new(7);
+    let mut list = linked7::List::new();
+    let mut handles = vec![list.push_back(0)];
+    for value in 1..(scale * 20_000) as i64 {
+        /* handles only has the nodes still in the list, so none is stale. */
+        let at = (rng.next_u64() >> 33) as usize % handles.len();
+        handles.push(list.insert_after(handles[at], value).unwrap());
+        if value % 4 == 0 {
+            let at = (rng.next_u64() >> 33) as usize % handles.len();
+            list.remove(handles.swap_remove(at));
+        }
+    }
+    list.iter().sum()
+}
+
+fn linked8_queue(scale: usize) -> i64 {
+    let mut list = linked8::List::new();
+    let mut sum = 0;
+    for value in 0..(scale * 400_000) as i64 {
+        list.push_back(value);
+        if list.len() > QUEUE_LEN {
+            sum += list.pop_front().unwrap_or(0);
+        }
+    }
+    sum
+}
+
+fn circular_rotate(scale: usize) -> i64 {
+    let mut list = circular::CircularList::from_iter(0..1000i64);
+    let mut sum = 0;
+    for step in 0..(scale * 1_000_000) as isize {
+        list.rotate(step % 17 - 8);
+        sum += list.current().copied().unwrap_or(0);
+    }
+    sum
+}
+
+fn unrolled_insert(scale: usize) -> i64 {
+    let mut rng = Lcg::new(7);
+    let mut list: unrolled::UnrolledList<i64> = unrolled::UnrolledList::new();
+    for value in 0..(scale * 1000) as i64 {
+        let at = (rng.next_u64() >> 33) as usize % (list.len() + 1);
+        list.insert(at, value);
+    }
+    list.get(list.len() / 2).copied().unwrap_or(0)
+}
+
+fn skiplist_search(scale: usize) -> i64 {
+    let values = shuffled(scale * 3000);
+    let mut list = skiplist::SkipList::with_seed(7);
+    for value in &values {
+        list.insert(*value);
+    }
+    values
+        .iter()
+        .filter(|value| list.contains(&(*value + 1)))
+        .count() as i64
+}
+
+fn indexed_get(scale: usize) -> i64 {
+    let mut rng = Lcg::new(7);
+    let list = indexed::IndexedList::from_slice(&shuffled(10_000));
+    (0..scale * 30_000)
+        .map(|_| {
+            list.get((rng.next_u64() >> 33) as usize % list.len())
+                .copied()
+                .unwrap_or(0)
+        })
+        .sum()
+}
+
+/* Most lookups go to a few keys, which is what move-to-front is for. */
+fn selforg_find(scale: usize) -> i64 {
+    let mut rng = Lcg::new(7);
+    let mut list = selforg::SelfOrgList::new(selforg::Strategy::MoveToFront);
+    list.extend(0..256i64);
+    (0..scale * 100_000)
+        .map(|_| {
+            let key = ((rng.next_u64() >> 33) % 256) as i64;
+            let key = if key % 4 == 0 { key } else { key % 8 };
+            list.find(&key).unwrap_or(0) as i64
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,34 @@
+use super::*;
+
+#[test]
+fn test_names() {
+    let mut names: Vec<&str> = PROFILES.iter().map(|profile| profile.name).collect();
+    names.sort_unstable();
+    names.dedup();
+    assert_eq!(names.len(), PROFILES.len());
+    assert!(names.iter().all(|name| !name.contains(' ')));
+    assert_eq!(find("linked5-concat").unwrap().name, "linked5-concat");
+    assert!(find("linked9").is_none());
+}
+
+/* All of them, small, twice: the checksums can't change between runs. */
+#[test]
+fn test_run_all() {
+    for profile in PROFILES {
+        let checksum = (profile.run)(1);
+        assert_eq!((profile.run)(1), checksum, "{}", profile.name);
+    }
+}
+
+#[test]
+fn test_checksums() {
+    /* 29 values adding up to 140, repeated `scale` times: the list and ten
+    concats per scale. */
+    assert_eq!(linked4_concat(1), 11 * 140);
+    assert_eq!(linked4_concat(2), 21 * 2 * 140);
+    assert_eq!(linked5_concat(2), 2 * 100 * 1000);
+    /* Everything but the last QUEUE_LEN values went out the front. */
+    let popped: i64 = (0..400_000 - QUEUE_LEN as i64).sum();
+    assert_eq!(linked6_queue(1), popped);
+    assert_eq!(linked8_queue(1), popped);
+}
@@ -0,0 +1,86 @@
+#![allow(dead_code)]
+/*
+Property tests, with proptest
+===========================================================================
+
+The unit tests check examples: this list, reversed, is that list. Property
+tests check laws, for lists that proptest makes up: any list, reversed
+twice, is itself. When one fails, proptest shrinks it, removing values and
+making them smaller while it still fails, and reports the smallest one. A
+failure on a 40 value list usually comes back as [0, 1] or so.
+
+Arbitrary is how proptest knows how to make up a value of a type. Here it's
+implemented for the lists themselves (any Vec of values, built into a list)
+and for the differential harness' Op, so a whole sequence of operations can
+be made up, run against the VecDeque oracle, and shrunk to the few steps
+that matter.
+
+This whole module only exists for `cargo test`: proptest is a
+dev-dependency, so nothing here gets into the library.
+*/
+use crate::differential::Op;
+use crate::{linked4, linked5, linked6};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use std::fmt::Debug;
+
+/* Long enough to have a middle, short enough to shrink fast. */
+pub const MAX_LEN: usize = 64;
+
+impl Arbitrary for Op {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Op>;
+
+    /* Small values and positions, so duplicates and both ends come up
+    often. Positions wrap around the len anyway, see differential. */
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let value = -100i64..100;
+        let at = 0usize..MAX_LEN;
+        prop_oneof![
+            value.clone().prop_map(Op::PushFront),
+            value.clone().prop_map(Op::PushBack),
+            Just(Op::PopFront),
+            Just(Op::PopBack),
+            (at.clone(), value).prop_map(|(at, value)| Op::Insert(at, value)),
+            at.clone().prop_map(Op::Remove),
+            at.prop_map(Op::Nth),
+        ]
+        .boxed()
+    }
+}
+
+impl<T: Arbitrary + Clone + Debug + 'static> Arbitrary for linked4::List<T> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        vec(any::<T>(), 0..MAX_LEN)
+            .prop_map(|values| linked4::List::new(&values))
+            .boxed()
+    }
+}
+
+impl Arbitrary for linked5::List {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        vec(any::<i64>(), 0..MAX_LEN)
+            .prop_map(|values| linked5::List::from_vec(&values))
+            .boxed()
+    }
+}
+
+impl<T: Arbitrary + Debug + 'static> Arbitrary for linked6::List<T> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        vec(any::<T>(), 0..MAX_LEN)
+            .prop_map(|values| values.into_iter().collect())
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod test;
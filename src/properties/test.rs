@@ -0,0 +1,166 @@
+use super::*;
+use crate::differential::{run_deque, run_positional};
+use crate::{hybrid, indexed, unrolled};
+
+fn is_sorted<T: Ord>(values: &[T]) -> bool {
+    values.windows(2).all(|w| w[0] <= w[1])
+}
+
+/* The same multiset: sorting both sides makes the order not matter. */
+fn is_permutation<T: Ord + Clone>(a: &[T], b: &[T]) -> bool {
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort();
+    b.sort();
+    a == b
+}
+
+proptest! {
+    #[test]
+    fn linked4_reverse_twice_is_identity(mut list in any::<linked4::List<i64>>()) {
+        let original = list.to_vec();
+        list.reverse();
+        let mut reversed = original.clone();
+        reversed.reverse();
+        prop_assert_eq!(list.to_vec(), reversed);
+        list.reverse();
+        prop_assert_eq!(list.to_vec(), original.clone());
+        prop_assert_eq!(list.len(), original.len());
+    }
+
+    /* u8 values, so there are plenty of equal ones to keep in order. */
+    #[test]
+    fn linked4_sort_is_sorted_permutation(mut list in any::<linked4::List<u8>>()) {
+        let original = list.to_vec();
+        list.sort();
+        let sorted = list.to_vec();
+        prop_assert!(is_sorted(&sorted));
+        prop_assert!(is_permutation(&sorted, &original));
+        prop_assert_eq!(list.len(), original.len());
+    }
+
+    #[test]
+    fn linked4_concat_adds_lengths(
+        mut a in any::<linked4::List<i64>>(),
+        b in any::<linked4::List<i64>>(),
+    ) {
+        let expected: Vec<i64> = a.iter().chain(b.iter()).copied().collect();
+        a.concat_copy(&b);
+        prop_assert_eq!(a.len(), expected.len());
+        prop_assert_eq!(a.to_vec(), expected);
+    }
+
+    #[test]
+    fn linked4_split_off_and_concat_is_identity(
+        mut list in any::<linked4::List<i64>>(),
+        at in 0..=MAX_LEN,
+    ) {
+        let original = list.to_vec();
+        let at = at % (original.len() + 1);
+        let rest = list.split_off(at);
+        prop_assert_eq!(list.to_vec(), original[..at].to_vec());
+        prop_assert_eq!(rest.to_vec(), original[at..].to_vec());
+        list.concat_copy(&rest);
+        prop_assert_eq!(list.to_vec(), original);
+    }
+
+    /* linked5 has no plain reverse(), but reversing every group of k is its
+    own inverse too, for any k. */
+    #[test]
+    fn linked5_reverse_chunks_twice_is_identity(
+        mut list in any::<linked5::List>(),
+        k in 1usize..8,
+    ) {
+        let original = list.to_vec();
+        list.reverse_chunks(k);
+        prop_assert!(is_permutation(&list.to_vec(), &original));
+        list.reverse_chunks(k);
+        prop_assert_eq!(list.to_vec(), original);
+        list.assert_invariants();
+    }
+
+    #[test]
+    fn linked5_sort_is_sorted_permutation(mut list in any::<linked5::List>()) {
+        let original = list.to_vec();
+        list.sort();
+        let sorted = list.to_vec();
+        prop_assert!(is_sorted(&sorted));
+        prop_assert!(is_permutation(&sorted, &original));
+        list.assert_invariants();
+    }
+
+    #[test]
+    fn linked5_concat_adds_lengths(
+        mut a in any::<linked5::List>(),
+        b in any::<linked5::List>(),
+    ) {
+        let expected: Vec<i64> = a.iter().chain(b.iter()).collect();
+        a.concat(b);
+        prop_assert_eq!(a.len(), expected.len());
+        prop_assert_eq!(a.to_vec(), expected);
+        a.assert_invariants();
+    }
+
+    #[test]
+    fn linked5_split_at_and_concat_is_identity(
+        list in any::<linked5::List>(),
+        at in 0..=MAX_LEN,
+    ) {
+        let original = list.to_vec();
+        let at = at % (original.len() + 1);
+        let (mut left, right) = list.split_at(at);
+        prop_assert_eq!(left.len(), at);
+        prop_assert_eq!(right.to_vec(), original[at..].to_vec());
+        left.concat(right);
+        prop_assert_eq!(left.to_vec(), original);
+        left.assert_invariants();
+    }
+
+    #[test]
+    fn linked6_reverse_twice_is_identity(mut list in any::<linked6::List<i64>>()) {
+        let original = list.to_vec();
+        list.reverse();
+        prop_assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), original.clone());
+        list.reverse();
+        prop_assert_eq!(list.to_vec(), original);
+    }
+
+    #[test]
+    fn linked6_append_adds_lengths(
+        mut a in any::<linked6::List<i64>>(),
+        mut b in any::<linked6::List<i64>>(),
+    ) {
+        let expected: Vec<i64> = a.iter().chain(b.iter()).copied().collect();
+        a.append(&mut b);
+        prop_assert!(b.is_empty());
+        prop_assert_eq!(a.len(), expected.len());
+        prop_assert_eq!(a.to_vec(), expected.clone());
+        prop_assert_eq!(a.iter().rev().count(), expected.len());
+    }
+
+    #[test]
+    fn linked6_split_off_and_append_is_identity(
+        mut list in any::<linked6::List<i64>>(),
+        at in 0..=MAX_LEN,
+    ) {
+        let original = list.to_vec();
+        let at = at % (original.len() + 1);
+        let mut rest = list.split_off(at);
+        prop_assert_eq!(list.len() + rest.len(), original.len());
+        list.append(&mut rest);
+        prop_assert_eq!(list.to_vec(), original);
+    }
+
+    /* The differential harness, with made up operations. If a list ever
+    disagrees with the VecDeque, this shrinks it down to a few steps. */
+    #[test]
+    fn ops_match_vecdeque(ops in vec(any::<Op>(), 0..200)) {
+        run_positional::<linked4::List<i64>>(&ops);
+        run_positional::<linked6::List<i64>>(&ops);
+        run_positional::<linked6::SentinelList<i64>>(&ops);
+        run_positional::<unrolled::UnrolledList<i64, 4>>(&ops);
+        run_positional::<indexed::IndexedList<i64>>(&ops);
+        run_positional::<hybrid::HybridList<i64, 4>>(&ops);
+        run_deque::<linked5::List>(&ops);
+    }
+}
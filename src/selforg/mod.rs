@@ -0,0 +1,302 @@
+#![allow(dead_code)]
+/*
+Self-organizing lists: let the lookups sort the list
+===========================================================================
+
+Searching a list is O(n) and there's nothing to be done about that, unless
+we can keep it sorted, as in the skip list. But in practice not every
+value is looked up equally often. A few values tend to be asked for all the
+time, and most of them hardly ever (caches, symbol tables, the words of any
+text...). If the popular ones were at the front, most searches would stop
+after a couple of steps.
+
+We don't know which ones are popular beforehand, but the list can find out
+as it goes: every time a value is found, move it towards the front.
+
+- MoveToFront: the found node goes straight to the front. It adapts very
+  quickly, but one lookup of a rare value also throws it all the way up.
+- Transpose: the found node swaps places with the one before it. It's much
+  slower to adapt, but a single odd lookup barely changes anything.
+- Static: nothing moves, as a baseline.
+
+It's all safe code on a Box chain like linked4, because moving a node is
+just unlinking a Box and linking it back somewhere else. Each node also
+counts how many times it was found, and the list counts how many values it
+compared in total, which is the real cost of a search.
+
+The selforg benchmarks in benches/benchmark.rs look up values with a skewed
+distribution (value k is asked for about twice as often as k + 1), and also
+with a uniform one, where moving things around can't help and only costs.
+With 256 values, the skewed lookups are about 40 times faster with either
+MoveToFront or Transpose than with Static, once the list has settled. The
+uniform ones are about twice as slow: moving a node walks the list a second
+time, and the shuffled nodes end up all over memory, so the scans stop being
+cache-friendly.
+*/
+use crate::footprint::HeapSize;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::mem::size_of;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    MoveToFront,
+    Transpose,
+    Static,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub lookups: u64,
+    pub misses: u64,
+    /* Every value looked at, in every lookup. */
+    pub comparisons: u64,
+}
+
+impl Stats {
+    pub fn average_comparisons(&self) -> f64 {
+        if self.lookups == 0 {
+            return 0.0;
+        }
+        self.comparisons as f64 / self.lookups as f64
+    }
+}
+
+struct Node<T> {
+    value: T,
+    hits: u64,
+    next: Option<Box<Node<T>>>,
+}
+
+pub struct SelfOrgList<T> {
+    head: Option<Box<Node<T>>>,
+    len: usize,
+    strategy: Strategy,
+    stats: Stats,
+}
+
+impl<T> SelfOrgList<T> {
+    pub fn new(strategy: Strategy) -> Self {
+        SelfOrgList {
+            head: None,
+            len: 0,
+            strategy,
+            stats: Stats::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = Stats::default();
+    }
+
+    pub fn clear(&mut self) {
+        let mut cur = self.head.take();
+        while let Some(mut node) = cur {
+            cur = node.next.take();
+        }
+        self.len = 0;
+    }
+
+    /* The link (the Option<Box> that points to it) of the node at `index`,
+    or the empty link at the end when index == len. */
+    fn link_at(&mut self, index: usize) -> &mut Option<Box<Node<T>>> {
+        let mut link = &mut self.head;
+        for _ in 0..index {
+            link = &mut link.as_mut().expect("index out of range").next;
+        }
+        link
+    }
+
+    fn take_at(&mut self, index: usize) -> Box<Node<T>> {
+        let link = self.link_at(index);
+        let mut node = link.take().expect("index out of range");
+        *link = node.next.take();
+        node
+    }
+
+    fn put_at(&mut self, index: usize, mut node: Box<Node<T>>) {
+        let link = self.link_at(index);
+        node.next = link.take();
+        *link = Some(node);
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        let node = Box::new(Node {
+            value,
+            hits: 0,
+            next: None,
+        });
+        self.put_at(0, node);
+        self.len += 1;
+    }
+
+    /* New values start at the back: they have to earn their place. */
+    pub fn push_back(&mut self, value: T) {
+        let node = Box::new(Node {
+            value,
+            hits: 0,
+            next: None,
+        });
+        self.put_at(self.len, node);
+        self.len += 1;
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            cursor: self.head.as_deref(),
+            remaining: self.len,
+        }
+    }
+
+    /* Values with how many times each one was found, in list order. */
+    pub fn iter_hits(&self) -> impl Iterator<Item = (&T, u64)> + '_ {
+        std::iter::successors(self.head.as_deref(), |node| node.next.as_deref())
+            .map(|node| (&node.value, node.hits))
+    }
+
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq> SelfOrgList<T> {
+    /* Where the value is, without counting it as a lookup or moving it. */
+    pub fn position(&self, value: &T) -> Option<usize> {
+        self.iter().position(|v| v == value)
+    }
+
+    pub fn hits(&self, value: &T) -> Option<u64> {
+        self.iter_hits()
+            .find(|(v, _)| *v == value)
+            .map(|(_, hits)| hits)
+    }
+
+    /* Looks the value up, and moves it forward according to the strategy.
+    Returns where it was found, before moving it: that's what this lookup
+    cost. */
+    pub fn find(&mut self, value: &T) -> Option<usize> {
+        self.stats.lookups += 1;
+        let mut found = None;
+        let mut cur = self.head.as_deref_mut();
+        let mut index = 0;
+        while let Some(node) = cur {
+            self.stats.comparisons += 1;
+            if node.value == *value {
+                node.hits += 1;
+                found = Some(index);
+                break;
+            }
+            cur = node.next.as_deref_mut();
+            index += 1;
+        }
+        let index = match found {
+            Some(index) => index,
+            None => {
+                self.stats.misses += 1;
+                return None;
+            }
+        };
+        if index > 0 {
+            match self.strategy {
+                Strategy::MoveToFront => {
+                    let node = self.take_at(index);
+                    self.put_at(0, node);
+                }
+                Strategy::Transpose => {
+                    let node = self.take_at(index);
+                    self.put_at(index - 1, node);
+                }
+                Strategy::Static => {}
+            }
+        }
+        Some(index)
+    }
+
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        let index = self.position(value)?;
+        self.len -= 1;
+        Some(self.take_at(index).value)
+    }
+}
+
+impl<T> Drop for SelfOrgList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> HeapSize for SelfOrgList<T> {
+    fn heap_size(&self) -> usize {
+        self.len * size_of::<Node<T>>()
+    }
+}
+
+pub struct Iter<'a, T> {
+    cursor: Option<&'a Node<T>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.cursor?;
+        self.cursor = node.next.as_deref();
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+impl<T> Extend<T> for SelfOrgList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        /* Walk to the end once, not once per value. */
+        let mut link = &mut self.head;
+        while let Some(node) = link {
+            link = &mut node.next;
+        }
+        for value in iter {
+            let node = link.insert(Box::new(Node {
+                value,
+                hits: 0,
+                next: None,
+            }));
+            link = &mut node.next;
+            self.len += 1;
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SelfOrgList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,102 @@
+use super::*;
+use crate::differential::Lcg;
+
+fn list_of(strategy: Strategy, n: i64) -> SelfOrgList<i64> {
+    let mut list = SelfOrgList::new(strategy);
+    list.extend(0..n);
+    list
+}
+
+#[test]
+fn test_move_to_front() {
+    let mut list = list_of(Strategy::MoveToFront, 5);
+    assert_eq!(list.find(&3), Some(3));
+    assert_eq!(list.to_vec(), vec![3, 0, 1, 2, 4]);
+    assert_eq!(list.find(&4), Some(4));
+    assert_eq!(list.find(&4), Some(0));
+    assert_eq!(list.to_vec(), vec![4, 3, 0, 1, 2]);
+    assert_eq!(list.find(&9), None);
+    assert_eq!(list.hits(&4), Some(2));
+    assert_eq!(list.hits(&0), Some(0));
+    assert_eq!(
+        list.stats(),
+        Stats {
+            lookups: 4,
+            misses: 1,
+            comparisons: 4 + 5 + 1 + 5
+        }
+    );
+}
+
+#[test]
+fn test_transpose() {
+    let mut list = list_of(Strategy::Transpose, 5);
+    assert_eq!(list.find(&3), Some(3));
+    assert_eq!(list.to_vec(), vec![0, 1, 3, 2, 4]);
+    assert_eq!(list.find(&3), Some(2));
+    assert_eq!(list.find(&3), Some(1));
+    assert_eq!(list.find(&3), Some(0));
+    assert_eq!(list.find(&3), Some(0));
+    assert_eq!(list.to_vec(), vec![3, 0, 1, 2, 4]);
+    assert_eq!(list.hits(&3), Some(5));
+}
+
+#[test]
+fn test_static() {
+    let mut list = list_of(Strategy::Static, 5);
+    for _ in 0..3 {
+        assert_eq!(list.find(&4), Some(4));
+    }
+    assert_eq!(list.to_vec(), vec![0, 1, 2, 3, 4]);
+    assert_eq!(list.stats().average_comparisons(), 5.0);
+    list.reset_stats();
+    assert_eq!(list.stats(), Stats::default());
+}
+
+#[test]
+fn test_push_remove() {
+    let mut list = SelfOrgList::new(Strategy::MoveToFront);
+    list.push_back("b");
+    list.push_front("a");
+    list.push_back("c");
+    assert_eq!(list.len(), 3);
+    assert_eq!(format!("{:?}", list), r#"["a", "b", "c"]"#);
+    assert_eq!(list.remove(&"b"), Some("b"));
+    assert_eq!(list.remove(&"b"), None);
+    assert_eq!(list.position(&"c"), Some(1));
+    assert_eq!(list.iter().len(), 2);
+}
+
+/* Value k is asked for about twice as often as k + 1. */
+fn skewed_lookups(list: &mut SelfOrgList<i64>, count: usize) -> f64 {
+    let mut rng = Lcg::new(1);
+    for _ in 0..count {
+        let key = (rng.next_u64() >> 11).trailing_ones() as i64;
+        list.find(&key);
+    }
+    list.stats().average_comparisons()
+}
+
+#[test]
+fn test_skewed_lookups_get_cheaper() {
+    // The popular values start at the back, the worst case for Static.
+    let mut lists: Vec<SelfOrgList<i64>> =
+        [Strategy::Static, Strategy::Transpose, Strategy::MoveToFront]
+            .iter()
+            .map(|s| {
+                let mut list = SelfOrgList::new(*s);
+                list.extend((0..64).rev());
+                list
+            })
+            .collect();
+    let costs: Vec<f64> = lists
+        .iter_mut()
+        .map(|list| skewed_lookups(list, 5000))
+        .collect();
+    assert!(costs[0] > 60.0);
+    assert!(costs[1] < costs[0] / 4.0);
+    assert!(costs[2] < costs[0] / 4.0);
+    // In the end the most popular value is at the front.
+    assert_eq!(lists[2].position(&0), Some(0));
+    assert_eq!(lists[1].iter().next(), Some(&0));
+}
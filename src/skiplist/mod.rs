@@ -0,0 +1,368 @@
+#![allow(dead_code)]
+/*
+Skip lists: when a linked list needs to search
+===========================================================================
+
+Finding a value in any list of this crate means walking from the front until
+we hit it: O(n), sorted or not. A sorted Vec can binary search because it can
+jump to the middle, and a list can't jump anywhere.
+
+Unless we give it somewhere to jump to. A skip list is a sorted linked list
+where some nodes also have a second next pointer that skips ahead, to the
+next node that also has a second pointer. Some of those have a third one,
+skipping even further, and so on. Each level is a sorted linked list of its
+own, with about half the nodes of the level below.
+
+Searching starts at the top level, where there are very few nodes, and runs
+along it until the next one would be too far. Then it goes one level down
+and does the same, and so on, until level 0. That's about two steps per
+level, and there are about log2(n) levels, so the search is O(log n), just
+like a binary search or a BTreeMap.
+
+Who gets how many levels is decided by flipping coins when a node is
+inserted: one level for sure, a second one with probability 1/2, a third one
+with 1/4... No rebalancing is ever needed, unlike with trees. The price is
+that O(log n) is only what we get on average. With very bad luck (or if
+someone knows the seed, which is fixed unless with_seed() is used) it can
+degrade to a plain list.
+
+The nodes are linked like linked6, with NonNull, and each node holds its
+tower of next pointers in a Vec, one per level it's part of. It's an ordered
+set: inserting a value that's already there does nothing.
+*/
+use crate::footprint::HeapSize;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ops::{Bound, RangeBounds};
+use std::ptr::NonNull;
+
+/* 2^32 values before the levels run out, far more than will fit in memory. */
+const MAX_LEVEL: usize = 32;
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+struct Node<T> {
+    value: T,
+    next: Vec<Link<T>>,
+}
+
+pub struct SkipList<T> {
+    /* The head is like a node without value that is part of every level. */
+    head: [Link<T>; MAX_LEVEL],
+    /* How many levels are in use. Only those are searched. */
+    levels: usize,
+    len: usize,
+    rng: u64,
+    _boxes: PhantomData<Box<Node<T>>>,
+}
+
+unsafe impl<T: Send> Send for SkipList<T> {}
+unsafe impl<T: Sync> Sync for SkipList<T> {}
+
+impl<T> Default for SkipList<T> {
+    fn default() -> Self {
+        Self::with_seed(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+impl<T> SkipList<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        SkipList {
+            head: [None; MAX_LEVEL],
+            levels: 0,
+            len: 0,
+            /* xorshift gets stuck on 0 forever. */
+            rng: seed.max(1),
+            _boxes: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn levels(&self) -> usize {
+        self.levels
+    }
+
+    pub fn clear(&mut self) {
+        let mut cur = self.head[0];
+        while let Some(node) = cur {
+            let node = unsafe { Box::from_raw(node.as_ptr()) };
+            cur = node.next[0];
+        }
+        self.head = [None; MAX_LEVEL];
+        self.levels = 0;
+        self.len = 0;
+    }
+
+    /* A xorshift generator is plenty for flipping coins. Each 1 bit at the
+    bottom is one coin that came up heads. */
+    fn random_level(&mut self) -> usize {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (1 + self.rng.trailing_ones() as usize).min(MAX_LEVEL)
+    }
+
+    /* The next link at `level` of a node, where None is the head. */
+    fn next_at(&self, node: Link<T>, level: usize) -> Link<T> {
+        match node {
+            None => self.head[level],
+            Some(node) => unsafe { (&(*node.as_ptr()).next)[level] },
+        }
+    }
+
+    fn set_next(&mut self, node: Link<T>, level: usize, next: Link<T>) {
+        match node {
+            None => self.head[level] = next,
+            Some(node) => unsafe { (&mut (*node.as_ptr()).next)[level] = next },
+        }
+    }
+
+    fn value_of(&self, node: NonNull<Node<T>>) -> &T {
+        unsafe { &(*node.as_ptr()).value }
+    }
+
+    /* The search itself: on every level, run along while the next value is
+    still "before" what we look for, then go down. Returns, for every level,
+    the last node that was before (None meaning the head). */
+    fn predecessors<F>(&self, mut before: F) -> [Link<T>; MAX_LEVEL]
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut update = [None; MAX_LEVEL];
+        let mut cur = None;
+        for level in (0..self.levels).rev() {
+            while let Some(next) = self.next_at(cur, level) {
+                if !before(self.value_of(next)) {
+                    break;
+                }
+                cur = Some(next);
+            }
+            update[level] = cur;
+        }
+        update
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.head[0].map(|node| self.value_of(node))
+    }
+
+    /* The last node is found like any search, always running to the end of
+    each level: O(log n) too. */
+    pub fn last(&self) -> Option<&T> {
+        let update = self.predecessors(|_| true);
+        update[0].map(|node| self.value_of(node))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head[0],
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+}
+
+impl<T: Ord> SkipList<T> {
+    fn predecessors_of(&self, value: &T) -> [Link<T>; MAX_LEVEL] {
+        self.predecessors(|v| v < value)
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.get(value).is_some()
+    }
+
+    pub fn get(&self, value: &T) -> Option<&T> {
+        let update = self.predecessors_of(value);
+        let found = self.value_of(self.next_at(update[0], 0)?);
+        if found == value {
+            Some(found)
+        } else {
+            None
+        }
+    }
+
+    /* Returns false, and drops `value`, if it was already there. */
+    pub fn insert(&mut self, value: T) -> bool {
+        let update = self.predecessors_of(&value);
+        if let Some(next) = self.next_at(update[0], 0) {
+            if *self.value_of(next) == value {
+                return false;
+            }
+        }
+        let height = self.random_level();
+        /* update already says None (the head) for levels not in use yet. */
+        self.levels = self.levels.max(height);
+        let node = NonNull::from(Box::leak(Box::new(Node {
+            value,
+            next: vec![None; height],
+        })));
+        for (level, prev) in update.iter().enumerate().take(height) {
+            let next = self.next_at(*prev, level);
+            unsafe { (&mut (*node.as_ptr()).next)[level] = next };
+            self.set_next(*prev, level, Some(node));
+        }
+        self.len += 1;
+        true
+    }
+
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        let update = self.predecessors_of(value);
+        let target = self.next_at(update[0], 0)?;
+        if self.value_of(target) != value {
+            return None;
+        }
+        let node = unsafe { Box::from_raw(target.as_ptr()) };
+        /* On every level the node is part of, it's right after the
+        predecessor of that level: skip over it. */
+        for (level, next) in node.next.iter().enumerate() {
+            self.set_next(update[level], level, *next);
+        }
+        while self.levels > 0 && self.head[self.levels - 1].is_none() {
+            self.levels -= 1;
+        }
+        self.len -= 1;
+        Some(node.value)
+    }
+
+    /* Iterates the values inside the range, in order. Finding where to start
+    is a search, O(log n); after that it's a walk on level 0. */
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Range<'_, T, R> {
+        let update = match range.start_bound() {
+            Bound::Included(start) => self.predecessors(|v| v < start),
+            Bound::Excluded(start) => self.predecessors(|v| v <= start),
+            Bound::Unbounded => [None; MAX_LEVEL],
+        };
+        Range {
+            next: self.next_at(update[0], 0),
+            range,
+            _list: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for SkipList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/* The nodes, and next to each one the Vec with its links, one per level. */
+impl<T> HeapSize for SkipList<T> {
+    fn heap_size(&self) -> usize {
+        let mut bytes = 0;
+        let mut cur = self.head[0];
+        while let Some(node) = cur {
+            let node = unsafe { &*node.as_ptr() };
+            bytes += size_of::<Node<T>>() + node.next.capacity() * size_of::<Link<T>>();
+            cur = node.next[0];
+        }
+        bytes
+    }
+}
+
+/* Level 0 is just a sorted singly-linked list with every value in it. */
+pub struct Iter<'a, T> {
+    next: Link<T>,
+    remaining: usize,
+    _list: PhantomData<&'a Node<T>>,
+}
+
+/* Shared borrows of the list in disguise: as thread safe as &SkipList<T>. */
+unsafe impl<'a, T: Sync> Send for Iter<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for Iter<'a, T> {}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = unsafe { &*self.next?.as_ptr() };
+        self.next = node.next[0];
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+pub struct Range<'a, T, R> {
+    next: Link<T>,
+    range: R,
+    _list: PhantomData<&'a Node<T>>,
+}
+
+unsafe impl<'a, T: Sync, R: Send> Send for Range<'a, T, R> {}
+unsafe impl<'a, T: Sync, R: Sync> Sync for Range<'a, T, R> {}
+
+impl<'a, T: Ord, R: RangeBounds<T>> Iterator for Range<'a, T, R> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = unsafe { &*self.next?.as_ptr() };
+        let inside = match self.range.end_bound() {
+            Bound::Included(end) => node.value.cmp(end) != Ordering::Greater,
+            Bound::Excluded(end) => node.value < *end,
+            Bound::Unbounded => true,
+        };
+        if !inside {
+            self.next = None;
+            return None;
+        }
+        self.next = node.next[0];
+        Some(&node.value)
+    }
+}
+
+impl<'a, T: Ord, R: RangeBounds<T>> FusedIterator for Range<'a, T, R> {}
+
+impl<'a, T> IntoIterator for &'a SkipList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Ord> Extend<T> for SkipList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SkipList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SkipList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,102 @@
+use super::*;
+use crate::differential::Lcg;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+#[test]
+fn test_insert_contains_remove() {
+    let mut list = SkipList::new();
+    assert!(list.is_empty());
+    assert_eq!(list.first(), None);
+    assert_eq!(list.last(), None);
+    assert!(list.insert(5));
+    assert!(list.insert(1));
+    assert!(list.insert(3));
+    assert!(!list.insert(3));
+    assert_eq!(list.len(), 3);
+    assert!(list.contains(&3));
+    assert!(!list.contains(&2));
+    assert_eq!(list.get(&5), Some(&5));
+    assert_eq!(list.first(), Some(&1));
+    assert_eq!(list.last(), Some(&5));
+    assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 3, 5]);
+    assert_eq!(list.remove(&2), None);
+    assert_eq!(list.remove(&3), Some(3));
+    assert_eq!(list.remove(&3), None);
+    assert_eq!(format!("{:?}", list), "{1, 5}");
+    list.remove(&1);
+    list.remove(&5);
+    assert!(list.is_empty());
+    assert_eq!(list.levels(), 0);
+}
+
+#[test]
+fn test_against_btreeset() {
+    let mut list = SkipList::with_seed(42);
+    let mut model = BTreeSet::new();
+    let mut rng = Lcg::new(9);
+    for _ in 0..5000 {
+        let r = rng.next_u64();
+        let value = (r >> 33) % 500;
+        if (r >> 20) % 3 == 1 {
+            assert_eq!(list.remove(&value), model.take(&value));
+        } else {
+            assert_eq!(list.insert(value), model.insert(value));
+        }
+        assert_eq!(list.len(), model.len());
+    }
+    assert!(list.iter().eq(model.iter()));
+    assert_eq!(list.last(), model.iter().next_back());
+    for value in 0..500 {
+        assert_eq!(list.contains(&value), model.contains(&value));
+    }
+}
+
+#[test]
+fn test_range() {
+    let list: SkipList<i64> = (0..50).map(|v| v * 2).collect();
+    assert_eq!(
+        list.range(10..16).copied().collect::<Vec<_>>(),
+        vec![10, 12, 14]
+    );
+    assert_eq!(
+        list.range(11..=16).copied().collect::<Vec<_>>(),
+        vec![12, 14, 16]
+    );
+    assert_eq!(list.range(..4).copied().collect::<Vec<_>>(), vec![0, 2]);
+    assert_eq!(list.range(95..).copied().collect::<Vec<_>>(), vec![96, 98]);
+    assert_eq!(
+        list.range((Bound::Excluded(96), Bound::Unbounded))
+            .copied()
+            .collect::<Vec<_>>(),
+        vec![98]
+    );
+    assert_eq!(list.range(200..).count(), 0);
+    assert_eq!(list.range(..).count(), 50);
+}
+
+#[test]
+fn test_levels_are_logarithmic() {
+    let list: SkipList<u32> = (0..1 << 14).collect();
+    assert!(list.levels() >= 10 && list.levels() <= 28);
+    // Level 1 should hold about half of the values.
+    let mut level1 = 0;
+    let mut cur = list.head[1];
+    while let Some(node) = cur {
+        level1 += 1;
+        cur = unsafe { (&(*node.as_ptr()).next)[1] };
+    }
+    assert!(level1 > (1 << 12) && level1 < (3 << 12));
+}
+
+#[test]
+fn test_values_are_dropped() {
+    let values: Vec<Rc<i64>> = (0..10).map(Rc::new).collect();
+    let mut list: SkipList<Rc<i64>> = values.iter().cloned().collect();
+    assert!(!list.insert(Rc::clone(&values[0])));
+    list.remove(&values[3]);
+    assert_eq!(Rc::strong_count(&values[3]), 1);
+    assert_eq!(Rc::strong_count(&values[0]), 2);
+    drop(list);
+    assert!(values.iter().all(|v| Rc::strong_count(v) == 1));
+}
@@ -0,0 +1,55 @@
+#![allow(dead_code)]
+/*
+Soak tests for the unsafe code, small enough for Miri
+===========================================================================
+
+Every list with a raw pointer in it promises, in a comment, that the pointer
+is fine. The tests agree, but a test passing only means the bytes looked
+right this time. A use after free can read the old value back just fine, and
+a pointer that Stacked Borrows says is dead still points to the right place.
+
+Miri runs the tests in an interpreter that knows where every allocation
+begins and ends, who may still use each pointer, and what's left unfreed at
+the end. It's the only thing here that actually checks the promises:
+
+    cargo +nightly miri test soak
+
+It's also a few thousand times slower than a normal run, and the usual tests
+build lists of a million nodes to check that drop doesn't recurse. So this
+group goes over every unsafe function and every unsafe block again, each one
+reached at least once, with lists short enough for Miri. A normal `cargo
+test` runs them too, with longer lists.
+
+The values are Strings on purpose. An i64 that's read after its node is freed
+still looks like an i64, but a String that's dropped twice, or never, is
+something Miri reports.
+
+linked4 and linked8 are the delicate ones: they keep raw pointers into nodes
+that a Box owns, and a pointer to a node whose Box later moved is dead, as if
+it had been written through the Box. How they avoid that is described on
+linked4::List and at the top of linked8 and linked4/doubly.rs.
+
+Both of Miri's models pass: the default, Stacked Borrows, and the newer Tree
+Borrows:
+
+    MIRIFLAGS=-Zmiri-tree-borrows cargo +nightly miri test soak
+
+Tree Borrows is the pickier one about where a pointer comes from. One taken
+from a &mut to the node is a new borrow, and once written through, the next
+read through the Box leaves it read-only. So linked4 takes its pointers from
+the Box itself, with node_ptr().
+*/
+
+/* How long the lists get. Under Miri, long enough to walk every branch: a
+few nodes in the middle, both ends, the list going empty and back. */
+pub const LEN: usize = if cfg!(miri) { 24 } else { 2_000 };
+
+/* For the tests with threads, how many values each one pushes. */
+pub const PER_THREAD: usize = if cfg!(miri) { 16 } else { 2_000 };
+
+pub fn values(len: usize) -> Vec<String> {
+    (0..len).map(|i| i.to_string()).collect()
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,564 @@
+use super::*;
+use crate::{branded, circular, indexed, intrusive, linked3, linked4, linked6};
+use crate::{linked8, lockfree, skiplist, unrolled};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::thread;
+
+/* linked4::List: the tail cache (tail_mut), both constructors that build
+chains backwards, and every method that moves the tail around. */
+#[test]
+fn test_linked4_tail_cache() {
+    let values = values(LEN);
+    let mut list = linked4::List::new(&values[..LEN / 2]);
+    list.append_slice(&values[LEN / 2..]);
+    list.push_front("front".to_string());
+    list.add_item("back".to_string());
+    assert_eq!(list.pop_back().as_deref(), Some("back"));
+    assert_eq!(list.pop_front().as_deref(), Some("front"));
+    assert_eq!(list.to_vec(), values);
+
+    /* The last value, through the cache. */
+    list.tail_mut().unwrap().push_str("!");
+    list.add_item("extra".to_string());
+    list.add_item("after".to_string());
+    assert_eq!(list.len(), LEN + 2);
+
+    list.rotate_left(3);
+    list.rotate_right(5);
+    list.reverse();
+    list.sort();
+    list.dedup();
+    list.retain(|v| !v.ends_with('7'));
+    list[0].push('!');
+    let mut rest = list.split_off(list.len() / 2);
+    rest.swap(0, rest.len() - 1);
+    list.concat_copy(&rest);
+    assert_eq!(
+        list.remove_first_value(&"extra".to_string()).as_deref(),
+        Some("extra")
+    );
+    let drained: Vec<String> = rest.drain().collect();
+    assert!(rest.is_empty() && !drained.is_empty());
+    let len = list.len();
+    assert_eq!(list.into_iter().count(), len);
+}
+
+/* The cursor keeps raw pointers to the current and previous nodes. */
+#[test]
+fn test_linked4_cursor() {
+    let values = values(LEN);
+    let mut list = linked4::List::new(&values);
+    let mut cursor = list.cursor_front_mut();
+    while let Some(value) = cursor.current().cloned() {
+        if value.ends_with('3') {
+            cursor.remove_current();
+        } else {
+            cursor.insert_after(format!("{}+", value));
+            cursor.move_next();
+            cursor.move_next();
+        }
+    }
+    /* On the ghost, insert_after() is a push_front. */
+    cursor.insert_after("first".to_string());
+    let odd: Vec<String> = list.drain_filter(|v| v.ends_with('+')).collect();
+    assert!(!odd.is_empty());
+    let mut cursor = list.cursor_front_mut();
+    for _ in 0..LEN / 3 {
+        cursor.move_next();
+    }
+    let rest = cursor.split_after();
+    assert_eq!(rest.iter().count(), rest.len());
+    list.add_item("last".to_string());
+    assert_eq!(list.iter().last().map(String::as_str), Some("last"));
+}
+
+/* With zero, one and two nodes the first node is also the last, or right
+before it. Every list is moved into a Vec and back between the steps, the
+way a List moves when it's returned or stored. */
+#[test]
+fn test_linked4_short_lists() {
+    for len in 0..4 {
+        let values = values(len);
+        let mut lists = vec![
+            linked4::List::new_fast(&values),
+            linked4::List::new(&values),
+        ];
+        for _ in 0..2 {
+            let mut list = lists.swap_remove(0);
+            if let Some(last) = list.get_mut(len.saturating_sub(1)) {
+                last.push('!');
+            }
+            if len > 1 {
+                list.swap(0, len - 1);
+            }
+            list.rotate_left(1);
+            list.add_item("back".to_string());
+            list.reverse();
+            list.append_slice(&values[..len.min(1)]);
+            list.push_front("front".to_string());
+            let mut moved = vec![list];
+            let mut list = moved.pop().unwrap();
+            assert_eq!(list.pop_front().as_deref(), Some("front"));
+            list.pop_back();
+            let mut cursor = list.cursor_front_mut();
+            while cursor.index().is_some() {
+                cursor.move_next();
+            }
+            cursor.move_next();
+            cursor.insert_after("ghost".to_string());
+            if let Some(value) = cursor.current() {
+                value.push('?');
+            }
+            if len > 0 {
+                cursor.move_next();
+                cursor.remove_current();
+            }
+            let mut rest = list.split_off(list.len().min(1));
+            rest.add_item("rest".to_string());
+            list.add_item("list".to_string());
+            let mut both = vec![list, rest];
+            both.reverse();
+            for list in &mut both {
+                list.dedup_by(|a, b| a.len() == b.len());
+                list.add_item("end".to_string());
+                assert_eq!(list.iter().count(), list.len());
+            }
+            lists.extend(both);
+        }
+    }
+}
+
+#[test]
+fn test_linked4_doubly_short_lists() {
+    for len in 0..4 {
+        let mut lists = vec![linked4::DoublyList::from_slice(&values(len))];
+        for step in 0..6 {
+            let mut list = lists.pop().unwrap();
+            match step % 3 {
+                0 => list.push_front(step.to_string()),
+                1 => list.push_back(step.to_string()),
+                _ => {
+                    list.pop_front();
+                    list.pop_back();
+                }
+            }
+            assert_eq!(list.iter().rev().count(), list.len());
+            assert_eq!(list.to_vec_rev().len(), list.len());
+            lists.push(list);
+        }
+    }
+}
+
+#[test]
+fn test_linked4_doubly() {
+    let values = values(LEN);
+    let mut list = linked4::DoublyList::from_slice(&values);
+    list.push_front("front".to_string());
+    list.push_back("back".to_string());
+    assert_eq!(list.iter().next_back().map(String::as_str), Some("back"));
+    while list.len() > 2 {
+        list.pop_back();
+        list.pop_front();
+    }
+    assert_eq!(list.to_vec_rev().len(), list.len());
+    while list.pop_back().is_some() {}
+    list.push_back("again".to_string());
+}
+
+#[test]
+fn test_linked6_list() {
+    let values = values(LEN);
+    let mut list: linked6::List<String> = values.iter().cloned().collect();
+    list.push_front("front".to_string());
+    list.push_back("back".to_string());
+    list.front_mut().unwrap().push('!');
+    list.back_mut().unwrap().push('!');
+    assert_eq!(list.front().map(String::as_str), Some("front!"));
+    assert_eq!(list.back().map(String::as_str), Some("back!"));
+    list.reverse();
+    for value in list.iter_mut().rev().step_by(2) {
+        value.push('?');
+    }
+    assert_eq!(list.iter().rev().count(), LEN + 2);
+    list.pop_front();
+    list.pop_back();
+    assert!(list.contains(&"1".to_string()) || list.contains(&"1?".to_string()));
+    let cloned = list.clone();
+    let mut into_iter = cloned.into_iter();
+    into_iter.next();
+    into_iter.next_back();
+    /* The rest is dropped with the iterator. */
+}
+
+/* split_off() is the only caller of split_after_node() from outside the
+cursor, and append() is its way back. */
+#[test]
+fn test_linked6_split_append() {
+    let mut list: linked6::List<String> = values(LEN).into_iter().collect();
+    let mut pieces = Vec::new();
+    while list.len() > 3 {
+        let at = list.len() / 3;
+        pieces.push(list.split_off(at));
+    }
+    pieces.push(list.split_off(0));
+    assert!(list.is_empty());
+    for mut piece in pieces.into_iter().rev() {
+        list.append(&mut piece);
+        assert!(piece.is_empty());
+    }
+    assert_eq!(list.len(), LEN);
+    let mut empty = linked6::List::new();
+    list.append(&mut empty);
+    empty.append(&mut list);
+    assert_eq!(empty.len(), LEN);
+}
+
+#[test]
+fn test_linked6_cursor() {
+    let mut list: linked6::List<String> = values(LEN).into_iter().collect();
+    let mut cursor = list.cursor_back_mut();
+    while cursor.index().is_some() {
+        if cursor.current().unwrap().ends_with('5') {
+            cursor.remove_current();
+            cursor.move_prev();
+        } else {
+            cursor.insert_before("<".to_string());
+            cursor.insert_after(">".to_string());
+            cursor.peek_next().unwrap().push('>');
+            cursor.move_prev();
+            if let Some(prev) = cursor.peek_prev() {
+                prev.push('<');
+            }
+            cursor.move_prev();
+        }
+    }
+    /* On the ghost: insert at both ends. */
+    cursor.insert_after("head".to_string());
+    cursor.insert_before("tail".to_string());
+    cursor.move_next();
+    for _ in 0..LEN / 2 {
+        cursor.move_next();
+    }
+    let rest = cursor.split_after();
+    cursor.move_prev();
+    cursor.splice_after(rest);
+    let middle: linked6::List<String> = values(3).into_iter().collect();
+    cursor.splice_after(middle);
+    cursor.move_next();
+    cursor.move_next();
+    cursor.move_next();
+    cursor.move_next();
+    /* Splicing on the ghost puts the other list at the front. */
+    while cursor.index().is_some() {
+        cursor.move_next();
+    }
+    cursor.splice_after(values(2).into_iter().collect());
+    assert_eq!(list.front().map(String::as_str), Some("0"));
+    assert_eq!(list.iter().rev().count(), list.len());
+}
+
+/* The unsafe fn here is unlink(), behind every pop and remove. */
+#[test]
+fn test_linked6_sentinel() {
+    let mut list: linked6::SentinelList<String> = values(LEN).into_iter().collect();
+    list.insert(0, "front".to_string());
+    list.insert(list.len(), "back".to_string());
+    list.insert(LEN / 2, "middle".to_string());
+    assert_eq!(list.remove(LEN / 2).as_deref(), Some("middle"));
+    assert_eq!(list.front().map(String::as_str), Some("front"));
+    assert_eq!(list.back().map(String::as_str), Some("back"));
+    assert_eq!(list.iter().rev().count(), list.len());
+    while list.len() > 1 {
+        list.pop_front();
+        list.pop_back();
+    }
+    list.clear();
+    assert_eq!(list.pop_back(), None);
+    list.push_front("again".to_string());
+}
+
+/* linked8 writes to the last node only through tail, and takes tail again
+whenever the last node's Box moves. Each of these moves it. */
+#[test]
+fn test_linked8_tail_pointer() {
+    let mut list = linked8::List::new();
+    for round in 0..LEN {
+        list.push_back(round.to_string());
+        list.push_front(round.to_string());
+        if round % 3 == 0 {
+            list.pop_front();
+        }
+        *list.back_mut().unwrap() += "!";
+    }
+    while list.len() > 1 {
+        list.pop_front();
+        list.push_back("x".to_string());
+        list.pop_front();
+    }
+    /* One node: it's both ends, and push_front moves its Box. */
+    list.push_front("new".to_string());
+    list.back_mut().unwrap().push('?');
+    list.clear();
+    list.push_back("again".to_string());
+    list.front_mut().unwrap().push('.');
+    assert_eq!(list.back().map(String::as_str), Some("again."));
+
+    /* A list is moved around like any value, and its only node moves with
+    it. */
+    let mut lists: Vec<linked8::List<String>> =
+        (0..3).map(|_| values(1).into_iter().collect()).collect();
+    let mut one = lists.swap_remove(0);
+    one.push_back("moved".to_string());
+    assert_eq!(one.len(), 2);
+}
+
+#[test]
+fn test_circular() {
+    let mut ring: circular::CircularList<String> = values(LEN).into_iter().collect();
+    ring.rotate(3);
+    ring.rotate(-(LEN as isize) - 5);
+    ring.push("pushed".to_string());
+    ring.push_front("front".to_string());
+    ring.push_back("back".to_string());
+    ring.current_mut().unwrap().push('!');
+    assert_eq!(ring.current().map(String::as_str), Some("pushed!"));
+    assert_eq!(
+        ring.iter_cycle().take(3 * ring.len()).count(),
+        3 * ring.len()
+    );
+    while ring.len() > 1 {
+        ring.pop();
+        ring.rotate(2);
+    }
+    ring.clear();
+    ring.push("again".to_string());
+}
+
+#[test]
+fn test_indexed() {
+    let mut list = indexed::IndexedList::with_seed(7);
+    for (i, value) in values(LEN).into_iter().enumerate() {
+        list.insert_at(i / 2, value);
+    }
+    list.get_mut(LEN / 2).unwrap().push('!');
+    while list.len() > LEN / 2 {
+        list.remove_at(list.len() / 3);
+        list.pop_back();
+    }
+    let mut sorted = indexed::IndexedList::with_seed(3);
+    for value in values(LEN).into_iter().rev() {
+        sorted.insert_sorted(value);
+    }
+    assert_eq!(
+        sorted.rank(&"5".to_string()),
+        sorted.to_vec().iter().position(|v| v == "5").unwrap()
+    );
+    assert_eq!(list.iter().count(), list.len());
+    list.clear();
+    list.push_front("again".to_string());
+}
+
+#[test]
+fn test_skiplist() {
+    let mut list: skiplist::SkipList<String> = skiplist::SkipList::with_seed(11);
+    for value in values(LEN).into_iter().rev() {
+        assert!(list.insert(value));
+    }
+    assert!(!list.insert("0".to_string()));
+    assert!(list.contains(&"3".to_string()));
+    assert_eq!(list.get(&"4".to_string()).map(String::as_str), Some("4"));
+    let in_range = list.range("1".to_string().."2".to_string()).count();
+    assert!(in_range > 0);
+    for value in values(LEN).iter().step_by(2) {
+        assert_eq!(list.remove(value).as_ref(), Some(value));
+    }
+    assert_eq!(list.iter().count(), list.len());
+    assert!(list.first().is_some() && list.last().is_some());
+    list.clear();
+    list.insert("again".to_string());
+}
+
+/* Small chunks, so values get moved between them all the time. */
+#[test]
+fn test_unrolled() {
+    let mut list: unrolled::UnrolledList<String, 4> = unrolled::UnrolledList::new();
+    for (i, value) in values(LEN).into_iter().enumerate() {
+        list.insert(i / 2, value);
+    }
+    list.push_front("front".to_string());
+    list.push_back("back".to_string());
+    list.get_mut(LEN / 2).unwrap().push('!');
+    while list.len() > LEN / 3 {
+        list.remove(list.len() / 2);
+        list.pop_front();
+    }
+    list.extend(values(5));
+    assert_eq!(list.iter().count(), list.len());
+    list.clear();
+    list.push_back("again".to_string());
+}
+
+struct Node {
+    value: String,
+    link: intrusive::Link<Node>,
+}
+
+unsafe impl intrusive::Linked for Node {
+    fn link(&self) -> &intrusive::Link<Self> {
+        &self.link
+    }
+}
+
+#[test]
+fn test_intrusive() {
+    let nodes: Vec<Pin<Box<Node>>> = values(LEN)
+        .into_iter()
+        .map(|value| {
+            Box::pin(Node {
+                value,
+                link: intrusive::Link::new(),
+            })
+        })
+        .collect();
+    let mut list = intrusive::List::new();
+    for (i, node) in nodes.iter().enumerate() {
+        if i % 2 == 0 {
+            list.push_back(node.as_ref());
+        } else {
+            list.push_front(node.as_ref());
+        }
+    }
+    for node in nodes.iter().step_by(3) {
+        assert!(list.remove(node));
+        assert!(!list.contains(node));
+    }
+    assert_eq!(list.iter().count(), list.len());
+    list.pop_front();
+    list.pop_back();
+    assert!(list.front().is_some() && list.back().is_some());
+    /* Dropping the list unlinks whatever is left, before the nodes go. */
+    drop(list);
+    assert!(nodes.iter().all(|node| !node.link.is_linked()));
+    assert_eq!(nodes[1].value, "1");
+}
+
+#[test]
+fn test_branded() {
+    branded::GhostToken::new(|mut token| {
+        let mut list = branded::List::new();
+        for value in values(LEN) {
+            list.push_back(value.clone(), &mut token);
+            list.push_front(value, &mut token);
+        }
+        list.for_each_mut(&mut token, |value| value.push('!'));
+        assert_eq!(
+            list.back(&token).map(String::as_str),
+            Some(&*format!("{}!", LEN - 1))
+        );
+        while list.len() > 2 {
+            list.pop_front(&mut token);
+            list.pop_back(&mut token);
+        }
+        assert_eq!(list.iter(&token).count(), 2);
+    });
+}
+
+/* Arena::alloc() hands out references that outlive the borrow of the arena,
+and the doubly linked list on top of it keeps them all in Cells. */
+#[test]
+fn test_linked3_arena() {
+    let arena = linked3::Arena::new();
+    let first = arena.alloc(values(1).remove(0));
+    let more: Vec<&String> = values(LEN).into_iter().map(|v| arena.alloc(v)).collect();
+    assert_eq!(first, "0");
+    assert_eq!(more[LEN - 1], &(LEN - 1).to_string());
+
+    let arena = linked3::Arena::new();
+    let list = linked3::DoublyLinkedList::new(&arena);
+    let nodes: Vec<_> = (0..LEN as i64).map(|i| list.push_back(i)).collect();
+    list.push_front(-1);
+    for node in nodes.iter().step_by(2) {
+        list.unlink(node);
+    }
+    assert_eq!(list.iter().rev().count(), list.len());
+    while list.pop_back().is_some() {
+        list.pop_front();
+    }
+}
+
+/* A few threads each, so that retire() and free_chain() actually have
+something to do. Miri tries other interleavings on every run. Not under
+loom, whose atomics only work inside loom::model() (see lockfree/loom_test.rs). */
+#[cfg(not(loom))]
+#[test]
+fn test_lockfree_stack_and_queue() {
+    let stack = lockfree::Stack::new();
+    let queue = lockfree::Queue::new();
+    thread::scope(|s| {
+        for t in 0..3 {
+            let (stack, queue) = (&stack, &queue);
+            s.spawn(move || {
+                for i in 0..PER_THREAD {
+                    stack.push(format!("{}-{}", t, i));
+                    queue.push(format!("{}-{}", t, i));
+                    if i % 2 == 0 {
+                        stack.pop();
+                        queue.pop();
+                    }
+                }
+            });
+        }
+    });
+    let mut left = 0;
+    while stack.pop().is_some() {
+        left += 1;
+    }
+    while queue.pop().is_some() {
+        left -= 1;
+    }
+    assert_eq!(left, 0);
+    /* Dropped with values still in them. */
+    stack.push("left".to_string());
+    queue.push("left".to_string());
+}
+
+/* Receiver::pop() is the unsafe fn, and try_recv() and recv() call it. */
+#[cfg(not(loom))]
+#[test]
+fn test_lockfree_mpsc() {
+    let (sender, receiver) = lockfree::mpsc::channel();
+    let received = thread::scope(|s| {
+        for t in 0..3 {
+            let sender = sender.clone();
+            s.spawn(move || {
+                for i in 0..PER_THREAD {
+                    sender.send(Arc::new(t * PER_THREAD + i));
+                }
+            });
+        }
+        drop(sender);
+        receiver.iter().count()
+    });
+    assert_eq!(received, 3 * PER_THREAD);
+    assert_eq!(receiver.try_recv(), None);
+    assert_eq!(receiver.recv(), None);
+}
+
+#[cfg(feature = "crossbeam")]
+#[test]
+fn test_lockfree_epoch() {
+    let stack = lockfree::EpochStack::new();
+    thread::scope(|s| {
+        for t in 0..3 {
+            let stack = &stack;
+            s.spawn(move || {
+                for i in 0..PER_THREAD {
+                    stack.push(format!("{}-{}", t, i));
+                    stack.pop();
+                }
+            });
+        }
+    });
+    assert!(stack.is_empty());
+}
@@ -0,0 +1,125 @@
+#![allow(dead_code)]
+/*
+Sharing a list between threads, the boring way
+===========================================================================
+
+lockfree showed how hard it is to share a list without locks. Most of the
+time the answer is to just take a lock. This is that answer.
+
+The first idea would be linked5 with Arc<Mutex<Node>> instead of
+Rc<RefCell<Node>>: a lock per node. It sounds more concurrent, but a doubly
+linked list touches two or three nodes on every change, and locking them in
+whatever order each operation needs is how deadlocks are made (push_back
+locks tail then its prev, pop_front locks head then its next, and a two
+node list has them meet in the middle). Doing it right needs hand-over-hand
+locking in a fixed order, and by then every operation takes several locks
+instead of one.
+
+So SyncList is one coarse Mutex around a linked6::List, behind an Arc so
+that clones are handles to the same list. Every operation locks, does its
+O(1) thing and unlocks. For anything that needs several steps without
+anybody in between, lock() hands out the guard and the whole List API.
+
+Iterating while other threads push and pop can't work with a plain borrow:
+the lock would have to be held for as long as the iterator lives. So
+lock_iter() copies the values under the lock and iterates the copy. It's a
+snapshot: what was in the list at one moment, no matter what happens after.
+
+linked6::List is Send and Sync whenever T is, so the Mutex makes SyncList
+Send and Sync for any T: Send. linked5::List can never be shared like this,
+because Rc isn't Send: two threads could clone the same Rc at once and
+corrupt the count. src/threadsafety keeps both facts checked by the
+compiler.
+
+If a thread panics while holding the lock, the Mutex is poisoned. No List
+method can leave it half-changed by panicking, so it's safe to keep going,
+and every lock here just takes the guard out of the poison error.
+*/
+use crate::linked6::List;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+pub struct SyncList<T> {
+    inner: Arc<Mutex<List<T>>>,
+}
+
+/* A new handle to the same list, not a copy of it. */
+impl<T> Clone for SyncList<T> {
+    fn clone(&self) -> Self {
+        SyncList {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> Default for SyncList<T> {
+    fn default() -> Self {
+        SyncList {
+            inner: Arc::new(Mutex::new(List::new())),
+        }
+    }
+}
+
+impl<T> SyncList<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, List<T>> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    pub fn push_front(&self, value: T) {
+        self.lock().push_front(value);
+    }
+
+    pub fn push_back(&self, value: T) {
+        self.lock().push_back(value);
+    }
+
+    pub fn pop_front(&self) -> Option<T> {
+        self.lock().pop_front()
+    }
+
+    pub fn pop_back(&self) -> Option<T> {
+        self.lock().pop_back()
+    }
+
+    /* Empties the list in one go, and hands back what was in it. */
+    pub fn take(&self) -> List<T> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    pub fn lock_iter(&self) -> std::vec::IntoIter<T>
+    where
+        T: Clone,
+    {
+        let snapshot: Vec<T> = self.lock().iter().cloned().collect();
+        snapshot.into_iter()
+    }
+}
+
+impl<T> From<List<T>> for SyncList<T> {
+    fn from(list: List<T>) -> Self {
+        SyncList {
+            inner: Arc::new(Mutex::new(list)),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SyncList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.lock().iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test;
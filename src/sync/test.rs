@@ -0,0 +1,97 @@
+use super::*;
+use std::thread;
+
+#[test]
+fn test_handles_share_the_list() {
+    let list = SyncList::new();
+    let other = list.clone();
+    list.push_back(2);
+    other.push_front(1);
+    list.push_back(3);
+    assert_eq!(other.len(), 3);
+    assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+    assert_eq!(other.pop_back(), Some(3));
+    assert_eq!(list.pop_front(), Some(1));
+    assert_eq!(list.take().into_iter().collect::<Vec<_>>(), vec![2]);
+    assert!(other.is_empty());
+}
+
+#[test]
+fn test_many_threads() {
+    let list = SyncList::new();
+    let handles: Vec<_> = (0..8)
+        .map(|t| {
+            let list = list.clone();
+            thread::spawn(move || {
+                for i in 0..1000 {
+                    if i & 1 == 1 {
+                        list.push_back(t * 1000 + i);
+                    } else {
+                        list.push_front(t * 1000 + i);
+                    }
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(list.len(), 8000);
+
+    let poppers: Vec<_> = (0..4)
+        .map(|_| {
+            let list = list.clone();
+            thread::spawn(move || {
+                let mut mine = Vec::new();
+                while let Some(v) = list.pop_front() {
+                    mine.push(v);
+                }
+                mine
+            })
+        })
+        .collect();
+    let mut all: Vec<i64> = poppers
+        .into_iter()
+        .flat_map(|h| h.join().unwrap())
+        .collect();
+    all.sort_unstable();
+    assert_eq!(all, (0..8000).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_lock_iter_is_a_snapshot() {
+    let list: SyncList<i64> = (1..=3).collect::<List<_>>().into();
+    let snapshot = list.lock_iter();
+    list.push_back(4);
+    list.pop_front();
+    assert_eq!(snapshot.collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert_eq!(list.lock_iter().collect::<Vec<_>>(), vec![2, 3, 4]);
+}
+
+#[test]
+fn test_lock_for_several_steps() {
+    let list = SyncList::new();
+    list.push_back(1);
+    {
+        let mut guard = list.lock();
+        let first = guard.pop_front().unwrap();
+        guard.push_back(first + 1);
+        guard.push_back(first + 2);
+    }
+    assert_eq!(list.lock_iter().collect::<Vec<_>>(), vec![2, 3]);
+}
+
+#[test]
+fn test_poisoned_lock_still_works() {
+    let list = SyncList::new();
+    list.push_back(1);
+    let other = list.clone();
+    let result = thread::spawn(move || {
+        let _guard = other.lock();
+        panic!("holding the lock");
+    })
+    .join();
+    assert!(result.is_err());
+    list.push_back(2);
+    assert_eq!(list.lock_iter().collect::<Vec<_>>(), vec![1, 2]);
+}
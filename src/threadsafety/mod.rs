@@ -0,0 +1,255 @@
+#![allow(dead_code)]
+/*
+Which lists can cross threads, checked by the compiler
+===========================================================================
+
+Send and Sync are auto traits: nobody writes `impl Send for List`, the
+compiler works it out from the fields. A Box<T> is Send if T is, an Rc never
+is, a Cell is Send but not Sync, a raw pointer is neither. So the answer for
+each list falls out of how it was built:
+
+- Box chains and Vec slots (linked4, linked6's nodes once we vouch for the
+  pointers, linked7, unrolled, selforg, fixed, ...) are Send and Sync
+  whenever T is. The ones with raw pointers say so with an unsafe impl,
+  because the compiler gives up on raw pointers.
+
+- Rc lists (linked5, branded) are neither, whatever T is. Two threads
+  cloning the same Rc would race on its count.
+
+- Cell and RefCell lists (linked3 and its arena) can move to another thread
+  as a whole, if nothing borrows them, but can't be shared: two threads
+  calling set() on the same Cell is a data race.
+
+- intrusive's List only holds pointers into nodes that somebody else owns
+  and pins, so it's neither Send nor Sync. Its nodes are also !Unpin, which
+  is what stops them from moving while linked.
+
+- The lock-free ones only need T: Send to be Sync. A Stack or Queue never
+  hands out a &T, values only move in and out whole, so sharing the stack
+  never shares a T. A Stack<Cell<i64>> is fine to share, even though a
+  Cell<i64> isn't.
+
+- mpsc::Receiver is Send but not Sync: that's how "single consumer" is
+  enforced.
+
+None of this shows up in the code that uses these types. Adding an Rc field
+to linked6::List, or forgetting an unsafe impl after swapping a Box for a
+raw pointer, compiles just fine until somebody tries to send the list to a
+thread. So every fact above is written down here, in a way that stops the
+build if it changes.
+
+The positive checks are plain trait bounds. The negative ones use the trick
+from the static_assertions crate: a trait with two blanket impls, one for
+every type and one for types that implement the trait we're asking about.
+If the type implements it, both apply, the compiler can't pick one for the
+`_` and refuses to compile. The macros are ours, not the crate's: the trick
+is ten lines, a dependency for it would be more.
+
+UnwindSafe and RefUnwindSafe are auto traits of the same kind, for a
+different boundary: catch_unwind(). A closure passed to it may only capture
+things that can't be seen half changed after a panic, so a &mut anything is
+out, and so is a &RefCell. They're answered here too, from the same fields:
+
+- The Box and raw pointer lists are UnwindSafe without anyone saying so. A
+  raw pointer is, as long as what it points to is RefUnwindSafe. That they
+  really are is something the compiler can't know. Every place where our
+  code calls yours (a closure for retain(), a clone(), an Ord) leaves the
+  list in a state check() accepts if it panics; src/panics goes over them.
+
+- linked3::Arena's RefCells are only borrowed inside its own methods, never
+  while your code runs, so it says so with an impl. mpsc's ends get one for
+  the same reason. linked5 doesn't: its nodes are handed out by iter_mut(),
+  and one borrowed from outside makes the list panic halfway through a
+  relink (src/panics has a test).
+
+- The Cell lists (linked3, branded, intrusive) stay as the compiler says.
+  Their cells are written through shared references (with the token, for
+  branded), so a panic really can leave a link changed and its neighbour
+  not yet.
+
+These only ever say "this type isn't Send". tests/compile_fail/ has the
+other half: small programs that try to actually use a !Send list from
+another thread, and must keep failing to compile (with trybuild, `cargo test
+--test compile_fail`).
+*/
+use crate::{
+    branded, circular, fixed, hybrid, indexed, intrusive, linked1, linked2, linked3, linked4,
+    linked5, linked6, linked7, linked8, lockfree, selforg, skiplist, sync, unrolled,
+};
+use std::cell::Cell;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::rc::Rc;
+
+/* Every trait listed must be implemented. */
+macro_rules! assert_impl {
+    ($ty:ty: $($trait:ident),+) => {
+        const _: fn() = || {
+            fn check<T: ?Sized $(+ $trait)+>() {}
+            check::<$ty>();
+        };
+    };
+}
+
+/* The trait must not be implemented. */
+macro_rules! assert_not_impl {
+    ($ty:ty: $($trait:ident),+) => {
+        $(
+            const _: fn() = || {
+                trait AmbiguousIfImpl<A> {
+                    fn some_item() {}
+                }
+                impl<T: ?Sized> AmbiguousIfImpl<()> for T {}
+                impl<T: ?Sized + $trait> AmbiguousIfImpl<u8> for T {}
+                let _ = <$ty as AmbiguousIfImpl<_>>::some_item;
+            };
+        )+
+    };
+}
+
+/* A node for the intrusive list. */
+pub struct Node {
+    value: i64,
+    link: intrusive::Link<Node>,
+}
+
+unsafe impl intrusive::Linked for Node {
+    fn link(&self) -> &intrusive::Link<Self> {
+        &self.link
+    }
+}
+
+// Owned lists: Send and Sync whenever T is.
+assert_impl!(linked1::InlineList<8>: Send, Sync, Unpin);
+assert_impl!(linked2::LinkedList2<'static, i64>: Send, Sync, Unpin);
+assert_impl!(linked4::LinkedList1<i64>: Send, Sync, Unpin);
+assert_impl!(linked4::List<i64>: Send, Sync, Unpin);
+assert_impl!(linked4::IntoIter<i64>: Send, Sync, Unpin);
+assert_impl!(linked4::DoublyList<i64>: Send, Sync, Unpin);
+assert_impl!(linked6::List<i64>: Send, Sync, Unpin);
+assert_impl!(linked6::IntoIter<i64>: Send, Sync, Unpin);
+assert_impl!(linked6::SentinelList<i64>: Send, Sync, Unpin);
+assert_impl!(linked7::List<i64>: Send, Sync, Unpin);
+assert_impl!(linked8::List<i64>: Send, Sync, Unpin);
+assert_impl!(circular::CircularList<i64>: Send, Sync, Unpin);
+assert_impl!(unrolled::UnrolledList<i64>: Send, Sync, Unpin);
+assert_impl!(skiplist::SkipList<i64>: Send, Sync, Unpin);
+assert_impl!(selforg::SelfOrgList<i64>: Send, Sync, Unpin);
+assert_impl!(fixed::FixedList<i64, 4>: Send, Sync, Unpin);
+assert_impl!(indexed::IndexedList<i64>: Send, Sync, Unpin);
+assert_impl!(hybrid::HybridList<i64>: Send, Sync, Unpin);
+
+// ...and they're only as thread safe as what they hold.
+assert_not_impl!(linked4::List<Rc<i64>>: Send, Sync);
+assert_not_impl!(linked6::List<Rc<i64>>: Send, Sync);
+assert_impl!(linked6::List<Cell<i64>>: Send);
+assert_not_impl!(linked6::List<Cell<i64>>: Sync);
+assert_impl!(linked8::List<Cell<i64>>: Send);
+assert_not_impl!(linked8::List<Cell<i64>>: Sync);
+// ...and as what they allocate from: a Bump is only for one thread at a time.
+#[cfg(feature = "allocator_api")]
+assert_impl!(crate::arena::Bump: Send);
+#[cfg(feature = "allocator_api")]
+assert_not_impl!(crate::arena::Bump: Sync);
+#[cfg(feature = "allocator_api")]
+assert_not_impl!(linked6::List<i64, &'static crate::arena::Bump>: Send, Sync);
+#[cfg(feature = "allocator_api")]
+assert_not_impl!(linked4::List<i64, &'static crate::arena::Bump>: Send, Sync);
+assert_not_impl!(skiplist::SkipList<Rc<i64>>: Send, Sync);
+
+// Cursors borrow the list mutably, so they're like a &mut List.
+assert_impl!(linked4::CursorMut<'static, i64>: Send, Sync);
+assert_impl!(linked6::CursorMut<'static, i64>: Send, Sync);
+assert_not_impl!(linked6::CursorMut<'static, Cell<i64>>: Sync);
+
+// Iterators borrow the list, so they're like a &List.
+assert_impl!(linked6::Iter<'static, i64>: Send, Sync);
+assert_impl!(linked6::IterMut<'static, i64>: Send, Sync);
+assert_not_impl!(linked6::Iter<'static, Cell<i64>>: Send, Sync);
+assert_impl!(linked8::Iter<'static, i64>: Send, Sync);
+assert_impl!(circular::Iter<'static, i64>: Send, Sync);
+assert_impl!(circular::IterCycle<'static, i64>: Send, Sync);
+assert_impl!(skiplist::Iter<'static, i64>: Send, Sync);
+assert_impl!(skiplist::Range<'static, i64, std::ops::Range<i64>>: Send, Sync);
+assert_impl!(indexed::Iter<'static, i64>: Send, Sync);
+assert_impl!(unrolled::Iter<'static, i64, 32>: Send, Sync);
+assert_impl!(linked7::Iter<'static, i64>: Send, Sync);
+assert_impl!(fixed::Iter<'static, i64, 4>: Send, Sync);
+assert_impl!(selforg::Iter<'static, i64>: Send, Sync);
+assert_impl!(hybrid::Iter<'static, i64, 8>: Send, Sync);
+
+// Rc inside: never.
+assert_not_impl!(linked5::List: Send, Sync);
+assert_not_impl!(linked5::Node: Send, Sync);
+assert_not_impl!(branded::List<'static, i64>: Send, Sync);
+
+// Cells inside: linked3's nodes point at each other through Cells, so even
+// moving them would mean sharing those Cells.
+assert_not_impl!(linked3::LinkedList1<'static, i64>: Send, Sync);
+assert_not_impl!(linked3::ManagedList<'static, i64>: Send, Sync);
+assert_not_impl!(linked3::DoublyLinkedList<'static, i64>: Send, Sync);
+assert_impl!(linked3::Arena<i64>: Send);
+assert_not_impl!(linked3::Arena<i64>: Sync);
+
+// Pointers into nodes we don't own, and the nodes can't move.
+assert_not_impl!(intrusive::List<'static, Node>: Send, Sync);
+assert_not_impl!(intrusive::Link<Node>: Send, Sync, Unpin);
+assert_not_impl!(Node: Unpin);
+
+// Shared between threads on purpose.
+assert_impl!(sync::SyncList<i64>: Send, Sync, Unpin);
+assert_impl!(sync::SyncList<String>: Send, Sync);
+assert_impl!(sync::SyncList<Cell<i64>>: Send, Sync);
+assert_not_impl!(sync::SyncList<Rc<i64>>: Send, Sync);
+assert_impl!(lockfree::Stack<i64>: Send, Sync, Unpin);
+assert_impl!(lockfree::Stack<Cell<i64>>: Send, Sync);
+assert_not_impl!(lockfree::Stack<Rc<i64>>: Send, Sync);
+assert_impl!(lockfree::Queue<i64>: Send, Sync, Unpin);
+assert_impl!(lockfree::Queue<Cell<i64>>: Send, Sync);
+assert_not_impl!(lockfree::Queue<Rc<i64>>: Send, Sync);
+assert_impl!(lockfree::mpsc::Sender<i64>: Send, Sync);
+assert_impl!(lockfree::mpsc::Receiver<i64>: Send);
+assert_not_impl!(lockfree::mpsc::Receiver<i64>: Sync);
+#[cfg(feature = "crossbeam")]
+assert_impl!(lockfree::EpochStack<i64>: Send, Sync);
+
+// Unwinding: every owned list can be carried across catch_unwind().
+assert_impl!(linked4::List<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(linked4::DoublyList<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(linked6::List<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(linked6::SentinelList<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(linked7::List<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(linked8::List<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(circular::CircularList<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(unrolled::UnrolledList<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(skiplist::SkipList<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(selforg::SelfOrgList<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(fixed::FixedList<i64, 4>: UnwindSafe, RefUnwindSafe);
+assert_impl!(indexed::IndexedList<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(hybrid::HybridList<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(sync::SyncList<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(lockfree::Stack<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(lockfree::Queue<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(lockfree::mpsc::Sender<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(lockfree::mpsc::Receiver<i64>: UnwindSafe, RefUnwindSafe);
+assert_impl!(linked3::Arena<i64>: UnwindSafe, RefUnwindSafe);
+
+// ...unless what they hold can be changed through a &T.
+assert_not_impl!(linked4::List<Cell<i64>>: RefUnwindSafe);
+assert_not_impl!(linked6::List<Cell<i64>>: RefUnwindSafe);
+assert_not_impl!(linked3::Arena<Cell<i64>>: RefUnwindSafe);
+
+// A cursor is a &mut List, and those never are.
+assert_not_impl!(linked4::CursorMut<'static, i64>: UnwindSafe);
+assert_not_impl!(linked6::CursorMut<'static, i64>: UnwindSafe);
+
+// Nodes anyone can borrow while the list relinks them.
+assert_not_impl!(linked5::List: UnwindSafe, RefUnwindSafe);
+
+// Cells anyone can set through a shared reference.
+assert_not_impl!(linked3::LinkedList1<'static, i64>: UnwindSafe, RefUnwindSafe);
+assert_not_impl!(linked3::DoublyLinkedList<'static, i64>: UnwindSafe, RefUnwindSafe);
+assert_not_impl!(branded::List<'static, i64>: UnwindSafe, RefUnwindSafe);
+assert_not_impl!(intrusive::List<'static, Node>: UnwindSafe, RefUnwindSafe);
+
+#[cfg(test)]
+mod test;
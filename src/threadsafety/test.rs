@@ -0,0 +1,62 @@
+use super::*;
+use std::thread;
+
+/* The assertions are checked by just compiling. These make sure the impls
+they vouch for also hold up when used: moving a list to another thread and
+back, and reading one from several threads at once. */
+#[test]
+fn test_lists_move_between_threads() {
+    let list = linked4::List::new(&(1..=100).collect::<Vec<i64>>());
+    let back = thread::spawn(move || {
+        let sum: i64 = list.iter().sum();
+        (list, sum)
+    })
+    .join()
+    .unwrap();
+    assert_eq!(back.1, 5050);
+    assert_eq!(back.0.len(), 100);
+
+    let mut list: linked6::List<String> = linked6::List::new();
+    list.push_back("a".to_string());
+    let list = thread::spawn(move || {
+        let mut list = list;
+        list.push_back("b".to_string());
+        list
+    })
+    .join()
+    .unwrap();
+    assert_eq!(list.iter().cloned().collect::<Vec<_>>(), vec!["a", "b"]);
+}
+
+#[test]
+fn test_lists_are_shared_between_threads() {
+    let list: linked6::List<i64> = (1..=1000).collect();
+    let skip: skiplist::SkipList<i64> = (1..=1000).collect();
+    thread::scope(|s| {
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                s.spawn(|| {
+                    let a: i64 = list.iter().sum();
+                    let b: i64 = skip.iter().sum();
+                    a + b
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 2 * 500500);
+        }
+    });
+}
+
+#[test]
+fn test_cursor_moves_to_another_thread() {
+    let mut list = linked4::List::new(&[1, 2, 3, 4, 5]);
+    thread::scope(|s| {
+        let mut cursor = list.cursor_front_mut();
+        s.spawn(move || {
+            cursor.move_next();
+            cursor.remove_current();
+        });
+    });
+    assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 3, 4, 5]);
+}
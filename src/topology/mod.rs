@@ -0,0 +1,67 @@
+#![allow(dead_code)]
+/*
+What the links look like, not just the values
+===========================================================================
+
+to_vec() says what's in a list, and that's what almost every test looks at.
+But two lists with the same values can be built very differently: split_off()
+followed by append() should hand back the very same nodes, sort() on linked4
+moves the Boxes around instead of the values, linked7 reuses the slot a pop
+left free. A version that copied everything would pass all those tests too,
+and be a lot slower.
+
+topology() describes the nodes themselves, first to last: an id for each,
+the ids its next and prev point to, and for linked5 how many Rc and Weak
+point at it. A test can keep the ids from before an operation and compare.
+
+The id is whatever stays the same for as long as the node lives: its address
+for the lists that allocate each node on its own, its slot for linked7.
+Addresses get reused once a node is freed, so comparing ids only means
+something while both nodes are alive.
+
+Pointers are written down as they are. A null one is None, and so is one to
+a sentinel, which isn't a node with a value. linked4::DoublyList leaves the
+second node's prev null on purpose, and its topology says so.
+
+The walk takes len() at its word and stops after that many nodes. On a list
+whose links are broken that's not the whole story, check() is for that.
+*/
+
+/* One node. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeLinks {
+    pub id: usize,
+    pub next: Option<usize>,
+    pub prev: Option<usize>,
+    /* linked5 only: (strong, weak) references to it, leaving out the one
+    used to look. */
+    pub counts: Option<(usize, usize)>,
+}
+
+impl NodeLinks {
+    /* For the lists without prev links or counts. */
+    pub fn singly(id: usize, next: Option<usize>) -> Self {
+        NodeLinks {
+            id,
+            next,
+            prev: None,
+            counts: None,
+        }
+    }
+}
+
+pub trait Topology {
+    fn topology(&self) -> Vec<NodeLinks>;
+
+    fn node_ids(&self) -> Vec<usize> {
+        self.topology().iter().map(|node| node.id).collect()
+    }
+}
+
+/* A node's address, as its id. */
+pub(crate) fn id_of<T>(node: *const T) -> usize {
+    node as usize
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,109 @@
+use super::*;
+use crate::ops::LinkedListOps;
+use crate::{circular, linked4, linked5, linked6, linked7, linked8};
+
+const LEN: usize = 10;
+
+/* Every id once, each next is the node after it, and with prev links each
+prev is the node before it. */
+fn assert_chain(nodes: &[NodeLinks], doubly: bool) {
+    assert_eq!(nodes.len(), LEN);
+    let ids: Vec<usize> = nodes.iter().map(|node| node.id).collect();
+    let mut unique = ids.clone();
+    unique.sort_unstable();
+    unique.dedup();
+    assert_eq!(unique.len(), LEN, "an id shows up twice: {:?}", ids);
+    for (i, node) in nodes.iter().enumerate() {
+        assert_eq!(node.next, ids.get(i + 1).copied(), "next of node {}", i);
+        if doubly {
+            assert_eq!(node.prev, i.checked_sub(1).map(|i| ids[i]), "prev of {}", i);
+        } else {
+            assert_eq!(node.prev, None);
+        }
+    }
+}
+
+fn values() -> Vec<i64> {
+    (0..LEN as i64).collect()
+}
+
+#[test]
+fn test_every_list_is_a_chain() {
+    assert_chain(&linked4::List::new(&values()).topology(), false);
+    assert_chain(&linked8::List::from_slice(&values()).topology(), false);
+    assert_chain(&linked5::List::from_vec(&values()).topology(), true);
+    assert_chain(&linked6::List::from_slice(&values()).topology(), true);
+    assert_chain(&linked6::SentinelList::from_slice(&values()).topology(), true);
+    assert_chain(&linked7::List::from_slice(&values()).topology(), true);
+    let ring: circular::CircularList<i64> = values().into_iter().collect();
+    assert_chain(&ring.topology(), true);
+}
+
+/* Except for the second node's prev, which is left null on purpose. */
+#[test]
+fn test_doubly_second_prev() {
+    let list = linked4::DoublyList::from_slice(&values());
+    let mut nodes = list.topology();
+    assert_eq!(nodes[1].prev, None);
+    nodes[1].prev = Some(nodes[0].id);
+    assert_chain(&nodes, true);
+}
+
+#[test]
+fn test_counts() {
+    let list = linked5::List::from_vec(&values());
+    let nodes = list.topology();
+    assert!(nodes.iter().all(|node| node.counts == Some((1, 1))));
+    assert!(linked6::List::from_slice(&values())
+        .topology()
+        .iter()
+        .all(|node| node.counts.is_none()));
+}
+
+#[test]
+fn test_empty() {
+    assert!(linked4::List::<i64>::default().topology().is_empty());
+    assert!(linked6::SentinelList::<i64>::new().node_ids().is_empty());
+    assert!(circular::CircularList::<i64>::new().node_ids().is_empty());
+}
+
+/* What the ids are for: operations that should move nodes instead of
+copying values. */
+#[test]
+fn test_nodes_are_reused() {
+    let mut list = linked4::List::new(&values());
+    let mut ids = list.node_ids();
+    list.reverse();
+    ids.reverse();
+    assert_eq!(list.node_ids(), ids);
+
+    let mut first = linked5::List::from_vec(&values()[..4]);
+    let second = linked5::List::from_vec(&values()[4..]);
+    let ids: Vec<usize> = [first.node_ids(), second.node_ids()].concat();
+    first.concat(second);
+    assert_chain(&first.topology(), true);
+    assert_eq!(first.node_ids(), ids);
+    assert!(first.topology().iter().all(|node| node.counts == Some((1, 1))));
+
+    let mut list = linked6::List::from_slice(&values());
+    let ids = list.node_ids();
+    let mut back = list.split_off(3);
+    assert_eq!(list.node_ids(), ids[..3]);
+    assert_eq!(back.node_ids(), ids[3..]);
+    list.append(&mut back);
+    assert_chain(&list.topology(), true);
+    assert_eq!(list.node_ids(), ids);
+
+    let mut list = linked7::List::from_slice(&values());
+    let ids = list.node_ids();
+    list.pop_front();
+    list.push_back(0);
+    assert_eq!(list.node_ids(), [&ids[1..], &ids[..1]].concat());
+
+    let mut ring: circular::CircularList<i64> = values().into_iter().collect();
+    let ids = ring.node_ids();
+    /* Only the current position moves, the ring is walked from the
+    sentinel. */
+    ring.rotate(3);
+    assert_eq!(ring.node_ids(), ids);
+}
@@ -0,0 +1,389 @@
+#![allow(dead_code)]
+/*
+Unrolled: a linked list of little arrays
+===========================================================================
+
+Every list in this crate so far puts one value per node, and every node is
+its own allocation somewhere on the heap. Walking the list means jumping
+from one allocation to the next, and each jump is likely a cache miss. A
+Vec, by comparison, is one block of memory that the CPU can read ahead of
+us. That's most of why "just use a Vec" wins almost every benchmark.
+
+An unrolled list meets them halfway: each node (here called a chunk) holds
+up to N values in an array, plus the pointer to the next chunk. Scanning
+reads N values in a row before each jump, so it's nearly as cache friendly
+as a Vec, and the allocator is called once every N values instead of every
+time. Inserting in the middle shifts at most N values around instead of the
+whole rest of the Vec.
+
+The unrolled benchmarks in benches/benchmark.rs show it: summing 10k values
+is about 15 times faster than with linked5, and building the list about 9
+times faster, on my machine at least.
+
+Keeping it that way needs two rules:
+- A full chunk that gets another insert is split in two halves, so there's
+  room again on both sides.
+- When removing leaves a chunk small enough to fit together with the next
+  one, they're merged, so the list doesn't fill up with almost empty chunks.
+Empty chunks are never kept.
+
+The chunk is an array of MaybeUninit<T>, with the first `len` initialized.
+That's the only unsafe part, and it lives in Chunk, behind as_slice(),
+insert(), remove(), split() and merge_next(). The list on top of them is a
+Box chain, all safe code, like linked4.
+*/
+use crate::footprint::HeapSize;
+use std::fmt;
+use std::iter::{FromIterator, FusedIterator};
+use std::mem::{size_of, MaybeUninit};
+use std::ptr;
+
+struct Chunk<T, const N: usize> {
+    len: usize,
+    values: [MaybeUninit<T>; N],
+    next: Option<Box<Chunk<T, N>>>,
+}
+
+impl<T, const N: usize> Chunk<T, N> {
+    /* With less than two values per chunk there's nothing to split. */
+    const SPLITTABLE: () = assert!(N >= 2, "an unrolled chunk holds at least 2 values");
+
+    fn new_box(next: Option<Box<Self>>) -> Box<Self> {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::SPLITTABLE;
+        Box::new(Chunk {
+            len: 0,
+            values: std::array::from_fn(|_| MaybeUninit::uninit()),
+            next,
+        })
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    fn as_ptr(&self) -> *const T {
+        self.values.as_ptr() as *const T
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self.values.as_mut_ptr() as *mut T
+    }
+
+    fn as_slice(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.as_ptr(), self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { std::slice::from_raw_parts_mut(self.as_mut_ptr(), self.len) }
+    }
+
+    /* Like Vec::insert: everything from `at` moves one place to the right.
+    The chunk must not be full. */
+    fn insert(&mut self, at: usize, value: T) {
+        assert!(at <= self.len && !self.is_full());
+        unsafe {
+            let p = self.as_mut_ptr().add(at);
+            ptr::copy(p, p.add(1), self.len - at);
+            ptr::write(p, value);
+        }
+        self.len += 1;
+    }
+
+    fn remove(&mut self, at: usize) -> T {
+        assert!(at < self.len);
+        unsafe {
+            let p = self.as_mut_ptr().add(at);
+            let value = ptr::read(p);
+            ptr::copy(p.add(1), p, self.len - at - 1);
+            self.len -= 1;
+            value
+        }
+    }
+
+    /* Moves the upper half of the values into a new chunk, which goes right
+    after this one. */
+    fn split(&mut self) {
+        let keep = self.len / 2;
+        let mut other = Self::new_box(self.next.take());
+        unsafe {
+            ptr::copy_nonoverlapping(self.as_ptr().add(keep), other.as_mut_ptr(), self.len - keep);
+        }
+        other.len = self.len - keep;
+        self.len = keep;
+        self.next = Some(other);
+    }
+
+    /* Moves all the values of the next chunk into this one, and drops the
+    next chunk. They must fit. */
+    fn merge_next(&mut self) {
+        let mut next = self.next.take().expect("merge_next: no next chunk");
+        assert!(self.len + next.len <= N);
+        unsafe {
+            ptr::copy_nonoverlapping(next.as_ptr(), self.as_mut_ptr().add(self.len), next.len);
+        }
+        self.len += next.len;
+        /* The values are ours now, next must not drop them. */
+        next.len = 0;
+        self.next = next.next.take();
+    }
+}
+
+impl<T, const N: usize> Drop for Chunk<T, N> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) };
+    }
+}
+
+pub struct UnrolledList<T, const N: usize = 32> {
+    head: Option<Box<Chunk<T, N>>>,
+    len: usize,
+}
+
+impl<T, const N: usize> Default for UnrolledList<T, N> {
+    fn default() -> Self {
+        UnrolledList { head: None, len: 0 }
+    }
+}
+
+impl<T, const N: usize> UnrolledList<T, N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /* How many chunks there are, to see the splits and merges at work. */
+    pub fn chunks(&self) -> usize {
+        self.chunk_iter().count()
+    }
+
+    pub fn clear(&mut self) {
+        /* One chunk at a time, so a long chain doesn't recurse in drop. */
+        let mut cur = self.head.take();
+        while let Some(mut chunk) = cur {
+            cur = chunk.next.take();
+        }
+        self.len = 0;
+    }
+
+    fn chunk_iter(&self) -> impl Iterator<Item = &Chunk<T, N>> {
+        std::iter::successors(self.head.as_deref(), |chunk| chunk.next.as_deref())
+    }
+
+    /* The chunk holding `index`, and where in it. */
+    fn locate(&self, mut index: usize) -> Option<(&Chunk<T, N>, usize)> {
+        for chunk in self.chunk_iter() {
+            if index < chunk.len {
+                return Some((chunk, index));
+            }
+            index -= chunk.len;
+        }
+        None
+    }
+
+    /* The link (the Option<Box> pointing to a chunk) of the chunk where
+    `index` is, and the index inside that chunk. With index == len it's the
+    end of the last chunk, or the empty head. Walking `&mut Option<Box>`
+    links instead of chunks is what lets the caller replace or unlink it. */
+    fn link_at(&mut self, mut index: usize) -> (&mut Option<Box<Chunk<T, N>>>, usize) {
+        let mut link = &mut self.head;
+        while link
+            .as_ref()
+            .is_some_and(|c| index > c.len || (index == c.len && c.next.is_some()))
+        {
+            let chunk = link.as_mut().unwrap();
+            index -= chunk.len;
+            link = &mut chunk.next;
+        }
+        (link, index)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let (chunk, at) = self.locate(index)?;
+        Some(&chunk.as_slice()[at])
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+        let (link, at) = self.link_at(index);
+        Some(&mut link.as_mut()?.as_mut_slice()[at])
+    }
+
+    pub fn insert(&mut self, index: usize, value: T) {
+        assert!(
+            index <= self.len,
+            "insert: the len is {} but the index is {}",
+            self.len,
+            index
+        );
+        let (link, mut at) = self.link_at(index);
+        let mut chunk = link.get_or_insert_with(|| Chunk::new_box(None));
+        if chunk.is_full() {
+            chunk.split();
+            if at > chunk.len {
+                at -= chunk.len;
+                chunk = chunk.next.as_mut().unwrap();
+            }
+        }
+        chunk.insert(at, value);
+        self.len += 1;
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        let (link, at) = self.link_at(index);
+        let chunk = link.as_mut().unwrap();
+        let value = chunk.remove(at);
+        if chunk.len == 0 {
+            let next = chunk.next.take();
+            *link = next;
+        } else if chunk.len <= N / 2
+            && chunk
+                .next
+                .as_ref()
+                .is_some_and(|next| chunk.len + next.len <= N)
+        {
+            chunk.merge_next();
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        self.insert(0, value);
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        self.insert(self.len, value);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.remove(0)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.remove(self.len.checked_sub(1)?)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, N> {
+        Iter {
+            chunk: self.head.as_deref(),
+            values: [].iter(),
+            remaining: self.len,
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T, const N: usize> Drop for UnrolledList<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/* Whole chunks, however full they are. */
+impl<T, const N: usize> HeapSize for UnrolledList<T, N> {
+    fn heap_size(&self) -> usize {
+        self.chunks() * size_of::<Chunk<T, N>>()
+    }
+}
+
+/* Walks the values of one chunk as a plain slice iterator, which is as fast
+as iterating a Vec, and only follows a pointer when the chunk runs out. */
+pub struct Iter<'a, T, const N: usize> {
+    chunk: Option<&'a Chunk<T, N>>,
+    values: std::slice::Iter<'a, T>,
+    remaining: usize,
+}
+
+impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(value) = self.values.next() {
+                self.remaining -= 1;
+                return Some(value);
+            }
+            let chunk = self.chunk?;
+            self.values = chunk.as_slice().iter();
+            self.chunk = chunk.next.as_deref();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T, const N: usize> ExactSizeIterator for Iter<'a, T, N> {}
+impl<'a, T, const N: usize> FusedIterator for Iter<'a, T, N> {}
+
+impl<'a, T, const N: usize> IntoIterator for &'a UnrolledList<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, const N: usize> Extend<T> for UnrolledList<T, N> {
+    /* push_back() would walk all the chunks for every value. Instead, walk
+    once to the last chunk, and keep filling new ones from there. */
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut link = &mut self.head;
+        while link.as_ref().is_some_and(|c| c.next.is_some()) {
+            link = &mut link.as_mut().unwrap().next;
+        }
+        for value in iter {
+            if link.as_ref().is_some_and(|c| c.is_full()) {
+                link = &mut link.as_mut().unwrap().next;
+            }
+            let chunk = link.get_or_insert_with(|| Chunk::new_box(None));
+            let at = chunk.len;
+            chunk.insert(at, value);
+            self.len += 1;
+        }
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for UnrolledList<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for UnrolledList<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for UnrolledList<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+#[cfg(test)]
+mod test;
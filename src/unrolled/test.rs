@@ -0,0 +1,107 @@
+use super::*;
+use crate::differential::{Harness, Op};
+use std::rc::Rc;
+
+fn chunk_lens<T, const N: usize>(list: &UnrolledList<T, N>) -> Vec<usize> {
+    list.chunk_iter().map(|chunk| chunk.len).collect()
+}
+
+#[test]
+fn test_push_pop() {
+    let mut list: UnrolledList<i64, 4> = UnrolledList::new();
+    assert_eq!(list.pop_front(), None);
+    assert_eq!(list.pop_back(), None);
+    for i in 0..10 {
+        list.push_back(i);
+    }
+    list.push_front(-1);
+    assert_eq!(list.len(), 11);
+    assert_eq!(list.to_vec(), (-1..10).collect::<Vec<_>>());
+    assert_eq!(list.pop_back(), Some(9));
+    assert_eq!(list.pop_front(), Some(-1));
+    assert_eq!(list.get(3), Some(&3));
+    assert_eq!(list.get(9), None);
+    *list.get_mut(8).unwrap() = 80;
+    assert_eq!(list.get_mut(9), None);
+    assert_eq!(list.to_vec(), vec![0, 1, 2, 3, 4, 5, 6, 7, 80]);
+    while list.pop_front().is_some() {}
+    assert!(list.is_empty());
+    assert_eq!(list.chunks(), 0);
+}
+
+#[test]
+fn test_split_and_merge() {
+    let mut list: UnrolledList<i64, 4> = (0..4).collect();
+    assert_eq!(chunk_lens(&list), vec![4]);
+    // The chunk is full: inserting splits it in two.
+    list.insert(4, 4);
+    assert_eq!(chunk_lens(&list), vec![2, 3]);
+    list.insert(1, 10);
+    assert_eq!(list.to_vec(), vec![0, 10, 1, 2, 3, 4]);
+    list.remove(1);
+    // 2 + 3 values don't fit in one chunk yet.
+    assert_eq!(chunk_lens(&list), vec![2, 3]);
+    list.remove(0);
+    assert_eq!(chunk_lens(&list), vec![4]);
+    assert_eq!(list.to_vec(), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn test_empty_chunks_go_away() {
+    let mut list: UnrolledList<i64, 4> = (0..4).collect();
+    list.push_back(4);
+    assert_eq!(chunk_lens(&list), vec![2, 3]);
+    assert_eq!(list.remove(2), Some(2));
+    assert_eq!(list.remove(2), Some(3));
+    assert_eq!(list.remove(2), Some(4));
+    assert_eq!(chunk_lens(&list), vec![2]);
+    assert_eq!(list.remove(2), None);
+}
+
+/* The harness checks the values after every step, we check the chunks. */
+#[test]
+fn test_against_vec() {
+    let mut harness: Harness<UnrolledList<i64, 4>> = Harness::new();
+    for op in Op::random_sequence(5, 3000) {
+        harness.positional_op(op);
+        let list = harness.list();
+        let lens = chunk_lens(list);
+        assert!(lens.iter().all(|len| *len > 0 && *len <= 4));
+        assert_eq!(lens.iter().sum::<usize>(), list.len());
+        assert_eq!(list.iter().len(), list.len());
+    }
+}
+
+#[test]
+fn test_values_are_dropped() {
+    let counter = Rc::new(());
+    let mut list: UnrolledList<Rc<()>, 4> = UnrolledList::new();
+    for i in 0..20 {
+        list.insert(i / 2, Rc::clone(&counter));
+    }
+    for _ in 0..8 {
+        list.remove(3);
+    }
+    assert_eq!(Rc::strong_count(&counter), 13);
+    drop(list);
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
+
+#[test]
+fn test_default_chunk_size() {
+    let list: UnrolledList<i64> = (0..100).collect();
+    assert_eq!(list.chunks(), 4);
+    assert_eq!(list.iter().sum::<i64>(), 4950);
+    assert_eq!(
+        format!("{:?}", list.iter().take(3).collect::<Vec<_>>()),
+        "[0, 1, 2]"
+    );
+    let other: UnrolledList<i64> = (0..100).collect();
+    assert_eq!(list, other);
+}
+
+#[test]
+fn test_drop_long_list() {
+    let list: UnrolledList<i64, 2> = (0..1_000_000).collect();
+    drop(list);
+}
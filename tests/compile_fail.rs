@@ -0,0 +1,14 @@
+/* The other half of src/threadsafety: programs that must not compile. Each
+file in compile_fail/ tries to use a list from a thread it can't go to, or
+change one that something still borrows, and the .stderr next to it is the
+error we expect.
+
+If a refactor makes one of them compile, this fails. If the compiler just
+words its error differently, regenerate the .stderr files with
+TRYBUILD=overwrite cargo test --test compile_fail, and check the diff says
+the same thing. */
+#[test]
+fn compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/compile_fail/*.rs");
+}
@@ -0,0 +1,20 @@
+/* The list only points at nodes that live somewhere else. */
+use crappylinkedlists::intrusive::{Link, Linked, List};
+use std::thread;
+
+struct Waiter {
+    link: Link<Waiter>,
+}
+
+unsafe impl Linked for Waiter {
+    fn link(&self) -> &Link<Self> {
+        &self.link
+    }
+}
+
+fn main() {
+    let list: List<'static, Waiter> = List::new();
+    thread::spawn(move || {
+        let _ = list;
+    });
+}
@@ -0,0 +1,19 @@
+/* A node with a Link can only be pinned with unsafe code, or on the stack
+or the heap with pin!/Box::pin, never with the safe Pin::new. */
+use crappylinkedlists::intrusive::{Link, Linked};
+use std::pin::Pin;
+
+struct Waiter {
+    link: Link<Waiter>,
+}
+
+unsafe impl Linked for Waiter {
+    fn link(&self) -> &Link<Self> {
+        &self.link
+    }
+}
+
+fn main() {
+    let mut waiter = Waiter { link: Link::new() };
+    let _ = Pin::new(&mut waiter);
+}
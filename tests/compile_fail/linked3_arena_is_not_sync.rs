@@ -0,0 +1,11 @@
+/* The arena hands out nodes through a RefCell: fine to move, not to share. */
+use crappylinkedlists::linked3::Arena;
+use std::thread;
+
+fn main() {
+    let arena = Arena::new();
+    thread::scope(|s| {
+        s.spawn(|| arena.alloc(1));
+        s.spawn(|| arena.alloc(2));
+    });
+}
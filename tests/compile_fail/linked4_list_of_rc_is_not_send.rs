@@ -0,0 +1,11 @@
+/* linked4::List is Send only when T is. */
+use crappylinkedlists::linked4::List;
+use std::rc::Rc;
+use std::thread;
+
+fn main() {
+    let list = List::new(&[Rc::new(1)]);
+    thread::spawn(move || {
+        let _ = list;
+    });
+}
@@ -0,0 +1,10 @@
+/* iter() borrows the list: if the list could shrink under it, the len() it
+promised would be a lie. */
+use crappylinkedlists::linked5::List;
+
+fn main() {
+    let mut list = List::from_vec(&[1, 2, 3]);
+    let iter = list.iter();
+    list.pop_tail();
+    assert_eq!(iter.len(), 3);
+}
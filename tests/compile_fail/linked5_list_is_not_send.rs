@@ -0,0 +1,10 @@
+/* Rc inside: sending it would let two threads touch the same counts. */
+use crappylinkedlists::linked5::List;
+use std::thread;
+
+fn main() {
+    let list = List::from_vec(&[1, 2, 3]);
+    thread::spawn(move || {
+        let _ = list;
+    });
+}
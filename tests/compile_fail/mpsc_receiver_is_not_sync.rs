@@ -0,0 +1,13 @@
+/* Two threads receiving from the same Receiver would both pop, and the
+queue only allows one. */
+use crappylinkedlists::lockfree::mpsc;
+use std::thread;
+
+fn main() {
+    let (tx, rx) = mpsc::channel::<i64>();
+    tx.send(1);
+    thread::scope(|s| {
+        s.spawn(|| rx.try_recv());
+        s.spawn(|| rx.try_recv());
+    });
+}